@@ -1,8 +1,13 @@
 use sp_cid::Cid;
 use sp_ipld::Ipld;
 use std::{
+  io::{
+    BufRead,
+    BufReader,
+  },
   path::PathBuf,
   rc::Rc,
+  str::FromStr,
 };
 use structopt::StructOpt;
 use yatima_cli::file::store::{
@@ -12,12 +17,32 @@ use yatima_cli::file::store::{
 #[cfg(not(target_arch = "wasm32"))]
 use yatima_cli::repl;
 use yatima_core::{
+  limits::Limits,
   name::Name,
   parse::parse_cid,
 };
-use yatima_runtime::transform::StdIORuntime;
+use yatima_runtime::{
+  debug::{
+    Breakpoint,
+    Debugger,
+    Pause,
+    Resume,
+  },
+  extract,
+  fs::Sandbox,
+  js,
+  replay::{
+    Clock,
+    Nondet,
+  },
+  transform::StdIORuntime,
+  wasm,
+  Engine,
+  Strategy,
+};
 use yatima_utils::{
   file,
+  golden,
   ipfs::IpfsApi,
   store::{
     show,
@@ -68,6 +93,136 @@ enum Command {
   Run {
     #[structopt(parse(from_os_str))]
     path: PathBuf,
+    #[structopt(
+      long = "allow-read",
+      parse(from_os_str),
+      help = "Grant file primitives read access to a directory."
+    )]
+    allow_read: Vec<PathBuf>,
+    #[structopt(
+      long = "allow-write",
+      parse(from_os_str),
+      help = "Grant file primitives write access to a directory."
+    )]
+    allow_write: Vec<PathBuf>,
+    #[structopt(long = "seed", help = "Seed the generator of io.random_bytes.")]
+    seed: Option<u64>,
+    #[structopt(
+      long = "fixed-time",
+      help = "Make io.now always return these milliseconds since the epoch."
+    )]
+    fixed_time: Option<u64>,
+    #[structopt(
+      long = "record",
+      parse(from_os_str),
+      conflicts_with = "replay",
+      help = "Record the results of io.now and io.random_bytes to a file."
+    )]
+    record: Option<PathBuf>,
+    #[structopt(
+      long = "replay",
+      parse(from_os_str),
+      conflicts_with_all = &["seed", "fixed-time"],
+      help = "Replay the results of io.now and io.random_bytes from a file."
+    )]
+    replay: Option<PathBuf>,
+    #[structopt(
+      long = "max-steps",
+      help = "Stop the program with an error after this many reduction steps."
+    )]
+    max_steps: Option<u64>,
+    #[structopt(
+      long = "max-nodes",
+      help = "Stop the program with an error once it holds this many DAG nodes."
+    )]
+    max_nodes: Option<u64>,
+    #[structopt(
+      long = "max-io",
+      help = "Stop the program with an error after this many IO operations."
+    )]
+    max_io: Option<u64>,
+    #[structopt(
+      long = "engine",
+      default_value = "graph",
+      help = "Evaluate with graph reduction (graph) or the bytecode machine (vm)."
+    )]
+    engine: Engine,
+    #[structopt(
+      long = "strategy",
+      default_value = "lazy",
+      help = "Evaluate arguments when they are needed (lazy) or when they are passed (strict)."
+    )]
+    strategy: Strategy,
+    #[structopt(
+      long = "dump-optimized",
+      help = "Print the term that would be run, after optimization, instead of running it."
+    )]
+    dump_optimized: bool,
+    #[structopt(
+      long = "profile",
+      help = "Print the reduction steps taken by each definition to stderr after the run."
+    )]
+    profile: bool,
+    #[structopt(
+      long = "profile-folded",
+      parse(from_os_str),
+      help = "Write the reduction steps by stack of definitions to a file, for flamegraphs."
+    )]
+    profile_folded: Option<PathBuf>,
+    #[structopt(
+      long = "debug",
+      help = "Pause before the first reduction step and read debugger commands from the terminal."
+    )]
+    debug: bool,
+    #[structopt(
+      long = "break",
+      number_of_values = 1,
+      help = "Debug, pausing at a definition, by name or CID, or at primitives starting with #."
+    )]
+    breakpoints: Vec<String>,
+    /// Arguments passed to the program after `--`
+    #[structopt(last = true)]
+    args: Vec<String>,
+  },
+  /// Compile the `main` definition of a file into a standalone program, or
+  /// its definitions into a JavaScript module
+  Build {
+    #[structopt(parse(from_os_str))]
+    path: PathBuf,
+    #[structopt(long = "target", help = "The kind of program to build: wasm or js.")]
+    target: Target,
+    #[structopt(
+      short = "o",
+      long = "output",
+      parse(from_os_str),
+      help = "Where to write the program. Defaults to the file with the target's extension."
+    )]
+    output: Option<PathBuf>,
+  },
+  /// Translate a definition of a file, and the definitions it refers to,
+  /// into source code of another language
+  Extract {
+    #[structopt(long = "lang", help = "The language to extract to: rust.")]
+    lang: Lang,
+    #[structopt(parse(from_os_str))]
+    path: PathBuf,
+    def: String,
+    #[structopt(
+      short = "o",
+      long = "output",
+      parse(from_os_str),
+      help = "Where to write the source. Defaults to stdout."
+    )]
+    output: Option<PathBuf>,
+  },
+  /// Run every program with a `main` in a directory and compare its stdout,
+  /// exit code and runtime error with the sibling `.out`, `.code` and `.err`
+  /// files
+  TestRun {
+    #[structopt(parse(from_os_str))]
+    dir: PathBuf,
+    #[structopt(long = "update", help = "Rewrite the expected output files.")]
+    update: bool,
   },
   Repl,
   Pin {
@@ -79,6 +234,55 @@ enum Command {
   },
 }
 
+/// The output formats of `yatima build`
+#[derive(Debug, Clone, Copy)]
+enum Target {
+  /// A WebAssembly module exporting `main`, see `yatima_runtime::wasm`
+  Wasm,
+  /// An ES module exporting a getter for each definition, see
+  /// `yatima_runtime::js`
+  Js,
+}
+
+impl Target {
+  fn extension(self) -> &'static str {
+    match self {
+      Self::Wasm => "wasm",
+      Self::Js => "mjs",
+    }
+  }
+}
+
+impl FromStr for Target {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "wasm" => Ok(Self::Wasm),
+      "js" => Ok(Self::Js),
+      _ => Err(format!("Unknown target {}, expected wasm or js", s)),
+    }
+  }
+}
+
+/// The languages of `yatima extract`
+#[derive(Debug, Clone, Copy)]
+enum Lang {
+  /// Rust source, see `yatima_runtime::extract`
+  Rust,
+}
+
+impl FromStr for Lang {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "rust" => Ok(Self::Rust),
+      _ => Err(format!("Unknown language {}, expected rust", s)),
+    }
+  }
+}
+
 #[derive(Debug, StructOpt)]
 enum ShowType {
   File {
@@ -224,7 +428,26 @@ fn run_cli() -> std::io::Result<()> {
       file::check_all_in_file(root, path, store)?;
       Ok(())
     }
-    Command::Run { path } => {
+    Command::Run {
+      path,
+      allow_read,
+      allow_write,
+      seed,
+      fixed_time,
+      record,
+      replay,
+      max_steps,
+      max_nodes,
+      max_io,
+      engine,
+      strategy,
+      dump_optimized,
+      profile,
+      profile_folded,
+      debug,
+      breakpoints,
+      args,
+    } => {
       let env = file::parse::PackageEnv::new(root, path.clone(), store.clone());
       let (_, p, defs) = file::parse::parse_file(env).map_err(handle_error_string)?;
       let p = Rc::new(p);
@@ -236,9 +459,157 @@ fn run_cli() -> std::io::Result<()> {
       let def = checked.get(&Name::from("main")).unwrap_or_else(|| {
         panic!("No `main` expression in package {} from file {:?}", p.name, path)
       });
-      let runtime_io = Rc::new(StdIORuntime::new());
+      let sandbox = Sandbox::new(allow_read, allow_write);
+      let clock = fixed_time.map_or(Clock::System, Clock::Fixed);
+      let mut nondet = Nondet::new(clock, seed);
+      if let Some(record) = record {
+        nondet = nondet.record(&record)?;
+      }
+      if let Some(replay) = replay {
+        nondet = nondet.replay(&replay)?;
+      }
+      let runtime_io = Rc::new(
+        StdIORuntime::new().with_sandbox(sandbox).with_args(args).with_nondet(nondet),
+      );
+
+      if dump_optimized {
+        let mut term = def.to_owned().term;
+        yatima_runtime::prepare(checked, &mut term, runtime_io)
+          .map_err(|e| handle_error_string(e.to_string()))?;
+        println!("{}", term);
+        return Ok(());
+      }
+      let limits = Limits { max_steps, max_nodes, max_io, ..Limits::default() };
+      let profiled = profile || profile_folded.is_some();
+      if strategy == Strategy::Strict && (engine != Engine::Graph || debug || profiled) {
+        return Err(handle_error_string(
+          "--strategy=strict requires --engine=graph, without debugging or profiling".to_owned(),
+        ));
+      }
+      let code = if debug || !breakpoints.is_empty() {
+        if engine != Engine::Graph {
+          return Err(handle_error_string("Debugging requires --engine=graph".to_owned()));
+        }
+        let breakpoints = breakpoints
+          .iter()
+          .map(|b| Breakpoint::parse(&checked, b))
+          .collect::<Result<Vec<_>, _>>()
+          .map_err(|e| handle_error_string(e.to_string()))?;
+        let prompt = prompt().map_err(handle_error_string)?;
+        let debugger = Debugger::new(breakpoints, Box::new(prompt));
+        yatima_runtime::run_debugged(
+          &mut def.to_owned().term,
+          checked,
+          runtime_io,
+          limits,
+          "main",
+          debugger,
+        )
+      } else if profiled {
+        if engine != Engine::Graph {
+          return Err(handle_error_string("Profiling requires --engine=graph".to_owned()));
+        }
+        let (res, steps) = yatima_runtime::run_profiled(
+          &mut def.to_owned().term,
+          checked,
+          runtime_io,
+          limits,
+          "main",
+        );
+        if profile {
+          eprint!("{}", steps.table());
+        }
+        if let Some(profile_folded) = profile_folded {
+          std::fs::write(profile_folded, steps.folded())?;
+        }
+        res
+      } else if strategy == Strategy::Strict {
+        yatima_runtime::run_with_strategy(
+          &mut def.to_owned().term,
+          checked,
+          runtime_io,
+          limits,
+          strategy,
+        )
+      } else {
+        yatima_runtime::run_with_engine(
+          &mut def.to_owned().term,
+          checked,
+          runtime_io,
+          limits,
+          engine,
+        )
+      }
+      .map_err(|e| handle_error_string(e.to_string()))?;
+      if code != 0 {
+        use std::io::Write;
+        std::io::stdout().flush()?;
+        std::process::exit(code);
+      }
+      Ok(())
+    }
+    Command::Build { path, target, output } => {
+      let env = file::parse::PackageEnv::new(root, path.clone(), store.clone());
+      let (_, p, defs) = file::parse::parse_file(env).map_err(handle_error_string)?;
+      let p = Rc::new(p);
+      let defs = Rc::new(defs);
+
+      let _cid = store.put(p.to_ipld());
 
-      yatima_runtime::run(&mut def.to_owned().term, checked, runtime_io);
+      let checked = file::check_all(p.clone(), defs, store).map_err(handle_error_string)?;
+      let program = match target {
+        Target::Wasm => {
+          let def = checked.get(&Name::from("main")).unwrap_or_else(|| {
+            panic!("No `main` expression in package {} from file {:?}", p.name, path)
+          });
+          wasm::build(checked.clone(), &def.term).map_err(|e| e.to_string())
+        }
+        Target::Js => {
+          js::build(checked.clone(), &p.index.0).map(String::into_bytes).map_err(|e| e.to_string())
+        }
+      }
+      .map_err(handle_error_string)?;
+      let output = output.unwrap_or_else(|| path.with_extension(target.extension()));
+      std::fs::write(&output, program)?;
+      println!("Built {}", output.display());
+      Ok(())
+    }
+    Command::Extract { lang, path, def, output } => {
+      let env = file::parse::PackageEnv::new(root, path.clone(), store.clone());
+      let (_, p, defs) = file::parse::parse_file(env).map_err(handle_error_string)?;
+      let p = Rc::new(p);
+      let defs = Rc::new(defs);
+
+      let _cid = store.put(p.to_ipld());
+
+      let checked = file::check_all(p, defs, store).map_err(handle_error_string)?;
+      let source = match lang {
+        Lang::Rust => extract::rust(&checked, &Name::from(def)).map_err(|e| e.to_string()),
+      }
+      .map_err(handle_error_string)?;
+      match output {
+        Some(output) => std::fs::write(output, source)?,
+        None => print!("{}", source),
+      }
+      Ok(())
+    }
+    Command::TestRun { dir, update } => {
+      let results = golden::test_dir(root, &dir, update, store)?;
+      let mut failed = 0;
+      for (path, status) in &results {
+        match status {
+          golden::Status::Passed => println!("✓ {}", path.display()),
+          golden::Status::Updated => println!("↻ {}", path.display()),
+          golden::Status::Failed(e) => {
+            failed += 1;
+            println!("✕ {}\n{}", path.display(), e);
+          }
+        }
+      }
+      println!("{} passed, {} failed", results.len() - failed, failed);
+      if failed > 0 {
+        std::process::exit(1);
+      }
       Ok(())
     }
     Command::Pin { path } => {
@@ -254,6 +625,56 @@ fn run_cli() -> std::io::Result<()> {
   }
 }
 
+/// The controlling terminal the debugger reads its commands from
+#[cfg(not(windows))]
+const TERMINAL: &str = "/dev/tty";
+#[cfg(windows)]
+const TERMINAL: &str = "CONIN$";
+
+/// Shows pauses of the debugger on stderr and reads its commands from the
+/// terminal, leaving stdin to the program. An empty line repeats the last
+/// command. Once the terminal's input ends, the debugger says so and the
+/// program runs to its end without pausing.
+fn prompt() -> Result<impl FnMut(&Pause) -> Resume, String> {
+  let terminal = std::fs::File::open(TERMINAL).map_err(|e| {
+    format!("The debugger reads its commands from {}: {}", TERMINAL, e)
+  })?;
+  let mut terminal = BufReader::new(terminal);
+  let mut last = Resume::Step;
+  let mut ended = false;
+  Ok(move |pause: &Pause| {
+    if ended {
+      return Resume::Continue;
+    }
+    eprint!("{}", pause);
+    loop {
+      eprint!("(debug) ");
+      let mut line = String::new();
+      let end = match terminal.read_line(&mut line) {
+        Ok(0) => Some("End of debugger input".to_owned()),
+        Err(e) => Some(format!("Cannot read debugger commands: {}", e)),
+        Ok(_) => None,
+      };
+      if let Some(end) = end {
+        eprintln!("\n{}, running to the end of the program", end);
+        ended = true;
+        return Resume::Continue;
+      }
+      let line = line.trim();
+      if line.is_empty() {
+        return last;
+      }
+      match Resume::from_str(line) {
+        Ok(resume) => {
+          last = resume;
+          return resume;
+        }
+        Err(e) => eprintln!("{}", e),
+      }
+    }
+  })
+}
+
 fn pin(path: PathBuf, root: PathBuf, store: Rc<FileStore>) {
   let env =
     file::parse::PackageEnv::new(root.clone(), path.clone(), store.clone());
@@ -0,0 +1,65 @@
+#![feature(test)]
+
+extern crate test;
+extern crate yatima_core;
+extern crate yatima_runtime;
+
+use nom_locate::LocatedSpan;
+use std::rc::Rc;
+use test::Bencher;
+use yatima_core::{
+  defs::Defs,
+  limits::Limits,
+  parse::term::input_cid,
+};
+use yatima_runtime::{
+  memory::MemIORuntime,
+  run_with_strategy,
+  Strategy,
+};
+
+/// Counts the constructors of 2^k in unary with an accumulator, which the
+/// lazy strategy builds as a chain of 2^k suspended additions
+fn bench_count(k: usize, strategy: Strategy, b: &mut Bencher) {
+  let s = "
+    type Peano { Z, S Peano }
+    def Peano.double (n: Peano): Peano = (case n) (λ _ => Peano) Peano.Z
+      (λ m => Peano.S (Peano.S (Peano.double m)))
+    def count (acc: #Nat) (n: Peano): #Nat = (case n) (λ _ => #Nat) acc
+      (λ m => count (#Nat.add acc 1) m)
+  ";
+  let (_, (defs, _)) = yatima_core::parse::package::parse_defs(
+    input_cid(s),
+    Defs::new(),
+  )(LocatedSpan::from(s))
+  .unwrap();
+  let defs = Rc::new(defs);
+  let main = format!(
+    "count 0 ({}(Peano.S Peano.Z){})",
+    "Peano.double (".repeat(k),
+    ")".repeat(k)
+  );
+  let term = yatima_core::parse::term::parse(&main, (*defs).clone()).unwrap().1;
+  b.iter(|| {
+    run_with_strategy(
+      &mut term.clone(),
+      defs.clone(),
+      Rc::new(MemIORuntime::new("")),
+      Limits::default(),
+      strategy,
+    )
+    .unwrap()
+  });
+}
+
+#[bench]
+fn count_lazy_8(b: &mut Bencher) { bench_count(8, Strategy::Lazy, b); }
+
+#[bench]
+fn count_strict_8(b: &mut Bencher) { bench_count(8, Strategy::Strict, b); }
+
+#[bench]
+fn count_lazy_12(b: &mut Bencher) { bench_count(12, Strategy::Lazy, b); }
+
+#[bench]
+fn count_strict_12(b: &mut Bencher) { bench_count(12, Strategy::Strict, b); }
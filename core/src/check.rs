@@ -9,6 +9,10 @@ use crate::{
   defs,
   defs::Defs,
   dll::*,
+  limits::{
+    LimitError,
+    Meter,
+  },
   literal::Literal,
   name::Name,
   parse,
@@ -35,29 +39,48 @@ use sp_std::{
 
 /// Generates a content id for a DAG pointer's anonymous term
 pub fn hash(dag: DAGPtr, dep: u64) -> Cid {
+  anon_term(dag, dep).embed().0.cid()
+}
+
+/// The term of a DAG pointer, with its bound variables at depth `dep`
+fn anon_term(dag: DAGPtr, dep: u64) -> Term {
   let mut map = BTreeMap::new();
-  DAG::dag_ptr_to_term(&dag, &mut map, dep, true).embed().0.cid()
+  DAG::dag_ptr_to_term(&dag, &mut map, dep, true)
+}
+
+/// Frees `dag` if `res` failed, so that errors such as exceeding a limit do
+/// not leak it
+fn free_on_err<T, E>(res: Result<T, E>, dag: &DAG) -> Result<T, E> {
+  if res.is_err() {
+    DAG::new(dag.head).free();
+  }
+  res
 }
 
-/// Lazily checks if two DAGs are beta equivalent. 
+/// Lazily checks if two DAGs are beta equivalent, charging each comparison
+/// to `meter` by the size of the terms it hashes
 pub fn equal(
   defs: &Defs,
   a: &mut DAG,
   b: &mut DAG,
   dep: u64,
   should_count: bool,
-) -> bool {
-  a.whnf(defs, should_count);
-  b.whnf(defs, should_count);
+  meter: &mut Meter,
+) -> Result<bool, LimitError> {
+  a.whnf_metered(defs, should_count, meter)?;
+  b.whnf_metered(defs, should_count, meter)?;
   let mut triples = vec![(a.head, b.head, dep)];
   let mut set: BTreeSet<(Cid, Cid)> = BTreeSet::new();
   while let Some((a, b, dep)) = triples.pop() {
     let mut a = DAG::new(a);
     let mut b = DAG::new(b);
-    a.whnf(defs, should_count);
-    b.whnf(defs, should_count);
-    let hash_a = hash(a.head, dep);
-    let hash_b = hash(b.head, dep);
+    a.whnf_metered(defs, should_count, meter)?;
+    b.whnf_metered(defs, should_count, meter)?;
+    let term_a = anon_term(a.head, dep);
+    let term_b = anon_term(b.head, dep);
+    meter.equal_step(term_a.size() + term_b.size())?;
+    let hash_a = term_a.embed().0.cid();
+    let hash_b = term_b.embed().0.cid();
     let eq = hash_a == hash_b
       || set.contains(&(hash_a, hash_b))
       || set.contains(&(hash_b, hash_a));
@@ -92,7 +115,7 @@ pub fn equal(
           let a_img = DAGPtr::Lam(a_img);
           let b_img = DAGPtr::Lam(b_img);
           if a_uses != b_uses {
-            return false;
+            return Ok(false);
           }
           triples.push((a_dom, b_dom, dep));
           triples.push((a_img, b_img, dep + 1));
@@ -103,11 +126,11 @@ pub fn equal(
           triples.push((a_fun, b_fun, dep));
           triples.push((a_arg, b_arg, dep));
         },
-        _ => return false,
+        _ => return Ok(false),
       }
     }
   }
-  true
+  Ok(true)
 }
 
 /// Typechecks a term, relying on type inference as needed
@@ -119,19 +142,41 @@ pub fn check(
   term: &Term,
   typ: &mut DAG,
   should_count: bool,
+  meter: &mut Meter,
 ) -> Result<(), CheckError> {
   match term {
-    Term::Lam(pos, _, bod) => {
-      check_lam(rec, defs, ctx, uses, term, typ, pos, &**bod, should_count)
-    }
-    Term::Dat(pos, bod) => {
-      check_dat(rec, defs, ctx, uses, term, typ, pos, &**bod, should_count)
-    }
+    Term::Lam(pos, _, bod) => check_lam(
+      rec,
+      defs,
+      ctx,
+      uses,
+      term,
+      typ,
+      pos,
+      &**bod,
+      should_count,
+      meter,
+    ),
+    Term::Dat(pos, bod) => check_dat(
+      rec,
+      defs,
+      ctx,
+      uses,
+      term,
+      typ,
+      pos,
+      &**bod,
+      should_count,
+      meter,
+    ),
     _ => {
       let depth = ctx.len();
       // TODO Should we clone ctx?
-      let mut detected_typ = infer(rec, defs, ctx, uses, term, should_count)?;
-      if equal(defs, typ, &mut detected_typ, depth as u64, should_count) {
+      let mut detected_typ =
+        infer(rec, defs, ctx, uses, term, should_count, meter)?;
+      let eq =
+        equal(defs, typ, &mut detected_typ, depth as u64, should_count, meter);
+      if free_on_err(eq, &detected_typ)? {
         detected_typ.free();
         Ok(())
       }
@@ -162,10 +207,11 @@ pub fn check_lam(
   pos: &Pos,
   bod: &Term,
   should_count: bool,
+  meter: &mut Meter,
 ) -> Result<(), CheckError> {
   // To check whether a lambda is well typed, its type must reduce to a forall;
   // otherwise we fail
-  typ.whnf(defs, should_count);
+  typ.whnf_metered(defs, should_count, meter)?;
   match typ.head {
     DAGPtr::All(all_link) => {
       // Extract the domain and image of the function and also the variable that
@@ -182,7 +228,7 @@ pub fn check_lam(
       let rest_ctx = div_ctx(uses, ctx);
       ctx.push((all_var.nam.to_string(), *lam_uses, dom));
       let mut img = DAG::new(*img);
-      check(rec, defs, ctx, Uses::Once, bod, &mut img, should_count)?;
+      check(rec, defs, ctx, Uses::Once, bod, &mut img, should_count, meter)?;
       // Check whether the rest 'contains' zero (i.e., zero is less than or
       // equal to the rest), otherwise the variable was not used enough
       let (_, rest, _) = ctx.last().unwrap();
@@ -228,10 +274,11 @@ pub fn check_dat(
   pos: &Pos,
   bod: &Term,
   should_count: bool,
+  meter: &mut Meter,
 ) -> Result<(), CheckError> {
   // To check whether data is well typed, its type must reduce to a self type;
   // otherwise we fail
-  typ.whnf(defs, should_count);
+  typ.whnf_metered(defs, should_count, meter)?;
   match typ.head {
     DAGPtr::Slf(slf_link) => {
       // Extract the body of the self type
@@ -256,7 +303,17 @@ pub fn check_dat(
       let root = alloc_val(DLL::singleton(ParentPtr::Root));
       let mut unrolled_typ =
         DAG::new(DAG::from_subdag(*slf_bod, &mut map, Some(root)));
-      check(rec, defs, ctx, uses, bod, &mut unrolled_typ, should_count)?;
+      let res = check(
+        rec,
+        defs,
+        ctx,
+        uses,
+        bod,
+        &mut unrolled_typ,
+        should_count,
+        meter,
+      );
+      free_on_err(res, &unrolled_typ)?;
       // We must free the newly created type as to not leak
       unrolled_typ.free();
       Ok(())
@@ -282,26 +339,49 @@ pub fn infer(
   uses: Uses,
   term: &Term,
   should_count: bool,
+  meter: &mut Meter,
 ) -> Result<DAG, CheckError> {
   match term {
     Term::Rec(_) => infer_rec(rec, defs),
     Term::Var(pos, nam, idx) => infer_var(rec, defs, ctx, uses, pos, nam, idx),
     Term::Ref(pos, nam, def_link, _) => infer_ref(defs, pos, nam, def_link),
-    Term::App(pos, fun_arg) => {
-      infer_app(rec, defs, ctx, uses, pos, &fun_arg.0, &fun_arg.1, should_count)
-    }
+    Term::App(pos, fun_arg) => infer_app(
+      rec,
+      defs,
+      ctx,
+      uses,
+      pos,
+      &fun_arg.0,
+      &fun_arg.1,
+      should_count,
+      meter,
+    ),
     Term::Cse(pos, exp) => {
-      infer_cse(rec, defs, ctx, uses, pos, exp, should_count)
-    }
-    Term::All(_, _, nam, dom_img) => {
-      infer_all(rec, defs, ctx, nam, &dom_img.0, &dom_img.1, should_count)
+      infer_cse(rec, defs, ctx, uses, pos, exp, should_count, meter)
     }
+    Term::All(_, _, nam, dom_img) => infer_all(
+      rec,
+      defs,
+      ctx,
+      nam,
+      &dom_img.0,
+      &dom_img.1,
+      should_count,
+      meter,
+    ),
     Term::Slf(_, nam, bod) => {
-      infer_slf(rec, defs, ctx, term, nam, bod, should_count)
-    }
-    Term::Ann(_, typ_exp) => {
-      infer_ann(rec, defs, ctx, uses, &typ_exp.0, &typ_exp.1, should_count)
+      infer_slf(rec, defs, ctx, term, nam, bod, should_count, meter)
     }
+    Term::Ann(_, typ_exp) => infer_ann(
+      rec,
+      defs,
+      ctx,
+      uses,
+      &typ_exp.0,
+      &typ_exp.1,
+      should_count,
+      meter,
+    ),
     Term::Let(pos, false, exp_uses, nam, triple) => infer_let(
       rec,
       defs,
@@ -314,6 +394,7 @@ pub fn infer(
       &triple.1,
       &triple.2,
       should_count,
+      meter,
     ),
     Term::Let(pos, true, exp_uses, nam, triple) => infer_letrec(
       rec,
@@ -327,6 +408,7 @@ pub fn infer(
       &triple.1,
       &triple.2,
       should_count,
+      meter,
     ),
     Term::Typ(_) => {
       let typ = DAG::from_term(&Term::Typ(Pos::None));
@@ -429,14 +511,15 @@ pub fn infer_app(
   fun: &Term,
   arg: &Term,
   should_count: bool,
+  meter: &mut Meter,
 ) -> Result<DAG, CheckError> {
-  let mut fun_typ = infer(rec, defs, ctx, uses, fun, should_count)?;
-  fun_typ.whnf(defs, should_count);
+  let mut fun_typ = infer(rec, defs, ctx, uses, fun, should_count, meter)?;
+  free_on_err(fun_typ.whnf_metered(defs, should_count, meter), &fun_typ)?;
   match fun_typ.head {
     DAGPtr::All(link) => {
       let All { uses: lam_uses, dom, img, .. } = unsafe { &mut *link.as_ptr() };
       let Lam { var, bod: img, .. } = unsafe { &mut *img.as_ptr() };
-      check(
+      let res = check(
         rec,
         defs,
         ctx,
@@ -444,7 +527,9 @@ pub fn infer_app(
         arg,
         &mut DAG::new(*dom),
         should_count,
-      )?;
+        meter,
+      );
+      free_on_err(res, &fun_typ)?;
       let mut map = BTreeMap::new();
       if var.parents.is_some() {
         map.insert(
@@ -482,9 +567,10 @@ pub fn infer_cse(
   pos: &Pos,
   exp: &Term,
   should_count: bool,
+  meter: &mut Meter,
 ) -> Result<DAG, CheckError> {
-  let mut exp_typ = infer(rec, defs, ctx, uses, exp, should_count)?;
-  exp_typ.whnf(defs, should_count);
+  let mut exp_typ = infer(rec, defs, ctx, uses, exp, should_count, meter)?;
+  free_on_err(exp_typ.whnf_metered(defs, should_count, meter), &exp_typ)?;
   match exp_typ.head {
     DAGPtr::Slf(link) => {
       let Slf { var, bod, .. } = unsafe { &mut *link.as_ptr() };
@@ -544,9 +630,12 @@ pub fn infer_all(
   dom: &Term,
   img: &Term,
   should_count: bool,
+  meter: &mut Meter,
 ) -> Result<DAG, CheckError> {
   let mut typ = DAG::from_term(&Term::Typ(Pos::None));
-  check(rec, defs, ctx, Uses::None, dom, &mut typ, should_count)?;
+  let res =
+    check(rec, defs, ctx, Uses::None, dom, &mut typ, should_count, meter);
+  free_on_err(res, &typ)?;
   let mut dom_dag = DAG::from_term_inner(
     dom,
     ctx.len() as u64,
@@ -555,9 +644,11 @@ pub fn infer_all(
     rec.clone(),
   );
   ctx.push((nam.to_string(), Uses::None, &mut dom_dag));
-  check(rec, defs, ctx, Uses::None, img, &mut typ, should_count)?;
+  let res =
+    check(rec, defs, ctx, Uses::None, img, &mut typ, should_count, meter);
   ctx.pop();
   free_dead_node(dom_dag);
+  free_on_err(res, &typ)?;
   Ok(typ)
 }
 
@@ -571,6 +662,7 @@ pub fn infer_slf(
   nam: &Name,
   bod: &Term,
   should_count: bool,
+  meter: &mut Meter,
 ) -> Result<DAG, CheckError> {
   let mut typ = DAG::from_term(&Term::Typ(Pos::None));
   let mut term_dag = DAG::from_term_inner(
@@ -581,9 +673,11 @@ pub fn infer_slf(
     rec.clone(),
   );
   ctx.push((nam.to_string(), Uses::None, &mut term_dag));
-  check(rec, defs, ctx, Uses::None, bod, &mut typ, should_count)?;
+  let res =
+    check(rec, defs, ctx, Uses::None, bod, &mut typ, should_count, meter);
   ctx.pop();
   free_dead_node(term_dag);
+  free_on_err(res, &typ)?;
   Ok(typ)
 }
 
@@ -601,6 +695,7 @@ pub fn infer_let(
   exp: &Term,
   bod: &Term,
   should_count: bool,
+  meter: &mut Meter,
 ) -> Result<DAG, CheckError> {
   let exp_dag = &mut DAG::new(DAG::from_term_inner(
     exp,
@@ -617,10 +712,21 @@ pub fn infer_let(
     Some(root),
     rec.clone(),
   ));
-  check(rec, defs, ctx, exp_uses * uses, exp, exp_typ_dag, should_count)?;
+  let res = check(
+    rec,
+    defs,
+    ctx,
+    exp_uses * uses,
+    exp,
+    exp_typ_dag,
+    should_count,
+    meter,
+  );
+  free_on_err(free_on_err(res, exp_dag), exp_typ_dag)?;
   let rest_ctx = div_ctx(uses, ctx);
   ctx.push((nam.to_string(), exp_uses, &mut exp_typ_dag.head));
-  let mut bod_typ = infer(rec, defs, ctx, Uses::Once, bod, should_count)?;
+  let res = infer(rec, defs, ctx, Uses::Once, bod, should_count, meter);
+  let mut bod_typ = free_on_err(free_on_err(res, exp_dag), exp_typ_dag)?;
   let (_, rest, _) = ctx.last().unwrap();
   // Have to check whether the rest 'contains' zero (i.e., zero is less than or
   // equal to the rest), otherwise the variable was not used enough
@@ -656,6 +762,7 @@ pub fn infer_letrec(
   exp: &Term,
   bod: &Term,
   should_count: bool,
+  meter: &mut Meter,
 ) -> Result<DAG, CheckError> {
   unsafe {
     // Allocates exp as a DAG, must be rootless
@@ -682,13 +789,16 @@ pub fn infer_letrec(
     // Check exp, noting it is a recursive definition
     let rest_ctx = div_ctx(Uses::Many, ctx);
     ctx.push((nam.to_string(), Uses::Many, &mut exp_typ_dag.head));
-    check(rec, defs, ctx, Uses::Many, exp, exp_typ_dag, should_count)?; // TODO better error message
+    let res =
+      check(rec, defs, ctx, Uses::Many, exp, exp_typ_dag, should_count, meter);
+    free_on_err(res, exp_typ_dag)?; // TODO better error message
     ctx.pop();
     // Check bod
     add_ctx(ctx, rest_ctx);
     let rest_ctx = div_ctx(uses, ctx);
     ctx.push((nam.to_string(), exp_uses, &mut exp_typ_dag.head));
-    let mut bod_typ = infer(rec, defs, ctx, Uses::Once, bod, should_count)?;
+    let res = infer(rec, defs, ctx, Uses::Once, bod, should_count, meter);
+    let mut bod_typ = free_on_err(res, exp_typ_dag)?;
     let (_, rest, _) = ctx.last().unwrap();
     // Have to check whether the rest 'contains' zero (i.e., zero is less than
     // or equal to the rest), otherwise the variable was not used enough
@@ -721,6 +831,7 @@ pub fn infer_ann(
   exp: &Term,
   typ: &Term,
   should_count: bool,
+  meter: &mut Meter,
 ) -> Result<DAG, CheckError> {
   let root = alloc_val(DLL::singleton(ParentPtr::Root));
   let mut typ_dag = DAG::new(DAG::from_term_inner(
@@ -730,7 +841,8 @@ pub fn infer_ann(
     Some(root),
     rec.clone(),
   ));
-  check(rec, defs, ctx, uses, exp, &mut typ_dag, should_count)?;
+  let res = check(rec, defs, ctx, uses, exp, &mut typ_dag, should_count, meter);
+  free_on_err(res, &typ_dag)?;
   Ok(typ_dag)
 }
 
@@ -762,9 +874,20 @@ pub fn infer_term(
   defs: &Defs,
   term: &Term,
   should_count: bool,
+) -> Result<Term, CheckError> {
+  infer_term_metered(defs, term, should_count, &mut Meter::default())
+}
+
+/// Infers the type of a term, failing with `CheckError::Limit` once the
+/// inference exceeds the limits of the meter
+pub fn infer_term_metered(
+  defs: &Defs,
+  term: &Term,
+  should_count: bool,
+  meter: &mut Meter,
 ) -> Result<Term, CheckError> {
   let typ_dag =
-    infer(&None, defs, &mut vec![], Uses::Once, term, should_count)?;
+    infer(&None, defs, &mut vec![], Uses::Once, term, should_count, meter)?;
   let typ = DAG::to_term(&typ_dag, true);
   typ_dag.free();
   Ok(typ)
@@ -775,6 +898,19 @@ pub fn check_def(
   defs: Rc<Defs>,
   name: &str,
   should_count: bool,
+) -> Result<Term, CheckError> {
+  check_def_metered(defs, name, should_count, &mut Meter::default())
+}
+
+/// Typechecks a definition with bounded cost. Every reduction step, DAG
+/// allocation and term node hashed by `equal` is charged to the meter, and
+/// checking fails with `CheckError::Limit` once it exceeds the meter's limits,
+/// such as `max_gas`. The same inputs always consume the same gas.
+pub fn check_def_metered(
+  defs: Rc<Defs>,
+  name: &str,
+  should_count: bool,
+  meter: &mut Meter,
 ) -> Result<Term, CheckError> {
   let def = defs.get(&Name::from(name)).ok_or_else(|| {
     CheckError::UndefinedReference(Pos::None, name.to_owned())
@@ -784,7 +920,7 @@ pub fn check_def(
   let ast_cid = a.cid();
   let rec = Some((Name::from(name), def_cid, ast_cid));
   let mut typ = DAG::from_term(&def.typ_);
-  check(
+  let res = check(
     &rec,
     &defs,
     &mut vec![],
@@ -792,7 +928,90 @@ pub fn check_def(
     &def.term,
     &mut typ,
     should_count,
-  )?;
+    meter,
+  );
+  free_on_err(res, &typ)?;
   typ.free();
   Ok(def.typ_.clone())
 }
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+  use crate::{
+    eval::test::parse_defs,
+    limits::{
+      Limits,
+      STEP_GAS,
+    },
+  };
+
+  #[test]
+  fn test_check_def_metered() {
+    let (_, defs) = parse_defs(
+      "def id : ∀ (A: Type) (x: A) -> A = λ A x => x
+       def two : #Nat = id #Nat 2",
+    )
+    .unwrap();
+    let defs = Rc::new(defs);
+    let gas = |max_gas| {
+      let mut meter = Meter::new(Limits { max_gas, ..Limits::default() });
+      let res = check_def_metered(defs.clone(), "two", false, &mut meter);
+      (res.map_err(|e| format!("{}", e)), meter.gas())
+    };
+    let (res, used) = gas(None);
+    assert!(res.is_ok());
+    assert!(used > 0);
+    // The same inputs always spend the same gas
+    assert_eq!(gas(None).1, used);
+    assert_eq!(gas(Some(used)).0, res);
+    assert_eq!(
+      gas(Some(STEP_GAS)).0,
+      Err(format!(
+        "Typechecking aborted: Exceeded the limit of {} gas",
+        STEP_GAS
+      ))
+    );
+  }
+
+  #[test]
+  fn test_equal_gas() {
+    let (_, defs) = parse_defs(
+      "def f : ∀ (x: #Nat) -> #Nat = λ x => x
+       def g : ∀ (x: ∀ (y: #Nat) -> #Nat) -> ∀ (y: #Nat) -> #Nat = λ x => x",
+    )
+    .unwrap();
+    let defs = Rc::new(defs);
+    let hashed = |name| {
+      let mut meter = Meter::default();
+      check_def_metered(defs.clone(), name, false, &mut meter).unwrap();
+      meter.equal
+    };
+    // Comparing larger types costs more
+    assert!(hashed("f") > 0);
+    assert!(hashed("g") > hashed("f"));
+  }
+
+  #[test]
+  fn test_check_limit_frees() {
+    let (_, defs) = parse_defs(
+      "def id : ∀ (A: Type) (x: A) -> A = λ A x => x
+       def two : #Nat = id #Nat 2",
+    )
+    .unwrap();
+    let defs = Rc::new(defs);
+    let run = |max_gas| {
+      let mut meter = Meter::new(Limits { max_gas, ..Limits::default() });
+      let res = check_def_metered(defs.clone(), "two", false, &mut meter);
+      (res.is_ok(), meter.gas(), meter.live_nodes())
+    };
+    let (ok, used, live) = run(None);
+    assert!(ok);
+    // Aborting at any point leaves no more nodes live than finishing
+    for max_gas in 0..used {
+      let (ok, _, aborted_live) = run(Some(max_gas));
+      assert!(!ok);
+      assert!(aborted_live <= live);
+    }
+  }
+}
@@ -7,6 +7,7 @@ use alloc::string::String;
 
 use crate::{
   check::ctx::*,
+  limits::LimitError,
   literal::LitType,
   position::Pos,
   term::Term,
@@ -29,6 +30,11 @@ pub enum CheckError {
   CseDatMismatch(Pos, ErrCtx, Term, Term),
   NonInductiveLitType(Pos, ErrCtx, LitType),
   GenericError(Pos, ErrCtx, String),
+  Limit(LimitError),
+}
+
+impl From<LimitError> for CheckError {
+  fn from(err: LimitError) -> Self { CheckError::Limit(err) }
 }
 
 impl CheckError {
@@ -220,6 +226,7 @@ impl fmt::Display for CheckError {
         }
         Ok(())
       }
+      CheckError::Limit(err) => write!(f, "Typechecking aborted: {}", err),
     }
   }
 }
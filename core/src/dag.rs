@@ -3,6 +3,10 @@
 use crate::{
   defs::Def,
   dll::*,
+  limits::{
+    count_alloc,
+    count_free,
+  },
   literal::{
     LitType,
     Literal,
@@ -222,9 +226,20 @@ pub struct Opr {
 /// Auxiliary allocation functions
 #[inline]
 pub fn alloc_val<T>(val: T) -> NonNull<T> {
+  count_alloc();
   NonNull::new(Box::leak(Box::new(val))).unwrap()
 }
 
+/// Frees a value allocated by `alloc_val`, returning it
+///
+/// # Safety
+/// The value must not be used through `link` again
+#[inline]
+pub unsafe fn free_val<T>(link: NonNull<T>) -> T {
+  count_free();
+  *Box::from_raw(link.as_ptr())
+}
+
 #[inline]
 pub fn alloc_lam(
   var_nam: Name,
@@ -530,145 +545,159 @@ pub fn add_to_parents(node: DAGPtr, plink: NonNull<Parents>) {
   }
 }
 
-/// Free parentless nodes.
+/// Free parentless nodes, using a work list rather than recursion.
 pub fn free_dead_node(node: DAGPtr) {
-  unsafe {
-    match node {
-      DAGPtr::Lam(link) => {
-        let Lam { bod, bod_ref, .. } = &link.as_ref();
-        let new_bod_parents = bod_ref.unlink_node();
-        set_parents(*bod, new_bod_parents);
-        if new_bod_parents.is_none() {
-          free_dead_node(*bod)
+  let mut dead = vec![node];
+  while let Some(node) = dead.pop() {
+    unsafe {
+      match node {
+        DAGPtr::Lam(link) => {
+          let Lam { bod, bod_ref, .. } = &link.as_ref();
+          let new_bod_parents = bod_ref.unlink_node();
+          set_parents(*bod, new_bod_parents);
+          if new_bod_parents.is_none() {
+            dead.push(*bod)
+          }
+          free_val(link);
         }
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::Slf(mut link) => {
-        let Slf { bod, bod_ref, .. } = &link.as_mut();
-        let new_bod_parents = bod_ref.unlink_node();
-        set_parents(*bod, new_bod_parents);
-        if new_bod_parents.is_none() {
-          free_dead_node(*bod)
+        DAGPtr::Slf(mut link) => {
+          let Slf { bod, bod_ref, .. } = &link.as_mut();
+          let new_bod_parents = bod_ref.unlink_node();
+          set_parents(*bod, new_bod_parents);
+          if new_bod_parents.is_none() {
+            dead.push(*bod)
+          }
+          free_val(link);
         }
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::Fix(mut link) => {
-        let Fix { bod, bod_ref, .. } = &link.as_mut();
-        let new_bod_parents = bod_ref.unlink_node();
-        set_parents(*bod, new_bod_parents);
-        if new_bod_parents.is_none() {
-          free_dead_node(*bod)
+        DAGPtr::Fix(mut link) => {
+          let Fix { bod, bod_ref, .. } = &link.as_mut();
+          let new_bod_parents = bod_ref.unlink_node();
+          set_parents(*bod, new_bod_parents);
+          if new_bod_parents.is_none() {
+            dead.push(*bod)
+          }
+          free_val(link);
         }
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::Cse(link) => {
-        let Cse { bod, bod_ref, .. } = link.as_ref();
-        let new_bod_parents = bod_ref.unlink_node();
-        set_parents(*bod, new_bod_parents);
-        if new_bod_parents.is_none() {
-          free_dead_node(*bod)
+        DAGPtr::Cse(link) => {
+          let Cse { bod, bod_ref, .. } = link.as_ref();
+          let new_bod_parents = bod_ref.unlink_node();
+          set_parents(*bod, new_bod_parents);
+          if new_bod_parents.is_none() {
+            dead.push(*bod)
+          }
+          free_val(link);
         }
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::Dat(link) => {
-        let Dat { bod, bod_ref, .. } = &link.as_ref();
-        let new_bod_parents = bod_ref.unlink_node();
-        set_parents(*bod, new_bod_parents);
-        if new_bod_parents.is_none() {
-          free_dead_node(*bod)
+        DAGPtr::Dat(link) => {
+          let Dat { bod, bod_ref, .. } = &link.as_ref();
+          let new_bod_parents = bod_ref.unlink_node();
+          set_parents(*bod, new_bod_parents);
+          if new_bod_parents.is_none() {
+            dead.push(*bod)
+          }
+          free_val(link);
         }
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::All(link) => {
-        let All { dom, img, dom_ref, img_ref, .. } = link.as_ref();
-        let img = DAGPtr::Lam(*img);
-        let new_dom_parents = dom_ref.unlink_node();
-        set_parents(*dom, new_dom_parents);
-        if new_dom_parents.is_none() {
-          free_dead_node(*dom)
+        DAGPtr::All(link) => {
+          let All { dom, img, dom_ref, img_ref, .. } = link.as_ref();
+          let img = DAGPtr::Lam(*img);
+          let new_dom_parents = dom_ref.unlink_node();
+          set_parents(*dom, new_dom_parents);
+          if new_dom_parents.is_none() {
+            dead.push(*dom)
+          }
+          let new_img_parents = img_ref.unlink_node();
+          set_parents(img, new_img_parents);
+          if new_img_parents.is_none() {
+            dead.push(img)
+          }
+          free_val(link);
         }
-        let new_img_parents = img_ref.unlink_node();
-        set_parents(img, new_img_parents);
-        if new_img_parents.is_none() {
-          free_dead_node(img)
+        DAGPtr::App(link) => {
+          let App { fun, arg, fun_ref, arg_ref, .. } = link.as_ref();
+          let new_fun_parents = fun_ref.unlink_node();
+          set_parents(*fun, new_fun_parents);
+          if new_fun_parents.is_none() {
+            dead.push(*fun)
+          }
+          let new_arg_parents = arg_ref.unlink_node();
+          set_parents(*arg, new_arg_parents);
+          if new_arg_parents.is_none() {
+            dead.push(*arg)
+          }
+          free_val(link);
         }
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::App(link) => {
-        let App { fun, arg, fun_ref, arg_ref, .. } = link.as_ref();
-        let new_fun_parents = fun_ref.unlink_node();
-        set_parents(*fun, new_fun_parents);
-        if new_fun_parents.is_none() {
-          free_dead_node(*fun)
+        DAGPtr::Ann(link) => {
+          let Ann { exp, typ, exp_ref, typ_ref, .. } = link.as_ref();
+          let new_exp_parents = exp_ref.unlink_node();
+          set_parents(*exp, new_exp_parents);
+          if new_exp_parents.is_none() {
+            dead.push(*exp)
+          }
+          let new_typ_parents = typ_ref.unlink_node();
+          set_parents(*typ, new_typ_parents);
+          if new_typ_parents.is_none() {
+            dead.push(*typ)
+          }
+          free_val(link);
         }
-        let new_arg_parents = arg_ref.unlink_node();
-        set_parents(*arg, new_arg_parents);
-        if new_arg_parents.is_none() {
-          free_dead_node(*arg)
+        DAGPtr::Let(link) => {
+          let Let { exp, typ, exp_ref, typ_ref, bod, bod_ref, .. } =
+            link.as_ref();
+          let bod = DAGPtr::Lam(*bod);
+          let new_exp_parents = exp_ref.unlink_node();
+          set_parents(*exp, new_exp_parents);
+          if new_exp_parents.is_none() {
+            dead.push(*exp)
+          }
+          let new_typ_parents = typ_ref.unlink_node();
+          set_parents(*typ, new_typ_parents);
+          if new_typ_parents.is_none() {
+            dead.push(*typ)
+          }
+          let new_bod_parents = bod_ref.unlink_node();
+          set_parents(bod, new_bod_parents);
+          if new_bod_parents.is_none() {
+            dead.push(bod)
+          }
+          free_val(link);
         }
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::Ann(link) => {
-        let Ann { exp, typ, exp_ref, typ_ref, .. } = link.as_ref();
-        let new_exp_parents = exp_ref.unlink_node();
-        set_parents(*exp, new_exp_parents);
-        if new_exp_parents.is_none() {
-          free_dead_node(*exp)
+        DAGPtr::Var(link) => {
+          let Var { binder, .. } = link.as_ref();
+          // only free Free variables, bound variables are freed with their
+          // binder
+          if let BinderPtr::Free = binder {
+            free_val(link);
+          }
         }
-        let new_typ_parents = typ_ref.unlink_node();
-        set_parents(*typ, new_typ_parents);
-        if new_typ_parents.is_none() {
-          free_dead_node(*typ)
+        DAGPtr::Ref(link) => {
+          free_val(link);
         }
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::Let(link) => {
-        let Let { exp, typ, exp_ref, typ_ref, bod, bod_ref, .. } =
-          link.as_ref();
-        let bod = DAGPtr::Lam(*bod);
-        let new_exp_parents = exp_ref.unlink_node();
-        set_parents(*exp, new_exp_parents);
-        if new_exp_parents.is_none() {
-          free_dead_node(*exp)
+        DAGPtr::Typ(link) => {
+          free_val(link);
         }
-        let new_typ_parents = typ_ref.unlink_node();
-        set_parents(*typ, new_typ_parents);
-        if new_typ_parents.is_none() {
-          free_dead_node(*typ)
+        DAGPtr::Lit(link) => {
+          free_val(link);
         }
-        let new_bod_parents = bod_ref.unlink_node();
-        set_parents(bod, new_bod_parents);
-        if new_bod_parents.is_none() {
-          free_dead_node(bod)
+        DAGPtr::LTy(link) => {
+          free_val(link);
         }
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::Var(link) => {
-        let Var { binder, .. } = link.as_ref();
-        // only free Free variables, bound variables are freed with their binder
-        if let BinderPtr::Free = binder {
-          Box::from_raw(link.as_ptr());
+        DAGPtr::Opr(link) => {
+          free_val(link);
         }
       }
-      DAGPtr::Ref(link) => {
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::Typ(link) => {
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::Lit(link) => {
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::LTy(link) => {
-        Box::from_raw(link.as_ptr());
-      }
-      DAGPtr::Opr(link) => {
-        Box::from_raw(link.as_ptr());
-      }
     }
   }
 }
 
+/// A subterm waiting to be converted by `DAG::from_term_inner`, with the
+/// field its node is written to
+struct FromTerm<'a> {
+  tree: &'a Term,
+  depth: u64,
+  ctx: BTreeMap<usize, DAGPtr>,
+  parents: Option<NonNull<Parents>>,
+  slot: *mut DAGPtr,
+}
+
 impl DAG {
   /// Constructs a new DAG from an existing pointer
   pub fn new(head: DAGPtr) -> DAG { DAG { head } }
@@ -678,7 +707,7 @@ impl DAG {
     match get_parents(self.head) {
       None => (),
       Some(pref) => unsafe {
-        Box::from_raw(pref.as_ptr());
+        free_val(pref);
         set_parents(self.head, None);
       },
     }
@@ -873,267 +902,269 @@ impl DAG {
     )
   }
 
-  /// Converts a Term into its DAG-node equivalent
+  /// Converts a Term into its DAG-node equivalent. Subterms are converted
+  /// from an explicit work stack, in the same order as a recursive traversal,
+  /// so deep terms cannot overflow the native stack.
   pub fn from_term_inner(
     tree: &Term,
     depth: u64,
-    mut ctx: BTreeMap<usize, DAGPtr>,
+    ctx: BTreeMap<usize, DAGPtr>,
     parents: Option<NonNull<Parents>>,
     rec_ref: Option<(Name, Cid, Cid)>,
   ) -> DAGPtr {
-    match tree {
-      Term::Rec(_) => match rec_ref {
-        Some((nam, exp, ast)) => {
-          let ref_ = alloc_val(Ref { nam, rec: true, exp, ast, parents });
-          DAGPtr::Ref(ref_)
-        }
-        None => {
-          let var = alloc_val(Var {
-            nam: Name::from("#^"),
-            rec: true,
-            dep: depth,
-            binder: BinderPtr::Free,
-            parents,
-          });
-          DAGPtr::Var(var)
-        }
-      },
-      Term::Var(_, name, idx) => {
-        let dep = (depth - 1 - *idx) as usize;
-        match ctx.get(&dep) {
-          Some(val) => {
-            if let Some(parents) = parents {
-              DLL::concat(parents, get_parents(*val));
-              set_parents(*val, Some(parents));
-            }
-            *val
+    let mut root: DAGPtr = unsafe { mem::zeroed() };
+    let mut work =
+      vec![FromTerm { tree, depth, ctx, parents, slot: &mut root }];
+    while let Some(FromTerm { tree, depth, mut ctx, parents, slot }) =
+      work.pop()
+    {
+      let node = match tree {
+        Term::Rec(_) => match rec_ref.clone() {
+          Some((nam, exp, ast)) => {
+            let ref_ = alloc_val(Ref { nam, rec: true, exp, ast, parents });
+            DAGPtr::Ref(ref_)
           }
           None => {
-            if depth < 1 + idx {
-              panic!("Negative index found.")
-            }
             let var = alloc_val(Var {
-              nam: name.clone(),
-              rec: false,
-              dep: depth - 1 - idx,
+              nam: Name::from("#^"),
+              rec: true,
+              dep: depth,
               binder: BinderPtr::Free,
               parents,
             });
             DAGPtr::Var(var)
           }
+        },
+        Term::Var(_, name, idx) => {
+          let dep = (depth - 1 - *idx) as usize;
+          match ctx.get(&dep) {
+            Some(val) => {
+              if let Some(parents) = parents {
+                DLL::concat(parents, get_parents(*val));
+                set_parents(*val, Some(parents));
+              }
+              *val
+            }
+            None => {
+              if depth < 1 + idx {
+                panic!("Negative index found.")
+              }
+              let var = alloc_val(Var {
+                nam: name.clone(),
+                rec: false,
+                dep: depth - 1 - idx,
+                binder: BinderPtr::Free,
+                parents,
+              });
+              DAGPtr::Var(var)
+            }
+          }
         }
-      }
-      Term::Typ(_) => DAGPtr::Typ(alloc_val(Typ { parents })),
-      Term::LTy(_, lty) => DAGPtr::LTy(alloc_val(LTy { lty: *lty, parents })),
-      Term::Lit(_, lit) => {
-        DAGPtr::Lit(alloc_val(Lit { lit: lit.clone(), parents }))
-      }
-      Term::Opr(_, opr) => DAGPtr::Opr(alloc_val(Opr { opr: opr.clone(), parents })),
-      Term::Ref(_, nam, exp, ast) => DAGPtr::Ref(alloc_val(Ref {
-        nam: nam.clone(),
-        rec: false,
-        exp: *exp,
-        ast: *ast,
-        parents,
-      })),
-      Term::Lam(_, nam, bod) => unsafe {
-        let lam = alloc_lam(nam.clone(), 0, mem::zeroed(), parents);
-        let Lam { var, bod_ref, .. } = &mut *lam.as_ptr();
-        ctx.insert(depth as usize, DAGPtr::Var(NonNull::new(var).unwrap()));
-        let bod = DAG::from_term_inner(
-          &**bod,
-          depth + 1,
-          ctx,
-          NonNull::new(bod_ref),
-          rec_ref.clone(),
-        );
-        (*lam.as_ptr()).bod = bod;
-        DAGPtr::Lam(lam)
-      },
-      Term::Slf(_, nam, bod) => unsafe {
-        let slf = alloc_slf(nam.clone(), 0, mem::zeroed(), parents);
-        let Slf { var, bod_ref, .. } = &mut *slf.as_ptr();
-        ctx.insert(depth as usize, DAGPtr::Var(NonNull::new(var).unwrap()));
-        let bod = DAG::from_term_inner(
-          &**bod,
-          depth + 1,
-          ctx,
-          NonNull::new(bod_ref),
-          rec_ref.clone(),
-        );
-        (*slf.as_ptr()).bod = bod;
-        DAGPtr::Slf(slf)
-      },
-      Term::Dat(_, bod) => unsafe {
-        let dat = alloc_dat(mem::zeroed(), parents);
-        let Dat { bod_ref, .. } = &mut *dat.as_ptr();
-        let bod = DAG::from_term_inner(
-          &**bod,
-          depth,
-          ctx,
-          NonNull::new(bod_ref),
-          rec_ref.clone(),
-        );
-        (*dat.as_ptr()).bod = bod;
-        DAGPtr::Dat(dat)
-      },
-      Term::Cse(_, bod) => unsafe {
-        let cse = alloc_cse(mem::zeroed(), parents);
-        let Cse { bod_ref, .. } = &mut *cse.as_ptr();
-        let bod = DAG::from_term_inner(
-          &**bod,
-          depth,
-          ctx,
-          NonNull::new(bod_ref),
-          rec_ref.clone(),
-        );
-        (*cse.as_ptr()).bod = bod;
-        DAGPtr::Cse(cse)
-      },
-      Term::All(_, uses, nam, dom_img) => unsafe {
-        let (dom, img) = &**dom_img;
-        let all = alloc_all(*uses, mem::zeroed(), NonNull::dangling(), parents);
-        let All { dom_ref, img_ref, .. } = &mut *all.as_ptr();
-        let lam =
-          alloc_lam(nam.clone(), 0, mem::zeroed(), NonNull::new(img_ref));
-        let Lam { var, bod_ref, .. } = &mut *lam.as_ptr();
-        let mut img_ctx = ctx.clone();
-        let dom = DAG::from_term_inner(
-          dom,
-          depth,
-          ctx,
-          NonNull::new(dom_ref),
-          rec_ref.clone(),
-        );
-        img_ctx.insert(depth as usize, DAGPtr::Var(NonNull::new(var).unwrap()));
-        let img = DAG::from_term_inner(
-          img,
-          depth + 1,
-          img_ctx,
-          NonNull::new(bod_ref),
-          rec_ref.clone(),
-        );
-        (*all.as_ptr()).dom = dom;
-        (*all.as_ptr()).img = lam;
-        (*lam.as_ptr()).bod = img;
-        DAGPtr::All(all)
-      },
-      Term::App(_, fun_arg) => unsafe {
-        let (fun, arg) = &**fun_arg;
-        let app = alloc_app(mem::zeroed(), mem::zeroed(), parents);
-        let App { fun_ref, arg_ref, .. } = &mut *app.as_ptr();
-        let fun = DAG::from_term_inner(
-          fun,
-          depth,
-          ctx.clone(),
-          NonNull::new(fun_ref),
-          rec_ref.clone(),
-        );
-        let arg = DAG::from_term_inner(
-          arg,
-          depth,
-          ctx,
-          NonNull::new(arg_ref),
-          rec_ref.clone(),
-        );
-        (*app.as_ptr()).fun = fun;
-        (*app.as_ptr()).arg = arg;
-        DAGPtr::App(app)
-      },
-      Term::Ann(_, typ_exp) => unsafe {
-        let (typ, exp) = &**typ_exp;
-        let ann = alloc_ann(mem::zeroed(), mem::zeroed(), parents);
-        let Ann { typ_ref, exp_ref, .. } = &mut *ann.as_ptr();
-        let typ = DAG::from_term_inner(
-          typ,
-          depth,
-          ctx.clone(),
-          NonNull::new(typ_ref),
-          rec_ref.clone(),
-        );
-        let exp = DAG::from_term_inner(
-          exp,
-          depth,
-          ctx,
-          NonNull::new(exp_ref),
-          rec_ref.clone(),
-        );
-        (*ann.as_ptr()).typ = typ;
-        (*ann.as_ptr()).exp = exp;
-        DAGPtr::Ann(ann)
-      },
-      Term::Let(_, rec, uses, nam, typ_exp_bod) => unsafe {
-        let (typ, exp, bod) = &**typ_exp_bod;
-        // Allocates the `Let` node and a `Lam` node for `bod`
-        let let_ = alloc_let(
-          *uses,
-          mem::zeroed(),
-          mem::zeroed(),
-          NonNull::dangling(),
+        Term::Typ(_) => DAGPtr::Typ(alloc_val(Typ { parents })),
+        Term::LTy(_, lty) => DAGPtr::LTy(alloc_val(LTy { lty: *lty, parents })),
+        Term::Lit(_, lit) => {
+          DAGPtr::Lit(alloc_val(Lit { lit: lit.clone(), parents }))
+        }
+        Term::Opr(_, opr) => {
+          DAGPtr::Opr(alloc_val(Opr { opr: opr.clone(), parents }))
+        }
+        Term::Ref(_, nam, exp, ast) => DAGPtr::Ref(alloc_val(Ref {
+          nam: nam.clone(),
+          rec: false,
+          exp: *exp,
+          ast: *ast,
           parents,
-        );
-        let Let { typ_ref, exp_ref, bod_ref, .. } = &mut *let_.as_ptr();
-        let lam =
-          alloc_lam(nam.clone(), 0, mem::zeroed(), NonNull::new(bod_ref));
-        let Lam { var: lam_var, bod_ref: lam_bod_ref, .. } = &mut *lam.as_ptr();
-        // Sets up the context for `typ` and `bod` conversion
-        let typ_ctx = ctx.clone();
-        let mut bod_ctx = ctx.clone();
-        bod_ctx
-          .insert(depth as usize, DAGPtr::Var(NonNull::new(lam_var).unwrap()));
-        // Convert `typ` and `bod` to DAG and add it to the newly created `Let`
-        // node
-        let typ = DAG::from_term_inner(
-          typ,
-          depth,
-          typ_ctx,
-          NonNull::new(typ_ref),
-          rec_ref.clone(),
-        );
-        let bod = DAG::from_term_inner(
-          bod,
-          depth + 1,
-          bod_ctx,
-          NonNull::new(lam_bod_ref),
-          rec_ref.clone(),
-        );
-        (*let_.as_ptr()).typ = typ;
-        (*let_.as_ptr()).bod = lam;
-        (*lam.as_ptr()).bod = bod;
-        // Do the same for `exp`, but set a `Fix` before if this is a recursive
-        // `Let`
-        if *rec {
-          let fix =
-            alloc_fix(nam.clone(), 0, mem::zeroed(), NonNull::new(exp_ref));
-          let Fix { var: fix_var, bod_ref: fix_bod_ref, .. } =
-            &mut *fix.as_ptr();
-          ctx.insert(
-            depth as usize,
-            DAGPtr::Var(NonNull::new(fix_var).unwrap()),
-          );
-          let exp = DAG::from_term_inner(
-            exp,
-            depth + 1,
+        })),
+        Term::Lam(_, nam, bod) => unsafe {
+          let lam = alloc_lam(nam.clone(), 0, mem::zeroed(), parents);
+          let Lam { var, bod_ref, .. } = &mut *lam.as_ptr();
+          ctx.insert(depth as usize, DAGPtr::Var(NonNull::new(var).unwrap()));
+          work.push(FromTerm {
+            tree: &**bod,
+            depth: depth + 1,
             ctx,
-            NonNull::new(fix_bod_ref),
-            rec_ref.clone(),
-          );
-          (*let_.as_ptr()).exp = DAGPtr::Fix(fix);
-          (*fix.as_ptr()).bod = exp;
-        }
-        else {
-          let exp = DAG::from_term_inner(
-            exp,
+            parents: NonNull::new(bod_ref),
+            slot: &mut (*lam.as_ptr()).bod,
+          });
+          DAGPtr::Lam(lam)
+        },
+        Term::Slf(_, nam, bod) => unsafe {
+          let slf = alloc_slf(nam.clone(), 0, mem::zeroed(), parents);
+          let Slf { var, bod_ref, .. } = &mut *slf.as_ptr();
+          ctx.insert(depth as usize, DAGPtr::Var(NonNull::new(var).unwrap()));
+          work.push(FromTerm {
+            tree: &**bod,
+            depth: depth + 1,
+            ctx,
+            parents: NonNull::new(bod_ref),
+            slot: &mut (*slf.as_ptr()).bod,
+          });
+          DAGPtr::Slf(slf)
+        },
+        Term::Dat(_, bod) => unsafe {
+          let dat = alloc_dat(mem::zeroed(), parents);
+          let Dat { bod_ref, .. } = &mut *dat.as_ptr();
+          work.push(FromTerm {
+            tree: &**bod,
+            depth,
+            ctx,
+            parents: NonNull::new(bod_ref),
+            slot: &mut (*dat.as_ptr()).bod,
+          });
+          DAGPtr::Dat(dat)
+        },
+        Term::Cse(_, bod) => unsafe {
+          let cse = alloc_cse(mem::zeroed(), parents);
+          let Cse { bod_ref, .. } = &mut *cse.as_ptr();
+          work.push(FromTerm {
+            tree: &**bod,
+            depth,
+            ctx,
+            parents: NonNull::new(bod_ref),
+            slot: &mut (*cse.as_ptr()).bod,
+          });
+          DAGPtr::Cse(cse)
+        },
+        Term::All(_, uses, nam, dom_img) => unsafe {
+          let (dom, img) = &**dom_img;
+          let all =
+            alloc_all(*uses, mem::zeroed(), NonNull::dangling(), parents);
+          let All { dom_ref, img_ref, .. } = &mut *all.as_ptr();
+          let lam =
+            alloc_lam(nam.clone(), 0, mem::zeroed(), NonNull::new(img_ref));
+          let Lam { var, bod_ref, .. } = &mut *lam.as_ptr();
+          (*all.as_ptr()).img = lam;
+          let mut img_ctx = ctx.clone();
+          img_ctx
+            .insert(depth as usize, DAGPtr::Var(NonNull::new(var).unwrap()));
+          work.push(FromTerm {
+            tree: img,
+            depth: depth + 1,
+            ctx: img_ctx,
+            parents: NonNull::new(bod_ref),
+            slot: &mut (*lam.as_ptr()).bod,
+          });
+          work.push(FromTerm {
+            tree: dom,
+            depth,
+            ctx,
+            parents: NonNull::new(dom_ref),
+            slot: &mut (*all.as_ptr()).dom,
+          });
+          DAGPtr::All(all)
+        },
+        Term::App(_, fun_arg) => unsafe {
+          let (fun, arg) = &**fun_arg;
+          let app = alloc_app(mem::zeroed(), mem::zeroed(), parents);
+          let App { fun_ref, arg_ref, .. } = &mut *app.as_ptr();
+          work.push(FromTerm {
+            tree: arg,
+            depth,
+            ctx: ctx.clone(),
+            parents: NonNull::new(arg_ref),
+            slot: &mut (*app.as_ptr()).arg,
+          });
+          work.push(FromTerm {
+            tree: fun,
+            depth,
+            ctx,
+            parents: NonNull::new(fun_ref),
+            slot: &mut (*app.as_ptr()).fun,
+          });
+          DAGPtr::App(app)
+        },
+        Term::Ann(_, typ_exp) => unsafe {
+          let (typ, exp) = &**typ_exp;
+          let ann = alloc_ann(mem::zeroed(), mem::zeroed(), parents);
+          let Ann { typ_ref, exp_ref, .. } = &mut *ann.as_ptr();
+          work.push(FromTerm {
+            tree: exp,
+            depth,
+            ctx: ctx.clone(),
+            parents: NonNull::new(exp_ref),
+            slot: &mut (*ann.as_ptr()).exp,
+          });
+          work.push(FromTerm {
+            tree: typ,
             depth,
             ctx,
-            NonNull::new(exp_ref),
-            rec_ref.clone(),
+            parents: NonNull::new(typ_ref),
+            slot: &mut (*ann.as_ptr()).typ,
+          });
+          DAGPtr::Ann(ann)
+        },
+        Term::Let(_, rec, uses, nam, typ_exp_bod) => unsafe {
+          let (typ, exp, bod) = &**typ_exp_bod;
+          // Allocates the `Let` node and a `Lam` node for `bod`
+          let let_ = alloc_let(
+            *uses,
+            mem::zeroed(),
+            mem::zeroed(),
+            NonNull::dangling(),
+            parents,
           );
-          (*let_.as_ptr()).exp = exp;
-        }
-        DAGPtr::Let(let_)
-      },
+          let Let { typ_ref, exp_ref, bod_ref, .. } = &mut *let_.as_ptr();
+          let lam =
+            alloc_lam(nam.clone(), 0, mem::zeroed(), NonNull::new(bod_ref));
+          let Lam { var: lam_var, bod_ref: lam_bod_ref, .. } =
+            &mut *lam.as_ptr();
+          (*let_.as_ptr()).bod = lam;
+          // Sets up the context for `typ` and `bod` conversion
+          let typ_ctx = ctx.clone();
+          let mut bod_ctx = ctx.clone();
+          bod_ctx.insert(
+            depth as usize,
+            DAGPtr::Var(NonNull::new(lam_var).unwrap()),
+          );
+          // Converts `exp` last, with a `Fix` before it if this is a recursive
+          // `Let`
+          if *rec {
+            let fix =
+              alloc_fix(nam.clone(), 0, mem::zeroed(), NonNull::new(exp_ref));
+            let Fix { var: fix_var, bod_ref: fix_bod_ref, .. } =
+              &mut *fix.as_ptr();
+            ctx.insert(
+              depth as usize,
+              DAGPtr::Var(NonNull::new(fix_var).unwrap()),
+            );
+            (*let_.as_ptr()).exp = DAGPtr::Fix(fix);
+            work.push(FromTerm {
+              tree: exp,
+              depth: depth + 1,
+              ctx,
+              parents: NonNull::new(fix_bod_ref),
+              slot: &mut (*fix.as_ptr()).bod,
+            });
+          }
+          else {
+            work.push(FromTerm {
+              tree: exp,
+              depth,
+              ctx,
+              parents: NonNull::new(exp_ref),
+              slot: &mut (*let_.as_ptr()).exp,
+            });
+          }
+          work.push(FromTerm {
+            tree: bod,
+            depth: depth + 1,
+            ctx: bod_ctx,
+            parents: NonNull::new(lam_bod_ref),
+            slot: &mut (*lam.as_ptr()).bod,
+          });
+          work.push(FromTerm {
+            tree: typ,
+            depth,
+            ctx: typ_ctx,
+            parents: NonNull::new(typ_ref),
+            slot: &mut (*let_.as_ptr()).typ,
+          });
+          DAGPtr::Let(let_)
+        },
+      };
+      unsafe { *slot = node };
     }
+    root
   }
 
   /// Creates a new DAG from a subsection
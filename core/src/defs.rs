@@ -8,6 +8,7 @@ use crate::{
     Import,
   },
   position::Pos,
+  prim::Op,
   term::Term,
 };
 
@@ -102,12 +103,20 @@ impl Def {
 
   /// Formats the def for pretty-printing
   pub fn pretty(&self, name: String, ind: bool) -> String {
-    format!(
-      "def {} : {} = {}",
-      name,
-      self.typ_.pretty(Some(&name), ind),
-      self.term.pretty(Some(&name), ind)
-    )
+    match &self.term {
+      Term::Opr(_, Op::Extern(op)) => format!(
+        "extern {} : {} = {}",
+        name,
+        self.typ_.pretty(Some(&name), ind),
+        op.symbol()
+      ),
+      _ => format!(
+        "def {} : {} = {}",
+        name,
+        self.typ_.pretty(Some(&name), ind),
+        self.term.pretty(Some(&name), ind)
+      ),
+    }
   }
 }
 
@@ -4,6 +4,12 @@ use crate::{
   dag::*,
   defs::Defs,
   dll::*,
+  limits::{
+    LimitError,
+    Meter,
+  },
+  literal::Literal,
+  prim::Op,
   upcopy::*,
 };
 
@@ -310,221 +316,284 @@ pub fn print_trail(trail: &Vec<NonNull<App>>) -> Vec<String> {
   res
 }
 
+/// A reduction suspended by `DAG::whnf` while it reduces a subterm whose weak
+/// head normal form it needs: the body of a `case` or an argument of a
+/// primitive operation
+struct Frame {
+  node: DAGPtr,
+  trail: Vec<NonNull<App>>,
+  args: Vec<Literal>,
+}
+
 impl DAG {
-  /// Reduces a DAG to its weak head normal form.
+  /// Reduces a DAG to its weak head normal form. Subterms that must be reduced
+  /// first are pushed on an explicit stack of suspended frames rather than
+  /// reduced recursively, so deep terms cannot overflow the native stack.
   pub fn whnf(&mut self, defs: &Defs, should_count: bool) {
+    // An unlimited meter never fails
+    let _ = self.whnf_metered(defs, should_count, &mut Meter::default());
+  }
+
+  /// Reduces a DAG to its weak head normal form, failing once the evaluation
+  /// exceeds the limits of the meter. The DAG is left partially reduced.
+  pub fn whnf_metered(
+    &mut self,
+    defs: &Defs,
+    should_count: bool,
+    meter: &mut Meter,
+  ) -> Result<(), LimitError> {
+    let mut stack: Vec<Frame> = vec![];
     let mut node = self.head;
     let mut trail: Vec<NonNull<App>> = vec![];
+    // The literal arguments collected so far for the primitive at the head
+    let mut args: Vec<Literal> = vec![];
+    // The weak head normal form of the subterm the current frame waited on
+    let mut forced: Option<DAGPtr> = None;
     loop {
-      match node {
-        DAGPtr::App(link) => {
-          let App { fun, .. } = unsafe { link.as_ref() };
-          trail.push(link);
-          node = *fun;
-        }
-        DAGPtr::Lam(link) => {
-          if let Some(app_link) = trail.pop() {
-            node = reduce_lam(app_link, link, should_count);
+      let pending = loop {
+        match node {
+          DAGPtr::App(link) => {
+            let App { fun, .. } = unsafe { link.as_ref() };
+            trail.push(link);
+            node = *fun;
           }
-          else {
-            break;
+          DAGPtr::Lam(link) => {
+            if trail.is_empty() {
+              break Ok(None);
+            }
+            if let Err(e) = meter.step() {
+              break Err(e);
+            }
+            node = reduce_lam(trail.pop().unwrap(), link, should_count);
           }
-        }
-        DAGPtr::Ann(link) => {
-          let Ann { exp, .. } = unsafe { link.as_ref() };
-          replace_child(node, *exp);
-          free_dead_node(node);
-          node = *exp;
-        }
-        DAGPtr::Cse(link) => {
-          let mut body = unsafe { DAG::new((*link.as_ptr()).bod) };
-          body.whnf(defs, should_count);
-          match body.head {
-            DAGPtr::Dat(body_link) => {
-              let bod = unsafe { body_link.as_ref().bod };
-              replace_child(node, bod);
-              free_dead_node(node);
-              node = bod;
+          DAGPtr::Ann(link) => {
+            let Ann { exp, .. } = unsafe { link.as_ref() };
+            if let Err(e) = meter.step() {
+              break Err(e);
             }
-            DAGPtr::Lit(link) => {
-              let Lit { lit, parents, .. } = unsafe { link.as_ref() };
-              match &lit.clone().expand() {
-                None => break,
-                Some(expand) => {
-                  let expand = DAG::from_term_inner(
-                    expand,
-                    0,
-                    BTreeMap::new(),
-                    *parents,
-                    None,
-                  );
-                  replace_child(node, expand);
-                  free_dead_node(node);
-                  node = expand;
+            replace_child(node, *exp);
+            free_dead_node(node);
+            node = *exp;
+          }
+          DAGPtr::Cse(link) => {
+            let body = match forced.take() {
+              Some(body) => body,
+              None => break Ok(Some(unsafe { (*link.as_ptr()).bod })),
+            };
+            match body {
+              DAGPtr::Dat(body_link) => {
+                if let Err(e) = meter.step() {
+                  break Err(e);
+                }
+                let bod = unsafe { body_link.as_ref().bod };
+                replace_child(node, bod);
+                free_dead_node(node);
+                node = bod;
+              }
+              DAGPtr::Lit(link) => {
+                let Lit { lit, parents, .. } = unsafe { link.as_ref() };
+                match &lit.clone().expand() {
+                  None => break Ok(None),
+                  Some(expand) => {
+                    if let Err(e) = meter.step() {
+                      break Err(e);
+                    }
+                    let expand = DAG::from_term_inner(
+                      expand,
+                      0,
+                      BTreeMap::new(),
+                      *parents,
+                      None,
+                    );
+                    replace_child(node, expand);
+                    free_dead_node(node);
+                    node = expand;
+                  }
                 }
               }
+              _ => break Ok(None),
             }
-            _ => break,
-          }
-        }
-        DAGPtr::Let(link) => {
-          node = reduce_let(link, should_count);
-        }
-        DAGPtr::Fix(link) => unsafe {
-          let Fix { var, bod, .. } = &mut *link.as_ptr();
-          replace_child(node, *bod);
-          if var.parents.is_some() {
-            let new_fix =
-              alloc_fix(var.nam.clone(), 0, mem::zeroed(), None).as_mut();
-            let result = subst(
-              *bod,
-              var,
-              DAGPtr::Var(NonNull::new_unchecked(&mut new_fix.var)),
-              true,
-              should_count,
-            );
-            new_fix.bod = result;
-            add_to_parents(
-              result,
-              NonNull::new_unchecked(&mut new_fix.bod_ref),
-            );
-            replace_child(
-              DAGPtr::Var(NonNull::new(var).unwrap()),
-              DAGPtr::Fix(NonNull::new_unchecked(new_fix)),
-            );
           }
-          free_dead_node(node);
-          node = *bod;
-        },
-        DAGPtr::Ref(link) => {
-          let Ref { nam, exp, ast, parents: ref_parents, .. } =
-            unsafe { &mut *link.as_ptr() };
-          if let Some(def) = defs.defs.get(exp) {
-            let parents = *ref_parents;
-            *ref_parents = None;
-            let ref_node = node;
-            node = DAG::from_ref(def, nam.clone(), *exp, *ast, parents);
-            free_dead_node(ref_node);
-            for parent in DLL::iter_option(parents) {
-              install_child(parent, node);
+          DAGPtr::Let(link) => {
+            if let Err(e) = meter.step() {
+              break Err(e);
             }
+            node = reduce_let(link, should_count);
           }
-          else {
-            panic!("undefined runtime reference: {}, {}", nam, exp);
-          }
-        }
-        DAGPtr::Opr(link) => {
-          let opr = unsafe { (*link.as_ptr()).opr.clone() };
-          let len = trail.len();
-          if len == 0 && opr.arity() == 0 {
-            let res = opr.apply0();
-            if let Some(res) = res {
-              node = DAGPtr::Lit(alloc_val(Lit { lit: res, parents: None }));
+          DAGPtr::Fix(link) => unsafe {
+            if let Err(e) = meter.step() {
+              break Err(e);
+            }
+            let Fix { var, bod, .. } = &mut *link.as_ptr();
+            replace_child(node, *bod);
+            if var.parents.is_some() {
+              let new_fix =
+                alloc_fix(var.nam.clone(), 0, mem::zeroed(), None).as_mut();
+              let result = subst(
+                *bod,
+                var,
+                DAGPtr::Var(NonNull::new_unchecked(&mut new_fix.var)),
+                true,
+                should_count,
+              );
+              new_fix.bod = result;
+              add_to_parents(
+                result,
+                NonNull::new_unchecked(&mut new_fix.bod_ref),
+              );
+              replace_child(
+                DAGPtr::Var(NonNull::new(var).unwrap()),
+                DAGPtr::Fix(NonNull::new_unchecked(new_fix)),
+              );
+            }
+            free_dead_node(node);
+            node = *bod;
+          },
+          DAGPtr::Ref(link) => {
+            let Ref { nam, exp, ast, parents: ref_parents, .. } =
+              unsafe { &mut *link.as_ptr() };
+            if let Some(def) = defs.defs.get(exp) {
+              if let Err(e) = meter.step() {
+                break Err(e);
+              }
+              let parents = *ref_parents;
+              *ref_parents = None;
+              let ref_node = node;
+              node = DAG::from_ref(def, nam.clone(), *exp, *ast, parents);
+              free_dead_node(ref_node);
+              for parent in DLL::iter_option(parents) {
+                install_child(parent, node);
+              }
             }
             else {
-              break;
+              panic!("undefined runtime reference: {}, {}", nam, exp);
             }
           }
-          else if len >= 1 && opr.arity() == 1 {
-            let mut arg = unsafe { DAG::new((*trail[len - 1].as_ptr()).arg) };
-            arg.whnf(defs, should_count);
-            match arg.head {
-              DAGPtr::Lit(link) => {
-                let x = unsafe { &(*link.as_ptr()).lit };
-                let res = opr.apply1(x);
-                if let Some(res) = res {
-                  let top = DAGPtr::App(trail.pop().unwrap());
-                  let new_node =
-                    DAGPtr::Lit(alloc_val(Lit { lit: res, parents: None }));
-                  replace_child(top, new_node);
-                  free_dead_node(top);
-                  node = new_node;
-                }
-                else {
-                  break;
+          DAGPtr::Opr(link) => {
+            let opr = unsafe { (*link.as_ptr()).opr.clone() };
+            let len = trail.len();
+            let arity = opr.arity() as usize;
+            if (arity == 0 && len == 0) || (arity > 0 && len >= arity) {
+              if let Some(arg) = forced.take() {
+                match arg {
+                  DAGPtr::Lit(link) => {
+                    args.push(unsafe { (*link.as_ptr()).lit.clone() })
+                  }
+                  _ => break Ok(None),
                 }
               }
-              _ => break,
-            }
-          }
-          else if len >= 2 && opr.arity() == 2 {
-            let mut arg1 = unsafe { DAG::new((*trail[len - 1].as_ptr()).arg) };
-            let mut arg2 = unsafe { DAG::new((*trail[len - 2].as_ptr()).arg) };
-            arg1.whnf(defs, should_count);
-            arg2.whnf(defs, should_count);
-            match (arg1.head, arg2.head) {
-              (DAGPtr::Lit(x_link), DAGPtr::Lit(y_link)) => {
-                let x = unsafe { &(*x_link.as_ptr()).lit };
-                let y = unsafe { &(*y_link.as_ptr()).lit };
-                let res = opr.apply2(x, y);
-                if let Some(res) = res {
-                  trail.pop();
-                  let top = DAGPtr::App(trail.pop().unwrap());
-                  let new_node =
-                    DAGPtr::Lit(alloc_val(Lit { lit: res, parents: None }));
-                  replace_child(top, new_node);
-                  free_dead_node(top);
-                  node = new_node;
+              // Collects the arguments in order, suspending on the first one
+              // that is not a literal yet
+              let mut next = None;
+              while args.len() < arity {
+                let arg =
+                  unsafe { (*trail[len - 1 - args.len()].as_ptr()).arg };
+                match arg {
+                  DAGPtr::Lit(link) => {
+                    args.push(unsafe { (*link.as_ptr()).lit.clone() })
+                  }
+                  _ => {
+                    next = Some(arg);
+                    break;
+                  }
                 }
-                else {
-                  break;
+              }
+              if next.is_some() {
+                break Ok(next);
+              }
+              let args = mem::take(&mut args);
+              if let Err(e) = meter.step() {
+                break Err(e);
+              }
+              if let Op::Io(_) = opr {
+                if let Err(e) = meter.io() {
+                  break Err(e);
                 }
               }
-              _ => break,
-            }
-          }
-          else if len >= 3 && opr.arity() == 3 {
-            let mut arg1 = unsafe { DAG::new((*trail[len - 1].as_ptr()).arg) };
-            let mut arg2 = unsafe { DAG::new((*trail[len - 2].as_ptr()).arg) };
-            let mut arg3 = unsafe { DAG::new((*trail[len - 3].as_ptr()).arg) };
-            arg1.whnf(defs, should_count);
-            arg2.whnf(defs, should_count);
-            arg3.whnf(defs, should_count);
-            match (arg1.head, arg2.head, arg3.head) {
-              (
-                DAGPtr::Lit(x_link),
-                DAGPtr::Lit(y_link),
-                DAGPtr::Lit(z_link),
-              ) => {
-                let x = unsafe { &(*x_link.as_ptr()).lit };
-                let y = unsafe { &(*y_link.as_ptr()).lit };
-                let z = unsafe { &(*z_link.as_ptr()).lit };
-                let res = opr.apply3(x, y, z);
-                if let Some(res) = res {
-                  trail.pop();
-                  trail.pop();
-                  let top = DAGPtr::App(trail.pop().unwrap());
-                  let new_node =
-                    DAGPtr::Lit(alloc_val(Lit { lit: res, parents: None }));
-                  replace_child(top, new_node);
-                  free_dead_node(top);
-                  node = new_node;
+              let new_node = match opr.apply(&args) {
+                Some(res) => {
+                  DAGPtr::Lit(alloc_val(Lit { lit: res, parents: None }))
                 }
-                else {
-                  break;
+                None => match opr.apply_term(&args) {
+                  Some(res) => {
+                    DAG::from_term_inner(&res, 0, BTreeMap::new(), None, None)
+                  }
+                  None => break Ok(None),
+                },
+              };
+              if arity > 0 {
+                for _ in 1..arity {
+                  trail.pop();
                 }
+                let top = DAGPtr::App(trail.pop().unwrap());
+                replace_child(top, new_node);
+                free_dead_node(top);
               }
-              _ => break,
+              node = new_node;
+            }
+            else {
+              break Ok(None);
             }
           }
-          else {
-            break;
+          _ => break Ok(None),
+        }
+      };
+      match pending {
+        // Leaves the DAG headed by the partially reduced root frame
+        Err(e) => {
+          let (node, trail) = match stack.first() {
+            Some(frame) => (frame.node, &frame.trail),
+            None => (node, &trail),
+          };
+          self.head =
+            if trail.is_empty() { node } else { DAGPtr::App(trail[0]) };
+          return Err(e);
+        }
+        // Suspends the current frame and reduces the subterm
+        Ok(Some(sub)) => {
+          stack.push(Frame {
+            node,
+            trail: mem::take(&mut trail),
+            args: mem::take(&mut args),
+          });
+          node = sub;
+        }
+        // The current frame is done: resumes the frame that waited on it
+        Ok(None) => {
+          let head =
+            if trail.is_empty() { node } else { DAGPtr::App(trail[0]) };
+          match stack.pop() {
+            Some(frame) => {
+              node = frame.node;
+              trail = frame.trail;
+              args = frame.args;
+              forced = Some(head);
+            }
+            None => {
+              self.head = head;
+              return Ok(());
+            }
           }
         }
-        _ => break,
       }
     }
-    if trail.is_empty() {
-      self.head = node;
-    }
-    else {
-      self.head = DAGPtr::App(trail[0]);
-    }
   }
 
   /// Reduces a DAG to its normal form.
   pub fn norm(&mut self, defs: &Defs, should_count: bool) {
-    self.whnf(defs, should_count);
+    // An unlimited meter never fails
+    let _ = self.norm_metered(defs, should_count, &mut Meter::default());
+  }
+
+  /// Reduces a DAG to its normal form, failing once the evaluation exceeds the
+  /// limits of the meter. The DAG is left partially reduced.
+  pub fn norm_metered(
+    &mut self,
+    defs: &Defs,
+    should_count: bool,
+    meter: &mut Meter,
+  ) -> Result<(), LimitError> {
+    self.whnf_metered(defs, should_count, meter)?;
     let mut trail = vec![self.head];
     while let Some(node) = trail.pop() {
       match node {
@@ -532,8 +601,8 @@ impl DAG {
           let app = link.as_ptr();
           let mut fun = DAG::new((*app).fun);
           let mut arg = DAG::new((*app).arg);
-          fun.whnf(defs, should_count);
-          arg.whnf(defs, should_count);
+          fun.whnf_metered(defs, should_count, meter)?;
+          arg.whnf_metered(defs, should_count, meter)?;
           trail.push(fun.head);
           trail.push(arg.head);
         },
@@ -541,38 +610,39 @@ impl DAG {
           let all = link.as_ptr();
           let mut dom = DAG::new((*all).dom);
           let mut img = DAG::new(DAGPtr::Lam((*all).img));
-          dom.whnf(defs, should_count);
-          img.whnf(defs, should_count);
+          dom.whnf_metered(defs, should_count, meter)?;
+          img.whnf_metered(defs, should_count, meter)?;
           trail.push(dom.head);
           trail.push(img.head);
         },
         DAGPtr::Lam(link) => unsafe {
           let lam = link.as_ptr();
           let mut body = DAG::new((*lam).bod);
-          body.whnf(defs, should_count);
+          body.whnf_metered(defs, should_count, meter)?;
           trail.push(body.head);
         },
         DAGPtr::Slf(link) => unsafe {
           let slf = link.as_ptr();
           let mut body = DAG::new((*slf).bod);
-          body.whnf(defs, should_count);
+          body.whnf_metered(defs, should_count, meter)?;
           trail.push(body.head);
         },
         DAGPtr::Cse(link) => unsafe {
           let cse = link.as_ptr();
           let mut body = DAG::new((*cse).bod);
-          body.whnf(defs, should_count);
+          body.whnf_metered(defs, should_count, meter)?;
           trail.push(body.head);
         },
         DAGPtr::Dat(link) => unsafe {
           let dat = link.as_ptr();
           let mut body = DAG::new((*dat).bod);
-          body.whnf(defs, should_count);
+          body.whnf_metered(defs, should_count, meter)?;
           trail.push(body.head);
         },
         _ => (),
       }
     }
+    Ok(())
   }
 }
 
@@ -725,4 +795,98 @@ pub mod test {
     // assert_eq!(true, false);
     norm_assert(trm_str, id);
   }
+
+  #[test]
+  pub fn reduce_test_deep() {
+    // Adding one a million times builds a million nested additions, each the
+    // argument of the next, which reduction must not recurse over
+    let ten = "λ f x => f (f (f (f (f (f (f (f (f (f x)))))))))";
+    let mul = "λ m n f => m (n f)";
+    let thousand = format!(
+      "({mul}) ({ten}) (({mul}) ({ten}) ({ten}))",
+      mul = mul,
+      ten = ten
+    );
+    let million = format!("({mul}) ({k}) ({k})", mul = mul, k = thousand);
+    norm_assert(&format!("({}) (λ n => #Nat.add n 1) 0", million), "1000000");
+  }
+
+  #[test]
+  pub fn reduce_test_limits() {
+    use crate::limits::{
+      LimitError,
+      Limits,
+      Meter,
+    };
+    let (_, mut dag) = parse("(λ x => x x) (λ x => x x)").unwrap();
+    let mut meter =
+      Meter::new(Limits { max_steps: Some(1000), ..Limits::default() });
+    assert_eq!(
+      dag.norm_metered(&Defs::new(), false, &mut meter),
+      Err(LimitError::Steps(1000))
+    );
+    assert_eq!(meter.steps, 1000);
+    // The term grows with every step
+    let (_, mut dag) = parse("(λ x => x x x) (λ x => x x x)").unwrap();
+    let mut meter =
+      Meter::new(Limits { max_nodes: Some(1000), ..Limits::default() });
+    assert_eq!(
+      dag.whnf_metered(&Defs::new(), false, &mut meter),
+      Err(LimitError::Nodes(1000))
+    );
+    let (_, mut dag) = parse("(λ x => x) ((λ f => f 2 3) #Nat.add)").unwrap();
+    let mut meter = Meter::new(Limits {
+      max_steps: Some(3),
+      max_nodes: Some(1000),
+      max_io: Some(0),
+      max_gas: None,
+    });
+    assert_eq!(dag.norm_metered(&Defs::new(), false, &mut meter), Ok(()));
+    assert_eq!(format!("{}", dag), "5");
+    assert_eq!(meter.steps, 3);
+  }
+
+  #[test]
+  pub fn reduce_test_nary_opr() {
+    use crate::{
+      defs,
+      literal::Literal,
+      parse,
+      prim::{
+        io::IoOp,
+        Op,
+      },
+      term::{
+        Pos,
+        Term,
+      },
+      yatima,
+    };
+    use num_bigint::BigUint;
+    use sp_std::rc::Rc;
+    let sum = IoOp {
+      fun_symbol: Rc::new(|| "sum".to_owned()),
+      fun_type_of: Rc::new(|| yatima!("∀ #Nat #Nat #Nat #Nat -> #Nat")),
+      fun_arity: Rc::new(|| 4),
+      fun_apply: Rc::new(|args: &[Literal]| {
+        let mut acc = BigUint::from(0u64);
+        for arg in args {
+          match arg {
+            Literal::Nat(x) => acc += x,
+            _ => return None,
+          }
+        }
+        Some(Literal::Nat(acc))
+      }),
+      fun_apply_term: Rc::new(|_: &[Literal]| -> Option<Term> { None }),
+    };
+    let opr = Term::Opr(Pos::None, Op::Io(sum));
+    let mut dag = DAG::from_term(&yatima!("#$0 1 2 3 4", opr.clone()));
+    dag.norm(&Defs::new(), false);
+    assert_eq!(format!("{}", dag), "10");
+    // Partial applications are stuck
+    let mut dag = DAG::from_term(&yatima!("#$0 1 2 3", opr));
+    dag.norm(&Defs::new(), false);
+    assert_eq!(format!("{}", dag), "#Io.sum 1 2 3");
+  }
 }
@@ -36,6 +36,7 @@ pub enum IpldError {
   I64Op(Ipld),
   I128Op(Ipld),
   IoOp(Ipld),
+  ExtOp(Ipld),
   Anon(Ipld),
   Meta(Ipld),
   Entry(Ipld),
@@ -19,6 +19,7 @@ pub mod dll;
 pub mod embed_error;
 pub mod eval;
 pub mod ipld_error;
+pub mod limits;
 pub mod literal;
 pub mod meta;
 pub mod name;
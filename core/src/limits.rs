@@ -0,0 +1,259 @@
+//! Resource limits for evaluating untrusted programs. A `Meter` counts the
+//! reduction steps, live DAG nodes and IO operations of an evaluation and
+//! fails with a `LimitError` as soon as one of them exceeds its `Limits`.
+//! Typechecking is also metered in gas, a weighted sum of the reduction steps,
+//! DAG allocations and term nodes hashed by `equal` it makes.
+
+use core::fmt;
+
+#[cfg(not(any(feature = "std", test)))]
+use core::sync::atomic::{
+  AtomicUsize,
+  Ordering,
+};
+
+/// Bounds on the work an evaluation may do. `None` means unbounded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+  /// Maximum number of reduction steps
+  pub max_steps: Option<u64>,
+  /// Maximum number of DAG nodes live at once. Nodes freed by the evaluation
+  /// no longer count.
+  pub max_nodes: Option<u64>,
+  /// Maximum number of IO operations applied
+  pub max_io: Option<u64>,
+  /// Maximum gas spent, as computed by `Meter::gas`
+  pub max_gas: Option<u64>,
+}
+
+/// Gas charged for each reduction step
+pub const STEP_GAS: u64 = 10;
+/// Gas charged for each DAG node allocated
+pub const NODE_GAS: u64 = 1;
+/// Gas charged for each term node hashed by `equal`
+pub const EQUAL_GAS: u64 = 5;
+
+/// The limit an evaluation exceeded, with its value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitError {
+  Steps(u64),
+  Nodes(u64),
+  Io(u64),
+  Gas(u64),
+}
+
+impl fmt::Display for LimitError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Steps(max) => {
+        write!(f, "Exceeded the limit of {} reduction steps", max)
+      }
+      Self::Nodes(max) => write!(f, "Exceeded the limit of {} DAG nodes", max),
+      Self::Io(max) => write!(f, "Exceeded the limit of {} IO operations", max),
+      Self::Gas(max) => write!(f, "Exceeded the limit of {} gas", max),
+    }
+  }
+}
+
+/// Tracks the usage of an evaluation against its limits
+#[derive(Clone, Debug)]
+pub struct Meter {
+  pub limits: Limits,
+  pub steps: u64,
+  pub io: u64,
+  /// The number of term nodes hashed by `equal`
+  pub equal: u64,
+  /// Reads the allocation counter of the evaluator being metered
+  allocated: fn() -> usize,
+  /// Reads the free counter of the evaluator being metered
+  freed: fn() -> usize,
+  /// The allocation and free counters when the meter was created
+  base: (usize, usize),
+}
+
+impl Meter {
+  /// A meter for `DAG` evaluation
+  pub fn new(limits: Limits) -> Self {
+    Meter::with_counters(limits, allocated, freed)
+  }
+
+  /// A meter for an evaluator with its own allocation and free counters
+  pub fn with_counters(
+    limits: Limits,
+    allocated: fn() -> usize,
+    freed: fn() -> usize,
+  ) -> Self {
+    let base = (allocated(), freed());
+    Meter { limits, steps: 0, io: 0, equal: 0, allocated, freed, base }
+  }
+
+  /// The number of nodes allocated since the meter was created
+  pub fn nodes(&self) -> u64 { ((self.allocated)() - self.base.0) as u64 }
+
+  /// The number of nodes allocated since the meter was created that are still
+  /// live. Nodes allocated before it and freed since make up for ones
+  /// allocated after, so this may undercount.
+  pub fn live_nodes(&self) -> u64 {
+    let freed = ((self.freed)() - self.base.1) as u64;
+    self.nodes().saturating_sub(freed)
+  }
+
+  /// Fails if the live nodes exceed the node limit. Evaluators call this on
+  /// every step and after building the result of a primitive operation,
+  /// whose size is not bounded by the steps taken.
+  pub fn check_nodes(&self) -> Result<(), LimitError> {
+    match self.limits.max_nodes {
+      Some(max) if self.live_nodes() > max => Err(LimitError::Nodes(max)),
+      _ => Ok(()),
+    }
+  }
+
+  /// The gas spent so far. It depends only on the work done, so the same
+  /// inputs always spend the same gas.
+  pub fn gas(&self) -> u64 {
+    self.steps * STEP_GAS + self.nodes() * NODE_GAS + self.equal * EQUAL_GAS
+  }
+
+  /// Fails if spending `cost` more gas would exceed the gas limit
+  fn charge(&self, cost: u64) -> Result<(), LimitError> {
+    match self.limits.max_gas {
+      Some(max) if self.gas() + cost > max => Err(LimitError::Gas(max)),
+      _ => Ok(()),
+    }
+  }
+
+  /// Counts a reduction step about to be made, failing instead if it would
+  /// exceed the step or gas limit or if the node limit is already exceeded
+  pub fn step(&mut self) -> Result<(), LimitError> {
+    if let Some(max) = self.limits.max_steps {
+      if self.steps >= max {
+        return Err(LimitError::Steps(max));
+      }
+    }
+    self.check_nodes()?;
+    self.charge(STEP_GAS)?;
+    self.steps += 1;
+    Ok(())
+  }
+
+  /// Counts a comparison about to be made by `equal` that hashes `nodes` term
+  /// nodes, failing instead if it would exceed the gas limit
+  pub fn equal_step(&mut self, nodes: u64) -> Result<(), LimitError> {
+    self.charge(nodes * EQUAL_GAS)?;
+    self.equal += nodes;
+    Ok(())
+  }
+
+  /// Counts an IO operation about to be applied, failing instead if it would
+  /// exceed the IO limit
+  pub fn io(&mut self) -> Result<(), LimitError> {
+    if let Some(max) = self.limits.max_io {
+      if self.io >= max {
+        return Err(LimitError::Io(max));
+      }
+    }
+    self.io += 1;
+    Ok(())
+  }
+}
+
+impl Default for Meter {
+  fn default() -> Self { Meter::new(Limits::default()) }
+}
+
+// The allocation counter is per thread where threads exist, so that
+// concurrent evaluations are metered independently
+#[cfg(any(feature = "std", test))]
+std::thread_local! {
+  static ALLOCATED: core::cell::Cell<usize> = core::cell::Cell::new(0);
+  static FREED: core::cell::Cell<usize> = core::cell::Cell::new(0);
+}
+
+/// Counts a DAG allocation
+#[cfg(any(feature = "std", test))]
+#[inline]
+pub fn count_alloc() { ALLOCATED.with(|n| n.set(n.get() + 1)) }
+
+/// Counts a DAG node being freed
+#[cfg(any(feature = "std", test))]
+#[inline]
+pub fn count_free() { FREED.with(|n| n.set(n.get() + 1)) }
+
+/// The number of DAG allocations so far
+#[cfg(any(feature = "std", test))]
+pub fn allocated() -> usize { ALLOCATED.with(|n| n.get()) }
+
+/// The number of DAG nodes freed so far
+#[cfg(any(feature = "std", test))]
+pub fn freed() -> usize { FREED.with(|n| n.get()) }
+
+// Without threads the counters are global to the process. Evaluations that
+// run concurrently, such as on several cores or in an interrupt handler, share
+// them, so each meter also counts the nodes allocated and freed by the others.
+#[cfg(not(any(feature = "std", test)))]
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(not(any(feature = "std", test)))]
+static FREED: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts a DAG allocation
+#[cfg(not(any(feature = "std", test)))]
+#[inline]
+pub fn count_alloc() { ALLOCATED.fetch_add(1, Ordering::Relaxed); }
+
+/// Counts a DAG node being freed
+#[cfg(not(any(feature = "std", test)))]
+#[inline]
+pub fn count_free() { FREED.fetch_add(1, Ordering::Relaxed); }
+
+/// The number of DAG allocations so far
+#[cfg(not(any(feature = "std", test)))]
+pub fn allocated() -> usize { ALLOCATED.load(Ordering::Relaxed) }
+
+/// The number of DAG nodes freed so far
+#[cfg(not(any(feature = "std", test)))]
+pub fn freed() -> usize { FREED.load(Ordering::Relaxed) }
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+
+  #[test]
+  fn test_meter() {
+    let mut meter = Meter::new(Limits {
+      max_steps: Some(2),
+      max_io: Some(1),
+      max_nodes: None,
+      max_gas: None,
+    });
+    assert_eq!(meter.step(), Ok(()));
+    assert_eq!(meter.step(), Ok(()));
+    assert_eq!(meter.step(), Err(LimitError::Steps(2)));
+    assert_eq!(meter.steps, 2);
+    assert_eq!(meter.io(), Ok(()));
+    assert_eq!(meter.io(), Err(LimitError::Io(1)));
+    let mut meter =
+      Meter::new(Limits { max_nodes: Some(1), ..Limits::default() });
+    count_alloc();
+    assert_eq!(meter.step(), Ok(()));
+    count_alloc();
+    assert_eq!(meter.step(), Err(LimitError::Nodes(1)));
+    // Only live nodes count against the limit
+    count_free();
+    assert_eq!(meter.live_nodes(), 1);
+    assert_eq!(meter.step(), Ok(()));
+    count_alloc();
+    assert_eq!(meter.check_nodes(), Err(LimitError::Nodes(1)));
+    assert_eq!(meter.nodes(), 3);
+    let mut meter =
+      Meter::new(Limits { max_gas: Some(2 * STEP_GAS), ..Limits::default() });
+    assert_eq!(meter.step(), Ok(()));
+    assert_eq!(meter.equal_step(1), Ok(()));
+    assert_eq!(meter.gas(), STEP_GAS + EQUAL_GAS);
+    assert_eq!(meter.step(), Err(LimitError::Gas(2 * STEP_GAS)));
+    // Comparisons are charged by the size of what they hash
+    assert_eq!(meter.equal_step(2), Err(LimitError::Gas(2 * STEP_GAS)));
+    assert_eq!(meter.equal_step(1), Ok(()));
+    assert_eq!(meter.equal, 2);
+  }
+}
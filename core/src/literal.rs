@@ -88,10 +88,11 @@ impl fmt::Display for Literal {
         _ => write!(f, "+{}", x.to_str_radix(10)),
       },
       Bytes(x) => {
-        let mut x = x.clone();
-        x.reverse();
-        let x: &[u8] = x.as_ref();
-        write!(f, "x\'{}\'", base::LitBase::Hex.encode(x))
+        write!(f, "x\'")?;
+        for b in x.iter().rev() {
+          write!(f, "{:02x}", b)?;
+        }
+        write!(f, "\'")
       }
       Bits(x) => {
         if x.len() % 4 == 0 {
@@ -169,7 +170,9 @@ impl fmt::Display for Literal {
 }
 
 impl Literal {
-  /// Converts a literal into a lambda expression
+  /// Converts a literal into a lambda expression. Each step copies the
+  /// remaining sequence, so folds over large `#Bits`, `#Bytes` or `#Text`
+  /// should use their `view` primitive instead
   pub fn expand(self) -> Option<Term> {
     match self {
       Self::Nat(n) => {
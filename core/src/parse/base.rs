@@ -230,9 +230,15 @@ pub fn parse_litbase_bytes(
 ) -> impl Fn(Span) -> IResult<Span, Vec<u8>, ParseError<Span>> {
   move |from: Span| {
     let (i, o) = parse_litbase_digits(base)(from)?;
-    match base_x::decode(base.base_digits(), &o) {
-      Ok(bytes) => Ok((i, bytes)),
-      Err(_) => Err(nom::Err::Error(ParseError::new(
+    // Hex digits are read two to a byte, so leading zeros are padding rather
+    // than the zero bytes base_x would make of them
+    let bytes = match base {
+      LitBase::Hex => decode_hex_bytes(&o),
+      _ => base_x::decode(base.base_digits(), &o).ok(),
+    };
+    match bytes {
+      Some(bytes) => Ok((i, bytes)),
+      None => Err(nom::Err::Error(ParseError::new(
         i,
         ParseErrorKind::InvalidBaseEncoding(base),
       ))),
@@ -240,6 +246,23 @@ pub fn parse_litbase_bytes(
   }
 }
 
+/// Decodes hex digits two to a byte, the first digit of an odd count making a
+/// byte on its own
+pub fn decode_hex_bytes(digits: &str) -> Option<Vec<u8>> {
+  let mut bytes = Vec::new();
+  let mut start = 0;
+  let mut end = digits.len() % 2;
+  if end == 0 {
+    end = 2;
+  }
+  while start < digits.len() {
+    bytes.push(u8::from_str_radix(digits.get(start..end)?, 16).ok()?);
+    start = end;
+    end += 2;
+  }
+  Some(bytes)
+}
+
 pub const MULTIBASE_DIGITS: &str =
   "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/-_";
 
@@ -265,4 +265,20 @@ pub mod tests {
     let res = parse_nat(Span::new("15"));
     assert_eq!(res.unwrap().1, Literal::Nat(15u64.into()));
   }
+
+  #[test]
+  fn test_parse_bytes() {
+    let res = parse_bytes(Span::new("x''"));
+    assert_eq!(res.unwrap().1, Literal::Bytes(vec![]));
+    let res = parse_bytes(Span::new("x'00'"));
+    assert_eq!(res.unwrap().1, Literal::Bytes(vec![0]));
+    let res = parse_bytes(Span::new("x'0102ff'"));
+    assert_eq!(res.unwrap().1, Literal::Bytes(vec![255, 2, 1]));
+    let res = parse_bytes(Span::new("x'102ff'"));
+    assert_eq!(res.unwrap().1, Literal::Bytes(vec![255, 2, 1]));
+    let res = parse_bytes(Span::new("x'000102ff'"));
+    assert_eq!(res.unwrap().1, Literal::Bytes(vec![255, 2, 1, 0]));
+    let bytes = Literal::Bytes(vec![255, 2, 1, 0]);
+    assert_eq!(format!("{}", bytes), "x'000102ff'");
+  }
 }
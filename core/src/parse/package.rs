@@ -16,13 +16,16 @@ use crate::{
       ParseErrorKind,
     },
     span::Span,
+    string::parse_string,
     term::*,
     typedef::parse_typedef_elaborated,
   },
+  prim::ext::ExtOp,
   term::*,
 };
 
 use sp_std::{
+  boxed::Box,
   cell::RefCell,
   collections::vec_deque::VecDeque,
   convert::TryFrom,
@@ -116,6 +119,47 @@ pub fn parse_entry(
   }
 }
 
+/// Parses an extern declaration `extern name : type = "symbol"`, which binds a
+/// typed name to a host primitive that the runtime resolves by its symbol
+pub fn parse_extern(
+  input: Cid,
+  defs: Rc<RefCell<Defs>>,
+) -> impl Fn(Span) -> IResult<Span, Vec<(Name, Def, Entry)>, ParseError<Span>> {
+  move |from: Span| {
+    let (i, _) = tag("extern")(from)?;
+    let (i, _) = parse_space(i)?;
+    let (i, nam) = parse_name(i)?;
+    if defs.borrow().names.get(&nam.clone()).is_some() {
+      Err(Err::Error(ParseError::new(
+        from,
+        ParseErrorKind::TopLevelRedefinition(nam),
+      )))
+    }
+    else {
+      let (i, _) = parse_space(i)?;
+      let (i, _) = tag(":")(i)?;
+      let (i, _) = parse_space(i)?;
+      let (i, typ_) = parse_expression(
+        input,
+        defs.clone(),
+        None,
+        ConsList::new(),
+        Rc::new(VecDeque::new()),
+      )(i)?;
+      let (i, _) = parse_space(i)?;
+      let (i, _) = tag("=")(i)?;
+      let (i, _) = parse_space(i)?;
+      let (i, _) = tag("\"")(i)?;
+      let (i, symbol) = parse_string("\"")(i)?;
+      let (upto, _) = tag("\"")(i)?;
+      let pos = Pos::from_upto(input, from, upto);
+      let opr = Op::Extern(ExtOp { symbol, typ: Box::new(typ_.clone()) });
+      let (def, entry) = Def::make(pos, typ_, Term::Opr(pos, opr));
+      Ok((upto, vec![(nam, def, entry)]))
+    }
+  }
+}
+
 pub fn parse_defs(
   input: Cid,
   import_defs: Defs,
@@ -134,6 +178,7 @@ pub fn parse_defs(
       else {
         let (i2, entries) = alt((
           parse_entry(input, defs.clone()),
+          parse_extern(input, defs.clone()),
           parse_typedef_elaborated(input, defs.clone()),
         ))(i)?;
         for (name, def, _) in entries {
@@ -166,3 +211,41 @@ pub fn parse_defs(
 //    assert!(res.is_ok());
 //  }
 // }
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+  use crate::yatima;
+
+  #[test]
+  fn extern_parse() {
+    fn test(i: &str) -> IResult<Span, (Defs, Index), ParseError<Span>> {
+      parse_defs(input_cid(i), Defs::new())(Span::new(i))
+    }
+    let res = test("extern print : ∀ #Bytes -> #Bool = \"io.print\"");
+    assert!(res.is_ok());
+    let (_, (defs, _)) = res.unwrap();
+    let def = defs.get(&Name::from("print")).unwrap();
+    assert_eq!(def.typ_, yatima!("∀ #Bytes -> #Bool"));
+    assert_eq!(
+      def.term,
+      Term::Opr(
+        Pos::None,
+        Op::Extern(ExtOp {
+          symbol: "io.print".to_owned(),
+          typ: Box::new(yatima!("∀ #Bytes -> #Bool")),
+        })
+      )
+    );
+    let res = test(
+      "extern print : ∀ #Bytes -> #Bool = \"io.print\"
+       def main : #Bool = print x'ff'",
+    );
+    assert!(res.is_ok());
+    let res = test(
+      "def print : #Bool = #Bool.true
+       extern print : ∀ #Bytes -> #Bool = \"io.print\"",
+    );
+    assert!(res.is_err());
+  }
+}
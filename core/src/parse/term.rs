@@ -9,10 +9,13 @@ use crate::{
     },
     literal::*,
     op::parse_opr,
+    string::parse_string,
   },
   position::Pos,
+  prim::ext::ExtOp,
   term::{
     LitType,
+    Op,
     Term,
     Uses,
   },
@@ -105,6 +108,7 @@ pub fn reserved_symbols() -> VecDeque<String> {
     String::from("type"),
     String::from("data"),
     String::from("def"),
+    String::from("extern"),
     String::from("open"),
     String::from("case"),
     String::from("Type"),
@@ -854,6 +858,36 @@ pub fn parse_lit(
   }
 }
 
+/// Parses an extern primitive `#extern "symbol" (type)`, the form an `extern`
+/// declaration takes once it is inlined into another term. The type is closed,
+/// so it is parsed without the surrounding context.
+pub fn parse_extern_opr(
+  input: Cid,
+  defs: Rc<RefCell<Defs>>,
+) -> impl Fn(Span) -> IResult<Span, Term, ParseError<Span>> {
+  move |from: Span| {
+    let (i, _) = tag("#extern")(from)?;
+    let (i, _) = parse_space(i)?;
+    let (i, _) = tag("\"")(i)?;
+    let (i, symbol) = parse_string("\"")(i)?;
+    let (i, _) = tag("\"")(i)?;
+    let (i, _) = parse_space(i)?;
+    let (i, _) = tag("(")(i)?;
+    let (i, _) = parse_space(i)?;
+    let (i, typ) = parse_expression(
+      input,
+      defs.clone(),
+      None,
+      ConsList::new(),
+      Rc::new(VecDeque::new()),
+    )(i)?;
+    let (i, _) = parse_space(i)?;
+    let (upto, _) = tag(")")(i)?;
+    let pos = Pos::from_upto(input, from, upto);
+    Ok((upto, Term::Opr(pos, Op::Extern(ExtOp { symbol, typ: Box::new(typ) }))))
+  }
+}
+
 /// Parse an expression which is an application sequence with a possible type
 /// annotation `f a b c :: F A B C`
 pub fn parse_expression(
@@ -892,6 +926,7 @@ pub fn parse_expression(
 pub fn parse_app_end(i: Span) -> IResult<Span, (), ParseError<Span>> {
   let (i, _) = alt((
     peek(tag("def")),
+    peek(tag("extern")),
     peek(tag("type")),
     peek(tag("::")),
     peek(tag("=")),
@@ -1003,6 +1038,7 @@ pub fn parse_term(
         parse_let(input, defs.clone(), rec.clone(), ctx.clone(), quasi.clone()),
         parse_type(input),
         parse_lty(input),
+        parse_extern_opr(input, defs.clone()),
         parse_opr(input),
         parse_lit(input),
         parse_antiquote(ctx.clone(), quasi.clone()),
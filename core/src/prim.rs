@@ -2,6 +2,8 @@ pub mod bits;
 pub mod bool;
 pub mod bytes;
 pub mod char;
+pub mod ext;
+pub mod host;
 // pub mod i128;
 pub mod i16;
 pub mod i32;
@@ -20,16 +22,21 @@ pub mod io;
 
 use sp_std::{
   borrow::ToOwned,
+  boxed::Box,
   fmt,
+  vec::Vec,
 };
 
 use alloc::string::String;
 
+use num_bigint::BigUint;
 use sp_ipld::Ipld;
 
 use crate::{
   ipld_error::IpldError,
   literal::Literal,
+  name::Name,
+  position::Pos,
   term::Term,
 };
 
@@ -38,6 +45,7 @@ use crate::prim::{
   bool::BoolOp,
   bytes::BytesOp,
   char::CharOp,
+  ext::ExtOp,
   i16::I16Op,
   i32::I32Op,
   i64::I64Op,
@@ -56,6 +64,7 @@ use crate::prim::{
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum Op {
   Io(IoOp),
+  Extern(ExtOp),
   Nat(NatOp),
   Int(IntOp),
   Bits(BitsOp),
@@ -80,6 +89,7 @@ impl Op {
   pub fn symbol(&self) -> String {
     match self {
       Self::Io(op) => format!("#Io.{}", op.symbol()),
+      Self::Extern(op) => format!("#extern {} ({})", op.symbol(), op.typ),
       Self::Nat(op) => format!("#Nat.{}", op.symbol()),
       Self::Int(op) => format!("#Int.{}", op.symbol()),
       Self::Text(op) => format!("#Text.{}", op.symbol()),
@@ -121,6 +131,7 @@ impl Op {
       Self::I32(op) => Ipld::List(vec![Ipld::Integer(14), op.to_ipld()]),
       Self::I64(op) => Ipld::List(vec![Ipld::Integer(15), op.to_ipld()]),
       // Self::I128(op) => Ipld::List(vec![Ipld::Integer(16), op.to_ipld()]),
+      Self::Extern(op) => Ipld::List(vec![Ipld::Integer(17), op.to_ipld()]),
     }
   }
 
@@ -147,6 +158,7 @@ impl Op {
         [Ipld::Integer(14), ys] => I32Op::from_ipld(ys).map(Self::I32),
         [Ipld::Integer(15), ys] => I64Op::from_ipld(ys).map(Self::I64),
         //[Ipld::Integer(16), ys] => I128Op::from_ipld(ys).map(Self::I128),
+        [Ipld::Integer(17), ys] => ExtOp::from_ipld(ys).map(Self::Extern),
         xs => Err(IpldError::PrimOp(Ipld::List(xs.to_owned()))),
       },
       xs => Err(IpldError::PrimOp(xs.to_owned())),
@@ -158,6 +170,7 @@ impl Op {
     match self {
       //#[cfg(feature = "std")]
       Self::Io(op) => op.arity(),
+      Self::Extern(op) => op.arity(),
       Self::Nat(op) => op.arity(),
       Self::Int(op) => op.arity(),
       Self::Bits(op) => op.arity(),
@@ -182,7 +195,7 @@ impl Op {
   pub fn apply0(&self) -> Option<Literal> {
     match self {
       //#[cfg(feature = "std")]
-      Self::Io(op) => op.apply0(),
+      Self::Io(op) => op.apply(&[]),
       Self::U8(op) => op.apply0(),
       Self::U16(op) => op.apply0(),
       Self::U32(op) => op.apply0(),
@@ -201,7 +214,7 @@ impl Op {
   pub fn apply1(&self, x: &Literal) -> Option<Literal> {
     match self {
       //#[cfg(feature = "std")]
-      Self::Io(op) => op.apply1(x),
+      Self::Io(op) => op.apply(&[x.clone()]),
       Self::Nat(op) => op.apply1(x),
       Self::Int(op) => op.apply1(x),
       Self::Bits(op) => op.apply1(x),
@@ -219,6 +232,7 @@ impl Op {
       Self::I32(op) => op.apply1(x),
       Self::I64(op) => op.apply1(x),
       // Self::I128(op) => op.apply1(x),
+      Self::Extern(_) => None,
     }
   }
 
@@ -226,7 +240,7 @@ impl Op {
   pub fn apply2(&self, x: &Literal, y: &Literal) -> Option<Literal> {
     match self {
       //#[cfg(feature = "std")]
-      Self::Io(op) => op.apply2(x, y),
+      Self::Io(op) => op.apply(&[x.clone(), y.clone()]),
       Self::Nat(op) => op.apply2(x, y),
       Self::Int(op) => op.apply2(x, y),
       Self::Bits(op) => op.apply2(x, y),
@@ -245,6 +259,7 @@ impl Op {
       Self::I32(op) => op.apply2(x, y),
       Self::I64(op) => op.apply2(x, y),
       // Self::I128(op) => op.apply2(x, y),
+      Self::Extern(_) => None,
       //_ => None,
     }
   }
@@ -257,6 +272,7 @@ impl Op {
     z: &Literal,
   ) -> Option<Literal> {
     match self {
+      Self::Io(op) => op.apply(&[x.clone(), y.clone(), z.clone()]),
       Self::Bytes(op) => op.apply3(x, y, z),
       Self::Bits(op) => op.apply3(x, y, z),
       Self::Text(op) => op.apply3(x, y, z),
@@ -264,11 +280,89 @@ impl Op {
     }
   }
 
+  /// Applies a unary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply1_term(&self, x: &Literal) -> Option<Term> {
+    match self {
+      Self::Io(op) => op.apply_term(&[x.clone()]),
+      Self::Text(op) => op.apply1_term(x),
+      _ => None,
+    }
+  }
+
+  /// Applies a binary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply2_term(&self, x: &Literal, y: &Literal) -> Option<Term> {
+    match self {
+      Self::Io(op) => op.apply_term(&[x.clone(), y.clone()]),
+      Self::Bits(op) => op.apply2_term(x, y),
+      Self::Bytes(op) => op.apply2_term(x, y),
+      Self::Text(op) => op.apply2_term(x, y),
+      Self::U8(op) => op.apply2_term(x, y),
+      Self::U16(op) => op.apply2_term(x, y),
+      Self::U32(op) => op.apply2_term(x, y),
+      Self::U64(op) => op.apply2_term(x, y),
+      Self::I8(op) => op.apply2_term(x, y),
+      Self::I16(op) => op.apply2_term(x, y),
+      Self::I32(op) => op.apply2_term(x, y),
+      Self::I64(op) => op.apply2_term(x, y),
+      _ => None,
+    }
+  }
+
+  /// Applies an operation to as many literals as its arity, first argument
+  /// first, and returns the result if successful. Built-in operations take at
+  /// most three arguments, while IO operations may take any number.
+  pub fn apply(&self, args: &[Literal]) -> Option<Literal> {
+    match (self, args) {
+      (Self::Io(op), _) => op.apply(args),
+      (_, []) => self.apply0(),
+      (_, [x]) => self.apply1(x),
+      (_, [x, y]) => self.apply2(x, y),
+      (_, [x, y, z]) => self.apply3(x, y, z),
+      _ => None,
+    }
+  }
+
+  /// Whether the operation returns an updated copy of a `#Bytes` or `#Text`
+  /// argument, which `apply_in_place` can reuse
+  pub fn updates(&self) -> bool {
+    match self {
+      Self::Bytes(op) => op.updates(),
+      Self::Text(op) => op.updates(),
+      _ => false,
+    }
+  }
+
+  /// Applies an operation like `apply`, but an updating operation takes the
+  /// buffers out of the arguments, leaving them empty, rather than cloning
+  /// them
+  pub fn apply_in_place(&self, args: &mut [Literal]) -> Option<Literal> {
+    let res = match self {
+      Self::Bytes(op) => op.apply_in_place(args),
+      Self::Text(op) => op.apply_in_place(args),
+      _ => None,
+    };
+    res.or_else(|| self.apply(args))
+  }
+
+  /// Applies an operation with a non-literal result to as many literals as
+  /// its arity and returns the result if successful
+  pub fn apply_term(&self, args: &[Literal]) -> Option<Term> {
+    match (self, args) {
+      (Self::Io(op), _) => op.apply_term(args),
+      (_, [x]) => self.apply1_term(x),
+      (_, [x, y]) => self.apply2_term(x, y),
+      _ => None,
+    }
+  }
+
   /// Returns the type of the primitive
   pub fn type_of(&self) -> Term {
     match self {
       //#[cfg(feature = "std")]
       Self::Io(op) => op.type_of(),
+      Self::Extern(op) => op.type_of(),
       Self::Nat(op) => op.type_of(),
       Self::Int(op) => op.type_of(),
       Self::Bits(op) => op.type_of(),
@@ -290,6 +384,91 @@ impl Op {
   }
 }
 
+/// Builds the Church-encoded option `λ P none some => ...` from an optional
+/// literal, as returned by partial primitive operations
+pub fn option_term(x: Option<Literal>) -> Term {
+  let body = match x {
+    None => Term::Var(Pos::None, Name::from("none"), 1),
+    Some(x) => Term::App(
+      Pos::None,
+      Box::new((
+        Term::Var(Pos::None, Name::from("some"), 0),
+        Term::Lit(Pos::None, x),
+      )),
+    ),
+  };
+  lams(&["P", "none", "some"], body)
+}
+
+/// Builds the Church-encoded list `λ P nil cons => ...` from a vector of
+/// literals, as returned by primitive operations that produce sequences
+pub fn list_term(xs: Vec<Literal>) -> Term {
+  let mut body = Term::Var(Pos::None, Name::from("nil"), 1);
+  for x in xs.into_iter().rev() {
+    let cons = Term::App(
+      Pos::None,
+      Box::new((
+        Term::Var(Pos::None, Name::from("cons"), 0),
+        Term::Lit(Pos::None, x),
+      )),
+    );
+    body = Term::App(Pos::None, Box::new((cons, body)));
+  }
+  lams(&["P", "nil", "cons"], body)
+}
+
+/// Builds the Church-encoded result `λ P err ok => ...` of a fallible
+/// primitive operation, such as a host IO call, carrying errors as text
+pub fn result_term(x: Result<Term, String>) -> Term {
+  let body = match x {
+    Err(msg) => Term::App(
+      Pos::None,
+      Box::new((
+        Term::Var(Pos::None, Name::from("err"), 1),
+        Term::Lit(Pos::None, Literal::Text(msg.as_str().into())),
+      )),
+    ),
+    Ok(x) => Term::App(
+      Pos::None,
+      Box::new((Term::Var(Pos::None, Name::from("ok"), 0), x)),
+    ),
+  };
+  lams(&["P", "err", "ok"], body)
+}
+
+/// Builds the Church-encoded view `λ P nil cons => ...` of the element at a
+/// given index of a sequence literal. The view only carries the element and
+/// the next index, so folding over a sequence never copies its buffer.
+pub fn view_term(idx: &BigUint, x: Option<Literal>) -> Term {
+  let body = match x {
+    None => Term::Var(Pos::None, Name::from("nil"), 1),
+    Some(x) => {
+      let cons = Term::App(
+        Pos::None,
+        Box::new((
+          Term::Var(Pos::None, Name::from("cons"), 0),
+          Term::Lit(Pos::None, x),
+        )),
+      );
+      Term::App(
+        Pos::None,
+        Box::new((
+          cons,
+          Term::Lit(Pos::None, Literal::Nat(idx + BigUint::from(1u64))),
+        )),
+      )
+    }
+  };
+  lams(&["P", "nil", "cons"], body)
+}
+
+/// Wraps a body in a sequence of lambdas
+fn lams(names: &[&str], body: Term) -> Term {
+  names.iter().rev().fold(body, |body, nam| {
+    Term::Lam(Pos::None, Name::from(*nam), Box::new(body))
+  })
+}
+
 impl fmt::Display for Op {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     write!(f, "{}", self.symbol())
@@ -13,6 +13,7 @@ use crate::{
   ipld_error::IpldError,
   literal::Literal,
   parse,
+  prim::view_term,
   term::Term,
   yatima,
 };
@@ -33,6 +34,7 @@ pub enum BitsOp {
   Remove,
   Index,
   ToBytes,
+  View,
 }
 
 impl BitsOp {
@@ -50,6 +52,7 @@ impl BitsOp {
       Self::Remove => "remove".to_owned(),
       Self::Index => "index".to_owned(),
       Self::ToBytes => "to_Bytes".to_owned(),
+      Self::View => "view".to_owned(),
     }
   }
 
@@ -67,6 +70,7 @@ impl BitsOp {
       "remove" => Some(Self::Remove),
       "index" => Some(Self::Index),
       "to_Bytes" => Some(Self::ToBytes),
+      "view" => Some(Self::View),
       _ => None,
     }
   }
@@ -85,6 +89,16 @@ impl BitsOp {
       Self::Remove => yatima!("∀ #Nat #Bits -> #Bits"),
       Self::Index => yatima!("∀ #Nat #Bits -> #U8"),
       Self::ToBytes => yatima!("∀ #Bits -> #Bytes"),
+      Self::View => yatima!(
+        "∀ (i: #Nat) (xs: #Bits)
+           -> ∀ (0 P: ∀ #Bits -> Type)
+                (& nil: P #b)
+                (& cons: ∀ (x: #Bool) (j: #Nat)
+                  -> P (#Bits.cons x
+                         (#Bits.take (#Nat.sub (#Bits.len xs) j) xs)))
+              -> P (#Bits.take (#Nat.sub (#Bits.len xs) i) xs)
+          "
+      ),
     }
   }
 
@@ -102,6 +116,7 @@ impl BitsOp {
       Self::Remove => Ipld::Integer(8),
       Self::Index => Ipld::Integer(9),
       Self::ToBytes => Ipld::Integer(10),
+      Self::View => Ipld::Integer(11),
     }
   }
 
@@ -119,6 +134,7 @@ impl BitsOp {
       Ipld::Integer(8) => Ok(Self::Remove),
       Ipld::Integer(9) => Ok(Self::Index),
       Ipld::Integer(10) => Ok(Self::ToBytes),
+      Ipld::Integer(11) => Ok(Self::View),
       xs => Err(IpldError::BitsOp(xs.to_owned())),
     }
   }
@@ -137,6 +153,7 @@ impl BitsOp {
       Self::Remove => 2,
       Self::Index => 2,
       Self::ToBytes => 1,
+      Self::View => 2,
     }
   }
 
@@ -230,6 +247,27 @@ impl BitsOp {
       _ => None,
     }
   }
+
+  /// Applies a binary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply2_term(self, x: &Literal, y: &Literal) -> Option<Term> {
+    use Literal::*;
+    match (self, x, y) {
+      (Self::View, Nat(idx), Bits(xs)) => {
+        Some(view_term(idx, safe_view(idx, xs).map(Bool)))
+      }
+      _ => None,
+    }
+  }
+}
+
+/// Gets the bit at a given index from the head of the bits, in the same
+/// order as `Literal::expand`
+pub fn safe_view(idx: &BigUint, xs: &[bool]) -> Option<bool> {
+  match usize::try_from(idx) {
+    Ok(idx) if idx < xs.len() => Some(xs[xs.len() - 1 - idx]),
+    _ => None,
+  }
 }
 
 /// Split bits into two lists of bits as booleans
@@ -330,7 +368,7 @@ pub mod tests {
   impl Arbitrary for BitsOp {
     fn arbitrary(_g: &mut Gen) -> Self {
       let mut rng = rand::thread_rng();
-      let gen: u32 = rng.gen_range(0..=10);
+      let gen: u32 = rng.gen_range(0..=11);
       match gen {
         0 => Self::Cons,
         1 => Self::Len,
@@ -342,7 +380,8 @@ pub mod tests {
         7 => Self::Insert,
         8 => Self::Remove,
         9 => Self::Index,
-        _ => Self::ToBytes,
+        10 => Self::ToBytes,
+        _ => Self::View,
       }
     }
   }
@@ -432,6 +471,13 @@ pub mod tests {
       )
     };
 
+    let apply2_nat_bits_term = |expected: Option<Term>| -> TestResult {
+      TestResult::from_bool(
+        BitsOp::apply2_term(op, &Nat(big(c)), &Literal::Bits(a.clone()))
+          == expected,
+      )
+    };
+
     let from_bool = TestResult::from_bool;
 
     match op {
@@ -508,6 +554,14 @@ pub mod tests {
           },
         }
       }
+      BitsOp::View => {
+        let idx = usize::try_from(c);
+        let x = match idx {
+          Ok(idx) if idx < a.len() => Some(Bool(a[a.len() - 1 - idx])),
+          _ => None,
+        };
+        apply2_nat_bits_term(Some(view_term(&big(c), x)))
+      }
     }
   }
 
@@ -587,6 +641,28 @@ pub mod tests {
       }
     };
 
+    let test_apply2_term_none_on_invalid =
+      |valid_arg: Literal, a_: Literal, b_: Literal| -> TestResult {
+        let go =
+          || TestResult::from_bool(BitsOp::apply2_term(op, &a_, &b_) == None);
+        if test_arg_2 {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&a_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+        else {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&b_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+      };
+
     match op {
       // Arity 1, valid is Bits.
       BitsOp::Len | BitsOp::Head | BitsOp::Tail | BitsOp::ToBytes => {
@@ -610,6 +686,15 @@ pub mod tests {
           test_apply2_none_on_invalid(Literal::Bits(b), Nat(big(d)), a)
         }
       }
+      // Arity 2 with a term result, valid are Nat on a and Bits on b.
+      BitsOp::View => {
+        if test_arg_2 {
+          test_apply2_term_none_on_invalid(Nat(big(d)), a, Literal::Bits(b))
+        }
+        else {
+          test_apply2_term_none_on_invalid(Literal::Bits(b), Nat(big(d)), a)
+        }
+      }
       // Arity 2, valid are Bits on a and b.
       BitsOp::Append => {
         if test_arg_2 {
@@ -3,6 +3,7 @@ use sp_ipld::Ipld;
 use sp_std::{
   borrow::ToOwned,
   fmt,
+  mem,
   vec::Vec,
 };
 
@@ -13,7 +14,10 @@ use crate::{
   ipld_error::IpldError,
   literal::Literal,
   parse,
-  prim::bits,
+  prim::{
+    bits,
+    view_term,
+  },
   term::Term,
   yatima,
 };
@@ -34,6 +38,7 @@ pub enum BytesOp {
   Remove,
   Index,
   ToBits,
+  View,
 }
 
 impl BytesOp {
@@ -51,6 +56,7 @@ impl BytesOp {
       Self::Remove => "remove".to_owned(),
       Self::Index => "index".to_owned(),
       Self::ToBits => "to_Bits".to_owned(),
+      Self::View => "view".to_owned(),
     }
   }
 
@@ -68,6 +74,7 @@ impl BytesOp {
       "remove" => Some(Self::Remove),
       "index" => Some(Self::Index),
       "to_Bits" => Some(Self::ToBits),
+      "view" => Some(Self::View),
       _ => None,
     }
   }
@@ -86,6 +93,16 @@ impl BytesOp {
       Self::Remove => yatima!("∀ #Nat #Bytes -> #Bytes"),
       Self::Index => yatima!("∀ #Nat #Bytes -> #U8"),
       Self::ToBits => yatima!("∀ #Nat #Bytes -> #Bits"),
+      Self::View => yatima!(
+        "∀ (i: #Nat) (xs: #Bytes)
+           -> ∀ (0 P: ∀ #Bytes -> Type)
+                (& nil: P x'')
+                (& cons: ∀ (x: #U8) (j: #Nat)
+                  -> P (#Bytes.cons x
+                         (#Bytes.take (#Nat.sub (#Bytes.len xs) j) xs)))
+              -> P (#Bytes.take (#Nat.sub (#Bytes.len xs) i) xs)
+          "
+      ),
     }
   }
 
@@ -103,6 +120,7 @@ impl BytesOp {
       Self::Remove => Ipld::Integer(8),
       Self::Index => Ipld::Integer(9),
       Self::ToBits => Ipld::Integer(10),
+      Self::View => Ipld::Integer(11),
     }
   }
 
@@ -120,6 +138,7 @@ impl BytesOp {
       Ipld::Integer(8) => Ok(Self::Remove),
       Ipld::Integer(9) => Ok(Self::Index),
       Ipld::Integer(10) => Ok(Self::ToBits),
+      Ipld::Integer(11) => Ok(Self::View),
       xs => Err(IpldError::BytesOp(xs.to_owned())),
     }
   }
@@ -138,6 +157,7 @@ impl BytesOp {
       Self::Remove => 2,
       Self::Index => 2,
       Self::ToBits => 2,
+      Self::View => 2,
     }
   }
 
@@ -234,6 +254,67 @@ impl BytesOp {
       _ => None,
     }
   }
+
+  /// Whether the operation returns an updated copy of its `#Bytes` argument
+  pub fn updates(self) -> bool {
+    matches!(self, Self::Cons | Self::Append | Self::Insert | Self::Remove)
+  }
+
+  /// Applies an updating operation like `apply2` or `apply3`, but takes the
+  /// bytes out of the arguments instead of cloning them
+  pub fn apply_in_place(self, args: &mut [Literal]) -> Option<Literal> {
+    use Literal::*;
+    match (self, args) {
+      (Self::Cons, [U8(x), Bytes(xs)]) => {
+        let mut xs = mem::take(xs);
+        xs.push(*x);
+        Some(Bytes(xs))
+      }
+      (Self::Append, [Bytes(xs), Bytes(ys)]) => {
+        let mut xs = mem::take(xs);
+        xs.extend_from_slice(ys);
+        Some(Bytes(xs))
+      }
+      (Self::Remove, [Nat(idx), Bytes(xs)]) => {
+        let mut xs = mem::take(xs);
+        let idx = usize::try_from(&*idx).unwrap_or(usize::MAX);
+        if idx < xs.len() {
+          xs.remove(idx);
+        }
+        Some(Bytes(xs))
+      }
+      (Self::Insert, [Nat(idx), U8(y), Bytes(xs)]) => {
+        let mut xs = mem::take(xs);
+        let idx = usize::try_from(&*idx).unwrap_or(usize::MAX);
+        if idx < xs.len() {
+          xs.insert(idx, *y);
+        }
+        Some(Bytes(xs))
+      }
+      _ => None,
+    }
+  }
+
+  /// Applies a binary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply2_term(self, x: &Literal, y: &Literal) -> Option<Term> {
+    use Literal::*;
+    match (self, x, y) {
+      (Self::View, Nat(idx), Bytes(xs)) => {
+        Some(view_term(idx, safe_view(idx, xs).map(U8)))
+      }
+      _ => None,
+    }
+  }
+}
+
+/// Gets the byte at a given index from the head of the bytes, in the same
+/// order as `Literal::expand`
+pub fn safe_view(idx: &BigUint, xs: &[u8]) -> Option<u8> {
+  match usize::try_from(idx) {
+    Ok(idx) if idx < xs.len() => Some(xs[xs.len() - 1 - idx]),
+    _ => None,
+  }
 }
 
 /// Split bytes
@@ -282,7 +363,7 @@ pub mod tests {
   impl Arbitrary for BytesOp {
     fn arbitrary(_g: &mut Gen) -> Self {
       let mut rng = rand::thread_rng();
-      let gen: u32 = rng.gen_range(0..=10);
+      let gen: u32 = rng.gen_range(0..=11);
       match gen {
         0 => Self::Cons,
         1 => Self::Len,
@@ -294,7 +375,8 @@ pub mod tests {
         7 => Self::Insert,
         8 => Self::Remove,
         9 => Self::Index,
-        _ => Self::ToBits,
+        10 => Self::ToBits,
+        _ => Self::View,
       }
     }
   }
@@ -307,6 +389,22 @@ pub mod tests {
     }
   }
 
+  #[test]
+  fn test_view_type() {
+    // The motive of an empty view is applied to the empty bytes
+    let mut typ = BytesOp::View.type_of();
+    let mut doms = vec![];
+    while let Term::All(_, _, _, dom_img) = typ {
+      let (dom, img) = *dom_img;
+      doms.push(dom);
+      typ = img;
+    }
+    match &doms[3] {
+      Term::App(_, fun_arg) => assert_eq!(fun_arg.1, yatima!("x''")),
+      dom => panic!("Unexpected nil type {:?}", dom),
+    }
+  }
+
   #[quickcheck]
   fn test_apply(
     op: BytesOp,
@@ -345,6 +443,12 @@ pub mod tests {
       )
     };
 
+    let apply2_nat_bytes_term = |expected: Option<Term>| -> TestResult {
+      TestResult::from_bool(
+        BytesOp::apply2_term(op, &Nat(big(c)), &Bytes(a.clone())) == expected,
+      )
+    };
+
     let from_bool = TestResult::from_bool;
 
     match op {
@@ -417,9 +521,39 @@ pub mod tests {
           },
         }
       }
+      BytesOp::View => {
+        let idx = usize::try_from(c);
+        let x = match idx {
+          Ok(idx) if idx < a.len() => Some(U8(a[a.len() - 1 - idx])),
+          _ => None,
+        };
+        apply2_nat_bytes_term(Some(view_term(&big(c), x)))
+      }
     }
   }
 
+  #[quickcheck]
+  fn test_apply_in_place(
+    op: BytesOp,
+    a: Vec<u8>,
+    b: u8,
+    c: u8,
+    d: Vec<u8>,
+  ) -> TestResult {
+    let args = match op {
+      BytesOp::Cons => vec![U8(b), Bytes(a)],
+      BytesOp::Append => vec![Bytes(a), Bytes(d)],
+      BytesOp::Remove => vec![Nat(c.into()), Bytes(a)],
+      BytesOp::Insert => vec![Nat(c.into()), U8(b), Bytes(a)],
+      _ => return TestResult::from_bool(!op.updates()),
+    };
+    let expected = match op.arity() {
+      2 => op.apply2(&args[0], &args[1]),
+      _ => op.apply3(&args[0], &args[1], &args[2]),
+    };
+    TestResult::from_bool(op.apply_in_place(&mut args.clone()) == expected)
+  }
+
   #[quickcheck]
   fn test_apply_none_on_invalid(
     op: BytesOp,
@@ -497,6 +631,28 @@ pub mod tests {
       }
     };
 
+    let test_apply2_term_none_on_invalid =
+      |valid_arg: Literal, a_: Literal, b_: Literal| -> TestResult {
+        let go =
+          || TestResult::from_bool(BytesOp::apply2_term(op, &a_, &b_) == None);
+        if test_arg_2 {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&a_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+        else {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&b_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+      };
+
     match op {
       // Arity 1, valid is Bytes.
       BytesOp::Len | BytesOp::Head | BytesOp::Tail => {
@@ -524,6 +680,15 @@ pub mod tests {
           test_apply2_none_on_invalid(Bytes(b), Nat(big(d)), a)
         }
       }
+      // Arity 2 with a term result, valid are Nat on a and Bytes on b.
+      BytesOp::View => {
+        if test_arg_2 {
+          test_apply2_term_none_on_invalid(Nat(big(d)), a, Bytes(b))
+        }
+        else {
+          test_apply2_term_none_on_invalid(Bytes(b), Nat(big(d)), a)
+        }
+      }
       // Arity 2, valid are Bytes on a and b.
       BytesOp::Append => {
         if test_arg_2 {
@@ -0,0 +1,100 @@
+use crate::{
+  anon::Anon,
+  ipld_error::IpldError,
+  meta::Meta,
+  term::Term,
+};
+
+use sp_ipld::Ipld;
+
+use sp_std::{
+  borrow::ToOwned,
+  boxed::Box,
+};
+
+use alloc::string::String;
+
+/// A host primitive bound by an `extern` declaration. The core only knows its
+/// symbol and declared type, so it never reduces; the runtime resolves the
+/// symbol to an implementation before evaluation.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ExtOp {
+  pub symbol: String,
+  pub typ: Box<Term>,
+}
+
+impl ExtOp {
+  /// Gets the syntax string of an extern primitive
+  pub fn symbol(&self) -> String { format!("\"{}\"", self.symbol) }
+
+  /// Returns the declared type of an extern primitive
+  pub fn type_of(&self) -> Term { (*self.typ).clone() }
+
+  /// Converts an extern primitive into an IPLD object
+  pub fn to_ipld(&self) -> Ipld {
+    let (anon, meta) = self.typ.embed();
+    Ipld::List(vec![
+      Ipld::String(self.symbol.clone()),
+      anon.to_ipld(),
+      meta.to_ipld(),
+    ])
+  }
+
+  /// Converts an IPLD object into an extern primitive
+  pub fn from_ipld(ipld: &Ipld) -> Result<Self, IpldError> {
+    match ipld {
+      Ipld::List(xs) => match xs.as_slice() {
+        [Ipld::String(symbol), anon, meta] => {
+          let anon = Anon::from_ipld(anon)?;
+          let meta = Meta::from_ipld(meta)?;
+          let typ = Term::unembed(&anon, &meta)
+            .map_err(|_| IpldError::ExtOp(ipld.to_owned()))?;
+          Ok(ExtOp { symbol: symbol.clone(), typ: Box::new(typ) })
+        }
+        _ => Err(IpldError::ExtOp(ipld.to_owned())),
+      },
+      _ => Err(IpldError::ExtOp(ipld.to_owned())),
+    }
+  }
+
+  /// Returns the number of parameters used in the operation. Extern
+  /// primitives are opaque to the core and so take none.
+  pub fn arity(&self) -> u64 { 0 }
+}
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+  use crate::{
+    defs,
+    parse,
+    position::Pos,
+    prim::Op,
+    yatima,
+  };
+
+  #[test]
+  fn ext_op_ipld() {
+    let op = ExtOp {
+      symbol: String::from("io.print"),
+      typ: Box::new(yatima!("∀ (0 A: Type) (x: A) -> #Bytes")),
+    };
+    assert_eq!(ExtOp::from_ipld(&op.to_ipld()), Ok(op));
+  }
+
+  #[test]
+  fn ext_op_display() {
+    let op = Term::Opr(
+      Pos::None,
+      Op::Extern(ExtOp {
+        symbol: String::from("io.print"),
+        typ: Box::new(yatima!("∀ (0 A: Type) #Bytes -> A")),
+      }),
+    );
+    let s = format!("{}", op);
+    assert_eq!(s, "#extern \"io.print\" (∀ (0 A: Type) (_: #Bytes) -> A)");
+    assert_eq!(parse::term::parse(&s, defs::Defs::new()).unwrap().1, op);
+    let app = format!("{} #Bool x'ff'", op);
+    assert!(parse::term::parse(&app, defs::Defs::new()).is_ok());
+  }
+}
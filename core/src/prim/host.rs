@@ -0,0 +1,263 @@
+//! Host functions: Rust closures registered under a name with a declared
+//! Yatima type. Programs reach them through `extern` declarations whose symbol
+//! is the registered name.
+
+use crate::{
+  literal::Literal,
+  prim::io::IoOp,
+  term::Term,
+};
+
+use sp_std::{
+  collections::btree_map::BTreeMap,
+  rc::Rc,
+  vec::Vec,
+};
+
+use alloc::string::{
+  String,
+  ToString,
+};
+
+use num_bigint::{
+  BigInt,
+  BigUint,
+};
+use sp_ropey::Rope;
+
+/// Converts a literal argument of a host function into a Rust value
+pub trait FromLiteral: Sized {
+  /// Returns `None` if the literal has the wrong type
+  fn from_literal(lit: &Literal) -> Option<Self>;
+}
+
+/// Converts the result of a host function into a literal
+pub trait IntoLiteral {
+  fn into_literal(self) -> Literal;
+}
+
+macro_rules! literal_conversions {
+  ($($t:ty => $variant:ident),* $(,)?) => {$(
+    impl FromLiteral for $t {
+      fn from_literal(lit: &Literal) -> Option<Self> {
+        match lit {
+          Literal::$variant(x) => Some(x.clone()),
+          _ => None,
+        }
+      }
+    }
+
+    impl IntoLiteral for $t {
+      fn into_literal(self) -> Literal { Literal::$variant(self) }
+    }
+  )*};
+}
+
+literal_conversions!(
+  BigUint => Nat,
+  BigInt => Int,
+  Vec<bool> => Bits,
+  Vec<u8> => Bytes,
+  Rope => Text,
+  char => Char,
+  bool => Bool,
+  u8 => U8,
+  u16 => U16,
+  u32 => U32,
+  u64 => U64,
+  u128 => U128,
+  i8 => I8,
+  i16 => I16,
+  i32 => I32,
+  i64 => I64,
+  i128 => I128,
+);
+
+impl FromLiteral for String {
+  fn from_literal(lit: &Literal) -> Option<Self> {
+    match lit {
+      Literal::Text(x) => Some(x.to_string()),
+      _ => None,
+    }
+  }
+}
+
+impl IntoLiteral for String {
+  fn into_literal(self) -> Literal { Literal::Text(self.as_str().into()) }
+}
+
+impl FromLiteral for Literal {
+  fn from_literal(lit: &Literal) -> Option<Self> { Some(lit.clone()) }
+}
+
+impl IntoLiteral for Literal {
+  fn into_literal(self) -> Literal { self }
+}
+
+/// A registry of host functions by name
+#[derive(Clone, Debug, Default)]
+pub struct HostFns {
+  fns: BTreeMap<String, IoOp>,
+}
+
+impl HostFns {
+  pub fn new() -> Self { HostFns { fns: BTreeMap::new() } }
+
+  /// Registers a function of any arity over raw literals. It receives exactly
+  /// `arity` arguments, first argument first, and returns `None` to leave the
+  /// application unreduced.
+  pub fn register(
+    &mut self,
+    name: &str,
+    typ: Term,
+    arity: u64,
+    f: impl Fn(&[Literal]) -> Option<Literal> + 'static,
+  ) {
+    let symbol = name.to_string();
+    let op = IoOp {
+      fun_symbol: Rc::new(move || symbol.clone()),
+      fun_type_of: Rc::new(move || typ.clone()),
+      fun_arity: Rc::new(move || arity),
+      fun_apply: Rc::new(f),
+      fun_apply_term: Rc::new(|_: &[Literal]| -> Option<Term> { None }),
+    };
+    self.fns.insert(name.to_string(), op);
+  }
+
+  /// Registers a constant computed by the host each time it is evaluated
+  pub fn register0<R: IntoLiteral>(
+    &mut self,
+    name: &str,
+    typ: Term,
+    f: impl Fn() -> R + 'static,
+  ) {
+    self.register(name, typ, 0, move |_| Some(f().into_literal()))
+  }
+
+  pub fn register1<A: FromLiteral, R: IntoLiteral>(
+    &mut self,
+    name: &str,
+    typ: Term,
+    f: impl Fn(A) -> R + 'static,
+  ) {
+    self.register(name, typ, 1, move |args| match args {
+      [a] => Some(f(A::from_literal(a)?).into_literal()),
+      _ => None,
+    })
+  }
+
+  pub fn register2<A: FromLiteral, B: FromLiteral, R: IntoLiteral>(
+    &mut self,
+    name: &str,
+    typ: Term,
+    f: impl Fn(A, B) -> R + 'static,
+  ) {
+    self.register(name, typ, 2, move |args| match args {
+      [a, b] => {
+        Some(f(A::from_literal(a)?, B::from_literal(b)?).into_literal())
+      }
+      _ => None,
+    })
+  }
+
+  pub fn register3<
+    A: FromLiteral,
+    B: FromLiteral,
+    C: FromLiteral,
+    R: IntoLiteral,
+  >(
+    &mut self,
+    name: &str,
+    typ: Term,
+    f: impl Fn(A, B, C) -> R + 'static,
+  ) {
+    self.register(name, typ, 3, move |args| match args {
+      [a, b, c] => Some(
+        f(A::from_literal(a)?, B::from_literal(b)?, C::from_literal(c)?)
+          .into_literal(),
+      ),
+      _ => None,
+    })
+  }
+
+  pub fn get(&self, name: &str) -> Option<&IoOp> { self.fns.get(name) }
+
+  /// The names of the registered functions, in order
+  pub fn names(&self) -> Vec<String> { self.fns.keys().cloned().collect() }
+
+  /// Checks the type given by an `extern` declaration against the type the
+  /// host function was registered with, ignoring binder names. Returns the
+  /// registered type on a mismatch.
+  pub fn check_type(&self, name: &str, declared: &Term) -> Result<(), Term> {
+    match self.get(name) {
+      Some(op) => {
+        let typ = op.type_of();
+        if typ.embed().0 == declared.embed().0 { Ok(()) } else { Err(typ) }
+      }
+      None => Ok(()),
+    }
+  }
+}
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+  use crate::{
+    defs,
+    parse,
+    yatima,
+  };
+
+  #[test]
+  fn test_register() {
+    let mut fns = HostFns::new();
+    fns.register2("add", yatima!("∀ #U64 #U64 -> #U64"), |x: u64, y: u64| {
+      x.wrapping_add(y)
+    });
+    fns.register1("greet", yatima!("∀ #Text -> #Text"), |name: String| {
+      format!("hello, {}", name)
+    });
+    fns.register3(
+      "mul_add",
+      yatima!("∀ #I32 #I32 #I32 -> #I32"),
+      |x: i32, y: i32, z: i32| x * y + z,
+    );
+    assert_eq!(fns.names(), vec!["add", "greet", "mul_add"]);
+    let add = fns.get("add").unwrap();
+    assert_eq!(add.symbol(), "add");
+    assert_eq!(add.arity(), 2);
+    assert_eq!(
+      add.apply(&[Literal::U64(2), Literal::U64(3)]),
+      Some(Literal::U64(5))
+    );
+    // Arguments of the wrong type leave the application unreduced
+    assert_eq!(add.apply(&[Literal::U64(2), Literal::Bool(true)]), None);
+    assert_eq!(
+      fns.get("greet").unwrap().apply(&[Literal::Text("yatima".into())]),
+      Some(Literal::Text("hello, yatima".into()))
+    );
+    assert_eq!(
+      fns.get("mul_add").unwrap().apply(&[
+        Literal::I32(3),
+        Literal::I32(-4),
+        Literal::I32(5)
+      ]),
+      Some(Literal::I32(-7))
+    );
+  }
+
+  #[test]
+  fn test_check_type() {
+    let mut fns = HostFns::new();
+    fns.register1("not", yatima!("∀ #Bool -> #Bool"), |x: bool| !x);
+    assert_eq!(
+      fns.check_type("not", &yatima!("∀ (b: #Bool) -> #Bool")),
+      Ok(())
+    );
+    assert_eq!(
+      fns.check_type("not", &yatima!("∀ #Nat -> #Bool")),
+      Err(yatima!("∀ #Bool -> #Bool"))
+    );
+    assert_eq!(fns.check_type("unknown", &yatima!("#Nat")), Ok(()));
+  }
+}
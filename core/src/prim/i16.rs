@@ -13,7 +13,10 @@ use crate::{
   ipld_error::IpldError,
   literal::Literal,
   parse,
-  prim::bits,
+  prim::{
+    bits,
+    option_term,
+  },
   term::Term,
   yatima,
 };
@@ -61,6 +64,15 @@ pub enum I16Op {
   ToInt,
   ToBits,
   ToBytes,
+  AddChecked,
+  SubChecked,
+  MulChecked,
+  AddWrapping,
+  SubWrapping,
+  MulWrapping,
+  AddSaturating,
+  SubSaturating,
+  MulSaturating,
 }
 
 impl I16Op {
@@ -105,6 +117,15 @@ impl I16Op {
       Self::ToInt => "to_Int".to_owned(),
       Self::ToBits => "to_Bits".to_owned(),
       Self::ToBytes => "to_Bytes".to_owned(),
+      Self::AddChecked => "add_checked".to_owned(),
+      Self::SubChecked => "sub_checked".to_owned(),
+      Self::MulChecked => "mul_checked".to_owned(),
+      Self::AddWrapping => "add_wrapping".to_owned(),
+      Self::SubWrapping => "sub_wrapping".to_owned(),
+      Self::MulWrapping => "mul_wrapping".to_owned(),
+      Self::AddSaturating => "add_saturating".to_owned(),
+      Self::SubSaturating => "sub_saturating".to_owned(),
+      Self::MulSaturating => "mul_saturating".to_owned(),
     }
   }
 
@@ -149,6 +170,15 @@ impl I16Op {
       "to_Int" => Some(Self::ToInt),
       "to_Bits" => Some(Self::ToBits),
       "to_Bytes" => Some(Self::ToBytes),
+      "add_checked" => Some(Self::AddChecked),
+      "sub_checked" => Some(Self::SubChecked),
+      "mul_checked" => Some(Self::MulChecked),
+      "add_wrapping" => Some(Self::AddWrapping),
+      "sub_wrapping" => Some(Self::SubWrapping),
+      "mul_wrapping" => Some(Self::MulWrapping),
+      "add_saturating" => Some(Self::AddSaturating),
+      "sub_saturating" => Some(Self::SubSaturating),
+      "mul_saturating" => Some(Self::MulSaturating),
       _ => None,
     }
   }
@@ -194,6 +224,24 @@ impl I16Op {
       Self::ToInt => yatima!("∀ #I16 -> #Int"),
       Self::ToBits => yatima!("∀ #I16 -> #Bits"),
       Self::ToBytes => yatima!("∀ #I16 -> #Bytes"),
+      Self::AddChecked => yatima!(
+        "∀ #I16 #I16
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #I16 -> P) -> P"
+      ),
+      Self::SubChecked => yatima!(
+        "∀ #I16 #I16
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #I16 -> P) -> P"
+      ),
+      Self::MulChecked => yatima!(
+        "∀ #I16 #I16
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #I16 -> P) -> P"
+      ),
+      Self::AddWrapping => yatima!("∀ #I16 #I16 -> #I16"),
+      Self::SubWrapping => yatima!("∀ #I16 #I16 -> #I16"),
+      Self::MulWrapping => yatima!("∀ #I16 #I16 -> #I16"),
+      Self::AddSaturating => yatima!("∀ #I16 #I16 -> #I16"),
+      Self::SubSaturating => yatima!("∀ #I16 #I16 -> #I16"),
+      Self::MulSaturating => yatima!("∀ #I16 #I16 -> #I16"),
     }
   }
 
@@ -238,6 +286,15 @@ impl I16Op {
       Self::ToInt => Ipld::Integer(35),
       Self::ToBits => Ipld::Integer(36),
       Self::ToBytes => Ipld::Integer(37),
+      Self::AddChecked => Ipld::Integer(38),
+      Self::SubChecked => Ipld::Integer(39),
+      Self::MulChecked => Ipld::Integer(40),
+      Self::AddWrapping => Ipld::Integer(41),
+      Self::SubWrapping => Ipld::Integer(42),
+      Self::MulWrapping => Ipld::Integer(43),
+      Self::AddSaturating => Ipld::Integer(44),
+      Self::SubSaturating => Ipld::Integer(45),
+      Self::MulSaturating => Ipld::Integer(46),
     }
   }
 
@@ -282,6 +339,15 @@ impl I16Op {
       Ipld::Integer(35) => Ok(Self::ToInt),
       Ipld::Integer(36) => Ok(Self::ToBits),
       Ipld::Integer(37) => Ok(Self::ToBytes),
+      Ipld::Integer(38) => Ok(Self::AddChecked),
+      Ipld::Integer(39) => Ok(Self::SubChecked),
+      Ipld::Integer(40) => Ok(Self::MulChecked),
+      Ipld::Integer(41) => Ok(Self::AddWrapping),
+      Ipld::Integer(42) => Ok(Self::SubWrapping),
+      Ipld::Integer(43) => Ok(Self::MulWrapping),
+      Ipld::Integer(44) => Ok(Self::AddSaturating),
+      Ipld::Integer(45) => Ok(Self::SubSaturating),
+      Ipld::Integer(46) => Ok(Self::MulSaturating),
       xs => Err(IpldError::NatOp(xs.to_owned())),
     }
   }
@@ -327,6 +393,15 @@ impl I16Op {
       Self::ToInt => 1,
       Self::ToBits => 1,
       Self::ToBytes => 1,
+      Self::AddChecked => 2,
+      Self::SubChecked => 2,
+      Self::MulChecked => 2,
+      Self::AddWrapping => 2,
+      Self::SubWrapping => 2,
+      Self::MulWrapping => 2,
+      Self::AddSaturating => 2,
+      Self::SubSaturating => 2,
+      Self::MulSaturating => 2,
     }
   }
 
@@ -411,6 +486,30 @@ impl I16Op {
       (Self::Shr, U32(x), I16(y)) => Some(I16(y.wrapping_shr(*x))),
       (Self::Rol, U32(x), I16(y)) => Some(I16(y.rotate_left(*x))),
       (Self::Ror, U32(x), I16(y)) => Some(I16(y.rotate_right(*x))),
+      (Self::AddWrapping, I16(x), I16(y)) => Some(I16(x.wrapping_add(*y))),
+      (Self::SubWrapping, I16(x), I16(y)) => Some(I16(x.wrapping_sub(*y))),
+      (Self::MulWrapping, I16(x), I16(y)) => Some(I16(x.wrapping_mul(*y))),
+      (Self::AddSaturating, I16(x), I16(y)) => Some(I16(x.saturating_add(*y))),
+      (Self::SubSaturating, I16(x), I16(y)) => Some(I16(x.saturating_sub(*y))),
+      (Self::MulSaturating, I16(x), I16(y)) => Some(I16(x.saturating_mul(*y))),
+      _ => None,
+    }
+  }
+
+  /// Applies a binary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply2_term(self, x: &Literal, y: &Literal) -> Option<Term> {
+    use Literal::*;
+    match (self, x, y) {
+      (Self::AddChecked, I16(x), I16(y)) => {
+        Some(option_term(x.checked_add(*y).map(I16)))
+      }
+      (Self::SubChecked, I16(x), I16(y)) => {
+        Some(option_term(x.checked_sub(*y).map(I16)))
+      }
+      (Self::MulChecked, I16(x), I16(y)) => {
+        Some(option_term(x.checked_mul(*y).map(I16)))
+      }
       _ => None,
     }
   }
@@ -458,7 +557,7 @@ pub mod tests {
   impl Arbitrary for I16Op {
     fn arbitrary(_g: &mut Gen) -> Self {
       let mut rng = rand::thread_rng();
-      let gen: u32 = rng.gen_range(0..=35);
+      let gen: u32 = rng.gen_range(0..=44);
       match gen {
         0 => Self::Abs,
         1 => Self::Sgn,
@@ -495,7 +594,16 @@ pub mod tests {
         32 => Self::ToI64,
         33 => Self::ToInt,
         34 => Self::ToBits,
-        _ => Self::ToBytes,
+        35 => Self::ToBytes,
+        36 => Self::AddChecked,
+        37 => Self::SubChecked,
+        38 => Self::MulChecked,
+        39 => Self::AddWrapping,
+        40 => Self::SubWrapping,
+        41 => Self::MulWrapping,
+        42 => Self::AddSaturating,
+        43 => Self::SubSaturating,
+        _ => Self::MulSaturating,
         /* 29 => Self::ToU128,
          * 34 => Self::ToI128, */
       }
@@ -532,6 +640,12 @@ pub mod tests {
       TestResult::from_bool(I16Op::apply2(op, &U32(c), &I16(a)) == expected)
     };
 
+    let apply2_i16_i16_term = |expected: Option<Term>| -> TestResult {
+      TestResult::from_bool(
+        I16Op::apply2_term(op, &I16(a), &I16(b)) == expected,
+      )
+    };
+
     let from_bool = TestResult::from_bool;
 
     match op {
@@ -622,6 +736,21 @@ pub mod tests {
         apply1_i16(Some(Bits(bits::bytes_to_bits(16, &a.to_be_bytes().into()))))
       }
       I16Op::ToBytes => apply1_i16(Some(Bytes(a.to_be_bytes().into()))),
+      I16Op::AddChecked => {
+        apply2_i16_i16_term(Some(option_term(a.checked_add(b).map(I16))))
+      }
+      I16Op::SubChecked => {
+        apply2_i16_i16_term(Some(option_term(a.checked_sub(b).map(I16))))
+      }
+      I16Op::MulChecked => {
+        apply2_i16_i16_term(Some(option_term(a.checked_mul(b).map(I16))))
+      }
+      I16Op::AddWrapping => apply2_i16_i16(Some(I16(a.wrapping_add(b)))),
+      I16Op::SubWrapping => apply2_i16_i16(Some(I16(a.wrapping_sub(b)))),
+      I16Op::MulWrapping => apply2_i16_i16(Some(I16(a.wrapping_mul(b)))),
+      I16Op::AddSaturating => apply2_i16_i16(Some(I16(a.saturating_add(b)))),
+      I16Op::SubSaturating => apply2_i16_i16(Some(I16(a.saturating_sub(b)))),
+      I16Op::MulSaturating => apply2_i16_i16(Some(I16(a.saturating_mul(b)))),
     }
   }
 
@@ -663,6 +792,28 @@ pub mod tests {
         }
       };
 
+    let test_apply2_term_none_on_invalid =
+      |valid_arg: Literal, a_: Literal, b_: Literal| -> TestResult {
+        let go =
+          || TestResult::from_bool(I16Op::apply2_term(op, &a_, &b_) == None);
+        if test_arg_2 {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&a_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+        else {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&b_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+      };
+
     match op {
       // Arity 0.
       I16Op::Max | I16Op::Min => TestResult::discard(),
@@ -698,7 +849,13 @@ pub mod tests {
       | I16Op::Sub
       | I16Op::Mul
       | I16Op::Div
-      | I16Op::Mod => {
+      | I16Op::Mod
+      | I16Op::AddWrapping
+      | I16Op::SubWrapping
+      | I16Op::MulWrapping
+      | I16Op::AddSaturating
+      | I16Op::SubSaturating
+      | I16Op::MulSaturating => {
         if test_arg_2 {
           test_apply2_none_on_invalid(I16(b), a, I16(b))
         }
@@ -706,6 +863,15 @@ pub mod tests {
           test_apply2_none_on_invalid(I16(b), I16(b), a)
         }
       }
+      // Arity 2 with a term result, valid are I16 on a and b.
+      I16Op::AddChecked | I16Op::SubChecked | I16Op::MulChecked => {
+        if test_arg_2 {
+          test_apply2_term_none_on_invalid(I16(b), a, I16(b))
+        }
+        else {
+          test_apply2_term_none_on_invalid(I16(b), I16(b), a)
+        }
+      }
       // Arity 2, valid are I16 on a and U32 on b.
       I16Op::Pow => {
         if test_arg_2 {
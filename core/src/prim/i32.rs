@@ -13,7 +13,10 @@ use crate::{
   ipld_error::IpldError,
   literal::Literal,
   parse,
-  prim::bits,
+  prim::{
+    bits,
+    option_term,
+  },
   term::Term,
   yatima,
 };
@@ -61,6 +64,15 @@ pub enum I32Op {
   ToInt,
   ToBits,
   ToBytes,
+  AddChecked,
+  SubChecked,
+  MulChecked,
+  AddWrapping,
+  SubWrapping,
+  MulWrapping,
+  AddSaturating,
+  SubSaturating,
+  MulSaturating,
 }
 
 impl I32Op {
@@ -104,6 +116,15 @@ impl I32Op {
       Self::ToI128 => "to_I128".to_owned(),
       Self::ToInt => "to_Int".to_owned(),
       Self::ToBytes => "to_Bytes".to_owned(),
+      Self::AddChecked => "add_checked".to_owned(),
+      Self::SubChecked => "sub_checked".to_owned(),
+      Self::MulChecked => "mul_checked".to_owned(),
+      Self::AddWrapping => "add_wrapping".to_owned(),
+      Self::SubWrapping => "sub_wrapping".to_owned(),
+      Self::MulWrapping => "mul_wrapping".to_owned(),
+      Self::AddSaturating => "add_saturating".to_owned(),
+      Self::SubSaturating => "sub_saturating".to_owned(),
+      Self::MulSaturating => "mul_saturating".to_owned(),
       Self::ToBits => "to_Bits".to_owned(),
     }
   }
@@ -149,6 +170,15 @@ impl I32Op {
       "to_Int" => Some(Self::ToInt),
       "to_Bits" => Some(Self::ToBits),
       "to_Bytes" => Some(Self::ToBytes),
+      "add_checked" => Some(Self::AddChecked),
+      "sub_checked" => Some(Self::SubChecked),
+      "mul_checked" => Some(Self::MulChecked),
+      "add_wrapping" => Some(Self::AddWrapping),
+      "sub_wrapping" => Some(Self::SubWrapping),
+      "mul_wrapping" => Some(Self::MulWrapping),
+      "add_saturating" => Some(Self::AddSaturating),
+      "sub_saturating" => Some(Self::SubSaturating),
+      "mul_saturating" => Some(Self::MulSaturating),
       _ => None,
     }
   }
@@ -193,6 +223,24 @@ impl I32Op {
       Self::ToI128 => yatima!("∀ #I32 -> #I128"),
       Self::ToInt => yatima!("∀ #I32 -> #Int"),
       Self::ToBytes => yatima!("∀ #I32 -> #Bytes"),
+      Self::AddChecked => yatima!(
+        "∀ #I32 #I32
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #I32 -> P) -> P"
+      ),
+      Self::SubChecked => yatima!(
+        "∀ #I32 #I32
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #I32 -> P) -> P"
+      ),
+      Self::MulChecked => yatima!(
+        "∀ #I32 #I32
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #I32 -> P) -> P"
+      ),
+      Self::AddWrapping => yatima!("∀ #I32 #I32 -> #I32"),
+      Self::SubWrapping => yatima!("∀ #I32 #I32 -> #I32"),
+      Self::MulWrapping => yatima!("∀ #I32 #I32 -> #I32"),
+      Self::AddSaturating => yatima!("∀ #I32 #I32 -> #I32"),
+      Self::SubSaturating => yatima!("∀ #I32 #I32 -> #I32"),
+      Self::MulSaturating => yatima!("∀ #I32 #I32 -> #I32"),
       Self::ToBits => yatima!("∀ #I32 -> #Bits"),
     }
   }
@@ -238,6 +286,15 @@ impl I32Op {
       Self::ToInt => Ipld::Integer(35),
       Self::ToBits => Ipld::Integer(36),
       Self::ToBytes => Ipld::Integer(37),
+      Self::AddChecked => Ipld::Integer(38),
+      Self::SubChecked => Ipld::Integer(39),
+      Self::MulChecked => Ipld::Integer(40),
+      Self::AddWrapping => Ipld::Integer(41),
+      Self::SubWrapping => Ipld::Integer(42),
+      Self::MulWrapping => Ipld::Integer(43),
+      Self::AddSaturating => Ipld::Integer(44),
+      Self::SubSaturating => Ipld::Integer(45),
+      Self::MulSaturating => Ipld::Integer(46),
     }
   }
 
@@ -282,6 +339,15 @@ impl I32Op {
       Ipld::Integer(35) => Ok(Self::ToInt),
       Ipld::Integer(36) => Ok(Self::ToBits),
       Ipld::Integer(37) => Ok(Self::ToBytes),
+      Ipld::Integer(38) => Ok(Self::AddChecked),
+      Ipld::Integer(39) => Ok(Self::SubChecked),
+      Ipld::Integer(40) => Ok(Self::MulChecked),
+      Ipld::Integer(41) => Ok(Self::AddWrapping),
+      Ipld::Integer(42) => Ok(Self::SubWrapping),
+      Ipld::Integer(43) => Ok(Self::MulWrapping),
+      Ipld::Integer(44) => Ok(Self::AddSaturating),
+      Ipld::Integer(45) => Ok(Self::SubSaturating),
+      Ipld::Integer(46) => Ok(Self::MulSaturating),
       xs => Err(IpldError::NatOp(xs.to_owned())),
     }
   }
@@ -327,6 +393,15 @@ impl I32Op {
       Self::ToInt => 1,
       Self::ToBits => 1,
       Self::ToBytes => 1,
+      Self::AddChecked => 2,
+      Self::SubChecked => 2,
+      Self::MulChecked => 2,
+      Self::AddWrapping => 2,
+      Self::SubWrapping => 2,
+      Self::MulWrapping => 2,
+      Self::AddSaturating => 2,
+      Self::SubSaturating => 2,
+      Self::MulSaturating => 2,
     }
   }
 
@@ -411,6 +486,30 @@ impl I32Op {
       (Self::Shr, U32(x), I32(y)) => Some(I32(y.wrapping_shr(*x))),
       (Self::Rol, U32(x), I32(y)) => Some(I32(y.rotate_left(*x))),
       (Self::Ror, U32(x), I32(y)) => Some(I32(y.rotate_right(*x))),
+      (Self::AddWrapping, I32(x), I32(y)) => Some(I32(x.wrapping_add(*y))),
+      (Self::SubWrapping, I32(x), I32(y)) => Some(I32(x.wrapping_sub(*y))),
+      (Self::MulWrapping, I32(x), I32(y)) => Some(I32(x.wrapping_mul(*y))),
+      (Self::AddSaturating, I32(x), I32(y)) => Some(I32(x.saturating_add(*y))),
+      (Self::SubSaturating, I32(x), I32(y)) => Some(I32(x.saturating_sub(*y))),
+      (Self::MulSaturating, I32(x), I32(y)) => Some(I32(x.saturating_mul(*y))),
+      _ => None,
+    }
+  }
+
+  /// Applies a binary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply2_term(self, x: &Literal, y: &Literal) -> Option<Term> {
+    use Literal::*;
+    match (self, x, y) {
+      (Self::AddChecked, I32(x), I32(y)) => {
+        Some(option_term(x.checked_add(*y).map(I32)))
+      }
+      (Self::SubChecked, I32(x), I32(y)) => {
+        Some(option_term(x.checked_sub(*y).map(I32)))
+      }
+      (Self::MulChecked, I32(x), I32(y)) => {
+        Some(option_term(x.checked_mul(*y).map(I32)))
+      }
       _ => None,
     }
   }
@@ -456,7 +555,7 @@ pub mod tests {
   impl Arbitrary for I32Op {
     fn arbitrary(_g: &mut Gen) -> Self {
       let mut rng = rand::thread_rng();
-      let gen: u32 = rng.gen_range(0..=35);
+      let gen: u32 = rng.gen_range(0..=44);
       match gen {
         0 => Self::Abs,
         1 => Self::Sgn,
@@ -493,7 +592,16 @@ pub mod tests {
         32 => Self::ToI64,
         33 => Self::ToInt,
         34 => Self::ToBytes,
-        _ => Self::ToBits,
+        35 => Self::ToBits,
+        36 => Self::AddChecked,
+        37 => Self::SubChecked,
+        38 => Self::MulChecked,
+        39 => Self::AddWrapping,
+        40 => Self::SubWrapping,
+        41 => Self::MulWrapping,
+        42 => Self::AddSaturating,
+        43 => Self::SubSaturating,
+        _ => Self::MulSaturating,
         /* 29 => Self::ToU128,
          * 34 => Self::ToI128, */
       }
@@ -530,6 +638,12 @@ pub mod tests {
       TestResult::from_bool(I32Op::apply2(op, &U32(c), &I32(a)) == expected)
     };
 
+    let apply2_i32_i32_term = |expected: Option<Term>| -> TestResult {
+      TestResult::from_bool(
+        I32Op::apply2_term(op, &I32(a), &I32(b)) == expected,
+      )
+    };
+
     let from_bool = TestResult::from_bool;
 
     match op {
@@ -627,6 +741,21 @@ pub mod tests {
         apply1_i32(Some(Bits(bits::bytes_to_bits(32, &a.to_be_bytes().into()))))
       }
       I32Op::ToBytes => apply1_i32(Some(Bytes(a.to_be_bytes().into()))),
+      I32Op::AddChecked => {
+        apply2_i32_i32_term(Some(option_term(a.checked_add(b).map(I32))))
+      }
+      I32Op::SubChecked => {
+        apply2_i32_i32_term(Some(option_term(a.checked_sub(b).map(I32))))
+      }
+      I32Op::MulChecked => {
+        apply2_i32_i32_term(Some(option_term(a.checked_mul(b).map(I32))))
+      }
+      I32Op::AddWrapping => apply2_i32_i32(Some(I32(a.wrapping_add(b)))),
+      I32Op::SubWrapping => apply2_i32_i32(Some(I32(a.wrapping_sub(b)))),
+      I32Op::MulWrapping => apply2_i32_i32(Some(I32(a.wrapping_mul(b)))),
+      I32Op::AddSaturating => apply2_i32_i32(Some(I32(a.saturating_add(b)))),
+      I32Op::SubSaturating => apply2_i32_i32(Some(I32(a.saturating_sub(b)))),
+      I32Op::MulSaturating => apply2_i32_i32(Some(I32(a.saturating_mul(b)))),
     }
   }
 
@@ -668,6 +797,28 @@ pub mod tests {
         }
       };
 
+    let test_apply2_term_none_on_invalid =
+      |valid_arg: Literal, a_: Literal, b_: Literal| -> TestResult {
+        let go =
+          || TestResult::from_bool(I32Op::apply2_term(op, &a_, &b_) == None);
+        if test_arg_2 {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&a_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+        else {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&b_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+      };
+
     match op {
       // Arity 0.
       I32Op::Max | I32Op::Min => TestResult::discard(),
@@ -703,7 +854,13 @@ pub mod tests {
       | I32Op::Sub
       | I32Op::Mul
       | I32Op::Div
-      | I32Op::Mod => {
+      | I32Op::Mod
+      | I32Op::AddWrapping
+      | I32Op::SubWrapping
+      | I32Op::MulWrapping
+      | I32Op::AddSaturating
+      | I32Op::SubSaturating
+      | I32Op::MulSaturating => {
         if test_arg_2 {
           test_apply2_none_on_invalid(I32(b), a, I32(b))
         }
@@ -711,6 +868,15 @@ pub mod tests {
           test_apply2_none_on_invalid(I32(b), I32(b), a)
         }
       }
+      // Arity 2 with a term result, valid are I32 on a and b.
+      I32Op::AddChecked | I32Op::SubChecked | I32Op::MulChecked => {
+        if test_arg_2 {
+          test_apply2_term_none_on_invalid(I32(b), a, I32(b))
+        }
+        else {
+          test_apply2_term_none_on_invalid(I32(b), I32(b), a)
+        }
+      }
       // Arity 2, valid are I32 on a and U32 on b.
       I32Op::Pow => {
         if test_arg_2 {
@@ -13,7 +13,10 @@ use crate::{
   ipld_error::IpldError,
   literal::Literal,
   parse,
-  prim::bits,
+  prim::{
+    bits,
+    option_term,
+  },
   term::Term,
   yatima,
 };
@@ -61,6 +64,15 @@ pub enum I8Op {
   ToInt,
   ToBits,
   ToBytes,
+  AddChecked,
+  SubChecked,
+  MulChecked,
+  AddWrapping,
+  SubWrapping,
+  MulWrapping,
+  AddSaturating,
+  SubSaturating,
+  MulSaturating,
 }
 
 impl I8Op {
@@ -105,6 +117,15 @@ impl I8Op {
       Self::ToInt => "to_Int".to_owned(),
       Self::ToBits => "to_Bits".to_owned(),
       Self::ToBytes => "to_Bytes".to_owned(),
+      Self::AddChecked => "add_checked".to_owned(),
+      Self::SubChecked => "sub_checked".to_owned(),
+      Self::MulChecked => "mul_checked".to_owned(),
+      Self::AddWrapping => "add_wrapping".to_owned(),
+      Self::SubWrapping => "sub_wrapping".to_owned(),
+      Self::MulWrapping => "mul_wrapping".to_owned(),
+      Self::AddSaturating => "add_saturating".to_owned(),
+      Self::SubSaturating => "sub_saturating".to_owned(),
+      Self::MulSaturating => "mul_saturating".to_owned(),
     }
   }
 
@@ -149,6 +170,15 @@ impl I8Op {
       "to_Int" => Some(Self::ToInt),
       "to_Bits" => Some(Self::ToBits),
       "to_Bytes" => Some(Self::ToBytes),
+      "add_checked" => Some(Self::AddChecked),
+      "sub_checked" => Some(Self::SubChecked),
+      "mul_checked" => Some(Self::MulChecked),
+      "add_wrapping" => Some(Self::AddWrapping),
+      "sub_wrapping" => Some(Self::SubWrapping),
+      "mul_wrapping" => Some(Self::MulWrapping),
+      "add_saturating" => Some(Self::AddSaturating),
+      "sub_saturating" => Some(Self::SubSaturating),
+      "mul_saturating" => Some(Self::MulSaturating),
       _ => None,
     }
   }
@@ -194,6 +224,24 @@ impl I8Op {
       Self::ToInt => yatima!("∀ #I8 -> #Int"),
       Self::ToBits => yatima!("∀ #I8 -> #Bits"),
       Self::ToBytes => yatima!("∀ #I8 -> #Bytes"),
+      Self::AddChecked => yatima!(
+        "∀ #I8 #I8
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #I8 -> P) -> P"
+      ),
+      Self::SubChecked => yatima!(
+        "∀ #I8 #I8
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #I8 -> P) -> P"
+      ),
+      Self::MulChecked => yatima!(
+        "∀ #I8 #I8
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #I8 -> P) -> P"
+      ),
+      Self::AddWrapping => yatima!("∀ #I8 #I8 -> #I8"),
+      Self::SubWrapping => yatima!("∀ #I8 #I8 -> #I8"),
+      Self::MulWrapping => yatima!("∀ #I8 #I8 -> #I8"),
+      Self::AddSaturating => yatima!("∀ #I8 #I8 -> #I8"),
+      Self::SubSaturating => yatima!("∀ #I8 #I8 -> #I8"),
+      Self::MulSaturating => yatima!("∀ #I8 #I8 -> #I8"),
     }
   }
 
@@ -238,6 +286,15 @@ impl I8Op {
       Self::ToInt => Ipld::Integer(35),
       Self::ToBits => Ipld::Integer(36),
       Self::ToBytes => Ipld::Integer(37),
+      Self::AddChecked => Ipld::Integer(38),
+      Self::SubChecked => Ipld::Integer(39),
+      Self::MulChecked => Ipld::Integer(40),
+      Self::AddWrapping => Ipld::Integer(41),
+      Self::SubWrapping => Ipld::Integer(42),
+      Self::MulWrapping => Ipld::Integer(43),
+      Self::AddSaturating => Ipld::Integer(44),
+      Self::SubSaturating => Ipld::Integer(45),
+      Self::MulSaturating => Ipld::Integer(46),
     }
   }
 
@@ -282,6 +339,15 @@ impl I8Op {
       Ipld::Integer(35) => Ok(Self::ToInt),
       Ipld::Integer(36) => Ok(Self::ToBits),
       Ipld::Integer(37) => Ok(Self::ToBytes),
+      Ipld::Integer(38) => Ok(Self::AddChecked),
+      Ipld::Integer(39) => Ok(Self::SubChecked),
+      Ipld::Integer(40) => Ok(Self::MulChecked),
+      Ipld::Integer(41) => Ok(Self::AddWrapping),
+      Ipld::Integer(42) => Ok(Self::SubWrapping),
+      Ipld::Integer(43) => Ok(Self::MulWrapping),
+      Ipld::Integer(44) => Ok(Self::AddSaturating),
+      Ipld::Integer(45) => Ok(Self::SubSaturating),
+      Ipld::Integer(46) => Ok(Self::MulSaturating),
       xs => Err(IpldError::NatOp(xs.to_owned())),
     }
   }
@@ -327,6 +393,15 @@ impl I8Op {
       Self::ToInt => 1,
       Self::ToBits => 1,
       Self::ToBytes => 1,
+      Self::AddChecked => 2,
+      Self::SubChecked => 2,
+      Self::MulChecked => 2,
+      Self::AddWrapping => 2,
+      Self::SubWrapping => 2,
+      Self::MulWrapping => 2,
+      Self::AddSaturating => 2,
+      Self::SubSaturating => 2,
+      Self::MulSaturating => 2,
     }
   }
 
@@ -411,6 +486,30 @@ impl I8Op {
       (Self::Shr, U32(x), I8(y)) => Some(I8(y.wrapping_shr(*x))),
       (Self::Rol, U32(x), I8(y)) => Some(I8(y.rotate_left(*x))),
       (Self::Ror, U32(x), I8(y)) => Some(I8(y.rotate_right(*x))),
+      (Self::AddWrapping, I8(x), I8(y)) => Some(I8(x.wrapping_add(*y))),
+      (Self::SubWrapping, I8(x), I8(y)) => Some(I8(x.wrapping_sub(*y))),
+      (Self::MulWrapping, I8(x), I8(y)) => Some(I8(x.wrapping_mul(*y))),
+      (Self::AddSaturating, I8(x), I8(y)) => Some(I8(x.saturating_add(*y))),
+      (Self::SubSaturating, I8(x), I8(y)) => Some(I8(x.saturating_sub(*y))),
+      (Self::MulSaturating, I8(x), I8(y)) => Some(I8(x.saturating_mul(*y))),
+      _ => None,
+    }
+  }
+
+  /// Applies a binary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply2_term(self, x: &Literal, y: &Literal) -> Option<Term> {
+    use Literal::*;
+    match (self, x, y) {
+      (Self::AddChecked, I8(x), I8(y)) => {
+        Some(option_term(x.checked_add(*y).map(I8)))
+      }
+      (Self::SubChecked, I8(x), I8(y)) => {
+        Some(option_term(x.checked_sub(*y).map(I8)))
+      }
+      (Self::MulChecked, I8(x), I8(y)) => {
+        Some(option_term(x.checked_mul(*y).map(I8)))
+      }
       _ => None,
     }
   }
@@ -458,7 +557,7 @@ pub mod tests {
   impl Arbitrary for I8Op {
     fn arbitrary(_g: &mut Gen) -> Self {
       let mut rng = rand::thread_rng();
-      let gen: u32 = rng.gen_range(0..=35);
+      let gen: u32 = rng.gen_range(0..=44);
       match gen {
         0 => Self::Abs,
         1 => Self::Sgn,
@@ -495,7 +594,16 @@ pub mod tests {
         32 => Self::ToI64,
         33 => Self::ToInt,
         34 => Self::ToBits,
-        _ => Self::ToBytes,
+        35 => Self::ToBytes,
+        36 => Self::AddChecked,
+        37 => Self::SubChecked,
+        38 => Self::MulChecked,
+        39 => Self::AddWrapping,
+        40 => Self::SubWrapping,
+        41 => Self::MulWrapping,
+        42 => Self::AddSaturating,
+        43 => Self::SubSaturating,
+        _ => Self::MulSaturating,
         /* 29 => Self::ToU128,
          * 34 => Self::ToI128, */
       }
@@ -532,6 +640,10 @@ pub mod tests {
       TestResult::from_bool(I8Op::apply2(op, &U32(c), &I8(a)) == expected)
     };
 
+    let apply2_i8_i8_term = |expected: Option<Term>| -> TestResult {
+      TestResult::from_bool(I8Op::apply2_term(op, &I8(a), &I8(b)) == expected)
+    };
+
     let from_bool = TestResult::from_bool;
 
     match op {
@@ -619,6 +731,21 @@ pub mod tests {
         apply1_i8(Some(Bits(bits::bytes_to_bits(8, &a.to_be_bytes().into()))))
       }
       I8Op::ToBytes => apply1_i8(Some(Bytes(a.to_be_bytes().into()))),
+      I8Op::AddChecked => {
+        apply2_i8_i8_term(Some(option_term(a.checked_add(b).map(I8))))
+      }
+      I8Op::SubChecked => {
+        apply2_i8_i8_term(Some(option_term(a.checked_sub(b).map(I8))))
+      }
+      I8Op::MulChecked => {
+        apply2_i8_i8_term(Some(option_term(a.checked_mul(b).map(I8))))
+      }
+      I8Op::AddWrapping => apply2_i8_i8(Some(I8(a.wrapping_add(b)))),
+      I8Op::SubWrapping => apply2_i8_i8(Some(I8(a.wrapping_sub(b)))),
+      I8Op::MulWrapping => apply2_i8_i8(Some(I8(a.wrapping_mul(b)))),
+      I8Op::AddSaturating => apply2_i8_i8(Some(I8(a.saturating_add(b)))),
+      I8Op::SubSaturating => apply2_i8_i8(Some(I8(a.saturating_sub(b)))),
+      I8Op::MulSaturating => apply2_i8_i8(Some(I8(a.saturating_mul(b)))),
     }
   }
 
@@ -660,6 +787,28 @@ pub mod tests {
         }
       };
 
+    let test_apply2_term_none_on_invalid =
+      |valid_arg: Literal, a_: Literal, b_: Literal| -> TestResult {
+        let go =
+          || TestResult::from_bool(I8Op::apply2_term(op, &a_, &b_) == None);
+        if test_arg_2 {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&a_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+        else {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&b_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+      };
+
     match op {
       // Arity 0.
       I8Op::Max | I8Op::Min => TestResult::discard(),
@@ -695,7 +844,13 @@ pub mod tests {
       | I8Op::Sub
       | I8Op::Mul
       | I8Op::Div
-      | I8Op::Mod => {
+      | I8Op::Mod
+      | I8Op::AddWrapping
+      | I8Op::SubWrapping
+      | I8Op::MulWrapping
+      | I8Op::AddSaturating
+      | I8Op::SubSaturating
+      | I8Op::MulSaturating => {
         if test_arg_2 {
           test_apply2_none_on_invalid(I8(b), a, I8(b))
         }
@@ -703,6 +858,15 @@ pub mod tests {
           test_apply2_none_on_invalid(I8(b), I8(b), a)
         }
       }
+      // Arity 2 with a term result, valid are I8 on a and b.
+      I8Op::AddChecked | I8Op::SubChecked | I8Op::MulChecked => {
+        if test_arg_2 {
+          test_apply2_term_none_on_invalid(I8(b), a, I8(b))
+        }
+        else {
+          test_apply2_term_none_on_invalid(I8(b), I8(b), a)
+        }
+      }
       // Arity 2, valid are I8 on a and U32 on b.
       I8Op::Pow => {
         if test_arg_2 {
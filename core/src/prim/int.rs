@@ -1,7 +1,9 @@
 use num_bigint::{
   BigInt,
+  BigUint,
   Sign,
 };
+use num_integer::Integer;
 use sp_ipld::Ipld;
 
 use sp_std::{
@@ -16,6 +18,10 @@ use crate::{
   ipld_error::IpldError,
   literal::Literal,
   parse,
+  prim::{
+    bits,
+    nat,
+  },
   term::Term,
   yatima,
 };
@@ -36,6 +42,19 @@ pub enum IntOp {
   Mul,
   Div,
   Mod,
+  ToText,
+  Pow,
+  Gcd,
+  Lcm,
+  Isqrt,
+  Log2,
+  Shl,
+  Shr,
+  And,
+  Or,
+  Xor,
+  ToBits,
+  FromBits,
 }
 
 impl IntOp {
@@ -55,6 +74,19 @@ impl IntOp {
       Self::Mul => "mul".to_owned(),
       Self::Div => "div".to_owned(),
       Self::Mod => "mod".to_owned(),
+      Self::ToText => "to_Text".to_owned(),
+      Self::Pow => "pow".to_owned(),
+      Self::Gcd => "gcd".to_owned(),
+      Self::Lcm => "lcm".to_owned(),
+      Self::Isqrt => "isqrt".to_owned(),
+      Self::Log2 => "log2".to_owned(),
+      Self::Shl => "shl".to_owned(),
+      Self::Shr => "shr".to_owned(),
+      Self::And => "and".to_owned(),
+      Self::Or => "or".to_owned(),
+      Self::Xor => "xor".to_owned(),
+      Self::ToBits => "to_Bits".to_owned(),
+      Self::FromBits => "from_Bits".to_owned(),
     }
   }
 
@@ -74,6 +106,19 @@ impl IntOp {
       "mul" => Some(Self::Mul),
       "div" => Some(Self::Div),
       "mod" => Some(Self::Mod),
+      "to_Text" => Some(Self::ToText),
+      "pow" => Some(Self::Pow),
+      "gcd" => Some(Self::Gcd),
+      "lcm" => Some(Self::Lcm),
+      "isqrt" => Some(Self::Isqrt),
+      "log2" => Some(Self::Log2),
+      "shl" => Some(Self::Shl),
+      "shr" => Some(Self::Shr),
+      "and" => Some(Self::And),
+      "or" => Some(Self::Or),
+      "xor" => Some(Self::Xor),
+      "to_Bits" => Some(Self::ToBits),
+      "from_Bits" => Some(Self::FromBits),
       _ => None,
     }
   }
@@ -94,6 +139,19 @@ impl IntOp {
       Self::Mul => yatima!("∀ #Int #Int -> #Int"),
       Self::Div => yatima!("∀ #Int #Int -> #Int"),
       Self::Mod => yatima!("∀ #Int #Int -> #Int"),
+      Self::ToText => yatima!("∀ #Int -> #Text"),
+      Self::Pow => yatima!("∀ #Int #U32 -> #Int"),
+      Self::Gcd => yatima!("∀ #Int #Int -> #Nat"),
+      Self::Lcm => yatima!("∀ #Int #Int -> #Nat"),
+      Self::Isqrt => yatima!("∀ #Int -> #Int"),
+      Self::Log2 => yatima!("∀ #Int -> #Nat"),
+      Self::Shl => yatima!("∀ #U32 #Int -> #Int"),
+      Self::Shr => yatima!("∀ #U32 #Int -> #Int"),
+      Self::And => yatima!("∀ #Int #Int -> #Int"),
+      Self::Or => yatima!("∀ #Int #Int -> #Int"),
+      Self::Xor => yatima!("∀ #Int #Int -> #Int"),
+      Self::ToBits => yatima!("∀ #Int -> #Bits"),
+      Self::FromBits => yatima!("∀ #Bits -> #Int"),
     }
   }
 
@@ -113,6 +171,19 @@ impl IntOp {
       Self::Mul => Ipld::Integer(10),
       Self::Div => Ipld::Integer(11),
       Self::Mod => Ipld::Integer(12),
+      Self::ToText => Ipld::Integer(13),
+      Self::Pow => Ipld::Integer(14),
+      Self::Gcd => Ipld::Integer(15),
+      Self::Lcm => Ipld::Integer(16),
+      Self::Isqrt => Ipld::Integer(17),
+      Self::Log2 => Ipld::Integer(18),
+      Self::Shl => Ipld::Integer(19),
+      Self::Shr => Ipld::Integer(20),
+      Self::And => Ipld::Integer(21),
+      Self::Or => Ipld::Integer(22),
+      Self::Xor => Ipld::Integer(23),
+      Self::ToBits => Ipld::Integer(24),
+      Self::FromBits => Ipld::Integer(25),
     }
   }
 
@@ -132,6 +203,19 @@ impl IntOp {
       Ipld::Integer(10) => Ok(Self::Mul),
       Ipld::Integer(11) => Ok(Self::Div),
       Ipld::Integer(12) => Ok(Self::Mod),
+      Ipld::Integer(13) => Ok(Self::ToText),
+      Ipld::Integer(14) => Ok(Self::Pow),
+      Ipld::Integer(15) => Ok(Self::Gcd),
+      Ipld::Integer(16) => Ok(Self::Lcm),
+      Ipld::Integer(17) => Ok(Self::Isqrt),
+      Ipld::Integer(18) => Ok(Self::Log2),
+      Ipld::Integer(19) => Ok(Self::Shl),
+      Ipld::Integer(20) => Ok(Self::Shr),
+      Ipld::Integer(21) => Ok(Self::And),
+      Ipld::Integer(22) => Ok(Self::Or),
+      Ipld::Integer(23) => Ok(Self::Xor),
+      Ipld::Integer(24) => Ok(Self::ToBits),
+      Ipld::Integer(25) => Ok(Self::FromBits),
       xs => Err(IpldError::IntOp(xs.to_owned())),
     }
   }
@@ -152,6 +236,19 @@ impl IntOp {
       Self::Mul => 2,
       Self::Div => 2,
       Self::Mod => 2,
+      Self::ToText => 1,
+      Self::Pow => 2,
+      Self::Gcd => 2,
+      Self::Lcm => 2,
+      Self::Isqrt => 1,
+      Self::Log2 => 1,
+      Self::Shl => 2,
+      Self::Shr => 2,
+      Self::And => 2,
+      Self::Or => 2,
+      Self::Xor => 2,
+      Self::ToBits => 1,
+      Self::FromBits => 1,
     }
   }
 
@@ -161,6 +258,18 @@ impl IntOp {
     match (self, x) {
       (Self::Sgn, Int(x)) => Some(Bool(matches!(x.sign(), Sign::Plus))),
       (Self::Abs, Int(x)) => Some(Nat(x.clone().into_parts().1)),
+      (Self::ToText, Int(x)) => Some(Text(x.to_str_radix(10).into())),
+      (Self::Isqrt, Int(x)) if x.sign() != Sign::Minus => Some(Int(x.sqrt())),
+      (Self::Log2, Int(x)) if x.sign() == Sign::Plus => {
+        Some(Nat(BigUint::from(x.bits() - 1)))
+      }
+      (Self::ToBits, Int(x)) => {
+        let bytes = x.to_signed_bytes_be();
+        Some(Bits(bits::bytes_to_bits(bytes.len() * 8, &bytes)))
+      }
+      (Self::FromBits, Bits(xs)) => {
+        Some(Int(BigInt::from_signed_bytes_be(&bits::bits_to_bytes(xs).1)))
+      }
       _ => None,
     }
   }
@@ -188,6 +297,18 @@ impl IntOp {
       (Self::Mul, Int(x), Int(y)) => Some(Int(x * y)),
       (Self::Div, Int(x), Int(y)) if *y != 0.into() => Some(Int(x / y)),
       (Self::Mod, Int(x), Int(y)) if *y != 0.into() => Some(Int(x % y)),
+      (Self::Pow, Int(x), U32(y)) if nat::pow_fits(x.bits(), *y) => {
+        Some(Int(x.pow(*y)))
+      }
+      (Self::Gcd, Int(x), Int(y)) => Some(Nat(x.gcd(y).into_parts().1)),
+      (Self::Lcm, Int(x), Int(y)) => Some(Nat(x.lcm(y).into_parts().1)),
+      (Self::Shl, U32(x), Int(y)) if nat::shl_fits(y.bits(), *x) => {
+        Some(Int(y << *x as usize))
+      }
+      (Self::Shr, U32(x), Int(y)) => Some(Int(y >> *x as usize)),
+      (Self::And, Int(x), Int(y)) => Some(Int(x & y)),
+      (Self::Or, Int(x), Int(y)) => Some(Int(x | y)),
+      (Self::Xor, Int(x), Int(y)) => Some(Int(x ^ y)),
       _ => None,
     }
   }
@@ -214,14 +335,17 @@ pub mod tests {
   use rand::Rng;
   use sp_std::mem;
   use Literal::{
+    Bits,
     Bool,
     Int,
     Nat,
+    Text,
+    U32,
   };
   impl Arbitrary for IntOp {
     fn arbitrary(_g: &mut Gen) -> Self {
       let mut rng = rand::thread_rng();
-      let gen: u32 = rng.gen_range(0..=12);
+      let gen: u32 = rng.gen_range(0..=25);
       match gen {
         0 => Self::New,
         1 => Self::Sgn,
@@ -235,7 +359,20 @@ pub mod tests {
         9 => Self::Sub,
         10 => Self::Mul,
         11 => Self::Div,
-        _ => Self::Mod,
+        12 => Self::Mod,
+        13 => Self::ToText,
+        14 => Self::Pow,
+        15 => Self::Gcd,
+        16 => Self::Lcm,
+        17 => Self::Isqrt,
+        18 => Self::Log2,
+        19 => Self::Shl,
+        20 => Self::Shr,
+        21 => Self::And,
+        22 => Self::Or,
+        23 => Self::Xor,
+        24 => Self::ToBits,
+        _ => Self::FromBits,
       }
     }
   }
@@ -248,10 +385,30 @@ pub mod tests {
     }
   }
 
+  #[test]
+  fn test_apply_too_large() {
+    let big = BigInt::from;
+    let pow = |x: i64, y| IntOp::apply2(IntOp::Pow, &Int(big(x)), &U32(y));
+    let shl = |x, y: i64| IntOp::apply2(IntOp::Shl, &U32(x), &Int(big(y)));
+    assert_eq!(pow(-10, u32::MAX), None);
+    assert_eq!(pow(-1, u32::MAX), Some(Int(big(-1))));
+    assert_eq!(shl(u32::MAX, -1), None);
+    assert_eq!(shl(u32::MAX, 0), Some(Int(big(0))));
+  }
+
   #[quickcheck]
-  fn test_apply(op: IntOp, a: i64, b: i64, c: bool, d: u64) -> TestResult {
+  fn test_apply(
+    op: IntOp,
+    a: i64,
+    b: i64,
+    c: bool,
+    d: u64,
+    e: u32,
+  ) -> TestResult {
     let big_int = BigInt::from;
     let big_uint = BigUint::from;
+    // Bound exponents and shifts so results stay small
+    let e = e % 128;
     let apply1_int = |expected: Option<Literal>| -> TestResult {
       TestResult::from_bool(IntOp::apply1(op, &Int(big_int(a))) == expected)
     };
@@ -268,6 +425,29 @@ pub mod tests {
       )
     };
 
+    let apply2_int_u32 = |expected: Option<Literal>| -> TestResult {
+      TestResult::from_bool(
+        IntOp::apply2(op, &Int(big_int(a)), &U32(e)) == expected,
+      )
+    };
+
+    let apply2_u32_int = |expected: Option<Literal>| -> TestResult {
+      TestResult::from_bool(
+        IntOp::apply2(op, &U32(e), &Int(big_int(a))) == expected,
+      )
+    };
+
+    let gcd_u64 = |mut x: u64, mut y: u64| -> u64 {
+      while y != 0 {
+        let r = x % y;
+        x = y;
+        y = r;
+      }
+      x
+    };
+
+    let from_bool = TestResult::from_bool;
+
     match op {
       IntOp::New => apply2_bool_nat(Some(Int(if c {
         BigInt::from(d)
@@ -297,6 +477,61 @@ pub mod tests {
       else {
         None
       }),
+      IntOp::ToText => apply1_int(Some(Text(a.to_string().into()))),
+      IntOp::Pow => {
+        let expected = (0..e).fold(big_int(1), |acc, _| acc * big_int(a));
+        apply2_int_u32(Some(Int(expected)))
+      }
+      IntOp::Gcd => apply2_int_int(Some(Nat(big_uint(gcd_u64(
+        a.unsigned_abs(),
+        b.unsigned_abs(),
+      ))))),
+      IntOp::Lcm => {
+        let (x, y) = (a.unsigned_abs(), b.unsigned_abs());
+        apply2_int_int(Some(Nat(if x == 0 || y == 0 {
+          big_uint(0)
+        }
+        else {
+          big_uint(x / gcd_u64(x, y)) * big_uint(y)
+        })))
+      }
+      IntOp::Isqrt => match IntOp::apply1(op, &Int(big_int(a))) {
+        Some(Int(r)) => from_bool(
+          a >= 0
+            && &r * &r <= big_int(a)
+            && (&r + big_int(1)) * (&r + big_int(1)) > big_int(a),
+        ),
+        None => from_bool(a < 0),
+        _ => from_bool(false),
+      },
+      IntOp::Log2 => apply1_int(if a <= 0 {
+        None
+      }
+      else {
+        Some(Nat(big_uint((63 - a.leading_zeros()).into())))
+      }),
+      IntOp::Shl => {
+        apply2_u32_int(Some(Int(big_int(a) * BigInt::from(2).pow(e))))
+      }
+      IntOp::Shr => apply2_u32_int(Some(Int(big_int(if e >= 64 {
+        if a < 0 { -1 } else { 0 }
+      }
+      else {
+        a >> e
+      })))),
+      IntOp::And => apply2_int_int(Some(Int(big_int(a & b)))),
+      IntOp::Or => apply2_int_int(Some(Int(big_int(a | b)))),
+      IntOp::Xor => apply2_int_int(Some(Int(big_int(a ^ b)))),
+      IntOp::ToBits => match IntOp::apply1(op, &Int(big_int(a))) {
+        Some(xs) => from_bool(
+          IntOp::apply1(IntOp::FromBits, &xs) == Some(Int(big_int(a))),
+        ),
+        _ => from_bool(false),
+      },
+      IntOp::FromBits => {
+        let bits = bits::bytes_to_bits(64, &a.to_be_bytes().into());
+        from_bool(IntOp::apply1(op, &Bits(bits)) == Some(Int(big_int(a))))
+      }
     }
   }
 
@@ -340,7 +575,14 @@ pub mod tests {
 
     match op {
       // Arity 1, valid is Int.
-      IntOp::Sgn | IntOp::Abs => test_apply1_none_on_invalid(Int(big(b))),
+      IntOp::Sgn
+      | IntOp::Abs
+      | IntOp::ToText
+      | IntOp::Isqrt
+      | IntOp::Log2
+      | IntOp::ToBits => test_apply1_none_on_invalid(Int(big(b))),
+      // Arity 1, valid is Bits.
+      IntOp::FromBits => test_apply1_none_on_invalid(Bits(vec![])),
       // Arity 2, valid are Int on a and b.
       IntOp::New
       | IntOp::Eql
@@ -352,7 +594,12 @@ pub mod tests {
       | IntOp::Sub
       | IntOp::Mul
       | IntOp::Div
-      | IntOp::Mod => {
+      | IntOp::Mod
+      | IntOp::Gcd
+      | IntOp::Lcm
+      | IntOp::And
+      | IntOp::Or
+      | IntOp::Xor => {
         if test_arg_2 {
           test_apply2_none_on_invalid(Int(big(b)), a, Int(big(b)))
         }
@@ -360,6 +607,24 @@ pub mod tests {
           test_apply2_none_on_invalid(Int(big(b)), Int(big(b)), a)
         }
       }
+      // Arity 2, valid are Int on a and U32 on b.
+      IntOp::Pow => {
+        if test_arg_2 {
+          test_apply2_none_on_invalid(Int(big(b)), a, U32(0))
+        }
+        else {
+          test_apply2_none_on_invalid(U32(0), Int(big(b)), a)
+        }
+      }
+      // Arity 2, valid are U32 on a and Int on b.
+      IntOp::Shl | IntOp::Shr => {
+        if test_arg_2 {
+          test_apply2_none_on_invalid(U32(0), a, Int(big(b)))
+        }
+        else {
+          test_apply2_none_on_invalid(Int(big(b)), U32(0), a)
+        }
+      }
     }
   }
 
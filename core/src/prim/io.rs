@@ -10,57 +10,51 @@ use alloc::string::String;
 
 #[derive(Clone)]
 pub struct IoOp {
-  pub fun_symbol: fn() -> String,
-  pub fun_type_of: fn() -> Term,
-  pub fun_arity: fn() -> u64,
-  pub fun_apply0: fn() -> Option<Literal>,
-  pub fun_apply1: Rc<dyn Fn(Literal) -> Option<Literal>>,
-  pub fun_apply2: fn(Literal, Literal) -> Option<Literal>,
+  pub fun_symbol: Rc<dyn Fn() -> String>,
+  pub fun_type_of: Rc<dyn Fn() -> Term>,
+  pub fun_arity: Rc<dyn Fn() -> u64>,
+  /// Applies the operation to exactly `arity` literal arguments, first
+  /// argument first
+  pub fun_apply: Rc<dyn Fn(&[Literal]) -> Option<Literal>>,
+  /// Like `fun_apply`, for operations with a non-literal result
+  pub fun_apply_term: Rc<dyn Fn(&[Literal]) -> Option<Term>>,
 }
 
 impl PartialEq for IoOp {
-  fn eq(&self, rhs: &Self) -> bool {
-    self.fun_symbol == rhs.fun_symbol
-  }
+  fn eq(&self, rhs: &Self) -> bool { self.symbol() == rhs.symbol() }
 }
 
 impl Eq for IoOp {}
 
 impl fmt::Debug for IoOp {
   fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-    let f = self.fun_symbol;
-    fmt.write_fmt(format_args!("IoOp {}", f()))
+    fmt.write_fmt(format_args!("IoOp {}", self.symbol()))
   }
 }
 
 impl IoOp {
   pub fn symbol(&self) -> String {
-    let f = self.fun_symbol;
+    let f = self.fun_symbol.clone();
     f()
   }
 
   pub fn type_of(&self) -> Term {
-    let f = self.fun_type_of;
+    let f = self.fun_type_of.clone();
     f()
   }
 
   pub fn arity(&self) -> u64 {
-    let f = self.fun_arity;
-    f()
-  }
-
-  pub fn apply0(&self) -> Option<Literal> {
-    let f = self.fun_apply0;
+    let f = self.fun_arity.clone();
     f()
   }
 
-  pub fn apply1(&self, x: &Literal) -> Option<Literal> {
-    let f = self.fun_apply1.clone();
-    f(x.clone())
+  pub fn apply(&self, args: &[Literal]) -> Option<Literal> {
+    let f = self.fun_apply.clone();
+    f(args)
   }
 
-  pub fn apply2(&self, x: &Literal, y: &Literal) -> Option<Literal> {
-    let f = self.fun_apply2;
-    f(x.clone(), y.clone())
+  pub fn apply_term(&self, args: &[Literal]) -> Option<Term> {
+    let f = self.fun_apply_term.clone();
+    f(args)
   }
 }
@@ -1,4 +1,5 @@
 use num_bigint::BigUint;
+use num_integer::Integer;
 use sp_ipld::Ipld;
 
 use sp_std::{
@@ -13,6 +14,7 @@ use crate::{
   ipld_error::IpldError,
   literal::Literal,
   parse,
+  prim::bits,
   term::Term,
   yatima,
 };
@@ -32,6 +34,19 @@ pub enum NatOp {
   Mul,
   Div,
   Mod,
+  ToText,
+  Pow,
+  Gcd,
+  Lcm,
+  Isqrt,
+  Log2,
+  Shl,
+  Shr,
+  And,
+  Or,
+  Xor,
+  ToBits,
+  FromBits,
 }
 
 impl NatOp {
@@ -50,6 +65,19 @@ impl NatOp {
       Self::Mul => "mul".to_owned(),
       Self::Div => "div".to_owned(),
       Self::Mod => "mod".to_owned(),
+      Self::ToText => "to_Text".to_owned(),
+      Self::Pow => "pow".to_owned(),
+      Self::Gcd => "gcd".to_owned(),
+      Self::Lcm => "lcm".to_owned(),
+      Self::Isqrt => "isqrt".to_owned(),
+      Self::Log2 => "log2".to_owned(),
+      Self::Shl => "shl".to_owned(),
+      Self::Shr => "shr".to_owned(),
+      Self::And => "and".to_owned(),
+      Self::Or => "or".to_owned(),
+      Self::Xor => "xor".to_owned(),
+      Self::ToBits => "to_Bits".to_owned(),
+      Self::FromBits => "from_Bits".to_owned(),
     }
   }
 
@@ -68,6 +96,19 @@ impl NatOp {
       "mul" => Some(Self::Mul),
       "div" => Some(Self::Div),
       "mod" => Some(Self::Mod),
+      "to_Text" => Some(Self::ToText),
+      "pow" => Some(Self::Pow),
+      "gcd" => Some(Self::Gcd),
+      "lcm" => Some(Self::Lcm),
+      "isqrt" => Some(Self::Isqrt),
+      "log2" => Some(Self::Log2),
+      "shl" => Some(Self::Shl),
+      "shr" => Some(Self::Shr),
+      "and" => Some(Self::And),
+      "or" => Some(Self::Or),
+      "xor" => Some(Self::Xor),
+      "to_Bits" => Some(Self::ToBits),
+      "from_Bits" => Some(Self::FromBits),
       _ => None,
     }
   }
@@ -87,6 +128,19 @@ impl NatOp {
       Self::Mul => yatima!("∀ #Nat #Nat -> #Nat"),
       Self::Div => yatima!("∀ #Nat #Nat -> #Nat"),
       Self::Mod => yatima!("∀ #Nat #Nat -> #Nat"),
+      Self::ToText => yatima!("∀ #Nat -> #Text"),
+      Self::Pow => yatima!("∀ #Nat #U32 -> #Nat"),
+      Self::Gcd => yatima!("∀ #Nat #Nat -> #Nat"),
+      Self::Lcm => yatima!("∀ #Nat #Nat -> #Nat"),
+      Self::Isqrt => yatima!("∀ #Nat -> #Nat"),
+      Self::Log2 => yatima!("∀ #Nat -> #Nat"),
+      Self::Shl => yatima!("∀ #U32 #Nat -> #Nat"),
+      Self::Shr => yatima!("∀ #U32 #Nat -> #Nat"),
+      Self::And => yatima!("∀ #Nat #Nat -> #Nat"),
+      Self::Or => yatima!("∀ #Nat #Nat -> #Nat"),
+      Self::Xor => yatima!("∀ #Nat #Nat -> #Nat"),
+      Self::ToBits => yatima!("∀ #Nat -> #Bits"),
+      Self::FromBits => yatima!("∀ #Bits -> #Nat"),
     }
   }
 
@@ -105,6 +159,19 @@ impl NatOp {
       Self::Mul => Ipld::Integer(9),
       Self::Div => Ipld::Integer(10),
       Self::Mod => Ipld::Integer(11),
+      Self::ToText => Ipld::Integer(12),
+      Self::Pow => Ipld::Integer(13),
+      Self::Gcd => Ipld::Integer(14),
+      Self::Lcm => Ipld::Integer(15),
+      Self::Isqrt => Ipld::Integer(16),
+      Self::Log2 => Ipld::Integer(17),
+      Self::Shl => Ipld::Integer(18),
+      Self::Shr => Ipld::Integer(19),
+      Self::And => Ipld::Integer(20),
+      Self::Or => Ipld::Integer(21),
+      Self::Xor => Ipld::Integer(22),
+      Self::ToBits => Ipld::Integer(23),
+      Self::FromBits => Ipld::Integer(24),
     }
   }
 
@@ -123,6 +190,19 @@ impl NatOp {
       Ipld::Integer(9) => Ok(Self::Mul),
       Ipld::Integer(10) => Ok(Self::Div),
       Ipld::Integer(11) => Ok(Self::Mod),
+      Ipld::Integer(12) => Ok(Self::ToText),
+      Ipld::Integer(13) => Ok(Self::Pow),
+      Ipld::Integer(14) => Ok(Self::Gcd),
+      Ipld::Integer(15) => Ok(Self::Lcm),
+      Ipld::Integer(16) => Ok(Self::Isqrt),
+      Ipld::Integer(17) => Ok(Self::Log2),
+      Ipld::Integer(18) => Ok(Self::Shl),
+      Ipld::Integer(19) => Ok(Self::Shr),
+      Ipld::Integer(20) => Ok(Self::And),
+      Ipld::Integer(21) => Ok(Self::Or),
+      Ipld::Integer(22) => Ok(Self::Xor),
+      Ipld::Integer(23) => Ok(Self::ToBits),
+      Ipld::Integer(24) => Ok(Self::FromBits),
       xs => Err(IpldError::NatOp(xs.to_owned())),
     }
   }
@@ -142,6 +222,19 @@ impl NatOp {
       Self::Mul => 2,
       Self::Div => 2,
       Self::Mod => 2,
+      Self::ToText => 1,
+      Self::Pow => 2,
+      Self::Gcd => 2,
+      Self::Lcm => 2,
+      Self::Isqrt => 1,
+      Self::Log2 => 1,
+      Self::Shl => 2,
+      Self::Shr => 2,
+      Self::And => 2,
+      Self::Or => 2,
+      Self::Xor => 2,
+      Self::ToBits => 1,
+      Self::FromBits => 1,
     }
   }
 
@@ -158,6 +251,18 @@ impl NatOp {
           Some(Nat(BigUint::from(0u64)))
         }
       }
+      (Self::ToText, Nat(x)) => Some(Text(x.to_str_radix(10).into())),
+      (Self::Isqrt, Nat(x)) => Some(Nat(x.sqrt())),
+      (Self::Log2, Nat(x)) if *x != 0u64.into() => {
+        Some(Nat(BigUint::from(x.bits() - 1)))
+      }
+      (Self::ToBits, Nat(x)) => {
+        let bytes = x.to_bytes_be();
+        Some(Bits(bits::bytes_to_bits(bytes.len() * 8, &bytes)))
+      }
+      (Self::FromBits, Bits(xs)) => {
+        Some(Nat(BigUint::from_bytes_be(&bits::bits_to_bytes(xs).1)))
+      }
       _ => None,
     }
   }
@@ -179,11 +284,39 @@ impl NatOp {
       (Self::Mul, Nat(x), Nat(y)) => Some(Nat(x * y)),
       (Self::Div, Nat(x), Nat(y)) if *y != (0u64).into() => Some(Nat(x / y)),
       (Self::Mod, Nat(x), Nat(y)) if *y != (0u64).into() => Some(Nat(x % y)),
+      (Self::Pow, Nat(x), U32(y)) if pow_fits(x.bits(), *y) => {
+        Some(Nat(x.pow(*y)))
+      }
+      (Self::Gcd, Nat(x), Nat(y)) => Some(Nat(x.gcd(y))),
+      (Self::Lcm, Nat(x), Nat(y)) => Some(Nat(x.lcm(y))),
+      (Self::Shl, U32(x), Nat(y)) if shl_fits(y.bits(), *x) => {
+        Some(Nat(y << *x as usize))
+      }
+      (Self::Shr, U32(x), Nat(y)) => Some(Nat(y >> *x as usize)),
+      (Self::And, Nat(x), Nat(y)) => Some(Nat(x & y)),
+      (Self::Or, Nat(x), Nat(y)) => Some(Nat(x | y)),
+      (Self::Xor, Nat(x), Nat(y)) => Some(Nat(x ^ y)),
       _ => None,
     }
   }
 }
 
+/// The most bits a result of `pow` or `shl` may have. Larger results are
+/// refused rather than computed, so that a single application cannot exhaust
+/// memory.
+pub const MAX_RESULT_BITS: u64 = 1 << 24;
+
+/// Whether a number of `bits` bits raised to `exp` fits in `MAX_RESULT_BITS`
+pub fn pow_fits(bits: u64, exp: u32) -> bool {
+  bits <= 1 || bits.saturating_mul(u64::from(exp)) <= MAX_RESULT_BITS
+}
+
+/// Whether a number of `bits` bits shifted left by `shift` fits in
+/// `MAX_RESULT_BITS`
+pub fn shl_fits(bits: u64, shift: u32) -> bool {
+  bits == 0 || bits + u64::from(shift) <= MAX_RESULT_BITS
+}
+
 impl fmt::Display for NatOp {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     write!(f, "{}", self.symbol())
@@ -202,13 +335,16 @@ pub mod tests {
   use rand::Rng;
   use sp_std::mem;
   use Literal::{
+    Bits,
     Bool,
     Nat,
+    Text,
+    U32,
   };
   impl Arbitrary for NatOp {
     fn arbitrary(_g: &mut Gen) -> Self {
       let mut rng = rand::thread_rng();
-      let gen: u32 = rng.gen_range(0..=11);
+      let gen: u32 = rng.gen_range(0..=24);
       match gen {
         0 => Self::Suc,
         1 => Self::Pre,
@@ -221,7 +357,20 @@ pub mod tests {
         8 => Self::Sub,
         9 => Self::Mul,
         10 => Self::Div,
-        _ => Self::Mod,
+        11 => Self::Mod,
+        12 => Self::ToText,
+        13 => Self::Pow,
+        14 => Self::Gcd,
+        15 => Self::Lcm,
+        16 => Self::Isqrt,
+        17 => Self::Log2,
+        18 => Self::Shl,
+        19 => Self::Shr,
+        20 => Self::And,
+        21 => Self::Or,
+        22 => Self::Xor,
+        23 => Self::ToBits,
+        _ => Self::FromBits,
       }
     }
   }
@@ -234,9 +383,23 @@ pub mod tests {
     }
   }
 
+  #[test]
+  fn test_apply_too_large() {
+    let big = BigUint::from;
+    let pow = |x: u64, y| NatOp::apply2(NatOp::Pow, &Nat(big(x)), &U32(y));
+    let shl = |x, y: u64| NatOp::apply2(NatOp::Shl, &U32(x), &Nat(big(y)));
+    assert_eq!(pow(10, u32::MAX), None);
+    assert_eq!(pow(1, u32::MAX), Some(Nat(big(1))));
+    assert_eq!(pow(2, 1 << 20), Some(Nat(big(1) << (1usize << 20))));
+    assert_eq!(shl(u32::MAX, 1), None);
+    assert_eq!(shl(u32::MAX, 0), Some(Nat(big(0))));
+  }
+
   #[quickcheck]
-  fn test_apply(op: NatOp, a: u64, b: u64) -> TestResult {
+  fn test_apply(op: NatOp, a: u64, b: u64, c: u32) -> TestResult {
     let big = BigUint::from;
+    // Bound exponents and shifts so results stay small
+    let c = c % 128;
     let apply1_nat = |expected: Option<Literal>| -> TestResult {
       TestResult::from_bool(NatOp::apply1(op, &Nat(big(a))) == expected)
     };
@@ -247,6 +410,29 @@ pub mod tests {
       )
     };
 
+    let apply2_nat_u32 = |expected: Option<Literal>| -> TestResult {
+      TestResult::from_bool(
+        NatOp::apply2(op, &Nat(big(a)), &U32(c)) == expected,
+      )
+    };
+
+    let apply2_u32_nat = |expected: Option<Literal>| -> TestResult {
+      TestResult::from_bool(
+        NatOp::apply2(op, &U32(c), &Nat(big(a))) == expected,
+      )
+    };
+
+    let gcd_u64 = |mut x: u64, mut y: u64| -> u64 {
+      while y != 0 {
+        let r = x % y;
+        x = y;
+        y = r;
+      }
+      x
+    };
+
+    let from_bool = TestResult::from_bool;
+
     match op {
       NatOp::Suc => apply1_nat(Some(Nat(big(a) + big(1)))),
       NatOp::Pre => apply1_nat(Some(Nat(big(if a == 0 { a } else { a - 1 })))),
@@ -266,6 +452,47 @@ pub mod tests {
       NatOp::Mod => {
         apply2_nat_nat(if b != 0 { Some(Nat(big(a % b))) } else { None })
       }
+      NatOp::ToText => apply1_nat(Some(Text(a.to_string().into()))),
+      NatOp::Pow => {
+        let expected = (0..c).fold(big(1), |acc, _| acc * big(a));
+        apply2_nat_u32(Some(Nat(expected)))
+      }
+      NatOp::Gcd => apply2_nat_nat(Some(Nat(big(gcd_u64(a, b))))),
+      NatOp::Lcm => apply2_nat_nat(Some(Nat(if a == 0 || b == 0 {
+        big(0)
+      }
+      else {
+        big(a / gcd_u64(a, b)) * big(b)
+      }))),
+      NatOp::Isqrt => match NatOp::apply1(op, &Nat(big(a))) {
+        Some(Nat(r)) => {
+          from_bool(&r * &r <= big(a) && (&r + big(1)) * (&r + big(1)) > big(a))
+        }
+        _ => from_bool(false),
+      },
+      NatOp::Log2 => apply1_nat(if a == 0 {
+        None
+      }
+      else {
+        Some(Nat(big((63 - a.leading_zeros()).into())))
+      }),
+      NatOp::Shl => apply2_u32_nat(Some(Nat(big(a) * big(2).pow(c)))),
+      NatOp::Shr => {
+        apply2_u32_nat(Some(Nat(if c >= 64 { big(0) } else { big(a >> c) })))
+      }
+      NatOp::And => apply2_nat_nat(Some(Nat(big(a & b)))),
+      NatOp::Or => apply2_nat_nat(Some(Nat(big(a | b)))),
+      NatOp::Xor => apply2_nat_nat(Some(Nat(big(a ^ b)))),
+      NatOp::ToBits => match NatOp::apply1(op, &Nat(big(a))) {
+        Some(xs) => {
+          from_bool(NatOp::apply1(NatOp::FromBits, &xs) == Some(Nat(big(a))))
+        }
+        _ => from_bool(false),
+      },
+      NatOp::FromBits => {
+        let bits = bits::bytes_to_bits(64, &a.to_be_bytes().into());
+        from_bool(NatOp::apply1(op, &Bits(bits)) == Some(Nat(big(a))))
+      }
     }
   }
 
@@ -309,7 +536,14 @@ pub mod tests {
 
     match op {
       // Arity 1, valid is Nat.
-      NatOp::Suc | NatOp::Pre => test_apply1_none_on_invalid(Nat(big(b))),
+      NatOp::Suc
+      | NatOp::Pre
+      | NatOp::ToText
+      | NatOp::Isqrt
+      | NatOp::Log2
+      | NatOp::ToBits => test_apply1_none_on_invalid(Nat(big(b))),
+      // Arity 1, valid is Bits.
+      NatOp::FromBits => test_apply1_none_on_invalid(Bits(vec![])),
       // Arity 2, valid are Nat on a and b.
       NatOp::Eql
       | NatOp::Lte
@@ -320,7 +554,12 @@ pub mod tests {
       | NatOp::Sub
       | NatOp::Mul
       | NatOp::Div
-      | NatOp::Mod => {
+      | NatOp::Mod
+      | NatOp::Gcd
+      | NatOp::Lcm
+      | NatOp::And
+      | NatOp::Or
+      | NatOp::Xor => {
         if test_arg_2 {
           test_apply2_none_on_invalid(Nat(big(b)), a, Nat(big(b)))
         }
@@ -328,6 +567,24 @@ pub mod tests {
           test_apply2_none_on_invalid(Nat(big(b)), Nat(big(b)), a)
         }
       }
+      // Arity 2, valid are Nat on a and U32 on b.
+      NatOp::Pow => {
+        if test_arg_2 {
+          test_apply2_none_on_invalid(Nat(big(b)), a, U32(0))
+        }
+        else {
+          test_apply2_none_on_invalid(U32(0), Nat(big(b)), a)
+        }
+      }
+      // Arity 2, valid are U32 on a and Nat on b.
+      NatOp::Shl | NatOp::Shr => {
+        if test_arg_2 {
+          test_apply2_none_on_invalid(U32(0), a, Nat(big(b)))
+        }
+        else {
+          test_apply2_none_on_invalid(Nat(big(b)), U32(0), a)
+        }
+      }
     }
   }
 
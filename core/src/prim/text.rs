@@ -1,10 +1,14 @@
-use num_bigint::BigUint;
+use num_bigint::{
+  BigInt,
+  BigUint,
+};
 use sp_ropey::Rope;
 use sp_ipld::Ipld;
 
 use sp_std::{
   borrow::ToOwned,
   fmt,
+  mem,
   vec::Vec,
 };
 
@@ -18,6 +22,11 @@ use crate::{
   ipld_error::IpldError,
   literal::Literal,
   parse,
+  prim::{
+    list_term,
+    option_term,
+    view_term,
+  },
   term::Term,
   yatima,
 };
@@ -54,6 +63,20 @@ pub enum TextOp {
   LineStartChar,
   LineStartByte,
   ToBytes,
+  Find,
+  /// Splits text on every occurrence of a separator. An empty separator
+  /// splits it into its characters, so empty text then has no pieces.
+  Split,
+  Replace,
+  Trim,
+  StartsWith,
+  EndsWith,
+  ToUpper,
+  ToLower,
+  ToNat,
+  ToInt,
+  View,
+  Abort,
 }
 
 impl TextOp {
@@ -84,6 +107,18 @@ impl TextOp {
       Self::LineStartByte => "line_start_byte".to_owned(),
       Self::LineStartChar => "line_start_char".to_owned(),
       Self::ToBytes => "to_bytes".to_owned(),
+      Self::Find => "find".to_owned(),
+      Self::Split => "split".to_owned(),
+      Self::Replace => "replace".to_owned(),
+      Self::Trim => "trim".to_owned(),
+      Self::StartsWith => "starts_with".to_owned(),
+      Self::EndsWith => "ends_with".to_owned(),
+      Self::ToUpper => "to_upper".to_owned(),
+      Self::ToLower => "to_lower".to_owned(),
+      Self::ToNat => "to_Nat".to_owned(),
+      Self::ToInt => "to_Int".to_owned(),
+      Self::View => "view".to_owned(),
+      Self::Abort => "abort".to_owned(),
     }
   }
 
@@ -114,6 +149,18 @@ impl TextOp {
       "line_start_byte" => Some(Self::LineStartByte),
       "line_start_char" => Some(Self::LineStartChar),
       "to_bytes" => Some(Self::ToBytes),
+      "find" => Some(Self::Find),
+      "split" => Some(Self::Split),
+      "replace" => Some(Self::Replace),
+      "trim" => Some(Self::Trim),
+      "starts_with" => Some(Self::StartsWith),
+      "ends_with" => Some(Self::EndsWith),
+      "to_upper" => Some(Self::ToUpper),
+      "to_lower" => Some(Self::ToLower),
+      "to_Nat" => Some(Self::ToNat),
+      "to_Int" => Some(Self::ToInt),
+      "view" => Some(Self::View),
+      "abort" => Some(Self::Abort),
       _ => None,
     }
   }
@@ -145,6 +192,36 @@ impl TextOp {
       Self::LineStartChar => yatima!("∀ #Nat #Text -> #Nat"),
       Self::LineStartByte => yatima!("∀ #Nat #Text -> #Nat"),
       Self::ToBytes => yatima!("∀ #Text -> #Bytes"),
+      Self::Find => yatima!(
+        "∀ #Text #Text
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #Nat -> P) -> P"
+      ),
+      Self::Split => yatima!(
+        "∀ #Text #Text
+           -> ∀ (0 P: Type) (& nil: P) (cons: ∀ #Text P -> P) -> P"
+      ),
+      Self::Replace => yatima!("∀ #Text #Text #Text -> #Text"),
+      Self::Trim => yatima!("∀ #Text -> #Text"),
+      Self::StartsWith => yatima!("∀ #Text #Text -> #Bool"),
+      Self::EndsWith => yatima!("∀ #Text #Text -> #Bool"),
+      Self::ToUpper => yatima!("∀ #Text -> #Text"),
+      Self::ToLower => yatima!("∀ #Text -> #Text"),
+      Self::ToNat => yatima!(
+        "∀ #Text -> ∀ (0 P: Type) (& none: P) (& some: ∀ #Nat -> P) -> P"
+      ),
+      Self::ToInt => yatima!(
+        "∀ #Text -> ∀ (0 P: Type) (& none: P) (& some: ∀ #Int -> P) -> P"
+      ),
+      Self::View => yatima!(
+        "∀ (i: #Nat) (xs: #Text)
+           -> ∀ (0 P: ∀ #Text -> Type)
+                (& nil: P \"\")
+                (& cons: ∀ (x: #Char) (j: #Nat)
+                  -> P (#Text.cons x (#Text.drop j xs)))
+              -> P (#Text.drop i xs)
+          "
+      ),
+      Self::Abort => yatima!("∀ (0 A: Type) #Text -> A"),
     }
   }
 
@@ -175,6 +252,18 @@ impl TextOp {
       Self::LineStartChar => Ipld::Integer(21),
       Self::LineStartByte => Ipld::Integer(22),
       Self::ToBytes => Ipld::Integer(23),
+      Self::Find => Ipld::Integer(24),
+      Self::Split => Ipld::Integer(25),
+      Self::Replace => Ipld::Integer(26),
+      Self::Trim => Ipld::Integer(27),
+      Self::StartsWith => Ipld::Integer(28),
+      Self::EndsWith => Ipld::Integer(29),
+      Self::ToUpper => Ipld::Integer(30),
+      Self::ToLower => Ipld::Integer(31),
+      Self::ToNat => Ipld::Integer(32),
+      Self::ToInt => Ipld::Integer(33),
+      Self::View => Ipld::Integer(34),
+      Self::Abort => Ipld::Integer(35),
     }
   }
 
@@ -205,6 +294,18 @@ impl TextOp {
       Ipld::Integer(21) => Ok(Self::LineStartChar),
       Ipld::Integer(22) => Ok(Self::LineStartByte),
       Ipld::Integer(23) => Ok(Self::ToBytes),
+      Ipld::Integer(24) => Ok(Self::Find),
+      Ipld::Integer(25) => Ok(Self::Split),
+      Ipld::Integer(26) => Ok(Self::Replace),
+      Ipld::Integer(27) => Ok(Self::Trim),
+      Ipld::Integer(28) => Ok(Self::StartsWith),
+      Ipld::Integer(29) => Ok(Self::EndsWith),
+      Ipld::Integer(30) => Ok(Self::ToUpper),
+      Ipld::Integer(31) => Ok(Self::ToLower),
+      Ipld::Integer(32) => Ok(Self::ToNat),
+      Ipld::Integer(33) => Ok(Self::ToInt),
+      Ipld::Integer(34) => Ok(Self::View),
+      Ipld::Integer(35) => Ok(Self::Abort),
       xs => Err(IpldError::TextOp(xs.to_owned())),
     }
   }
@@ -236,6 +337,18 @@ impl TextOp {
       Self::LineStartChar => 2,
       Self::LineStartByte => 2,
       Self::ToBytes => 1,
+      Self::Find => 2,
+      Self::Split => 2,
+      Self::Replace => 3,
+      Self::Trim => 1,
+      Self::StartsWith => 2,
+      Self::EndsWith => 2,
+      Self::ToUpper => 1,
+      Self::ToLower => 1,
+      Self::ToNat => 1,
+      Self::ToInt => 1,
+      Self::View => 2,
+      Self::Abort => 2,
     }
   }
 
@@ -247,6 +360,13 @@ impl TextOp {
       (Self::LenBytes, Text(xs)) => Some(Nat(xs.len_bytes().into())),
       (Self::LenLines, Text(xs)) => Some(Nat(xs.len_lines().into())),
       (Self::ToBytes, Text(xs)) => Some(Bytes(xs.bytes().collect::<Vec<u8>>())),
+      (Self::Trim, Text(xs)) => Some(Text(xs.to_string().trim().into())),
+      (Self::ToUpper, Text(xs)) => {
+        Some(Text(xs.to_string().to_uppercase().into()))
+      }
+      (Self::ToLower, Text(xs)) => {
+        Some(Text(xs.to_string().to_lowercase().into()))
+      }
       _ => None,
     }
   }
@@ -278,6 +398,8 @@ impl TextOp {
       (Self::Lth, Text(xs), Text(ys)) => Some(Bool(xs < ys)),
       (Self::Gte, Text(xs), Text(ys)) => Some(Bool(xs >= ys)),
       (Self::Gth, Text(xs), Text(ys)) => Some(Bool(xs > ys)),
+      (Self::StartsWith, Text(xs), Text(ys)) => Some(Bool(starts_with(xs, ys))),
+      (Self::EndsWith, Text(xs), Text(ys)) => Some(Bool(ends_with(xs, ys))),
       (Self::Char, Nat(idx), Text(ys)) => {
         let idx: usize = idx.clone().try_into().ok()?;
         if idx < ys.len_chars() { Some(Char(ys.char(idx))) } else { None }
@@ -368,6 +490,69 @@ impl TextOp {
       (Self::Remove, Nat(x), Nat(y), Text(xs)) => {
         Some(Text(safe_remove(x, y, xs.clone())))
       }
+      (Self::Replace, Text(x), Text(y), Text(xs)) => {
+        Some(Text(replace(x, y, xs)))
+      }
+      _ => None,
+    }
+  }
+
+  /// Whether the operation returns an updated copy of its `#Text` argument
+  pub fn updates(self) -> bool {
+    matches!(self, Self::Cons | Self::Append | Self::Insert | Self::Remove)
+  }
+
+  /// Applies an updating operation like `apply2` or `apply3`, but takes the
+  /// text out of the arguments instead of cloning it
+  pub fn apply_in_place(self, args: &mut [Literal]) -> Option<Literal> {
+    use Literal::*;
+    match (self, args) {
+      (Self::Cons, [Char(c), Text(cs)]) => {
+        let mut cs = mem::replace(cs, Rope::new());
+        cs.insert_char(0, *c);
+        Some(Text(cs))
+      }
+      (Self::Append, [Text(xs), Text(ys)]) => {
+        let mut xs = mem::replace(xs, Rope::new());
+        xs.append(mem::replace(ys, Rope::new()));
+        Some(Text(xs))
+      }
+      (Self::Insert, [Nat(x), Text(y), Text(xs)]) => {
+        let xs = mem::replace(xs, Rope::new());
+        Some(Text(safe_insert(x, mem::replace(y, Rope::new()), xs)))
+      }
+      (Self::Remove, [Nat(x), Nat(y), Text(xs)]) => {
+        Some(Text(safe_remove(x, y, mem::replace(xs, Rope::new()))))
+      }
+      _ => None,
+    }
+  }
+
+  /// Applies a unary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply1_term(self, x: &Literal) -> Option<Term> {
+    use Literal::*;
+    match (self, x) {
+      (Self::ToNat, Text(xs)) => Some(option_term(parse_nat(xs).map(Nat))),
+      (Self::ToInt, Text(xs)) => Some(option_term(parse_int(xs).map(Int))),
+      _ => None,
+    }
+  }
+
+  /// Applies a binary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply2_term(self, x: &Literal, y: &Literal) -> Option<Term> {
+    use Literal::*;
+    match (self, x, y) {
+      (Self::Find, Text(xs), Text(ys)) => {
+        Some(option_term(safe_find(xs, ys).map(|idx| Nat(idx.into()))))
+      }
+      (Self::Split, Text(xs), Text(ys)) => {
+        Some(list_term(split_on(xs, ys).into_iter().map(Text).collect()))
+      }
+      (Self::View, Nat(idx), Text(xs)) => {
+        Some(view_term(idx, safe_view(idx, xs).map(Char)))
+      }
       _ => None,
     }
   }
@@ -430,6 +615,155 @@ pub fn safe_split(idx: &BigUint, mut xs: Rope) -> (Rope, Rope) {
   }
 }
 
+/// The byte indices of the non-overlapping occurrences of a non-empty pattern
+/// in a text, from the left. The text is read a byte at a time with the
+/// Knuth-Morris-Pratt algorithm, so that it is not copied.
+struct Matches<'a> {
+  pat: Vec<u8>,
+  /// The length of the longest proper prefix of each prefix of the pattern
+  /// that is also a suffix of it
+  fail: Vec<usize>,
+  bytes: sp_ropey::iter::Bytes<'a>,
+  /// The number of bytes read
+  idx: usize,
+  /// The length of the prefix of the pattern matched by the last bytes read
+  len: usize,
+}
+
+impl<'a> Matches<'a> {
+  fn new(pat: &Rope, xs: &'a Rope) -> Self {
+    let pat: Vec<u8> = pat.bytes().collect();
+    let mut fail = vec![0; pat.len()];
+    let mut len = 0;
+    for i in 1..pat.len() {
+      while len > 0 && pat[i] != pat[len] {
+        len = fail[len - 1];
+      }
+      if pat[i] == pat[len] {
+        len += 1;
+      }
+      fail[i] = len;
+    }
+    Matches { pat, fail, bytes: xs.bytes(), idx: 0, len: 0 }
+  }
+}
+
+impl<'a> Iterator for Matches<'a> {
+  type Item = usize;
+
+  fn next(&mut self) -> Option<usize> {
+    for x in &mut self.bytes {
+      self.idx += 1;
+      while self.len > 0 && x != self.pat[self.len] {
+        self.len = self.fail[self.len - 1];
+      }
+      if x == self.pat[self.len] {
+        self.len += 1;
+      }
+      if self.len == self.pat.len() {
+        self.len = 0;
+        return Some(self.idx - self.pat.len());
+      }
+    }
+    None
+  }
+}
+
+/// Finds the char index of the first occurrence of a pattern
+pub fn safe_find(pat: &Rope, xs: &Rope) -> Option<usize> {
+  if pat.len_bytes() == 0 {
+    Some(0)
+  }
+  else {
+    Matches::new(pat, xs).next().map(|idx| xs.byte_to_char(idx))
+  }
+}
+
+/// Splits text on every occurrence of a separator, or into its characters if
+/// the separator is empty
+pub fn split_on(sep: &Rope, xs: &Rope) -> Vec<Rope> {
+  if sep.len_bytes() == 0 {
+    return xs
+      .chars()
+      .map(|c| Rope::from_str(c.encode_utf8(&mut [0; 4])))
+      .collect();
+  }
+  let mut pieces = Vec::new();
+  let mut start = 0;
+  for idx in Matches::new(sep, xs) {
+    let end = xs.byte_to_char(idx);
+    pieces.push(Rope::from(xs.slice(start..end)));
+    start = end + sep.len_chars();
+  }
+  pieces.push(Rope::from(xs.slice(start..)));
+  pieces
+}
+
+/// Replaces every occurrence of a pattern in text. As with `str::replace`, an
+/// empty pattern occurs before every character and at the end.
+pub fn replace(pat: &Rope, rep: &Rope, xs: &Rope) -> Rope {
+  let mut res = Rope::new();
+  if pat.len_bytes() == 0 {
+    for c in xs.chars() {
+      res.append(rep.clone());
+      res.insert_char(res.len_chars(), c);
+    }
+    res.append(rep.clone());
+    return res;
+  }
+  let mut start = 0;
+  for idx in Matches::new(pat, xs) {
+    let end = xs.byte_to_char(idx);
+    res.append(Rope::from(xs.slice(start..end)));
+    res.append(rep.clone());
+    start = end + pat.len_chars();
+  }
+  res.append(Rope::from(xs.slice(start..)));
+  res
+}
+
+/// Whether text starts with a prefix
+pub fn starts_with(pre: &Rope, xs: &Rope) -> bool {
+  pre.len_bytes() <= xs.len_bytes()
+    && xs.bytes().take(pre.len_bytes()).eq(pre.bytes())
+}
+
+/// Whether text ends with a suffix
+pub fn ends_with(suf: &Rope, xs: &Rope) -> bool {
+  suf.len_bytes() <= xs.len_bytes()
+    && xs.bytes_at(xs.len_bytes() - suf.len_bytes()).eq(suf.bytes())
+}
+
+/// Gets the character at a given index of the text
+pub fn safe_view(idx: &BigUint, xs: &Rope) -> Option<char> {
+  match usize::try_from(idx) {
+    Ok(idx) if idx < xs.len_chars() => Some(xs.char(idx)),
+    _ => None,
+  }
+}
+
+/// Parses text as a decimal natural number
+pub fn parse_nat(xs: &Rope) -> Option<BigUint> {
+  let xs = xs.to_string();
+  if !xs.is_empty() && xs.bytes().all(|x| x.is_ascii_digit()) {
+    BigUint::parse_bytes(xs.as_bytes(), 10)
+  }
+  else {
+    None
+  }
+}
+
+/// Parses text as a decimal integer with an optional sign
+pub fn parse_int(xs: &Rope) -> Option<BigInt> {
+  let xs = xs.to_string();
+  let (neg, digits) = match xs.strip_prefix('-') {
+    Some(digits) => (true, digits),
+    None => (false, xs.strip_prefix('+').unwrap_or(xs.as_str())),
+  };
+  let abs = BigInt::from(parse_nat(&Rope::from_str(digits))?);
+  Some(if neg { -abs } else { abs })
+}
+
 /// Gets a line of text at given index
 pub fn safe_line(idx: BigUint, xs: Rope) -> Rope {
   if let Ok(i) = usize::try_from(idx) {
@@ -456,6 +790,7 @@ pub mod tests {
     Bool,
     Bytes,
     Char,
+    Int,
     Nat,
     Text,
     U8,
@@ -463,7 +798,7 @@ pub mod tests {
   impl Arbitrary for TextOp {
     fn arbitrary(_g: &mut Gen) -> Self {
       let mut rng = rand::thread_rng();
-      let gen: u32 = rng.gen_range(0..=23);
+      let gen: u32 = rng.gen_range(0..=35);
       match gen {
         0 => Self::Cons,
         1 => Self::LenChars,
@@ -488,7 +823,19 @@ pub mod tests {
         20 => Self::LineAtChar,
         21 => Self::LineStartChar,
         22 => Self::LineStartByte,
-        _ => Self::ToBytes,
+        23 => Self::ToBytes,
+        24 => Self::Find,
+        25 => Self::Split,
+        26 => Self::Replace,
+        27 => Self::Trim,
+        28 => Self::StartsWith,
+        29 => Self::EndsWith,
+        30 => Self::ToUpper,
+        31 => Self::ToLower,
+        32 => Self::ToNat,
+        33 => Self::ToInt,
+        34 => Self::View,
+        _ => Self::Abort,
       }
     }
   }
@@ -521,6 +868,81 @@ pub mod tests {
     assert_eq!(res, (Rope::from_str("foo"), Rope::from_str("")));
   }
 
+  #[test]
+  fn test_safe_find() {
+    let rope: Rope = Rope::from_str("λ foo foo");
+    assert_eq!(safe_find(&Rope::from_str("foo"), &rope), Some(2));
+    assert_eq!(safe_find(&Rope::from_str(""), &rope), Some(0));
+    assert_eq!(safe_find(&Rope::from_str("bar"), &rope), None);
+  }
+
+  #[test]
+  fn test_split_on() {
+    let split = |sep: &str, xs: &str| {
+      split_on(&Rope::from_str(sep), &Rope::from_str(xs))
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(split(",", "a,b,,c"), vec!["a", "b", "", "c"]);
+    assert_eq!(split("ab", "aab λ abab"), vec!["a", " λ ", "", ""]);
+    assert_eq!(split(",", ""), vec![""]);
+    assert_eq!(split("", "aλc"), vec!["a", "λ", "c"]);
+    assert!(split("", "").is_empty());
+  }
+
+  #[quickcheck]
+  fn test_search(p: String, r: String, x: String, inner: bool) -> bool {
+    // Half of the patterns are drawn from the text, so that they occur
+    let p = if inner { x.chars().skip(1).take(2).collect() } else { p };
+    let (pat, rep, xs) =
+      (Rope::from(p.as_str()), Rope::from(r.as_str()), Rope::from(x.as_str()));
+    let pieces: Vec<String> =
+      split_on(&pat, &xs).iter().map(|x| x.to_string()).collect();
+    safe_find(&pat, &xs) == x.find(&p).map(|idx| x[..idx].chars().count())
+      && replace(&pat, &rep, &xs) == x.replace(&p, &r)
+      && starts_with(&pat, &xs) == x.starts_with(&p)
+      && ends_with(&pat, &xs) == x.ends_with(&p)
+      && (p.is_empty() || pieces == x.split(&p).collect::<Vec<_>>())
+  }
+
+  #[test]
+  fn test_parse_nat_int() {
+    let nat = |x: &str| parse_nat(&Rope::from_str(x));
+    let int = |x: &str| parse_int(&Rope::from_str(x));
+    assert_eq!(nat("0"), Some(BigUint::from(0u64)));
+    assert_eq!(nat("00042"), Some(BigUint::from(42u64)));
+    assert_eq!(nat(""), None);
+    assert_eq!(nat("+1"), None);
+    assert_eq!(nat("1_000"), None);
+    assert_eq!(nat(" 1"), None);
+    assert_eq!(int("-17"), Some(BigInt::from(-17i64)));
+    assert_eq!(int("+17"), Some(BigInt::from(17i64)));
+    assert_eq!(int("17"), Some(BigInt::from(17i64)));
+    assert_eq!(int("-"), None);
+    assert_eq!(int("--1"), None);
+  }
+
+  #[test]
+  fn test_apply_term() {
+    assert_eq!(
+      TextOp::apply1_term(TextOp::ToNat, &Text(Rope::from_str("12"))),
+      Some(yatima!("λ P none some => some 12"))
+    );
+    assert_eq!(
+      TextOp::apply1_term(TextOp::ToInt, &Text(Rope::from_str("x"))),
+      Some(yatima!("λ P none some => none"))
+    );
+    assert_eq!(
+      TextOp::apply2_term(
+        TextOp::Split,
+        &Text(Rope::from_str(" ")),
+        &Text(Rope::from_str("a b"))
+      ),
+      Some(yatima!("λ P nil cons => cons \"a\" (cons \"b\" nil)"))
+    );
+  }
+
   #[quickcheck]
   fn test_apply(
     op: TextOp,
@@ -569,6 +991,35 @@ pub mod tests {
       )
     };
 
+    let apply3_text_text_text = |expected: Option<Literal>| -> TestResult {
+      TestResult::from_bool(
+        TextOp::apply3(
+          op,
+          &Text(a.clone()),
+          &Text(c.clone()),
+          &Text(c.clone()),
+        ) == expected,
+      )
+    };
+
+    let apply1_text_term = |expected: Option<Term>| -> TestResult {
+      TestResult::from_bool(
+        TextOp::apply1_term(op, &Text(a.clone())) == expected,
+      )
+    };
+
+    let apply2_nat_text_term = |expected: Option<Term>| -> TestResult {
+      TestResult::from_bool(
+        TextOp::apply2_term(op, &Nat(big(d)), &Text(a.clone())) == expected,
+      )
+    };
+
+    let apply2_text_text_term = |expected: Option<Term>| -> TestResult {
+      TestResult::from_bool(
+        TextOp::apply2_term(op, &Text(a.clone()), &Text(c.clone())) == expected,
+      )
+    };
+
     match op {
       TextOp::Cons => {
         let mut cs = a.clone();
@@ -733,9 +1184,72 @@ pub mod tests {
       TextOp::ToBytes => {
         apply1_text(Some(Bytes(a.bytes().collect::<Vec<u8>>())))
       }
+      TextOp::Find => apply2_text_text_term(Some(option_term(
+        safe_find(&a, &c).map(|idx| Nat(idx.into())),
+      ))),
+      TextOp::Split => apply2_text_text_term(Some(list_term(
+        split_on(&a, &c).into_iter().map(Text).collect(),
+      ))),
+      TextOp::Replace => apply3_text_text_text(Some(Text(
+        c.to_string().replace(&a.to_string(), &c.to_string()).into(),
+      ))),
+      TextOp::Trim => apply1_text(Some(Text(a.to_string().trim().into()))),
+      TextOp::StartsWith => {
+        apply2_text_text(Some(Bool(c.to_string().starts_with(&a.to_string()))))
+      }
+      TextOp::EndsWith => {
+        apply2_text_text(Some(Bool(c.to_string().ends_with(&a.to_string()))))
+      }
+      TextOp::ToUpper => {
+        apply1_text(Some(Text(a.to_string().to_uppercase().into())))
+      }
+      TextOp::ToLower => {
+        apply1_text(Some(Text(a.to_string().to_lowercase().into())))
+      }
+      TextOp::ToNat => {
+        apply1_text_term(Some(option_term(parse_nat(&a).map(Nat))))
+      }
+      TextOp::ToInt => {
+        apply1_text_term(Some(option_term(parse_int(&a).map(Int))))
+      }
+      TextOp::View => {
+        let x = if d < a.len_chars() as u64 {
+          Some(Char(a.char(d as usize)))
+        }
+        else {
+          None
+        };
+        apply2_nat_text_term(Some(view_term(&big(d), x)))
+      }
+      TextOp::Abort => apply2_text_text(None),
     }
   }
 
+  #[quickcheck]
+  fn test_apply_in_place(
+    op: TextOp,
+    a: String,
+    b: char,
+    c: u8,
+    d: String,
+    e: u8,
+  ) -> TestResult {
+    let a = Rope::from(a);
+    let d = Rope::from(d);
+    let args = match op {
+      TextOp::Cons => vec![Char(b), Text(a)],
+      TextOp::Append => vec![Text(a), Text(d)],
+      TextOp::Insert => vec![Nat(c.into()), Text(d), Text(a)],
+      TextOp::Remove => vec![Nat(c.into()), Nat(e.into()), Text(a)],
+      _ => return TestResult::from_bool(!op.updates()),
+    };
+    let expected = match op.arity() {
+      2 => op.apply2(&args[0], &args[1]),
+      _ => op.apply3(&args[0], &args[1], &args[2]),
+    };
+    TestResult::from_bool(op.apply_in_place(&mut args.clone()) == expected)
+  }
+
   #[derive(Debug, Clone)]
   struct ArgsApplyNoneOnInvalid(
     TextOp,
@@ -802,6 +1316,37 @@ pub mod tests {
         }
       };
 
+    let test_apply1_term_none_on_invalid = |valid_arg: Literal| -> TestResult {
+      if mem::discriminant(&valid_arg) == mem::discriminant(&a) {
+        TestResult::discard()
+      }
+      else {
+        TestResult::from_bool(TextOp::apply1_term(op, &a) == None)
+      }
+    };
+
+    let test_apply2_term_none_on_invalid =
+      |valid_arg: Literal, a_: Literal, b_: Literal| -> TestResult {
+        let go =
+          || TestResult::from_bool(TextOp::apply2_term(op, &a_, &b_) == None);
+        if test_arg_2 {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&a_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+        else {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&b_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+      };
+
     let test_apply3_none_on_invalid = |valid_arg: Literal,
                                        a_: Literal,
                                        b_: Literal,
@@ -842,7 +1387,14 @@ pub mod tests {
       TextOp::LenChars
       | TextOp::LenBytes
       | TextOp::LenLines
-      | TextOp::ToBytes => test_apply1_none_on_invalid(Text(b)),
+      | TextOp::ToBytes
+      | TextOp::Trim
+      | TextOp::ToUpper
+      | TextOp::ToLower => test_apply1_none_on_invalid(Text(b)),
+      // Arity 1 with a term result, valid is Text.
+      TextOp::ToNat | TextOp::ToInt => {
+        test_apply1_term_none_on_invalid(Text(b))
+      }
       // Arity 2, valid are Char on a and Text on b.
       TextOp::Cons => {
         if test_arg_2 {
@@ -858,7 +1410,9 @@ pub mod tests {
       | TextOp::Lte
       | TextOp::Lth
       | TextOp::Gte
-      | TextOp::Gth => {
+      | TextOp::Gth
+      | TextOp::StartsWith
+      | TextOp::EndsWith => {
         if test_arg_2 {
           test_apply2_none_on_invalid(Text(b.clone()), a, Text(b.clone()))
         }
@@ -866,6 +1420,29 @@ pub mod tests {
           test_apply2_none_on_invalid(Text(b.clone()), Text(b.clone()), a)
         }
       }
+      // Arity 2 with a term result, valid are Nat on a and Text on b.
+      TextOp::View => {
+        if test_arg_2 {
+          test_apply2_term_none_on_invalid(Nat(big(e)), a, Text(b.clone()))
+        }
+        else {
+          test_apply2_term_none_on_invalid(Text(b.clone()), Nat(big(e)), a)
+        }
+      }
+      // Arity 2, never reduces.
+      TextOp::Abort => TestResult::from_bool(
+        TextOp::apply2(op, &a, &Text(b.clone())) == None
+          && TextOp::apply2_term(op, &a, &Text(b.clone())) == None,
+      ),
+      // Arity 2 with a term result, valid are Text on a and b.
+      TextOp::Find | TextOp::Split => {
+        if test_arg_2 {
+          test_apply2_term_none_on_invalid(Text(b.clone()), a, Text(b.clone()))
+        }
+        else {
+          test_apply2_term_none_on_invalid(Text(b.clone()), Text(b.clone()), a)
+        }
+      }
       // Arity 2, valid are Nat on a and Text on b.
       TextOp::Take
       | TextOp::Drop
@@ -927,6 +1504,27 @@ pub mod tests {
           a,
         ),
       },
+      // Arity 3, valid are Text on a, b and c.
+      TextOp::Replace => match test_arg_3 {
+        TestArg3::A => test_apply3_none_on_invalid(
+          Text(b.clone()),
+          a,
+          Text(b.clone()),
+          Text(d.clone()),
+        ),
+        TestArg3::B => test_apply3_none_on_invalid(
+          Text(b.clone()),
+          Text(b.clone()),
+          a,
+          Text(d.clone()),
+        ),
+        TestArg3::C => test_apply3_none_on_invalid(
+          Text(b.clone()),
+          Text(b.clone()),
+          Text(d.clone()),
+          a,
+        ),
+      },
     }
   }
 }
@@ -13,7 +13,10 @@ use crate::{
   ipld_error::IpldError,
   literal::Literal,
   parse,
-  prim::bits,
+  prim::{
+    bits,
+    option_term,
+  },
   term::Term,
   yatima,
 };
@@ -59,6 +62,15 @@ pub enum U16Op {
   ToInt,
   ToBits,
   ToBytes,
+  AddChecked,
+  SubChecked,
+  MulChecked,
+  AddWrapping,
+  SubWrapping,
+  MulWrapping,
+  AddSaturating,
+  SubSaturating,
+  MulSaturating,
 }
 
 impl U16Op {
@@ -101,6 +113,15 @@ impl U16Op {
       Self::ToInt => "to_Int".to_owned(),
       Self::ToBits => "to_Bits".to_owned(),
       Self::ToBytes => "to_Bytes".to_owned(),
+      Self::AddChecked => "add_checked".to_owned(),
+      Self::SubChecked => "sub_checked".to_owned(),
+      Self::MulChecked => "mul_checked".to_owned(),
+      Self::AddWrapping => "add_wrapping".to_owned(),
+      Self::SubWrapping => "sub_wrapping".to_owned(),
+      Self::MulWrapping => "mul_wrapping".to_owned(),
+      Self::AddSaturating => "add_saturating".to_owned(),
+      Self::SubSaturating => "sub_saturating".to_owned(),
+      Self::MulSaturating => "mul_saturating".to_owned(),
     }
   }
 
@@ -143,6 +164,15 @@ impl U16Op {
       "to_Int" => Some(Self::ToInt),
       "to_Bits" => Some(Self::ToBits),
       "to_Bytes" => Some(Self::ToBytes),
+      "add_checked" => Some(Self::AddChecked),
+      "sub_checked" => Some(Self::SubChecked),
+      "mul_checked" => Some(Self::MulChecked),
+      "add_wrapping" => Some(Self::AddWrapping),
+      "sub_wrapping" => Some(Self::SubWrapping),
+      "mul_wrapping" => Some(Self::MulWrapping),
+      "add_saturating" => Some(Self::AddSaturating),
+      "sub_saturating" => Some(Self::SubSaturating),
+      "mul_saturating" => Some(Self::MulSaturating),
       _ => None,
     }
   }
@@ -186,6 +216,24 @@ impl U16Op {
       Self::ToInt => yatima!("∀ #U16 -> #Int"),
       Self::ToBits => yatima!("∀ #U8 -> #Bits"),
       Self::ToBytes => yatima!("∀ #U16 -> #Bytes"),
+      Self::AddChecked => yatima!(
+        "∀ #U16 #U16
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #U16 -> P) -> P"
+      ),
+      Self::SubChecked => yatima!(
+        "∀ #U16 #U16
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #U16 -> P) -> P"
+      ),
+      Self::MulChecked => yatima!(
+        "∀ #U16 #U16
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #U16 -> P) -> P"
+      ),
+      Self::AddWrapping => yatima!("∀ #U16 #U16 -> #U16"),
+      Self::SubWrapping => yatima!("∀ #U16 #U16 -> #U16"),
+      Self::MulWrapping => yatima!("∀ #U16 #U16 -> #U16"),
+      Self::AddSaturating => yatima!("∀ #U16 #U16 -> #U16"),
+      Self::SubSaturating => yatima!("∀ #U16 #U16 -> #U16"),
+      Self::MulSaturating => yatima!("∀ #U16 #U16 -> #U16"),
     }
   }
 
@@ -228,6 +276,15 @@ impl U16Op {
       Self::ToInt => Ipld::Integer(33),
       Self::ToBits => Ipld::Integer(34),
       Self::ToBytes => Ipld::Integer(35),
+      Self::AddChecked => Ipld::Integer(36),
+      Self::SubChecked => Ipld::Integer(37),
+      Self::MulChecked => Ipld::Integer(38),
+      Self::AddWrapping => Ipld::Integer(39),
+      Self::SubWrapping => Ipld::Integer(40),
+      Self::MulWrapping => Ipld::Integer(41),
+      Self::AddSaturating => Ipld::Integer(42),
+      Self::SubSaturating => Ipld::Integer(43),
+      Self::MulSaturating => Ipld::Integer(44),
     }
   }
 
@@ -270,6 +327,15 @@ impl U16Op {
       Ipld::Integer(33) => Ok(Self::ToInt),
       Ipld::Integer(34) => Ok(Self::ToBits),
       Ipld::Integer(35) => Ok(Self::ToBytes),
+      Ipld::Integer(36) => Ok(Self::AddChecked),
+      Ipld::Integer(37) => Ok(Self::SubChecked),
+      Ipld::Integer(38) => Ok(Self::MulChecked),
+      Ipld::Integer(39) => Ok(Self::AddWrapping),
+      Ipld::Integer(40) => Ok(Self::SubWrapping),
+      Ipld::Integer(41) => Ok(Self::MulWrapping),
+      Ipld::Integer(42) => Ok(Self::AddSaturating),
+      Ipld::Integer(43) => Ok(Self::SubSaturating),
+      Ipld::Integer(44) => Ok(Self::MulSaturating),
       xs => Err(IpldError::NatOp(xs.to_owned())),
     }
   }
@@ -313,6 +379,15 @@ impl U16Op {
       Self::ToInt => 1,
       Self::ToBits => 1,
       Self::ToBytes => 1,
+      Self::AddChecked => 2,
+      Self::SubChecked => 2,
+      Self::MulChecked => 2,
+      Self::AddWrapping => 2,
+      Self::SubWrapping => 2,
+      Self::MulWrapping => 2,
+      Self::AddSaturating => 2,
+      Self::SubSaturating => 2,
+      Self::MulSaturating => 2,
     }
   }
 
@@ -390,6 +465,30 @@ impl U16Op {
       (Self::Shr, U32(x), U16(y)) => Some(U16(y.wrapping_shr(*x))),
       (Self::Rol, U32(x), U16(y)) => Some(U16(y.rotate_left(*x))),
       (Self::Ror, U32(x), U16(y)) => Some(U16(y.rotate_right(*x))),
+      (Self::AddWrapping, U16(x), U16(y)) => Some(U16(x.wrapping_add(*y))),
+      (Self::SubWrapping, U16(x), U16(y)) => Some(U16(x.wrapping_sub(*y))),
+      (Self::MulWrapping, U16(x), U16(y)) => Some(U16(x.wrapping_mul(*y))),
+      (Self::AddSaturating, U16(x), U16(y)) => Some(U16(x.saturating_add(*y))),
+      (Self::SubSaturating, U16(x), U16(y)) => Some(U16(x.saturating_sub(*y))),
+      (Self::MulSaturating, U16(x), U16(y)) => Some(U16(x.saturating_mul(*y))),
+      _ => None,
+    }
+  }
+
+  /// Applies a binary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply2_term(self, x: &Literal, y: &Literal) -> Option<Term> {
+    use Literal::*;
+    match (self, x, y) {
+      (Self::AddChecked, U16(x), U16(y)) => {
+        Some(option_term(x.checked_add(*y).map(U16)))
+      }
+      (Self::SubChecked, U16(x), U16(y)) => {
+        Some(option_term(x.checked_sub(*y).map(U16)))
+      }
+      (Self::MulChecked, U16(x), U16(y)) => {
+        Some(option_term(x.checked_mul(*y).map(U16)))
+      }
       _ => None,
     }
   }
@@ -436,7 +535,7 @@ pub mod tests {
   impl Arbitrary for U16Op {
     fn arbitrary(_g: &mut Gen) -> Self {
       let mut rng = rand::thread_rng();
-      let gen: u32 = rng.gen_range(0..=33);
+      let gen: u32 = rng.gen_range(0..=42);
       match gen {
         0 => Self::Max,
         1 => Self::Min,
@@ -471,7 +570,16 @@ pub mod tests {
         30 => Self::ToI64,
         31 => Self::ToInt,
         32 => Self::ToBytes,
-        _ => Self::ToBits,
+        33 => Self::ToBits,
+        34 => Self::AddChecked,
+        35 => Self::SubChecked,
+        36 => Self::MulChecked,
+        37 => Self::AddWrapping,
+        38 => Self::SubWrapping,
+        39 => Self::MulWrapping,
+        40 => Self::AddSaturating,
+        41 => Self::SubSaturating,
+        _ => Self::MulSaturating,
       }
     }
   }
@@ -506,6 +614,12 @@ pub mod tests {
       TestResult::from_bool(U16Op::apply2(op, &U32(c), &U16(a)) == expected)
     };
 
+    let apply2_u16_u16_term = |expected: Option<Term>| -> TestResult {
+      TestResult::from_bool(
+        U16Op::apply2_term(op, &U16(a), &U16(b)) == expected,
+      )
+    };
+
     let from_bool = TestResult::from_bool;
 
     match op {
@@ -583,6 +697,21 @@ pub mod tests {
         apply1_u16(Some(Bits(bits::bytes_to_bits(16, &a.to_be_bytes().into()))))
       }
       U16Op::ToBytes => apply1_u16(Some(Bytes(a.to_be_bytes().into()))),
+      U16Op::AddChecked => {
+        apply2_u16_u16_term(Some(option_term(a.checked_add(b).map(U16))))
+      }
+      U16Op::SubChecked => {
+        apply2_u16_u16_term(Some(option_term(a.checked_sub(b).map(U16))))
+      }
+      U16Op::MulChecked => {
+        apply2_u16_u16_term(Some(option_term(a.checked_mul(b).map(U16))))
+      }
+      U16Op::AddWrapping => apply2_u16_u16(Some(U16(a.wrapping_add(b)))),
+      U16Op::SubWrapping => apply2_u16_u16(Some(U16(a.wrapping_sub(b)))),
+      U16Op::MulWrapping => apply2_u16_u16(Some(U16(a.wrapping_mul(b)))),
+      U16Op::AddSaturating => apply2_u16_u16(Some(U16(a.saturating_add(b)))),
+      U16Op::SubSaturating => apply2_u16_u16(Some(U16(a.saturating_sub(b)))),
+      U16Op::MulSaturating => apply2_u16_u16(Some(U16(a.saturating_mul(b)))),
     }
   }
 
@@ -624,6 +753,28 @@ pub mod tests {
         }
       };
 
+    let test_apply2_term_none_on_invalid =
+      |valid_arg: Literal, a_: Literal, b_: Literal| -> TestResult {
+        let go =
+          || TestResult::from_bool(U16Op::apply2_term(op, &a_, &b_) == None);
+        if test_arg_2 {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&a_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+        else {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&b_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+      };
+
     match op {
       // Arity 0.
       U16Op::Max | U16Op::Min => TestResult::discard(),
@@ -657,7 +808,13 @@ pub mod tests {
       | U16Op::Sub
       | U16Op::Mul
       | U16Op::Div
-      | U16Op::Mod => {
+      | U16Op::Mod
+      | U16Op::AddWrapping
+      | U16Op::SubWrapping
+      | U16Op::MulWrapping
+      | U16Op::AddSaturating
+      | U16Op::SubSaturating
+      | U16Op::MulSaturating => {
         if test_arg_2 {
           test_apply2_none_on_invalid(U16(b), a, U16(b))
         }
@@ -665,6 +822,15 @@ pub mod tests {
           test_apply2_none_on_invalid(U16(b), U16(b), a)
         }
       }
+      // Arity 2 with a term result, valid are U16 on a and b.
+      U16Op::AddChecked | U16Op::SubChecked | U16Op::MulChecked => {
+        if test_arg_2 {
+          test_apply2_term_none_on_invalid(U16(b), a, U16(b))
+        }
+        else {
+          test_apply2_term_none_on_invalid(U16(b), U16(b), a)
+        }
+      }
       // Arity 2, valid are U16 on a and U32 on b.
       U16Op::Pow => {
         if test_arg_2 {
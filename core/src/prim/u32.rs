@@ -13,7 +13,10 @@ use crate::{
   ipld_error::IpldError,
   literal::Literal,
   parse,
-  prim::bits,
+  prim::{
+    bits,
+    option_term,
+  },
   term::Term,
   yatima,
 };
@@ -60,6 +63,15 @@ pub enum U32Op {
   ToBits,
   ToBytes,
   ToChar,
+  AddChecked,
+  SubChecked,
+  MulChecked,
+  AddWrapping,
+  SubWrapping,
+  MulWrapping,
+  AddSaturating,
+  SubSaturating,
+  MulSaturating,
 }
 
 impl U32Op {
@@ -103,6 +115,15 @@ impl U32Op {
       Self::ToBits => "to_Bits".to_owned(),
       Self::ToBytes => "to_Bytes".to_owned(),
       Self::ToChar => "to_Char".to_owned(),
+      Self::AddChecked => "add_checked".to_owned(),
+      Self::SubChecked => "sub_checked".to_owned(),
+      Self::MulChecked => "mul_checked".to_owned(),
+      Self::AddWrapping => "add_wrapping".to_owned(),
+      Self::SubWrapping => "sub_wrapping".to_owned(),
+      Self::MulWrapping => "mul_wrapping".to_owned(),
+      Self::AddSaturating => "add_saturating".to_owned(),
+      Self::SubSaturating => "sub_saturating".to_owned(),
+      Self::MulSaturating => "mul_saturating".to_owned(),
     }
   }
 
@@ -146,6 +167,15 @@ impl U32Op {
       "to_Bits" => Some(Self::ToBits),
       "to_Bytes" => Some(Self::ToBytes),
       "to_Char" => Some(Self::ToChar),
+      "add_checked" => Some(Self::AddChecked),
+      "sub_checked" => Some(Self::SubChecked),
+      "mul_checked" => Some(Self::MulChecked),
+      "add_wrapping" => Some(Self::AddWrapping),
+      "sub_wrapping" => Some(Self::SubWrapping),
+      "mul_wrapping" => Some(Self::MulWrapping),
+      "add_saturating" => Some(Self::AddSaturating),
+      "sub_saturating" => Some(Self::SubSaturating),
+      "mul_saturating" => Some(Self::MulSaturating),
       _ => None,
     }
   }
@@ -190,6 +220,24 @@ impl U32Op {
       Self::ToBits => yatima!("∀ #U32 -> #Bits"),
       Self::ToBytes => yatima!("∀ #U32 -> #Bytes"),
       Self::ToChar => yatima!("∀ #U32 -> #Char"),
+      Self::AddChecked => yatima!(
+        "∀ #U32 #U32
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #U32 -> P) -> P"
+      ),
+      Self::SubChecked => yatima!(
+        "∀ #U32 #U32
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #U32 -> P) -> P"
+      ),
+      Self::MulChecked => yatima!(
+        "∀ #U32 #U32
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #U32 -> P) -> P"
+      ),
+      Self::AddWrapping => yatima!("∀ #U32 #U32 -> #U32"),
+      Self::SubWrapping => yatima!("∀ #U32 #U32 -> #U32"),
+      Self::MulWrapping => yatima!("∀ #U32 #U32 -> #U32"),
+      Self::AddSaturating => yatima!("∀ #U32 #U32 -> #U32"),
+      Self::SubSaturating => yatima!("∀ #U32 #U32 -> #U32"),
+      Self::MulSaturating => yatima!("∀ #U32 #U32 -> #U32"),
     }
   }
 
@@ -233,6 +281,15 @@ impl U32Op {
       Self::ToBits => Ipld::Integer(34),
       Self::ToBytes => Ipld::Integer(35),
       Self::ToChar => Ipld::Integer(36),
+      Self::AddChecked => Ipld::Integer(37),
+      Self::SubChecked => Ipld::Integer(38),
+      Self::MulChecked => Ipld::Integer(39),
+      Self::AddWrapping => Ipld::Integer(40),
+      Self::SubWrapping => Ipld::Integer(41),
+      Self::MulWrapping => Ipld::Integer(42),
+      Self::AddSaturating => Ipld::Integer(43),
+      Self::SubSaturating => Ipld::Integer(44),
+      Self::MulSaturating => Ipld::Integer(45),
     }
   }
 
@@ -276,6 +333,15 @@ impl U32Op {
       Ipld::Integer(34) => Ok(Self::ToBits),
       Ipld::Integer(35) => Ok(Self::ToBytes),
       Ipld::Integer(36) => Ok(Self::ToChar),
+      Ipld::Integer(37) => Ok(Self::AddChecked),
+      Ipld::Integer(38) => Ok(Self::SubChecked),
+      Ipld::Integer(39) => Ok(Self::MulChecked),
+      Ipld::Integer(40) => Ok(Self::AddWrapping),
+      Ipld::Integer(41) => Ok(Self::SubWrapping),
+      Ipld::Integer(42) => Ok(Self::MulWrapping),
+      Ipld::Integer(43) => Ok(Self::AddSaturating),
+      Ipld::Integer(44) => Ok(Self::SubSaturating),
+      Ipld::Integer(45) => Ok(Self::MulSaturating),
       xs => Err(IpldError::NatOp(xs.to_owned())),
     }
   }
@@ -320,6 +386,15 @@ impl U32Op {
       Self::ToBits => 1,
       Self::ToBytes => 1,
       Self::ToChar => 1,
+      Self::AddChecked => 2,
+      Self::SubChecked => 2,
+      Self::MulChecked => 2,
+      Self::AddWrapping => 2,
+      Self::SubWrapping => 2,
+      Self::MulWrapping => 2,
+      Self::AddSaturating => 2,
+      Self::SubSaturating => 2,
+      Self::MulSaturating => 2,
     }
   }
 
@@ -398,6 +473,30 @@ impl U32Op {
       (Self::Shr, U32(x), U32(y)) => Some(U32(y.wrapping_shr(*x))),
       (Self::Rol, U32(x), U32(y)) => Some(U32(y.rotate_left(*x))),
       (Self::Ror, U32(x), U32(y)) => Some(U32(y.rotate_right(*x))),
+      (Self::AddWrapping, U32(x), U32(y)) => Some(U32(x.wrapping_add(*y))),
+      (Self::SubWrapping, U32(x), U32(y)) => Some(U32(x.wrapping_sub(*y))),
+      (Self::MulWrapping, U32(x), U32(y)) => Some(U32(x.wrapping_mul(*y))),
+      (Self::AddSaturating, U32(x), U32(y)) => Some(U32(x.saturating_add(*y))),
+      (Self::SubSaturating, U32(x), U32(y)) => Some(U32(x.saturating_sub(*y))),
+      (Self::MulSaturating, U32(x), U32(y)) => Some(U32(x.saturating_mul(*y))),
+      _ => None,
+    }
+  }
+
+  /// Applies a binary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply2_term(self, x: &Literal, y: &Literal) -> Option<Term> {
+    use Literal::*;
+    match (self, x, y) {
+      (Self::AddChecked, U32(x), U32(y)) => {
+        Some(option_term(x.checked_add(*y).map(U32)))
+      }
+      (Self::SubChecked, U32(x), U32(y)) => {
+        Some(option_term(x.checked_sub(*y).map(U32)))
+      }
+      (Self::MulChecked, U32(x), U32(y)) => {
+        Some(option_term(x.checked_mul(*y).map(U32)))
+      }
       _ => None,
     }
   }
@@ -444,7 +543,7 @@ pub mod tests {
   impl Arbitrary for U32Op {
     fn arbitrary(_g: &mut Gen) -> Self {
       let mut rng = rand::thread_rng();
-      let gen: u32 = rng.gen_range(0..=34);
+      let gen: u32 = rng.gen_range(0..=43);
       match gen {
         0 => Self::Max,
         1 => Self::Min,
@@ -480,7 +579,16 @@ pub mod tests {
         31 => Self::ToI64,
         32 => Self::ToInt,
         33 => Self::ToBytes,
-        _ => Self::ToBits,
+        34 => Self::ToBits,
+        35 => Self::AddChecked,
+        36 => Self::SubChecked,
+        37 => Self::MulChecked,
+        38 => Self::AddWrapping,
+        39 => Self::SubWrapping,
+        40 => Self::MulWrapping,
+        41 => Self::AddSaturating,
+        42 => Self::SubSaturating,
+        _ => Self::MulSaturating,
         /* 27 => Self::ToU128,
          * 33 => Self::ToI128, */
       }
@@ -517,6 +625,12 @@ pub mod tests {
       TestResult::from_bool(U32Op::apply2(op, &U32(c), &U32(a)) == expected)
     };
 
+    let apply2_u32_u32_term = |expected: Option<Term>| -> TestResult {
+      TestResult::from_bool(
+        U32Op::apply2_term(op, &U32(a), &U32(b)) == expected,
+      )
+    };
+
     let from_bool = TestResult::from_bool;
 
     match op {
@@ -607,6 +721,27 @@ pub mod tests {
       }
       U32Op::ToBytes => apply1_u32(Some(Bytes(a.to_be_bytes().into()))),
       U32Op::ToChar => apply1_u32(char::from_u32(a).map(Char)),
+      U32Op::AddChecked => {
+        apply2_u32_u32_term(Some(option_term(a.checked_add(b).map(U32))))
+      }
+      U32Op::SubChecked => {
+        apply2_u32_u32_term(Some(option_term(a.checked_sub(b).map(U32))))
+      }
+      U32Op::MulChecked => {
+        apply2_u32_u32_term(Some(option_term(a.checked_mul(b).map(U32))))
+      }
+      U32Op::AddWrapping => apply2_u32_a_u32_b(Some(U32(a.wrapping_add(b)))),
+      U32Op::SubWrapping => apply2_u32_a_u32_b(Some(U32(a.wrapping_sub(b)))),
+      U32Op::MulWrapping => apply2_u32_a_u32_b(Some(U32(a.wrapping_mul(b)))),
+      U32Op::AddSaturating => {
+        apply2_u32_a_u32_b(Some(U32(a.saturating_add(b))))
+      }
+      U32Op::SubSaturating => {
+        apply2_u32_a_u32_b(Some(U32(a.saturating_sub(b))))
+      }
+      U32Op::MulSaturating => {
+        apply2_u32_a_u32_b(Some(U32(a.saturating_mul(b))))
+      }
     }
   }
 
@@ -648,6 +783,28 @@ pub mod tests {
         }
       };
 
+    let test_apply2_term_none_on_invalid =
+      |valid_arg: Literal, a_: Literal, b_: Literal| -> TestResult {
+        let go =
+          || TestResult::from_bool(U32Op::apply2_term(op, &a_, &b_) == None);
+        if test_arg_2 {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&a_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+        else {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&b_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+      };
+
     match op {
       // Arity 0.
       U32Op::Max | U32Op::Min => TestResult::discard(),
@@ -682,7 +839,13 @@ pub mod tests {
       | U32Op::Sub
       | U32Op::Mul
       | U32Op::Div
-      | U32Op::Mod => {
+      | U32Op::Mod
+      | U32Op::AddWrapping
+      | U32Op::SubWrapping
+      | U32Op::MulWrapping
+      | U32Op::AddSaturating
+      | U32Op::SubSaturating
+      | U32Op::MulSaturating => {
         if test_arg_2 {
           test_apply2_none_on_invalid(U32(b), a, U32(b))
         }
@@ -690,6 +853,15 @@ pub mod tests {
           test_apply2_none_on_invalid(U32(b), U32(b), a)
         }
       }
+      // Arity 2 with a term result, valid are U32 on a and b.
+      U32Op::AddChecked | U32Op::SubChecked | U32Op::MulChecked => {
+        if test_arg_2 {
+          test_apply2_term_none_on_invalid(U32(b), a, U32(b))
+        }
+        else {
+          test_apply2_term_none_on_invalid(U32(b), U32(b), a)
+        }
+      }
       // Arity 2, valid are U32 on a and U32 on b.
       U32Op::Pow => {
         if test_arg_2 {
@@ -13,7 +13,10 @@ use crate::{
   ipld_error::IpldError,
   literal::Literal,
   parse,
-  prim::bits,
+  prim::{
+    bits,
+    option_term,
+  },
   term::Term,
   yatima,
 };
@@ -59,6 +62,15 @@ pub enum U64Op {
   ToInt,
   ToBits,
   ToBytes,
+  AddChecked,
+  SubChecked,
+  MulChecked,
+  AddWrapping,
+  SubWrapping,
+  MulWrapping,
+  AddSaturating,
+  SubSaturating,
+  MulSaturating,
 }
 
 impl U64Op {
@@ -101,6 +113,15 @@ impl U64Op {
       Self::ToInt => "to_Int".to_owned(),
       Self::ToBits => "to_Bits".to_owned(),
       Self::ToBytes => "to_Bytes".to_owned(),
+      Self::AddChecked => "add_checked".to_owned(),
+      Self::SubChecked => "sub_checked".to_owned(),
+      Self::MulChecked => "mul_checked".to_owned(),
+      Self::AddWrapping => "add_wrapping".to_owned(),
+      Self::SubWrapping => "sub_wrapping".to_owned(),
+      Self::MulWrapping => "mul_wrapping".to_owned(),
+      Self::AddSaturating => "add_saturating".to_owned(),
+      Self::SubSaturating => "sub_saturating".to_owned(),
+      Self::MulSaturating => "mul_saturating".to_owned(),
     }
   }
 
@@ -143,6 +164,15 @@ impl U64Op {
       "to_Int" => Some(Self::ToInt),
       "to_Bits" => Some(Self::ToBits),
       "to_Bytes" => Some(Self::ToBytes),
+      "add_checked" => Some(Self::AddChecked),
+      "sub_checked" => Some(Self::SubChecked),
+      "mul_checked" => Some(Self::MulChecked),
+      "add_wrapping" => Some(Self::AddWrapping),
+      "sub_wrapping" => Some(Self::SubWrapping),
+      "mul_wrapping" => Some(Self::MulWrapping),
+      "add_saturating" => Some(Self::AddSaturating),
+      "sub_saturating" => Some(Self::SubSaturating),
+      "mul_saturating" => Some(Self::MulSaturating),
       _ => None,
     }
   }
@@ -186,6 +216,24 @@ impl U64Op {
       Self::ToInt => yatima!("∀ #U64 -> #Int"),
       Self::ToBits => yatima!("∀ #U64 -> #Bits"),
       Self::ToBytes => yatima!("∀ #U64 -> #Bytes"),
+      Self::AddChecked => yatima!(
+        "∀ #U64 #U64
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #U64 -> P) -> P"
+      ),
+      Self::SubChecked => yatima!(
+        "∀ #U64 #U64
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #U64 -> P) -> P"
+      ),
+      Self::MulChecked => yatima!(
+        "∀ #U64 #U64
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #U64 -> P) -> P"
+      ),
+      Self::AddWrapping => yatima!("∀ #U64 #U64 -> #U64"),
+      Self::SubWrapping => yatima!("∀ #U64 #U64 -> #U64"),
+      Self::MulWrapping => yatima!("∀ #U64 #U64 -> #U64"),
+      Self::AddSaturating => yatima!("∀ #U64 #U64 -> #U64"),
+      Self::SubSaturating => yatima!("∀ #U64 #U64 -> #U64"),
+      Self::MulSaturating => yatima!("∀ #U64 #U64 -> #U64"),
     }
   }
 
@@ -228,6 +276,15 @@ impl U64Op {
       Self::ToInt => Ipld::Integer(33),
       Self::ToBits => Ipld::Integer(34),
       Self::ToBytes => Ipld::Integer(35),
+      Self::AddChecked => Ipld::Integer(36),
+      Self::SubChecked => Ipld::Integer(37),
+      Self::MulChecked => Ipld::Integer(38),
+      Self::AddWrapping => Ipld::Integer(39),
+      Self::SubWrapping => Ipld::Integer(40),
+      Self::MulWrapping => Ipld::Integer(41),
+      Self::AddSaturating => Ipld::Integer(42),
+      Self::SubSaturating => Ipld::Integer(43),
+      Self::MulSaturating => Ipld::Integer(44),
     }
   }
 
@@ -270,6 +327,15 @@ impl U64Op {
       Ipld::Integer(33) => Ok(Self::ToInt),
       Ipld::Integer(34) => Ok(Self::ToBits),
       Ipld::Integer(35) => Ok(Self::ToBytes),
+      Ipld::Integer(36) => Ok(Self::AddChecked),
+      Ipld::Integer(37) => Ok(Self::SubChecked),
+      Ipld::Integer(38) => Ok(Self::MulChecked),
+      Ipld::Integer(39) => Ok(Self::AddWrapping),
+      Ipld::Integer(40) => Ok(Self::SubWrapping),
+      Ipld::Integer(41) => Ok(Self::MulWrapping),
+      Ipld::Integer(42) => Ok(Self::AddSaturating),
+      Ipld::Integer(43) => Ok(Self::SubSaturating),
+      Ipld::Integer(44) => Ok(Self::MulSaturating),
       xs => Err(IpldError::NatOp(xs.to_owned())),
     }
   }
@@ -313,6 +379,15 @@ impl U64Op {
       Self::ToInt => 1,
       Self::ToBits => 1,
       Self::ToBytes => 1,
+      Self::AddChecked => 2,
+      Self::SubChecked => 2,
+      Self::MulChecked => 2,
+      Self::AddWrapping => 2,
+      Self::SubWrapping => 2,
+      Self::MulWrapping => 2,
+      Self::AddSaturating => 2,
+      Self::SubSaturating => 2,
+      Self::MulSaturating => 2,
     }
   }
 
@@ -388,6 +463,30 @@ impl U64Op {
       (Self::Shr, U32(x), U64(y)) => Some(U64(y.wrapping_shr(*x))),
       (Self::Rol, U32(x), U64(y)) => Some(U64(y.rotate_left(*x))),
       (Self::Ror, U32(x), U64(y)) => Some(U64(y.rotate_right(*x))),
+      (Self::AddWrapping, U64(x), U64(y)) => Some(U64(x.wrapping_add(*y))),
+      (Self::SubWrapping, U64(x), U64(y)) => Some(U64(x.wrapping_sub(*y))),
+      (Self::MulWrapping, U64(x), U64(y)) => Some(U64(x.wrapping_mul(*y))),
+      (Self::AddSaturating, U64(x), U64(y)) => Some(U64(x.saturating_add(*y))),
+      (Self::SubSaturating, U64(x), U64(y)) => Some(U64(x.saturating_sub(*y))),
+      (Self::MulSaturating, U64(x), U64(y)) => Some(U64(x.saturating_mul(*y))),
+      _ => None,
+    }
+  }
+
+  /// Applies a binary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply2_term(self, x: &Literal, y: &Literal) -> Option<Term> {
+    use Literal::*;
+    match (self, x, y) {
+      (Self::AddChecked, U64(x), U64(y)) => {
+        Some(option_term(x.checked_add(*y).map(U64)))
+      }
+      (Self::SubChecked, U64(x), U64(y)) => {
+        Some(option_term(x.checked_sub(*y).map(U64)))
+      }
+      (Self::MulChecked, U64(x), U64(y)) => {
+        Some(option_term(x.checked_mul(*y).map(U64)))
+      }
       _ => None,
     }
   }
@@ -434,7 +533,7 @@ pub mod tests {
   impl Arbitrary for U64Op {
     fn arbitrary(_g: &mut Gen) -> Self {
       let mut rng = rand::thread_rng();
-      let gen: u32 = rng.gen_range(0..=33);
+      let gen: u32 = rng.gen_range(0..=42);
       match gen {
         0 => Self::Max,
         1 => Self::Min,
@@ -469,7 +568,16 @@ pub mod tests {
         30 => Self::ToI64,
         31 => Self::ToInt,
         32 => Self::ToBytes,
-        _ => Self::ToBits,
+        33 => Self::ToBits,
+        34 => Self::AddChecked,
+        35 => Self::SubChecked,
+        36 => Self::MulChecked,
+        37 => Self::AddWrapping,
+        38 => Self::SubWrapping,
+        39 => Self::MulWrapping,
+        40 => Self::AddSaturating,
+        41 => Self::SubSaturating,
+        _ => Self::MulSaturating,
         /* 26 => Self::ToU128,
          * 32 => Self::ToI128, */
       }
@@ -506,6 +614,12 @@ pub mod tests {
       TestResult::from_bool(U64Op::apply2(op, &U32(c), &U64(a)) == expected)
     };
 
+    let apply2_u64_u64_term = |expected: Option<Term>| -> TestResult {
+      TestResult::from_bool(
+        U64Op::apply2_term(op, &U64(a), &U64(b)) == expected,
+      )
+    };
+
     let from_bool = TestResult::from_bool;
 
     match op {
@@ -593,6 +707,21 @@ pub mod tests {
         apply1_u64(Some(Bits(bits::bytes_to_bits(64, &a.to_be_bytes().into()))))
       }
       U64Op::ToBytes => apply1_u64(Some(Bytes(a.to_be_bytes().into()))),
+      U64Op::AddChecked => {
+        apply2_u64_u64_term(Some(option_term(a.checked_add(b).map(U64))))
+      }
+      U64Op::SubChecked => {
+        apply2_u64_u64_term(Some(option_term(a.checked_sub(b).map(U64))))
+      }
+      U64Op::MulChecked => {
+        apply2_u64_u64_term(Some(option_term(a.checked_mul(b).map(U64))))
+      }
+      U64Op::AddWrapping => apply2_u64_u64(Some(U64(a.wrapping_add(b)))),
+      U64Op::SubWrapping => apply2_u64_u64(Some(U64(a.wrapping_sub(b)))),
+      U64Op::MulWrapping => apply2_u64_u64(Some(U64(a.wrapping_mul(b)))),
+      U64Op::AddSaturating => apply2_u64_u64(Some(U64(a.saturating_add(b)))),
+      U64Op::SubSaturating => apply2_u64_u64(Some(U64(a.saturating_sub(b)))),
+      U64Op::MulSaturating => apply2_u64_u64(Some(U64(a.saturating_mul(b)))),
     }
   }
 
@@ -634,6 +763,28 @@ pub mod tests {
         }
       };
 
+    let test_apply2_term_none_on_invalid =
+      |valid_arg: Literal, a_: Literal, b_: Literal| -> TestResult {
+        let go =
+          || TestResult::from_bool(U64Op::apply2_term(op, &a_, &b_) == None);
+        if test_arg_2 {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&a_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+        else {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&b_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+      };
+
     match op {
       // Arity 0.
       U64Op::Max | U64Op::Min => TestResult::discard(),
@@ -667,7 +818,13 @@ pub mod tests {
       | U64Op::Sub
       | U64Op::Mul
       | U64Op::Div
-      | U64Op::Mod => {
+      | U64Op::Mod
+      | U64Op::AddWrapping
+      | U64Op::SubWrapping
+      | U64Op::MulWrapping
+      | U64Op::AddSaturating
+      | U64Op::SubSaturating
+      | U64Op::MulSaturating => {
         if test_arg_2 {
           test_apply2_none_on_invalid(U64(b), a, U64(b))
         }
@@ -675,6 +832,15 @@ pub mod tests {
           test_apply2_none_on_invalid(U64(b), U64(b), a)
         }
       }
+      // Arity 2 with a term result, valid are U64 on a and b.
+      U64Op::AddChecked | U64Op::SubChecked | U64Op::MulChecked => {
+        if test_arg_2 {
+          test_apply2_term_none_on_invalid(U64(b), a, U64(b))
+        }
+        else {
+          test_apply2_term_none_on_invalid(U64(b), U64(b), a)
+        }
+      }
       // Arity 2, valid are U64 on a and U32 on b.
       U64Op::Pow => {
         if test_arg_2 {
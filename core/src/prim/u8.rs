@@ -13,7 +13,10 @@ use crate::{
   ipld_error::IpldError,
   literal::Literal,
   parse,
-  prim::bits,
+  prim::{
+    bits,
+    option_term,
+  },
   term::Term,
   yatima,
 };
@@ -60,6 +63,15 @@ pub enum U8Op {
   ToBits,
   ToBytes,
   ToChar,
+  AddChecked,
+  SubChecked,
+  MulChecked,
+  AddWrapping,
+  SubWrapping,
+  MulWrapping,
+  AddSaturating,
+  SubSaturating,
+  MulSaturating,
 }
 
 impl U8Op {
@@ -103,6 +115,15 @@ impl U8Op {
       Self::ToBits => "to_Bits".to_owned(),
       Self::ToBytes => "to_Bytes".to_owned(),
       Self::ToChar => "to_Char".to_owned(),
+      Self::AddChecked => "add_checked".to_owned(),
+      Self::SubChecked => "sub_checked".to_owned(),
+      Self::MulChecked => "mul_checked".to_owned(),
+      Self::AddWrapping => "add_wrapping".to_owned(),
+      Self::SubWrapping => "sub_wrapping".to_owned(),
+      Self::MulWrapping => "mul_wrapping".to_owned(),
+      Self::AddSaturating => "add_saturating".to_owned(),
+      Self::SubSaturating => "sub_saturating".to_owned(),
+      Self::MulSaturating => "mul_saturating".to_owned(),
     }
   }
 
@@ -146,6 +167,15 @@ impl U8Op {
       "to_Bits" => Some(Self::ToBits),
       "to_Bytes" => Some(Self::ToBytes),
       "to_Char" => Some(Self::ToChar),
+      "add_checked" => Some(Self::AddChecked),
+      "sub_checked" => Some(Self::SubChecked),
+      "mul_checked" => Some(Self::MulChecked),
+      "add_wrapping" => Some(Self::AddWrapping),
+      "sub_wrapping" => Some(Self::SubWrapping),
+      "mul_wrapping" => Some(Self::MulWrapping),
+      "add_saturating" => Some(Self::AddSaturating),
+      "sub_saturating" => Some(Self::SubSaturating),
+      "mul_saturating" => Some(Self::MulSaturating),
       _ => None,
     }
   }
@@ -190,6 +220,24 @@ impl U8Op {
       Self::ToBits => yatima!("∀ #U8 -> #Bits"),
       Self::ToBytes => yatima!("∀ #U8 -> #Bytes"),
       Self::ToChar => yatima!("∀ #U8 -> #Char"),
+      Self::AddChecked => yatima!(
+        "∀ #U8 #U8
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #U8 -> P) -> P"
+      ),
+      Self::SubChecked => yatima!(
+        "∀ #U8 #U8
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #U8 -> P) -> P"
+      ),
+      Self::MulChecked => yatima!(
+        "∀ #U8 #U8
+           -> ∀ (0 P: Type) (& none: P) (& some: ∀ #U8 -> P) -> P"
+      ),
+      Self::AddWrapping => yatima!("∀ #U8 #U8 -> #U8"),
+      Self::SubWrapping => yatima!("∀ #U8 #U8 -> #U8"),
+      Self::MulWrapping => yatima!("∀ #U8 #U8 -> #U8"),
+      Self::AddSaturating => yatima!("∀ #U8 #U8 -> #U8"),
+      Self::SubSaturating => yatima!("∀ #U8 #U8 -> #U8"),
+      Self::MulSaturating => yatima!("∀ #U8 #U8 -> #U8"),
     }
   }
 
@@ -233,6 +281,15 @@ impl U8Op {
       Self::ToBits => Ipld::Integer(34),
       Self::ToBytes => Ipld::Integer(35),
       Self::ToChar => Ipld::Integer(36),
+      Self::AddChecked => Ipld::Integer(37),
+      Self::SubChecked => Ipld::Integer(38),
+      Self::MulChecked => Ipld::Integer(39),
+      Self::AddWrapping => Ipld::Integer(40),
+      Self::SubWrapping => Ipld::Integer(41),
+      Self::MulWrapping => Ipld::Integer(42),
+      Self::AddSaturating => Ipld::Integer(43),
+      Self::SubSaturating => Ipld::Integer(44),
+      Self::MulSaturating => Ipld::Integer(45),
     }
   }
 
@@ -276,6 +333,15 @@ impl U8Op {
       Ipld::Integer(34) => Ok(Self::ToBits),
       Ipld::Integer(35) => Ok(Self::ToBytes),
       Ipld::Integer(36) => Ok(Self::ToChar),
+      Ipld::Integer(37) => Ok(Self::AddChecked),
+      Ipld::Integer(38) => Ok(Self::SubChecked),
+      Ipld::Integer(39) => Ok(Self::MulChecked),
+      Ipld::Integer(40) => Ok(Self::AddWrapping),
+      Ipld::Integer(41) => Ok(Self::SubWrapping),
+      Ipld::Integer(42) => Ok(Self::MulWrapping),
+      Ipld::Integer(43) => Ok(Self::AddSaturating),
+      Ipld::Integer(44) => Ok(Self::SubSaturating),
+      Ipld::Integer(45) => Ok(Self::MulSaturating),
       xs => Err(IpldError::NatOp(xs.to_owned())),
     }
   }
@@ -320,6 +386,15 @@ impl U8Op {
       Self::ToInt => 1,
       Self::ToBytes => 1,
       Self::ToBits => 1,
+      Self::AddChecked => 2,
+      Self::SubChecked => 2,
+      Self::MulChecked => 2,
+      Self::AddWrapping => 2,
+      Self::SubWrapping => 2,
+      Self::MulWrapping => 2,
+      Self::AddSaturating => 2,
+      Self::SubSaturating => 2,
+      Self::MulSaturating => 2,
     }
   }
 
@@ -398,6 +473,30 @@ impl U8Op {
       (Self::Shr, U32(x), U8(y)) => Some(U8(y.wrapping_shr(*x))),
       (Self::Rol, U32(x), U8(y)) => Some(U8(y.rotate_left(*x))),
       (Self::Ror, U32(x), U8(y)) => Some(U8(y.rotate_right(*x))),
+      (Self::AddWrapping, U8(x), U8(y)) => Some(U8(x.wrapping_add(*y))),
+      (Self::SubWrapping, U8(x), U8(y)) => Some(U8(x.wrapping_sub(*y))),
+      (Self::MulWrapping, U8(x), U8(y)) => Some(U8(x.wrapping_mul(*y))),
+      (Self::AddSaturating, U8(x), U8(y)) => Some(U8(x.saturating_add(*y))),
+      (Self::SubSaturating, U8(x), U8(y)) => Some(U8(x.saturating_sub(*y))),
+      (Self::MulSaturating, U8(x), U8(y)) => Some(U8(x.saturating_mul(*y))),
+      _ => None,
+    }
+  }
+
+  /// Applies a binary operation with a non-literal result to a literal and
+  /// returns it if successful
+  pub fn apply2_term(self, x: &Literal, y: &Literal) -> Option<Term> {
+    use Literal::*;
+    match (self, x, y) {
+      (Self::AddChecked, U8(x), U8(y)) => {
+        Some(option_term(x.checked_add(*y).map(U8)))
+      }
+      (Self::SubChecked, U8(x), U8(y)) => {
+        Some(option_term(x.checked_sub(*y).map(U8)))
+      }
+      (Self::MulChecked, U8(x), U8(y)) => {
+        Some(option_term(x.checked_mul(*y).map(U8)))
+      }
       _ => None,
     }
   }
@@ -445,7 +544,7 @@ pub mod tests {
   impl Arbitrary for U8Op {
     fn arbitrary(_g: &mut Gen) -> Self {
       let mut rng = rand::thread_rng();
-      let gen: u32 = rng.gen_range(0..=34);
+      let gen: u32 = rng.gen_range(0..=43);
       match gen {
         0 => Self::Max,
         1 => Self::Min,
@@ -481,7 +580,16 @@ pub mod tests {
         31 => Self::ToI64,
         32 => Self::ToInt,
         33 => Self::ToBytes,
-        _ => Self::ToBits,
+        34 => Self::ToBits,
+        35 => Self::AddChecked,
+        36 => Self::SubChecked,
+        37 => Self::MulChecked,
+        38 => Self::AddWrapping,
+        39 => Self::SubWrapping,
+        40 => Self::MulWrapping,
+        41 => Self::AddSaturating,
+        42 => Self::SubSaturating,
+        _ => Self::MulSaturating,
         /* 27 => Self::ToU128,
          * 33 => Self::ToI128, */
       }
@@ -518,6 +626,10 @@ pub mod tests {
       TestResult::from_bool(U8Op::apply2(op, &U32(c), &U8(a)) == expected)
     };
 
+    let apply2_u8_u8_term = |expected: Option<Term>| -> TestResult {
+      TestResult::from_bool(U8Op::apply2_term(op, &U8(a), &U8(b)) == expected)
+    };
+
     let from_bool = TestResult::from_bool;
 
     match op {
@@ -590,6 +702,21 @@ pub mod tests {
       }
       U8Op::ToBytes => apply1_u8(Some(Bytes(a.to_be_bytes().into()))),
       U8Op::ToChar => apply1_u8(Some(Char((a).into()))),
+      U8Op::AddChecked => {
+        apply2_u8_u8_term(Some(option_term(a.checked_add(b).map(U8))))
+      }
+      U8Op::SubChecked => {
+        apply2_u8_u8_term(Some(option_term(a.checked_sub(b).map(U8))))
+      }
+      U8Op::MulChecked => {
+        apply2_u8_u8_term(Some(option_term(a.checked_mul(b).map(U8))))
+      }
+      U8Op::AddWrapping => apply2_u8_u8(Some(U8(a.wrapping_add(b)))),
+      U8Op::SubWrapping => apply2_u8_u8(Some(U8(a.wrapping_sub(b)))),
+      U8Op::MulWrapping => apply2_u8_u8(Some(U8(a.wrapping_mul(b)))),
+      U8Op::AddSaturating => apply2_u8_u8(Some(U8(a.saturating_add(b)))),
+      U8Op::SubSaturating => apply2_u8_u8(Some(U8(a.saturating_sub(b)))),
+      U8Op::MulSaturating => apply2_u8_u8(Some(U8(a.saturating_mul(b)))),
     }
   }
 
@@ -631,6 +758,28 @@ pub mod tests {
         }
       };
 
+    let test_apply2_term_none_on_invalid =
+      |valid_arg: Literal, a_: Literal, b_: Literal| -> TestResult {
+        let go =
+          || TestResult::from_bool(U8Op::apply2_term(op, &a_, &b_) == None);
+        if test_arg_2 {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&a_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+        else {
+          if mem::discriminant(&valid_arg) == mem::discriminant(&b_) {
+            TestResult::discard()
+          }
+          else {
+            go()
+          }
+        }
+      };
+
     match op {
       // Arity 0.
       U8Op::Max | U8Op::Min => TestResult::discard(),
@@ -665,7 +814,13 @@ pub mod tests {
       | U8Op::Sub
       | U8Op::Mul
       | U8Op::Div
-      | U8Op::Mod => {
+      | U8Op::Mod
+      | U8Op::AddWrapping
+      | U8Op::SubWrapping
+      | U8Op::MulWrapping
+      | U8Op::AddSaturating
+      | U8Op::SubSaturating
+      | U8Op::MulSaturating => {
         if test_arg_2 {
           test_apply2_none_on_invalid(U8(b), a, U8(b))
         }
@@ -673,6 +828,15 @@ pub mod tests {
           test_apply2_none_on_invalid(U8(b), U8(b), a)
         }
       }
+      // Arity 2 with a term result, valid are U8 on a and b.
+      U8Op::AddChecked | U8Op::SubChecked | U8Op::MulChecked => {
+        if test_arg_2 {
+          test_apply2_term_none_on_invalid(U8(b), a, U8(b))
+        }
+        else {
+          test_apply2_term_none_on_invalid(U8(b), U8(b), a)
+        }
+      }
       // Arity 2, valid are U8 on a and U32 on b.
       U8Op::Pow => {
         if test_arg_2 {
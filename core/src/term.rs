@@ -125,6 +125,8 @@ impl PartialEq for Term {
   }
 }
 
+impl Eq for Term {}
+
 impl Term {
   /// Returns the position of the term
   pub fn pos(&self) -> Pos {
@@ -147,6 +149,32 @@ impl Term {
     }
   }
 
+  /// The number of nodes of the term
+  pub fn size(&self) -> u64 {
+    let mut size = 0;
+    let mut todo = vec![self];
+    while let Some(term) = todo.pop() {
+      size += 1;
+      match term {
+        Term::Lam(_, _, bod)
+        | Term::Slf(_, _, bod)
+        | Term::Dat(_, bod)
+        | Term::Cse(_, bod) => todo.push(bod),
+        Term::App(_, two) | Term::All(_, _, _, two) | Term::Ann(_, two) => {
+          todo.push(&two.0);
+          todo.push(&two.1);
+        }
+        Term::Let(_, _, _, _, three) => {
+          todo.push(&three.0);
+          todo.push(&three.1);
+          todo.push(&three.2);
+        }
+        _ => (),
+      }
+    }
+    size
+  }
+
   /// Shifts the term's de Bruijn index by a given incrementor
   pub fn shift(self, inc: i64, dep: Option<u64>) -> Self {
     match self {
@@ -0,0 +1,82 @@
+// Generated by `yatima build --target js`
+
+class $Thunk {
+  constructor(f) {
+    this.f = f;
+    this.v = undefined;
+  }
+}
+
+const $loop = () => {
+  throw new Error("A value depends on itself");
+};
+
+const $stuck = (op) => {
+  throw new Error(`${op} does not reduce`);
+};
+
+function $force(x) {
+  if (!(x instanceof $Thunk)) return x;
+  if (x.f !== null) {
+    const f = x.f;
+    x.f = $loop;
+    x.v = f();
+    x.f = null;
+  }
+  return x.v;
+}
+
+function $prim(arity, f) {
+  const go = (args) => (x) => {
+    const xs = [...args, x];
+    return xs.length < arity ? go(xs) : f(...xs.map($force));
+  };
+  return go([]);
+}
+
+// #Nat.add
+const $op0 = $prim(2, (a, b) => a + b);
+// #Text.append
+const $op1 = $prim(2, (a, b) => a + b);
+
+// id
+const $d0 = new $Thunk(() => (x0) => (x1) => $force(x1));
+
+// const
+const $d1 = new $Thunk(() => (x0) => (x1) => (x2) => (x3) => $force(x2));
+
+// double
+const $d2 = new $Thunk(() => (x0) => $op0(x0)(x0));
+
+// quadruple
+const $d3 = new $Thunk(() => (x0) => $force($d2)(new $Thunk(() => $force($d2)(x0))));
+
+// greeting
+const $d4 = new $Thunk(() => $op1("hello, ")("world"));
+
+// twice
+const $d5 = new $Thunk(() => (x0) => (x1) => (x2) => $force(x1)(new $Thunk(() => $force(x1)(x2))));
+
+// ones
+const $d6 = new $Thunk(() => (() => { const x0 = 1n; return $op0(x0)(x0); })());
+
+// forever
+const $d7 = new $Thunk(() => (x0) => $force($d7)(x0));
+
+// yes
+const $d8 = new $Thunk(() => $force($d9)(true));
+
+// return
+const $d9 = new $Thunk(() => (x0) => $force(x0));
+
+export default {
+  get "id"() { return $force($d0); },
+  get "const"() { return $force($d1); },
+  get "double"() { return $force($d2); },
+  get "quadruple"() { return $force($d3); },
+  get "greeting"() { return $force($d4); },
+  get "twice"() { return $force($d5); },
+  get "ones"() { return $force($d6); },
+  get "forever"() { return $force($d7); },
+  get "yes"() { return $force($d8); },
+};
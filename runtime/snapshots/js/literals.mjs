@@ -0,0 +1,79 @@
+// Generated by `yatima build --target js`
+
+class $Thunk {
+  constructor(f) {
+    this.f = f;
+    this.v = undefined;
+  }
+}
+
+const $loop = () => {
+  throw new Error("A value depends on itself");
+};
+
+const $stuck = (op) => {
+  throw new Error(`${op} does not reduce`);
+};
+
+function $force(x) {
+  if (!(x instanceof $Thunk)) return x;
+  if (x.f !== null) {
+    const f = x.f;
+    x.f = $loop;
+    x.v = f();
+    x.f = null;
+  }
+  return x.v;
+}
+
+function $prim(arity, f) {
+  const go = (args) => (x) => {
+    const xs = [...args, x];
+    return xs.length < arity ? go(xs) : f(...xs.map($force));
+  };
+  return go([]);
+}
+
+
+// big
+const $d0 = new $Thunk(() => 123456789012345678901234567890n);
+
+// neg
+const $d1 = new $Thunk(() => (-42n));
+
+// small
+const $d2 = new $Thunk(() => (-5));
+
+// wide
+const $d3 = new $Thunk(() => 18446744073709551615n);
+
+// byte
+const $d4 = new $Thunk(() => 255);
+
+// letter
+const $d5 = new $Thunk(() => "λ");
+
+// message
+const $d6 = new $Thunk(() => "say \"hi\"\n");
+
+// blob
+const $d7 = new $Thunk(() => new Uint8Array([255]));
+
+// flag
+const $d8 = new $Thunk(() => false);
+
+// header
+const $d9 = new $Thunk(() => new Uint8Array([1, 2, 255]));
+
+export default {
+  get "big"() { return $force($d0); },
+  get "neg"() { return $force($d1); },
+  get "small"() { return $force($d2); },
+  get "wide"() { return $force($d3); },
+  get "byte"() { return $force($d4); },
+  get "letter"() { return $force($d5); },
+  get "message"() { return $force($d6); },
+  get "blob"() { return $force($d7); },
+  get "flag"() { return $force($d8); },
+  get "header"() { return $force($d9); },
+};
@@ -0,0 +1,61 @@
+// Extracted from `total` by `yatima extract --lang rust`
+#![allow(non_camel_case_types, non_snake_case, unused)]
+
+use num_bigint::{BigInt, BigUint};
+use std::rc::Rc;
+
+fn lam<A, B>(f: impl Fn(A) -> B + 'static) -> Rc<dyn Fn(A) -> B> {
+    Rc::new(f)
+}
+
+fn app<A, B>(f: &Rc<dyn Fn(A) -> B>, a: A) -> B {
+    f(a)
+}
+
+fn nat_add(a: BigUint, b: BigUint) -> BigUint { a + b }
+
+fn nat_mul(a: BigUint, b: BigUint) -> BigUint { a * b }
+
+#[derive(Clone)]
+pub enum List<A> {
+    Nil,
+    Cons(A, Box<List<A>>),
+}
+
+pub fn total(xs: List<BigUint>) -> BigUint {
+    List_sum(List_map(lam(move |x| double(x)), List_tail(xs)))
+}
+
+pub fn List_sum(xs: List<BigUint>) -> BigUint {
+    match xs {
+        List::Nil => BigUint::from(0u64),
+        List::Cons(x, xs_1) => {
+            let xs_1 = *xs_1;
+            nat_add(x, List_sum(xs_1))
+        }
+    }
+}
+
+pub fn List_map<A: Clone + 'static, B: Clone + 'static>(f: Rc<dyn Fn(A) -> B>, xs: List<A>) -> List<B> {
+    match xs {
+        List::Nil => List::Nil,
+        List::Cons(x, xs_1) => {
+            let xs_1 = *xs_1;
+            List::Cons(app(&f, x), Box::new(List_map(Clone::clone(&f), xs_1)))
+        }
+    }
+}
+
+pub fn double(x: BigUint) -> BigUint {
+    nat_mul(BigUint::from(2u64), x)
+}
+
+pub fn List_tail<A: Clone + 'static>(xs: List<A>) -> List<A> {
+    match &xs {
+        List::Nil => Clone::clone(&xs),
+        List::Cons(y, ys) => {
+            let ys = Clone::clone(&**ys);
+            ys
+        }
+    }
+}
@@ -0,0 +1,52 @@
+// Extracted from `demo` by `yatima extract --lang rust`
+#![allow(non_camel_case_types, non_snake_case, unused)]
+
+use num_bigint::{BigInt, BigUint};
+use std::rc::Rc;
+
+fn lam<A, B>(f: impl Fn(A) -> B + 'static) -> Rc<dyn Fn(A) -> B> {
+    Rc::new(f)
+}
+
+fn app<A, B>(f: &Rc<dyn Fn(A) -> B>, a: A) -> B {
+    f(a)
+}
+
+fn text_append(a: String, b: String) -> String { a + &b }
+
+fn nat_add(a: BigUint, b: BigUint) -> BigUint { a + b }
+
+#[derive(Clone)]
+pub enum Pair<A, B> {
+    New(A, B),
+}
+
+pub fn demo(p: Pair<BigUint, String>) -> Pair<String, BigUint> {
+    Pair_swap(match p {
+        Pair::New(n, t) => Pair::New(app(&adder(), n), greet(t)),
+    })
+}
+
+pub fn Pair_swap<A: Clone + 'static, B: Clone + 'static>(p: Pair<A, B>) -> Pair<B, A> {
+    match p {
+        Pair::New(a, b) => Pair::New(b, a),
+    }
+}
+
+pub fn adder() -> Rc<dyn Fn(BigUint) -> BigUint> {
+    {
+        let x = BigUint::from(1u64);
+        lam(move |x_1| add(Clone::clone(&x), x_1))
+    }
+}
+
+pub fn greet(name: String) -> String {
+    {
+        let hello = String::from("hello, ");
+        text_append(hello, name)
+    }
+}
+
+pub fn add(x: BigUint, y: BigUint) -> BigUint {
+    nat_add(x, y)
+}
@@ -0,0 +1,309 @@
+//! An interactive debugger for graph reduction. `runtime::whnf` shows the
+//! debugger every step it takes while counting, and the debugger pauses on
+//! the steps the user asked for: the next step, the next step that is not
+//! taken while forcing an argument, or a breakpoint. A breakpoint on a
+//! definition pauses when reduction enters its code from another definition,
+//! and on every unfolding of a recursive definition. A breakpoint on a
+//! primitive pauses before it is applied to its arguments.
+//!
+//! Steps are attributed to definitions as in `profile`, which must be active
+//! while debugging.
+
+use std::{
+  collections::BTreeMap,
+  convert::TryFrom,
+  fmt,
+  ptr::NonNull,
+};
+
+use sp_cid::Cid;
+use yatima_core::{
+  defs::Defs,
+  name::Name,
+};
+
+use crate::{
+  profile::{
+    self,
+    Origin,
+    Step,
+  },
+  runtime::{
+    App,
+    Fix,
+    Lam,
+    Lit,
+    Opr,
+    Var,
+    DAG,
+  },
+};
+
+/// How deep the terms of a pause are shown
+pub const SHOW_DEPTH: usize = 6;
+
+/// Errors that may occur while setting breakpoints
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugError {
+  /// A breakpoint on a name or CID without a definition
+  Undefined(String),
+}
+
+impl fmt::Display for DebugError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Undefined(def) => write!(f, "Undefined definition {}", def),
+    }
+  }
+}
+
+/// Where the debugger pauses
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Breakpoint {
+  /// The code of a definition
+  Def(Name),
+  /// The primitives whose symbol starts with the given prefix
+  Prim(String),
+}
+
+impl Breakpoint {
+  /// Parses a breakpoint: the prefix of a primitive symbol such as `#Nat.add`
+  /// or `#` for every primitive, or the name or CID of a definition of `defs`
+  pub fn parse(defs: &Defs, s: &str) -> Result<Self, DebugError> {
+    if s.starts_with('#') {
+      return Ok(Breakpoint::Prim(s.to_owned()));
+    }
+    let name = Name::from(s);
+    if defs.names.contains_key(&name) {
+      return Ok(Breakpoint::Def(name));
+    }
+    if let Ok(cid) = Cid::try_from(s) {
+      for (name, key) in &defs.names {
+        let found = defs.defs.get(key).map_or(false, |def| {
+          *key == cid || def.def_cid == cid || def.ast_cid == cid
+        });
+        if found {
+          return Ok(Breakpoint::Def(name.clone()));
+        }
+      }
+    }
+    Err(DebugError::Undefined(s.to_owned()))
+  }
+}
+
+impl fmt::Display for Breakpoint {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Def(name) => write!(f, "{}", name),
+      Self::Prim(prefix) => write!(f, "{}", prefix),
+    }
+  }
+}
+
+/// What the debugger does after a pause
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resume {
+  /// Pauses at the next step
+  Step,
+  /// Pauses at the next step not taken while forcing an argument of the
+  /// current redex
+  Next,
+  /// Pauses at the next breakpoint
+  Continue,
+}
+
+impl std::str::FromStr for Resume {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "s" | "step" => Ok(Resume::Step),
+      "n" | "next" => Ok(Resume::Next),
+      "c" | "continue" => Ok(Resume::Continue),
+      _ => Err(format!(
+        "Unknown debugger command \"{}\", expected step, next or continue",
+        s
+      )),
+    }
+  }
+}
+
+/// A step the debugger paused on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pause {
+  pub step: Step,
+  /// The definition of the step
+  pub def: String,
+  /// The breakpoint hit, if any
+  pub breakpoint: Option<Breakpoint>,
+  /// The node at the head of the redex
+  pub head: String,
+  /// The arguments the head is applied to, first to last
+  pub spine: Vec<String>,
+  /// The number of suspended frames forcing arguments
+  pub depth: usize,
+}
+
+impl fmt::Display for Pause {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if let Some(breakpoint) = &self.breakpoint {
+      writeln!(f, "Breakpoint {}", breakpoint)?;
+    }
+    let step = match self.step {
+      Step::Beta => "beta",
+      Step::Prim => "prim",
+      Step::Unfold => "unfold",
+    };
+    writeln!(f, "{} in {} at depth {}", step, self.def, self.depth)?;
+    writeln!(f, "  head: {}", self.head)?;
+    for arg in &self.spine {
+      writeln!(f, "   arg: {}", arg)?;
+    }
+    Ok(())
+  }
+}
+
+/// The state of a debugging session
+pub struct Debugger<'a> {
+  breakpoints: Vec<Breakpoint>,
+  resume: Resume,
+  /// The depth of the last pause, for `Resume::Next`
+  depth: usize,
+  /// The definition of the last step
+  last: Origin,
+  /// Shows a pause to the user and asks how to resume
+  prompt: Box<dyn FnMut(&Pause) -> Resume + 'a>,
+}
+
+impl<'a> Debugger<'a> {
+  /// A debugger pausing at the given breakpoints, or at the first step if
+  /// there are none
+  pub fn new(
+    breakpoints: Vec<Breakpoint>,
+    prompt: Box<dyn FnMut(&Pause) -> Resume + 'a>,
+  ) -> Self {
+    let resume =
+      if breakpoints.is_empty() { Resume::Step } else { Resume::Continue };
+    Debugger { breakpoints, resume, depth: 0, last: 0, prompt }
+  }
+
+  /// Shows a step of the definition `origin` on the redex of `head` and
+  /// `trail` to the debugger, taken under `depth` suspended frames, pausing if
+  /// it was asked to
+  pub(crate) fn step(
+    &mut self,
+    step: Step,
+    origin: Origin,
+    head: DAG,
+    trail: &[NonNull<App>],
+    depth: usize,
+  ) {
+    let def = profile::name(origin).unwrap_or_default();
+    let entered = origin != self.last;
+    self.last = origin;
+    let breakpoint = self
+      .breakpoints
+      .iter()
+      .find(|breakpoint| match (breakpoint, head) {
+        (Breakpoint::Def(name), _) => {
+          (entered || step == Step::Unfold) && name.to_string() == def
+        }
+        (Breakpoint::Prim(prefix), DAG::Opr(link)) => unsafe {
+          link.as_ref().opr.symbol().starts_with(prefix.as_str())
+        },
+        _ => false,
+      })
+      .cloned();
+    let paused = breakpoint.is_some()
+      || match self.resume {
+        Resume::Step => true,
+        Resume::Next => depth <= self.depth,
+        Resume::Continue => false,
+      };
+    if !paused {
+      return;
+    }
+    let mut names = BTreeMap::new();
+    let head = show(head, SHOW_DEPTH, &mut names);
+    let spine = trail
+      .iter()
+      .rev()
+      .map(|link| show(unsafe { link.as_ref().arg }, SHOW_DEPTH, &mut names))
+      .collect();
+    let pause = Pause { step, def, breakpoint, head, spine, depth };
+    self.resume = (self.prompt)(&pause);
+    self.depth = depth;
+  }
+}
+
+/// Shows a node up to the given depth, naming variables by their address
+fn show(
+  node: DAG,
+  depth: usize,
+  names: &mut BTreeMap<usize, String>,
+) -> String {
+  fn name(var: usize, names: &mut BTreeMap<usize, String>) -> String {
+    let len = names.len();
+    names.entry(var).or_insert_with(|| format!("x{}", len)).clone()
+  }
+  fn arg(
+    node: DAG,
+    depth: usize,
+    names: &mut BTreeMap<usize, String>,
+  ) -> String {
+    match node {
+      DAG::App(_) | DAG::Lam(_) => format!("({})", show(node, depth, names)),
+      DAG::Fix(link) if unsafe { link.as_ref().origin } == 0 => {
+        format!("({})", show(node, depth, names))
+      }
+      _ => show(node, depth, names),
+    }
+  }
+  if depth == 0 {
+    return "...".to_owned();
+  }
+  match node {
+    DAG::Var(link) => name(link.as_ptr() as usize, names),
+    DAG::Lit(link) => {
+      let Lit { lit, .. } = unsafe { link.as_ref() };
+      format!("{}", lit)
+    }
+    DAG::Opr(link) => {
+      let Opr { opr, .. } = unsafe { link.as_ref() };
+      format!("{}", opr)
+    }
+    DAG::Lam(link) => {
+      let Lam { var, bod, .. } = unsafe { link.as_ref() };
+      let var = name(var as *const Var as usize, names);
+      format!("λ {} => {}", var, show(*bod, depth - 1, names))
+    }
+    DAG::Fix(link) => {
+      let Fix { var, bod, origin, .. } = unsafe { link.as_ref() };
+      // Recursive definitions are shown by name rather than unfolded
+      match profile::name(*origin) {
+        Some(def) if *origin != 0 => def,
+        _ => {
+          let var = name(var as *const Var as usize, names);
+          format!("fix {} => {}", var, show(*bod, depth - 1, names))
+        }
+      }
+    }
+    DAG::App(mut link) => {
+      let mut args = vec![];
+      let fun = loop {
+        let App { fun, arg: app_arg, .. } = unsafe { link.as_ref() };
+        args.push(arg(*app_arg, depth - 1, names));
+        match fun {
+          DAG::App(fun_link) => link = *fun_link,
+          _ => break *fun,
+        }
+      };
+      let mut res = arg(fun, depth - 1, names);
+      for arg in args.iter().rev() {
+        res.push(' ');
+        res.push_str(arg);
+      }
+      res
+    }
+  }
+}
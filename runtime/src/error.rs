@@ -0,0 +1,81 @@
+use std::fmt;
+
+use yatima_core::{
+  limits::LimitError,
+  term::Term,
+};
+
+/// Errors that may occur while running a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuntimeError {
+  /// Raised by `#Text.abort` with the given message
+  Abort(String),
+  /// An `extern` symbol that the runtime cannot resolve, with the symbols it
+  /// does provide
+  UnknownExtern(String, Vec<String>),
+  /// An `extern` declaration of a host function whose type differs from the
+  /// type the function was registered with: the symbol, the declared type and
+  /// the registered type
+  ExternType(String, Term, Term),
+  /// An `extern` declaration of a built-in symbol whose type does not start
+  /// with the parameters the runtime's implementation takes: the symbol, the
+  /// declared type and the expected parameters
+  ExternParams(String, Term, String),
+  /// Raised by `io.exit` to stop the program with the given status
+  Exit(i32),
+  /// The program exceeded one of the resource limits it was run with
+  Limit(LimitError),
+  /// A replayed program asked for a different input than the one logged next:
+  /// the input it asked for and the logged event
+  ReplayDiverged(String, String),
+  /// A replayed program asked for more inputs than were logged
+  ReplayEnded,
+  /// An input of a recorded program could not be written to the log
+  ReplayWrite(String),
+  /// A reference to a definition missing from the ones the program is run
+  /// with: its name and content address
+  UndefinedReference(String, String),
+  /// A variable with the given index bound outside of the program
+  FreeVariable(usize),
+}
+
+impl fmt::Display for RuntimeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Abort(msg) => write!(f, "Aborted: {}", msg),
+      Self::UnknownExtern(symbol, known) => write!(
+        f,
+        "Unknown extern symbol \"{}\", this runtime provides: {}",
+        symbol,
+        known.join(", ")
+      ),
+      Self::ExternType(symbol, declared, registered) => write!(
+        f,
+        "Extern symbol \"{}\" is declared with type {} but the host function \
+         has type {}",
+        symbol, declared, registered
+      ),
+      Self::ExternParams(symbol, declared, params) => write!(
+        f,
+        "Extern symbol \"{}\" is declared with type {} but the runtime \
+         expects the parameters {}",
+        symbol, declared, params
+      ),
+      Self::Exit(code) => write!(f, "Exited with status {}", code),
+      Self::Limit(e) => write!(f, "Resource limit exceeded: {}", e),
+      Self::ReplayDiverged(expected, found) => write!(
+        f,
+        "Replay log diverged from the program: expected {}, found `{}`",
+        expected, found
+      ),
+      Self::ReplayEnded => {
+        write!(f, "Replay log ended before the program did")
+      }
+      Self::ReplayWrite(e) => write!(f, "Failed to write replay log: {}", e),
+      Self::UndefinedReference(name, cid) => {
+        write!(f, "Undefined reference {} to {}", name, cid)
+      }
+      Self::FreeVariable(idx) => write!(f, "Free variable with index {}", idx),
+    }
+  }
+}
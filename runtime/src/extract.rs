@@ -0,0 +1,1412 @@
+//! Extracts definitions to Rust source.
+//!
+//! A definition becomes a Rust function, and the definitions it refers to are
+//! extracted with it. Types are erased using quantities: binders of quantity
+//! `0`, and binders of types, disappear from functions, constructors and
+//! calls, and binders of `Type` become generic parameters. The self types
+//! elaborated from `type` declarations become enums with a variant for each
+//! constructor, and case expressions on them become `match` expressions.
+//! Primitive types map to Rust types: `#Nat` to `BigUint` and `#Int` to
+//! `BigInt` from the `num-bigint` crate, `#Text` to `String`, `#Bytes` to
+//! `Vec<u8>` and the fixed-width integers to the integer types of the same
+//! width. Function values are `Rc<dyn Fn>` closures taking one argument.
+//!
+//! The extracted code is strict where the runtime is lazy, so arguments are
+//! evaluated even when a definition does not use them. A variable of quantity
+//! `1` or `&`, or that occurs once and outside of lambdas, is moved unless a
+//! closure captures it, and every other use of a variable clones it. A
+//! `match` on a variable that is used again borrows it and clones the fields
+//! its arm uses. Dependent types, polymorphic function values,
+//! recursive lets, externs and the primitive operations missing from `OPS`
+//! are rejected.
+
+use std::{
+  collections::BTreeSet,
+  fmt,
+};
+
+use sp_cid::Cid;
+use yatima_core::{
+  defs::{
+    Def,
+    Defs,
+  },
+  literal::{
+    LitType,
+    Literal,
+  },
+  name::Name,
+  prim::Op,
+  term::Term,
+  uses::Uses,
+};
+
+/// Errors that may occur while extracting definitions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractError {
+  /// The definition to extract does not exist
+  Undefined(Name),
+  /// A term or type without a Rust counterpart
+  Unsupported(String),
+}
+
+impl fmt::Display for ExtractError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Undefined(name) => write!(f, "Undefined definition {}", name),
+      Self::Unsupported(what) => {
+        write!(f, "Extraction to Rust does not support {}", what)
+      }
+    }
+  }
+}
+
+fn unsupported(what: String) -> ExtractError { ExtractError::Unsupported(what) }
+
+/// The primitive operations extraction supports, as the name and the
+/// signature and body of a Rust function
+#[rustfmt::skip]
+const OPS: &[(&str, &str, &str)] = &[
+  ("#Nat.suc", "nat_suc", "(a: BigUint) -> BigUint { a + 1u32 }"),
+  ("#Nat.pre", "nat_pre", "(a: BigUint) -> BigUint { if a == BigUint::from(0u32) { a } else { a - 1u32 } }"),
+  ("#Nat.eql", "nat_eql", "(a: BigUint, b: BigUint) -> bool { a == b }"),
+  ("#Nat.lte", "nat_lte", "(a: BigUint, b: BigUint) -> bool { a <= b }"),
+  ("#Nat.lth", "nat_lth", "(a: BigUint, b: BigUint) -> bool { a < b }"),
+  ("#Nat.gte", "nat_gte", "(a: BigUint, b: BigUint) -> bool { a >= b }"),
+  ("#Nat.gth", "nat_gth", "(a: BigUint, b: BigUint) -> bool { a > b }"),
+  ("#Nat.add", "nat_add", "(a: BigUint, b: BigUint) -> BigUint { a + b }"),
+  ("#Nat.sub", "nat_sub", "(a: BigUint, b: BigUint) -> BigUint { if a >= b { a - b } else { panic!(\"#Nat.sub does not reduce\") } }"),
+  ("#Nat.mul", "nat_mul", "(a: BigUint, b: BigUint) -> BigUint { a * b }"),
+  ("#Nat.div", "nat_div", "(a: BigUint, b: BigUint) -> BigUint { if b == BigUint::from(0u32) { panic!(\"#Nat.div does not reduce\") } else { a / b } }"),
+  ("#Nat.mod", "nat_mod", "(a: BigUint, b: BigUint) -> BigUint { if b == BigUint::from(0u32) { panic!(\"#Nat.mod does not reduce\") } else { a % b } }"),
+  ("#Nat.to_Text", "nat_to_text", "(a: BigUint) -> String { a.to_string() }"),
+  ("#Nat.pow", "nat_pow", "(a: BigUint, b: u32) -> BigUint { a.pow(b) }"),
+  ("#Int.new", "int_new", "(a: bool, b: BigUint) -> BigInt { if a { BigInt::from(b) } else { -BigInt::from(b) } }"),
+  ("#Int.eql", "int_eql", "(a: BigInt, b: BigInt) -> bool { a == b }"),
+  ("#Int.lte", "int_lte", "(a: BigInt, b: BigInt) -> bool { a <= b }"),
+  ("#Int.lth", "int_lth", "(a: BigInt, b: BigInt) -> bool { a < b }"),
+  ("#Int.gte", "int_gte", "(a: BigInt, b: BigInt) -> bool { a >= b }"),
+  ("#Int.gth", "int_gth", "(a: BigInt, b: BigInt) -> bool { a > b }"),
+  ("#Int.add", "int_add", "(a: BigInt, b: BigInt) -> BigInt { a + b }"),
+  ("#Int.sub", "int_sub", "(a: BigInt, b: BigInt) -> BigInt { a - b }"),
+  ("#Int.mul", "int_mul", "(a: BigInt, b: BigInt) -> BigInt { a * b }"),
+  ("#Int.to_Text", "int_to_text", "(a: BigInt) -> String { a.to_string() }"),
+  ("#Bool.eql", "bool_eql", "(a: bool, b: bool) -> bool { a == b }"),
+  ("#Bool.and", "bool_and", "(a: bool, b: bool) -> bool { a && b }"),
+  ("#Bool.or", "bool_or", "(a: bool, b: bool) -> bool { a || b }"),
+  ("#Bool.xor", "bool_xor", "(a: bool, b: bool) -> bool { a != b }"),
+  ("#Bool.not", "bool_not", "(a: bool) -> bool { !a }"),
+  ("#Text.append", "text_append", "(a: String, b: String) -> String { a + &b }"),
+  ("#Text.eql", "text_eql", "(a: String, b: String) -> bool { a == b }"),
+  ("#Text.len_chars", "text_len_chars", "(a: String) -> BigUint { BigUint::from(a.chars().count()) }"),
+  ("#Text.len_bytes", "text_len_bytes", "(a: String) -> BigUint { BigUint::from(a.len()) }"),
+  ("#Text.to_bytes", "text_to_bytes", "(a: String) -> Vec<u8> { a.into_bytes() }"),
+  ("#Text.abort", "text_abort", "<A>(a: String) -> A { panic!(\"{}\", a) }"),
+  ("#Bytes.len", "bytes_len", "(a: Vec<u8>) -> BigUint { BigUint::from(a.len()) }"),
+  ("#Bytes.append", "bytes_append", "(mut a: Vec<u8>, b: Vec<u8>) -> Vec<u8> { a.extend(b); a }"),
+  ("#Char.eql", "char_eql", "(a: char, b: char) -> bool { a == b }"),
+];
+
+/// The imports and closures of the extracted code. Closures are built and
+/// called through `lam` and `app`, which let Rust infer the types of their
+/// parameters.
+const PRELUDE: &str = "use num_bigint::{BigInt, BigUint};
+use std::rc::Rc;
+
+fn lam<A, B>(f: impl Fn(A) -> B + 'static) -> Rc<dyn Fn(A) -> B> {
+    Rc::new(f)
+}
+
+fn app<A, B>(f: &Rc<dyn Fn(A) -> B>, a: A) -> B {
+    f(a)
+}
+";
+
+#[rustfmt::skip]
+const KEYWORDS: &[&str] = &[
+  "abstract", "as", "async", "await", "become", "box", "break", "const",
+  "continue", "crate", "do", "dyn", "else", "enum", "extern", "false", "final",
+  "fn", "for", "if", "impl", "in", "let", "loop", "macro", "match", "mod",
+  "move", "mut", "override", "priv", "pub", "ref", "return", "self", "Self",
+  "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+  "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Extracts the definition `name` and the definitions it refers to as Rust
+/// source. A datatype or one of its constructors is extracted as its enum.
+pub fn rust(defs: &Defs, name: &Name) -> Result<String, ExtractError> {
+  let cid = defs
+    .names
+    .get(name)
+    .ok_or_else(|| ExtractError::Undefined(name.clone()))?;
+  let mut globals: BTreeSet<String> = defs.names.keys().map(ident).collect();
+  globals.extend(["lam", "app"].iter().map(|x| x.to_string()));
+  globals.extend(OPS.iter().map(|(_, op, _)| op.to_string()));
+  let mut extract = Extract {
+    defs,
+    globals,
+    datas: vec![],
+    fns: vec![],
+    ops: vec![],
+    this: (name.clone(), *cid),
+  };
+  if extract.data(name).is_none() && extract.constructor(name).is_none() {
+    extract.def(name, cid)?;
+  }
+  let mut fns = vec![];
+  while fns.len() < extract.fns.len() {
+    let (name, cid) = extract.fns[fns.len()].clone();
+    fns.push(extract.function(&name, &cid)?);
+  }
+  let mut datas = vec![];
+  while datas.len() < extract.datas.len() {
+    datas.push(extract.enumeration(datas.len())?);
+  }
+  let mut out = format!(
+    "// Extracted from `{}` by `yatima extract --lang \
+     rust`\n#![allow(non_camel_case_types, non_snake_case, unused)]\n\n",
+    name
+  );
+  out.push_str(PRELUDE);
+  for idx in &extract.ops {
+    let (_, op, code) = OPS[*idx];
+    out.push_str(&format!("\nfn {}{}\n", op, code));
+  }
+  for item in datas.iter().chain(fns.iter()) {
+    out.push('\n');
+    out.push_str(item);
+  }
+  Ok(out)
+}
+
+/// A datatype recovered from the self type a `type` declaration elaborates to
+#[derive(Clone)]
+struct Data {
+  name: Name,
+  /// The generic parameter of each type parameter, or `None` if the
+  /// parameter is not a `Type` and is erased
+  params: Vec<Option<String>>,
+  /// The number of type indices, which are erased
+  indices: usize,
+  /// The name of each variant and the binders of its fields, in the context
+  /// of the self type
+  variants: Vec<(Name, Vec<(Uses, Name, Term)>)>,
+}
+
+/// A variable in scope of the term being extracted
+#[derive(Clone)]
+struct Local {
+  /// The Rust variable, or `None` if the variable is erased
+  ident: Option<String>,
+  uses: Uses,
+  /// The depth of the closure the variable is bound in
+  level: usize,
+  /// The datatype of the variable, if it is known
+  data: Option<usize>,
+  /// Whether each argument of the variable is erased, if it is a function
+  /// of a known type
+  args: Vec<bool>,
+  /// Whether the variable occurs once in its scope and outside of lambdas,
+  /// so that its use may move it
+  moves: bool,
+}
+
+impl Local {
+  fn erased() -> Self {
+    Local {
+      ident: None,
+      uses: Uses::None,
+      level: 0,
+      data: None,
+      args: vec![],
+      moves: false,
+    }
+  }
+}
+
+/// A Rust function or constructor taking its arguments at once
+enum Callee {
+  /// A function with a parameter for each binder that is not erased
+  Fn(String),
+  /// An enum variant, with whether each of its fields is boxed
+  Con(String, Vec<bool>),
+}
+
+impl Callee {
+  fn call(&self, args: Vec<String>) -> String {
+    match self {
+      Self::Fn(name) => format!("{}({})", name, args.join(", ")),
+      Self::Con(name, _) if args.is_empty() => name.clone(),
+      Self::Con(name, boxed) => {
+        let args: Vec<String> = args
+          .into_iter()
+          .zip(boxed)
+          .map(
+            |(arg, boxed)| {
+              if *boxed { format!("Box::new({})", arg) } else { arg }
+            },
+          )
+          .collect();
+        format!("{}({})", name, args.join(", "))
+      }
+    }
+  }
+}
+
+/// The state of an extraction
+struct Extract<'a> {
+  defs: &'a Defs,
+  /// The Rust names of every definition and of the prelude, which local
+  /// variables avoid
+  globals: BTreeSet<String>,
+  /// The datatypes found so far
+  datas: Vec<Data>,
+  /// The definitions to extract as functions, in the order they were found
+  fns: Vec<(Name, Cid)>,
+  /// The primitive operations used, as indices into `OPS`
+  ops: Vec<usize>,
+  /// The definition being extracted
+  this: (Name, Cid),
+}
+
+impl<'a> Extract<'a> {
+  /// Registers a definition to extract as a function
+  fn def(&mut self, name: &Name, cid: &Cid) -> Result<(), ExtractError> {
+    if let Term::Opr(_, Op::Extern(op)) = &self.defs.defs[cid].term {
+      return Err(unsupported(format!("the extern {}", op.symbol)));
+    }
+    if !self.fns.iter().any(|(_, c)| c == cid) {
+      self.fns.push((name.clone(), *cid));
+    }
+    Ok(())
+  }
+
+  /// The datatype `name` is the definition of, if it is the self type of a
+  /// `type` declaration
+  fn data(&mut self, name: &Name) -> Option<usize> {
+    if let Some(idx) = self.datas.iter().position(|d| d.name == *name) {
+      return Some(idx);
+    }
+    let defs = self.defs;
+    let def = defs.get(name)?;
+    // The parameters and indices are bound by lambdas around the self type,
+    // and the motive by the first forall inside it
+    let mut term = &def.term;
+    let mut lams: usize = 0;
+    while let Term::Lam(_, _, bod) = term {
+      lams += 1;
+      term = &**bod;
+    }
+    let mut term = match term {
+      Term::Slf(_, _, bod) => &**bod,
+      _ => return None,
+    };
+    let indices = match term {
+      Term::All(_, Uses::None, _, dom_cod) => {
+        term = &dom_cod.1;
+        binders(&dom_cod.0).len().checked_sub(1)?
+      }
+      _ => return None,
+    };
+    let mut variants = vec![];
+    while let Term::All(_, _, variant, dom_cod) = term {
+      let fields = binders(&dom_cod.0)
+        .into_iter()
+        .map(|(uses, name, typ)| (uses, name.clone(), typ.clone()))
+        .collect();
+      variants.push((variant.clone(), fields));
+      term = &dom_cod.1;
+    }
+    let params: Vec<Option<String>> = binders(&def.typ_)
+      .into_iter()
+      .take(lams.checked_sub(indices)?)
+      .map(|(_, name, typ)| match typ {
+        Term::Typ(_) => Some(ident(name)),
+        _ => None,
+      })
+      .collect();
+    if params.len() + indices != lams {
+      return None;
+    }
+    self.datas.push(Data { name: name.clone(), params, indices, variants });
+    Some(self.datas.len() - 1)
+  }
+
+  /// The datatype and variant of a constructor definition
+  fn constructor(&mut self, name: &Name) -> Option<(usize, usize)> {
+    let defs = self.defs;
+    let mut term = &defs.get(name)?.term;
+    while let Term::Lam(_, _, bod) = term {
+      term = &**bod;
+    }
+    if !matches!(term, Term::Dat(..)) {
+      return None;
+    }
+    let full = name.to_string();
+    let (typ, variant) = full.rsplit_once('.')?;
+    let data = self.data(&Name::from(typ))?;
+    let variant = self.datas[data]
+      .variants
+      .iter()
+      .position(|(v, _)| v.to_string() == variant)?;
+    Some((data, variant))
+  }
+
+  /// The datatype at the head of a type
+  fn data_head(&mut self, typ: &Term, rec: Option<usize>) -> Option<usize> {
+    match spine(typ).0 {
+      Term::Ref(_, name, ..) => self.data(name),
+      Term::Rec(_) => rec,
+      _ => None,
+    }
+  }
+
+  /// The datatype of a term, if its type is known without type checking
+  fn data_of(&mut self, term: &Term, ctx: &[Local]) -> Option<usize> {
+    let defs = self.defs;
+    let (head, args) = spine(term);
+    let def = match head {
+      Term::Var(_, _, idx) if args.is_empty() => {
+        return ctx[ctx.len() - 1 - *idx as usize].data;
+      }
+      Term::Ann(_, typ_exp) => return self.data_head(&typ_exp.0, None),
+      Term::Ref(_, name, cid, _) => {
+        if let Some((data, _)) = self.constructor(name) {
+          return Some(data);
+        }
+        defs.defs.get(cid)?
+      }
+      Term::Rec(_) => defs.defs.get(&self.this.1)?,
+      _ => return None,
+    };
+    let mut typ = &def.typ_;
+    for _ in args {
+      match typ {
+        Term::All(_, _, _, dom_cod) => typ = &dom_cod.1,
+        _ => return None,
+      }
+    }
+    self.data_head(typ, None)
+  }
+
+  /// The Rust type of `typ`, whose variables are the generic parameters in
+  /// `ctx` or erased, and whose recursion marker is the datatype `rec`
+  fn rust_type(
+    &mut self,
+    typ: &Term,
+    ctx: &[Option<String>],
+    rec: Option<usize>,
+  ) -> Result<String, ExtractError> {
+    match typ {
+      Term::LTy(_, lty) => lit_type(*lty),
+      Term::Var(_, _, idx) => ctx[ctx.len() - 1 - *idx as usize]
+        .clone()
+        .ok_or_else(|| unsupported(format!("the dependent type {}", typ))),
+      Term::All(_, uses, _, dom_cod) => {
+        let (dom, cod) = &**dom_cod;
+        let mut cod_ctx = ctx.to_vec();
+        cod_ctx.push(None);
+        if matches!(dom, Term::Typ(_)) {
+          Err(unsupported(format!("the polymorphic type {}", typ)))
+        }
+        else if *uses == Uses::None {
+          self.rust_type(cod, &cod_ctx, rec)
+        }
+        else {
+          Ok(format!(
+            "Rc<dyn Fn({}) -> {}>",
+            self.rust_type(dom, ctx, rec)?,
+            self.rust_type(cod, &cod_ctx, rec)?
+          ))
+        }
+      }
+      Term::Ann(_, typ_exp) => self.rust_type(&typ_exp.1, ctx, rec),
+      _ => {
+        let (head, args) = spine(typ);
+        let data = match head {
+          Term::Ref(_, name, ..) => self.data(name),
+          Term::Rec(_) => rec,
+          _ => None,
+        };
+        match (data, head) {
+          (Some(data), _) => {
+            let params = self.datas[data].params.clone();
+            if args.len() < params.len() {
+              return Err(unsupported(format!("the type {}", typ)));
+            }
+            let mut generics = vec![];
+            for (param, arg) in params.iter().zip(args) {
+              if param.is_some() {
+                generics.push(self.rust_type(arg, ctx, rec)?);
+              }
+            }
+            let name = ident(&self.datas[data].name);
+            if generics.is_empty() {
+              Ok(name)
+            }
+            else {
+              Ok(format!("{}<{}>", name, generics.join(", ")))
+            }
+          }
+          // A definition of a closed type, such as `def Text: Type = #Text`
+          (None, Term::Ref(_, name, ..)) if args.is_empty() => {
+            let defs = self.defs;
+            match defs.get(name) {
+              Some(def) if matches!(def.typ_, Term::Typ(_)) => {
+                self.rust_type(&def.term, &[], None)
+              }
+              _ => Err(unsupported(format!("the type {}", typ))),
+            }
+          }
+          _ => Err(unsupported(format!("the type {}", typ))),
+        }
+      }
+    }
+  }
+
+  /// The enum of a datatype
+  fn enumeration(&mut self, idx: usize) -> Result<String, ExtractError> {
+    let data = self.datas[idx].clone();
+    let generics: Vec<String> = data.params.iter().flatten().cloned().collect();
+    // The fields of a variant are in the scope of the parameters, the
+    // indices, the self type, the motive and the previous variants
+    let mut ctx = data.params.clone();
+    ctx.extend(vec![None; data.indices + 2]);
+    let mut out = format!(
+      "#[derive(Clone)]\npub enum {}{} {{\n",
+      ident(&data.name),
+      generic_params(&generics, "")
+    );
+    for (variant, fields) in &data.variants {
+      let mut field_ctx = ctx.clone();
+      let mut types = vec![];
+      for (uses, _, typ) in fields {
+        if !erased(*uses, typ) {
+          let rust_type = self.rust_type(typ, &field_ctx, Some(idx))?;
+          types.push(if has_rec(typ) {
+            format!("Box<{}>", rust_type)
+          }
+          else {
+            rust_type
+          });
+        }
+        field_ctx.push(None);
+      }
+      if types.is_empty() {
+        out.push_str(&format!("    {},\n", ident(variant)));
+      }
+      else {
+        out.push_str(&format!(
+          "    {}({}),\n",
+          ident(variant),
+          types.join(", ")
+        ));
+      }
+      ctx.push(None);
+    }
+    out.push_str("}\n");
+    Ok(out)
+  }
+
+  /// The Rust function of a definition
+  fn function(
+    &mut self,
+    name: &Name,
+    cid: &Cid,
+  ) -> Result<String, ExtractError> {
+    let defs = self.defs;
+    let def = &defs.defs[cid];
+    self.this = (name.clone(), *cid);
+    let (binders, ret, body) = signature(def);
+    let mut types = vec![];
+    let mut ctx = vec![];
+    let mut generics = vec![];
+    let mut params = vec![];
+    for (uses, name, typ) in binders {
+      let generic = match typ {
+        Term::Typ(_) => Some(ident(name)),
+        _ => None,
+      };
+      if erased(uses, typ) {
+        generics.extend(generic.clone());
+        ctx.push(Local::erased());
+      }
+      else {
+        let var = self.fresh(name, &ctx, &[]);
+        params.push(format!("{}: {}", var, self.rust_type(typ, &types, None)?));
+        let data = self.data_head(typ, None);
+        ctx.push(Local {
+          ident: Some(var),
+          uses,
+          level: 0,
+          data,
+          args: erased_args(typ),
+          moves: false,
+        });
+      }
+      types.push(generic);
+    }
+    set_moves(&mut ctx, body);
+    let ret = self.rust_type(ret, &types, None)?;
+    let body = self.term(body, &mut ctx, 0, 4)?;
+    Ok(format!(
+      "pub fn {}{}({}) -> {} {{\n    {}\n}}\n",
+      ident(name),
+      generic_params(&generics, ": Clone + 'static"),
+      params.join(", "),
+      ret,
+      body
+    ))
+  }
+
+  /// A Rust variable for `name` that shadows neither a variable in `ctx` or
+  /// `taken` nor a definition
+  fn fresh(&self, name: &Name, ctx: &[Local], taken: &[String]) -> String {
+    let base = ident(name);
+    let free = |var: &String| {
+      !self.globals.contains(var)
+        && !taken.contains(var)
+        && !ctx.iter().any(|local| local.ident.as_ref() == Some(var))
+    };
+    if free(&base) {
+      return base;
+    }
+    (1..).map(|i| format!("{}_{}", base, i)).find(free).unwrap()
+  }
+
+  /// The Rust expression of `term`, in the closure `level`, whose lines after
+  /// the first are indented by `ind`
+  fn term(
+    &mut self,
+    term: &Term,
+    ctx: &mut Vec<Local>,
+    level: usize,
+    ind: usize,
+  ) -> Result<String, ExtractError> {
+    let defs = self.defs;
+    let (head, args) = spine(term);
+    match head {
+      Term::Var(_, name, idx) => {
+        let local = ctx[ctx.len() - 1 - *idx as usize].clone();
+        let var = local.ident.clone().ok_or_else(|| {
+          unsupported(format!("the erased variable {} at runtime", name))
+        })?;
+        let mut res = None;
+        for (i, arg) in args.iter().enumerate() {
+          if local.args.get(i) != Some(&true) {
+            let fun = res.unwrap_or_else(|| var.clone());
+            let arg = self.term(arg, ctx, level, ind)?;
+            res = Some(format!("app(&{}, {})", fun, arg));
+          }
+        }
+        Ok(res.unwrap_or_else(|| use_var(&local, &var, level)))
+      }
+      Term::Ref(_, name, cid, _) => {
+        if let Some((data, variant)) = self.constructor(name) {
+          let data = self.datas[data].clone();
+          let (variant, fields) = &data.variants[variant];
+          let boxed = fields
+            .iter()
+            .filter(|(uses, _, typ)| !erased(*uses, typ))
+            .map(|(_, _, typ)| has_rec(typ))
+            .collect();
+          let callee = Callee::Con(
+            format!("{}::{}", ident(&data.name), ident(variant)),
+            boxed,
+          );
+          let binders = telescope(&defs.defs[cid].typ_);
+          let arity = binders.len();
+          self.call(callee, &binders, arity, &args, ctx, level, ind)
+        }
+        else if self.data(name).is_some() {
+          Err(unsupported(format!("the type {} at runtime", name)))
+        }
+        else {
+          self.def(name, cid)?;
+          let def = &defs.defs[cid];
+          let arity = signature(def).0.len();
+          let callee = Callee::Fn(ident(name));
+          self.call(
+            callee,
+            &telescope(&def.typ_),
+            arity,
+            &args,
+            ctx,
+            level,
+            ind,
+          )
+        }
+      }
+      Term::Rec(_) => {
+        let def = &defs.defs[&self.this.1];
+        let arity = signature(def).0.len();
+        let callee = Callee::Fn(ident(&self.this.0));
+        self.call(callee, &telescope(&def.typ_), arity, &args, ctx, level, ind)
+      }
+      Term::Opr(_, Op::Extern(op)) => {
+        Err(unsupported(format!("the extern {}", op.symbol)))
+      }
+      Term::Opr(_, opr) => {
+        let symbol = opr.symbol();
+        let idx =
+          OPS.iter().position(|(op, ..)| *op == symbol).ok_or_else(|| {
+            unsupported(format!("the primitive operation {}", symbol))
+          })?;
+        if !self.ops.contains(&idx) {
+          self.ops.push(idx);
+        }
+        let binders = telescope(&opr.type_of());
+        let arity = binders.len();
+        let callee = Callee::Fn(OPS[idx].1.to_owned());
+        self.call(callee, &binders, arity, &args, ctx, level, ind)
+      }
+      Term::Cse(_, scrut) => self.case(head, scrut, &args, ctx, level, ind),
+      _ => {
+        let mut res = match head {
+          Term::Lam(..) => self.closure(head, &[], ctx, level, ind)?,
+          Term::Let(..) => self.block(head, ctx, level, ind)?,
+          Term::Lit(_, lit) => literal(lit)?,
+          Term::Ann(_, typ_exp) => self.term(&typ_exp.1, ctx, level, ind)?,
+          Term::Dat(..) => {
+            return Err(unsupported(format!(
+              "the data term {} outside of a constructor",
+              head
+            )));
+          }
+          _ => {
+            return Err(unsupported(format!("the type {} at runtime", head)));
+          }
+        };
+        for arg in args {
+          res = format!("app(&{}, {})", res, self.term(arg, ctx, level, ind)?);
+        }
+        Ok(res)
+      }
+    }
+  }
+
+  /// The Rust expression of an argument whose type erases the arguments
+  /// `erased` of a lambda
+  fn argument(
+    &mut self,
+    term: &Term,
+    erased: &[bool],
+    ctx: &mut Vec<Local>,
+    level: usize,
+    ind: usize,
+  ) -> Result<String, ExtractError> {
+    match term {
+      Term::Lam(..) => self.closure(term, erased, ctx, level, ind),
+      _ => self.term(term, ctx, level, ind),
+    }
+  }
+
+  /// A closure for each lambda parameter of `term` that is not erased
+  fn closure(
+    &mut self,
+    term: &Term,
+    erased: &[bool],
+    ctx: &mut Vec<Local>,
+    level: usize,
+    ind: usize,
+  ) -> Result<String, ExtractError> {
+    match term {
+      Term::Lam(_, name, bod) => {
+        let rest = erased.get(1..).unwrap_or(&[]);
+        if erased.first() == Some(&true) {
+          ctx.push(Local::erased());
+          let bod = self.closure(bod, rest, ctx, level, ind)?;
+          ctx.pop();
+          Ok(bod)
+        }
+        else {
+          let var = self.fresh(name, ctx, &[]);
+          let uses = Uses::Many;
+          let local = Local {
+            ident: Some(var.clone()),
+            uses,
+            level: level + 1,
+            data: None,
+            args: vec![],
+            moves: only_use(bod, 0),
+          };
+          ctx.push(local);
+          let bod = self.closure(bod, rest, ctx, level + 1, ind)?;
+          ctx.pop();
+          Ok(format!("lam(move |{}| {})", var, bod))
+        }
+      }
+      _ => self.term(term, ctx, level, ind),
+    }
+  }
+
+  /// Calls `callee` with `args`, given the telescope of its type. The first
+  /// `arity` binders are parameters of the callee, and the others are applied
+  /// to its result. A callee missing arguments becomes a closure.
+  #[allow(clippy::too_many_arguments)]
+  fn call(
+    &mut self,
+    callee: Callee,
+    binders: &[(bool, Vec<bool>)],
+    arity: usize,
+    args: &[&Term],
+    ctx: &mut Vec<Local>,
+    level: usize,
+    ind: usize,
+  ) -> Result<String, ExtractError> {
+    let params = &binders[..arity];
+    let missing = params.iter().skip(args.len()).filter(|(e, _)| !e).count();
+    let mut res = if missing == 0 {
+      let mut given = vec![];
+      for (arg, (erased, flags)) in args.iter().zip(params) {
+        if !erased {
+          given.push(self.argument(arg, flags, ctx, level, ind)?);
+        }
+      }
+      callee.call(given)
+    }
+    else {
+      // The arguments given are bound to variables the closures capture
+      let inner = ind + 4;
+      let mut lines = vec![];
+      let mut vars = vec![];
+      for (arg, (erased, flags)) in args.iter().zip(params) {
+        if !erased {
+          let var = self.fresh(&Name::from("x"), ctx, &vars);
+          let arg = self.argument(arg, flags, ctx, level, inner)?;
+          lines.push(format!("let {} = {};", var, arg));
+          vars.push(var);
+        }
+      }
+      let mut call: Vec<String> =
+        vars.iter().map(|var| format!("Clone::clone(&{})", var)).collect();
+      let mut lams = vec![];
+      for _ in 0..missing {
+        let var = self.fresh(&Name::from("x"), ctx, &vars);
+        call.push(format!("Clone::clone(&{})", var));
+        lams.push(var.clone());
+        vars.push(var);
+      }
+      // The innermost closure moves its own parameter
+      let last = call.len() - 1;
+      call[last] = lams[lams.len() - 1].clone();
+      let mut res = callee.call(call);
+      for var in lams.iter().rev() {
+        res = format!("lam(move |{}| {})", var, res);
+      }
+      if lines.is_empty() {
+        res
+      }
+      else {
+        lines.push(res);
+        block_lines(&lines, ind)
+      }
+    };
+    for (arg, (erased, flags)) in args.iter().zip(binders).skip(arity) {
+      if !erased {
+        let arg = self.argument(arg, flags, ctx, level, ind)?;
+        res = format!("app(&{}, {})", res, arg);
+      }
+    }
+    for arg in args.iter().skip(binders.len()) {
+      res = format!("app(&{}, {})", res, self.term(arg, ctx, level, ind)?);
+    }
+    Ok(res)
+  }
+
+  /// A `match` expression for `case`, whose first argument is the erased
+  /// motive and whose next arguments are the branches of each variant
+  fn case(
+    &mut self,
+    case: &Term,
+    scrut: &Term,
+    args: &[&Term],
+    ctx: &mut Vec<Local>,
+    level: usize,
+    ind: usize,
+  ) -> Result<String, ExtractError> {
+    let idx = self.data_of(scrut, ctx).ok_or_else(|| {
+      unsupported(format!("the case expression {} of an unknown type", case))
+    })?;
+    let data = self.datas[idx].clone();
+    if args.len() <= data.variants.len() {
+      return Err(unsupported(format!(
+        "the partially applied case expression {}",
+        case
+      )));
+    }
+    let arm_ind = ind + 4;
+    // A variable used again is borrowed rather than cloned as a whole
+    let borrowed = match scrut {
+      Term::Var(_, _, idx) => {
+        let local = &ctx[ctx.len() - 1 - *idx as usize];
+        local.ident.as_ref().filter(|_| !moves(local, level)).cloned()
+      }
+      _ => None,
+    };
+    let scrut = match &borrowed {
+      Some(var) => format!("&{}", var),
+      None => self.term(scrut, ctx, level, ind)?,
+    };
+    let mut out = format!("match {} {{\n", scrut);
+    for ((variant, fields), branch) in data.variants.iter().zip(&args[1..]) {
+      let mut branch = *branch;
+      let mut pattern = vec![];
+      let mut unboxed = vec![];
+      let mut applied = vec![];
+      let bound = ctx.len();
+      for (uses, name, typ) in fields {
+        let is_erased = erased(*uses, typ);
+        if let Term::Lam(_, name, bod) = branch {
+          branch = &**bod;
+          if is_erased {
+            ctx.push(Local::erased());
+            continue;
+          }
+          let var = self.fresh(name, ctx, &applied);
+          let data = self.data_head(typ, Some(idx));
+          ctx.push(Local {
+            ident: Some(var.clone()),
+            uses: *uses,
+            level,
+            data,
+            args: erased_args(typ),
+            moves: false,
+          });
+          pattern.push(var.clone());
+          if has_rec(typ) {
+            unboxed.push(var);
+          }
+        }
+        else if !is_erased {
+          // Fields the branch does not bind are applied to it
+          let var = self.fresh(name, ctx, &applied);
+          pattern.push(var.clone());
+          if has_rec(typ) {
+            unboxed.push(var.clone());
+          }
+          applied.push(var);
+        }
+      }
+      let pattern = if pattern.is_empty() {
+        format!("{}::{}", ident(&data.name), ident(variant))
+      }
+      else {
+        format!(
+          "{}::{}({})",
+          ident(&data.name),
+          ident(variant),
+          pattern.join(", ")
+        )
+      };
+      set_moves(&mut ctx[bound..], branch);
+      let mut lines: Vec<String> = vec![];
+      if borrowed.is_some() {
+        // The fields are references, so the ones used are cloned
+        let fields = ctx.len() - bound;
+        let used: Vec<&String> = ctx[bound..]
+          .iter()
+          .enumerate()
+          .filter(|(i, _)| occurrences(branch, (fields - 1 - i) as u64).0 > 0)
+          .filter_map(|(_, local)| local.ident.as_ref())
+          .chain(&applied)
+          .collect();
+        for var in used {
+          lines.push(if unboxed.contains(var) {
+            format!("let {} = Clone::clone(&**{});", var, var)
+          }
+          else {
+            format!("let {} = Clone::clone({});", var, var)
+          });
+        }
+      }
+      else {
+        for var in &unboxed {
+          lines.push(format!("let {} = *{};", var, var));
+        }
+      }
+      let body_ind = if lines.is_empty() { arm_ind } else { arm_ind + 4 };
+      let mut body = self.term(branch, ctx, level, body_ind)?;
+      ctx.truncate(bound);
+      for var in &applied {
+        body = format!("app(&{}, {})", body, var);
+      }
+      if lines.is_empty() {
+        out.push_str(&format!("{}{} => {},\n", spaces(arm_ind), pattern, body));
+      }
+      else {
+        lines.push(body);
+        out.push_str(&format!(
+          "{}{} => {}\n",
+          spaces(arm_ind),
+          pattern,
+          block_lines(&lines, arm_ind)
+        ));
+      }
+    }
+    out.push_str(&format!("{}}}", spaces(ind)));
+    for arg in &args[data.variants.len() + 1..] {
+      out = format!("app(&{}, {})", out, self.term(arg, ctx, level, ind)?);
+    }
+    Ok(out)
+  }
+
+  /// A block binding the lets around a term
+  fn block(
+    &mut self,
+    term: &Term,
+    ctx: &mut Vec<Local>,
+    level: usize,
+    ind: usize,
+  ) -> Result<String, ExtractError> {
+    let inner = ind + 4;
+    let bound = ctx.len();
+    let mut lines = vec![];
+    let mut term = term;
+    while let Term::Let(_, rec, uses, name, typ_exp_bod) = term {
+      let (typ, exp, bod) = &**typ_exp_bod;
+      if *rec {
+        return Err(unsupported(format!("the recursive let {}", name)));
+      }
+      if erased(*uses, typ) {
+        ctx.push(Local::erased());
+      }
+      else {
+        let flags = erased_args(typ);
+        let exp = self.argument(exp, &flags, ctx, level, inner)?;
+        let var = self.fresh(name, ctx, &[]);
+        lines.push(format!("let {} = {};", var, exp));
+        let data = self.data_head(typ, None);
+        ctx.push(Local {
+          ident: Some(var),
+          uses: *uses,
+          level,
+          data,
+          args: flags,
+          moves: only_use(bod, 0),
+        });
+      }
+      term = bod;
+    }
+    let res = if lines.is_empty() {
+      self.term(term, ctx, level, ind)?
+    }
+    else {
+      lines.push(self.term(term, ctx, level, inner)?);
+      block_lines(&lines, ind)
+    };
+    ctx.truncate(bound);
+    Ok(res)
+  }
+}
+
+/// The binders of a telescope of foralls
+fn binders(typ: &Term) -> Vec<(Uses, &Name, &Term)> {
+  let mut res = vec![];
+  let mut typ = typ;
+  while let Term::All(_, uses, name, dom_cod) = typ {
+    res.push((*uses, name, &dom_cod.0));
+    typ = &dom_cod.1;
+  }
+  res
+}
+
+/// Whether each binder of a telescope of foralls is erased
+fn erased_args(typ: &Term) -> Vec<bool> {
+  binders(typ).into_iter().map(|(uses, _, typ)| erased(uses, typ)).collect()
+}
+
+/// Whether each binder of a telescope of foralls is erased, and which
+/// arguments of its own type are
+fn telescope(typ: &Term) -> Vec<(bool, Vec<bool>)> {
+  binders(typ)
+    .into_iter()
+    .map(|(uses, _, typ)| (erased(uses, typ), erased_args(typ)))
+    .collect()
+}
+
+/// Whether a binder is erased: of quantity `0`, or of a type
+fn erased(uses: Uses, typ: &Term) -> bool {
+  uses == Uses::None || matches!(typ, Term::Typ(_))
+}
+
+/// The binders a definition's lambdas take as the parameters of its Rust
+/// function, its result type and its body
+fn signature(def: &Def) -> (Vec<(Uses, &Name, &Term)>, &Term, &Term) {
+  let mut binders = vec![];
+  let (mut typ, mut term) = (&def.typ_, &def.term);
+  while let (Term::All(_, uses, name, dom_cod), Term::Lam(_, _, bod)) =
+    (typ, term)
+  {
+    binders.push((*uses, name, &dom_cod.0));
+    typ = &dom_cod.1;
+    term = &**bod;
+  }
+  (binders, typ, term)
+}
+
+/// The head of a chain of applications and its arguments, in order
+fn spine(term: &Term) -> (&Term, Vec<&Term>) {
+  let mut args = vec![];
+  let mut term = term;
+  while let Term::App(_, fun_arg) = term {
+    args.push(&fun_arg.1);
+    term = &fun_arg.0;
+  }
+  args.reverse();
+  (term, args)
+}
+
+/// Whether a term contains a recursion marker, so that a field of its type
+/// must be boxed
+fn has_rec(term: &Term) -> bool {
+  match term {
+    Term::Rec(_) => true,
+    Term::Lam(_, _, bod)
+    | Term::Slf(_, _, bod)
+    | Term::Dat(_, bod)
+    | Term::Cse(_, bod) => has_rec(bod),
+    Term::App(_, xy) | Term::All(_, _, _, xy) | Term::Ann(_, xy) => {
+      has_rec(&xy.0) || has_rec(&xy.1)
+    }
+    Term::Let(_, _, _, _, xyz) => {
+      has_rec(&xyz.0) || has_rec(&xyz.1) || has_rec(&xyz.2)
+    }
+    _ => false,
+  }
+}
+
+/// A use of a variable, which moves it if it may and clones it otherwise
+fn use_var(local: &Local, var: &str, level: usize) -> String {
+  if moves(local, level) {
+    var.to_owned()
+  }
+  else {
+    format!("Clone::clone(&{})", var)
+  }
+}
+
+/// Whether a use of a variable may move it: the variable is bound in the
+/// current closure and is used at most once, either by its quantity or
+/// because it occurs once
+fn moves(local: &Local, level: usize) -> bool {
+  local.level == level
+    && (local.moves || matches!(local.uses, Uses::Once | Uses::Affi))
+}
+
+/// Marks the variables of `ctx`, innermost last, that occur once in `term`
+/// and outside of lambdas
+fn set_moves(ctx: &mut [Local], term: &Term) {
+  for (idx, local) in ctx.iter_mut().rev().enumerate() {
+    local.moves = only_use(term, idx as u64);
+  }
+}
+
+/// Whether the variable `idx` occurs once in `term` and outside of lambdas,
+/// which may become closures that run more than once
+fn only_use(term: &Term, idx: u64) -> bool {
+  occurrences(term, idx) == (1, false)
+}
+
+/// How many times the variable `idx` occurs in `term`, and whether it occurs
+/// under a lambda
+fn occurrences(term: &Term, idx: u64) -> (usize, bool) {
+  let add = |(n, x): (usize, bool), (m, y): (usize, bool)| (n + m, x || y);
+  match term {
+    Term::Var(_, _, var) => ((*var == idx) as usize, false),
+    Term::Lam(_, _, bod) => {
+      let (n, _) = occurrences(bod, idx + 1);
+      (n, n > 0)
+    }
+    Term::Slf(_, _, bod) => occurrences(bod, idx + 1),
+    Term::Dat(_, bod) | Term::Cse(_, bod) => occurrences(bod, idx),
+    Term::App(_, xy) | Term::Ann(_, xy) => {
+      add(occurrences(&xy.0, idx), occurrences(&xy.1, idx))
+    }
+    Term::All(_, _, _, xy) => {
+      add(occurrences(&xy.0, idx), occurrences(&xy.1, idx + 1))
+    }
+    Term::Let(_, rec, _, _, xyz) => {
+      let exp = occurrences(&xyz.1, if *rec { idx + 1 } else { idx });
+      add(add(occurrences(&xyz.0, idx), exp), occurrences(&xyz.2, idx + 1))
+    }
+    _ => (0, false),
+  }
+}
+
+/// A Rust identifier for a name
+fn ident(name: &Name) -> String {
+  let mut res: String = name
+    .to_string()
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+    .collect();
+  if res == "_" || res.starts_with(|c: char| c.is_ascii_digit()) {
+    res.insert(0, '_');
+  }
+  if KEYWORDS.contains(&res.as_str()) {
+    res.push('_');
+  }
+  res
+}
+
+fn spaces(ind: usize) -> String { " ".repeat(ind) }
+
+/// A block of statements and a final expression, whose lines are indented
+/// by `ind` and four more spaces
+fn block_lines(lines: &[String], ind: usize) -> String {
+  let mut out = String::from("{\n");
+  for line in lines {
+    out.push_str(&format!("{}{}\n", spaces(ind + 4), line));
+  }
+  out.push_str(&format!("{}}}", spaces(ind)));
+  out
+}
+
+/// The generic parameters of an item, each followed by `bound`
+fn generic_params(generics: &[String], bound: &str) -> String {
+  if generics.is_empty() {
+    String::new()
+  }
+  else {
+    let params: Vec<String> =
+      generics.iter().map(|g| format!("{}{}", g, bound)).collect();
+    format!("<{}>", params.join(", "))
+  }
+}
+
+/// The Rust type of a primitive type
+fn lit_type(lty: LitType) -> Result<String, ExtractError> {
+  let res = match lty {
+    LitType::Nat => "BigUint",
+    LitType::Int => "BigInt",
+    LitType::Bytes => "Vec<u8>",
+    LitType::Text => "String",
+    LitType::Char => "char",
+    LitType::Bool => "bool",
+    LitType::U8 => "u8",
+    LitType::U16 => "u16",
+    LitType::U32 => "u32",
+    LitType::U64 => "u64",
+    LitType::U128 => "u128",
+    LitType::I8 => "i8",
+    LitType::I16 => "i16",
+    LitType::I32 => "i32",
+    LitType::I64 => "i64",
+    LitType::I128 => "i128",
+    LitType::Bits => return Err(unsupported("the type #Bits".to_owned())),
+  };
+  Ok(res.to_owned())
+}
+
+/// The Rust value of a literal
+fn literal(lit: &Literal) -> Result<String, ExtractError> {
+  match lit {
+    Literal::Nat(x) => {
+      let x = x.to_string();
+      if x.parse::<u64>().is_ok() {
+        Ok(format!("BigUint::from({}u64)", x))
+      }
+      else {
+        Ok(format!("\"{}\".parse::<BigUint>().unwrap()", x))
+      }
+    }
+    Literal::Int(x) => {
+      let x = x.to_string();
+      if x.parse::<i64>().is_ok() {
+        Ok(format!("BigInt::from({}i64)", x))
+      }
+      else {
+        Ok(format!("\"{}\".parse::<BigInt>().unwrap()", x))
+      }
+    }
+    Literal::Bool(b) => Ok(b.to_string()),
+    Literal::Text(xs) => Ok(format!("String::from({:?})", xs.to_string())),
+    Literal::Char(c) => Ok(format!("{:?}", c)),
+    Literal::Bytes(xs) if xs.is_empty() => Ok("Vec::<u8>::new()".to_owned()),
+    Literal::Bytes(xs) => Ok(format!(
+      "vec![{}]",
+      xs.iter().map(|x| format!("{}u8", x)).collect::<Vec<_>>().join(", ")
+    )),
+    Literal::U8(x) => Ok(format!("{}u8", x)),
+    Literal::U16(x) => Ok(format!("{}u16", x)),
+    Literal::U32(x) => Ok(format!("{}u32", x)),
+    Literal::U64(x) => Ok(format!("{}u64", x)),
+    Literal::U128(x) => Ok(format!("{}u128", x)),
+    Literal::I8(x) => Ok(format!("{}i8", x)),
+    Literal::I16(x) => Ok(format!("{}i16", x)),
+    Literal::I32(x) => Ok(format!("{}i32", x)),
+    Literal::I64(x) => Ok(format!("{}i64", x)),
+    Literal::I128(x) => Ok(format!("{}i128", x)),
+    _ => Err(unsupported(format!("the literal {}", lit))),
+  }
+}
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+  use crate::snapshot;
+  use std::{
+    fs,
+    process::Command,
+  };
+  use yatima_core::parse::{
+    package::parse_defs,
+    span::Span,
+    term::input_cid,
+  };
+
+  /// Extracts the definition `name` of `src`
+  fn extract_src(src: &str, name: &str) -> Result<String, ExtractError> {
+    let (_, (defs, _)) =
+      parse_defs(input_cid(src), Defs::new())(Span::new(src)).unwrap();
+    rust(&defs, &Name::from(name))
+  }
+
+  /// Compares the source extracted from `src` with
+  /// `snapshots/rust/<name>.rs`
+  fn assert_snapshot(name: &str, src: &str, def: &str) {
+    let source = extract_src(src, def).unwrap();
+    snapshot::assert_snapshot("rust", &format!("{}.rs", name), &source);
+  }
+
+  #[test]
+  fn test_datatypes() {
+    assert_snapshot(
+      "list",
+      "type List (A: Type) {
+         Nil,
+         Cons A (List A),
+       }
+       def List.map (0 A: Type) (0 B: Type) (f: ∀ A -> B) (xs: List A): List B
+         = (case xs) (λ _ => List B) (List.Nil B)
+           (λ x xs => List.Cons B (f x) (List.map A B f xs))
+       def List.sum (xs: List #Nat): #Nat
+         = (case xs) (λ _ => #Nat) 0 (λ x xs => #Nat.add x (List.sum xs))
+       def List.tail (0 A: Type) (xs: List A): List A
+         = (case xs) (λ _ => List A) xs (λ y ys => ys)
+       def double (x: #Nat): #Nat = #Nat.mul 2 x
+       def total (xs: List #Nat): #Nat
+         = List.sum (List.map #Nat #Nat double (List.tail #Nat xs))",
+      "total",
+    );
+  }
+
+  #[test]
+  fn test_quantities() {
+    assert_snapshot(
+      "pair",
+      "type Pair (A: Type) (B: Type) {
+         New A B,
+       }
+       def Pair.swap (0 A: Type) (0 B: Type) (1 p: Pair A B): Pair B A
+         = (case p) (λ _ => Pair B A) (λ a b => Pair.New B A b a)
+       def greet (name: #Text): #Text
+         = let hello: #Text = \"hello, \"; #Text.append hello name
+       def add (x: #Nat) (y: #Nat): #Nat = #Nat.add x y
+       def adder: ∀ #Nat -> #Nat = add 1
+       def demo (1 p: Pair #Nat #Text): Pair #Text #Nat
+         = Pair.swap #Nat #Text ((case p) (λ _ => Pair #Nat #Text)
+           (λ n t => Pair.New #Nat #Text (adder n) (greet t)))",
+      "demo",
+    );
+  }
+
+  #[test]
+  fn test_snapshots_compile() {
+    // The tests run from the directory of the rlibs they are built with,
+    // which has the `num-bigint` the extracted code depends on
+    let deps = std::env::current_exe().unwrap().parent().unwrap().to_owned();
+    let rlibs: Vec<_> = fs::read_dir(&deps)
+      .unwrap()
+      .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+      .filter(|path| {
+        let file = path.file_name().unwrap().to_string_lossy();
+        file.starts_with("libnum_bigint-") && file.ends_with(".rlib")
+      })
+      .collect();
+    assert!(!rlibs.is_empty(), "num-bigint not found in {}", deps.display());
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+    let out = std::env::temp_dir().join("yatima-extract-snapshots");
+    for name in &["list", "pair"] {
+      let source = snapshot::path("rust", &format!("{}.rs", name));
+      // Any version of `num-bigint` in the tree will do
+      let compiles = rlibs.iter().any(|rlib| {
+        Command::new(&rustc)
+          .args(&["--edition", "2018", "--crate-type", "lib"])
+          .args(&["--emit", "metadata", "--crate-name", *name])
+          .arg("-L")
+          .arg(format!("dependency={}", deps.display()))
+          .arg("--extern")
+          .arg(format!("num_bigint={}", rlib.display()))
+          .arg("--out-dir")
+          .arg(&out)
+          .arg(&source)
+          .status()
+          .map_or(false, |status| status.success())
+      });
+      assert!(compiles, "{} does not compile", source.display());
+    }
+  }
+
+  #[test]
+  fn test_literals() {
+    let src = "def big: #Nat = 123456789012345678901234567890
+       def neg: #Int = -42
+       def small: #I8 = -5i8
+       def letter: #Char = 'λ'
+       def message: #Text = \"say \\\"hi\\\"\\n\"
+       def blob: #Bytes = x'ff'";
+    let body = |name: &str| {
+      let source = extract_src(src, name).unwrap();
+      let start = source.find("pub fn").unwrap();
+      source[start..].to_owned()
+    };
+    assert_eq!(
+      body("big"),
+      "pub fn big() -> BigUint {\n    \
+       \"123456789012345678901234567890\".parse::<BigUint>().unwrap()\n}\n"
+    );
+    assert_eq!(
+      body("neg"),
+      "pub fn neg() -> BigInt {\n    BigInt::from(-42i64)\n}\n"
+    );
+    assert_eq!(body("small"), "pub fn small() -> i8 {\n    -5i8\n}\n");
+    assert_eq!(body("letter"), "pub fn letter() -> char {\n    'λ'\n}\n");
+    assert_eq!(
+      body("message"),
+      "pub fn message() -> String {\n    String::from(\"say \
+       \\\"hi\\\"\\n\")\n}\n"
+    );
+    assert_eq!(
+      body("blob"),
+      "pub fn blob() -> Vec<u8> {\n    vec![255u8]\n}\n"
+    );
+  }
+
+  #[test]
+  fn test_unsupported() {
+    assert_eq!(
+      extract_src("def one: #Nat = 1", "two"),
+      Err(ExtractError::Undefined(Name::from("two")))
+    );
+    assert_eq!(
+      extract_src("def len: #Nat = #Bytes.head x'ff'", "len"),
+      Err(ExtractError::Unsupported(
+        "the primitive operation #Bytes.head".to_owned()
+      ))
+    );
+    assert_eq!(
+      extract_src(
+        "extern print : ∀ #Bytes -> #Bool = \"io.print\"
+         def main: #Bool = print x'ff'",
+        "main"
+      ),
+      Err(ExtractError::Unsupported("the extern io.print".to_owned()))
+    );
+    assert_eq!(
+      extract_src("def loop: #Nat = letrec x: #Nat = x; x", "loop"),
+      Err(ExtractError::Unsupported("the recursive let x".to_owned()))
+    );
+  }
+}
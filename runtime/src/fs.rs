@@ -0,0 +1,398 @@
+use std::{
+  fs,
+  io::Write,
+  path::{
+    Path,
+    PathBuf,
+  },
+  rc::Rc,
+};
+
+use yatima_core::{
+  defs,
+  literal::Literal,
+  parse,
+  prim::{
+    io::IoOp,
+    list_term,
+    result_term,
+  },
+  term::{
+    Pos,
+    Term,
+  },
+  yatima,
+};
+
+/// The directories a program is allowed to read from and write to. Every path
+/// given to a file primitive is resolved, following symlinks, and must land
+/// inside one of the granted roots.
+#[derive(Clone, Debug, Default)]
+pub struct Sandbox {
+  pub read: Vec<PathBuf>,
+  pub write: Vec<PathBuf>,
+}
+
+impl Sandbox {
+  pub fn new(read: Vec<PathBuf>, write: Vec<PathBuf>) -> Self {
+    Sandbox { read, write }
+  }
+
+  /// Resolves a path for reading or writing, failing if it escapes the
+  /// granted roots. The file itself need not exist, but its parent must. A
+  /// dangling symlink is refused, since creating its target could write
+  /// outside the roots.
+  pub fn resolve(&self, path: &str, write: bool) -> Result<PathBuf, String> {
+    let (roots, access) =
+      if write { (&self.write, "writing") } else { (&self.read, "reading") };
+    let path = Path::new(path);
+    let full = match path.canonicalize() {
+      Ok(full) => full,
+      Err(_) => {
+        if fs::symlink_metadata(path).is_ok() {
+          return Err(format!(
+            "{} is a symlink to a missing file",
+            path.display()
+          ));
+        }
+        let name = path
+          .file_name()
+          .ok_or_else(|| format!("Invalid path {}", path.display()))?;
+        let parent = match path.parent() {
+          Some(parent) if !parent.as_os_str().is_empty() => parent,
+          _ => Path::new("."),
+        };
+        let parent = parent
+          .canonicalize()
+          .map_err(|e| format!("{}: {}", path.display(), e))?;
+        parent.join(name)
+      }
+    };
+    let granted = roots
+      .iter()
+      .filter_map(|root| root.canonicalize().ok())
+      .any(|root| full.starts_with(root));
+    if granted {
+      Ok(full)
+    }
+    else {
+      Err(format!(
+        "{} is outside the directories allowed for {}",
+        path.display(),
+        access
+      ))
+    }
+  }
+
+  /// `fs.open`: creates the file if it is missing, returning whether it was
+  /// created
+  pub fn open_file_op(&self) -> IoOp {
+    let sandbox = self.clone();
+    file_op1(
+      || "open_file".to_string(),
+      || {
+        yatima!(
+          "∀ #Text
+             -> ∀ (0 P: Type) (& err: ∀ #Text -> P) (& ok: ∀ #Bool -> P) -> P"
+        )
+      },
+      move |path| {
+        let path = sandbox.resolve(&path, true)?;
+        let created = !path.exists();
+        fs::OpenOptions::new()
+          .create(true)
+          .append(true)
+          .open(&path)
+          .map_err(|e| e.to_string())?;
+        Ok(Term::Lit(Pos::None, Literal::Bool(created)))
+      },
+    )
+  }
+
+  /// `fs.read`: reads the whole contents of a file
+  pub fn read_file_op(&self) -> IoOp {
+    let sandbox = self.clone();
+    file_op1(
+      || "read_file".to_string(),
+      || {
+        yatima!(
+          "∀ #Text
+             -> ∀ (0 P: Type) (& err: ∀ #Text -> P) (& ok: ∀ #Bytes -> P) -> P"
+        )
+      },
+      move |path| {
+        let path = sandbox.resolve(&path, false)?;
+        let bytes = fs::read(&path).map_err(|e| e.to_string())?;
+        Ok(Term::Lit(Pos::None, Literal::Bytes(bytes)))
+      },
+    )
+  }
+
+  /// `fs.write`: replaces the contents of a file, returning the number of
+  /// bytes written
+  pub fn write_file_op(&self) -> IoOp {
+    let sandbox = self.clone();
+    file_op2(
+      || "write_file".to_string(),
+      || {
+        yatima!(
+          "∀ #Text #Bytes
+             -> ∀ (0 P: Type) (& err: ∀ #Text -> P) (& ok: ∀ #Nat -> P) -> P"
+        )
+      },
+      move |path, bytes| {
+        let path = sandbox.resolve(&path, true)?;
+        fs::write(&path, &bytes).map_err(|e| e.to_string())?;
+        Ok(Term::Lit(Pos::None, Literal::Nat(bytes.len().into())))
+      },
+    )
+  }
+
+  /// `fs.append`: appends to a file, creating it if missing, and returns the
+  /// number of bytes written
+  pub fn append_file_op(&self) -> IoOp {
+    let sandbox = self.clone();
+    file_op2(
+      || "append_file".to_string(),
+      || {
+        yatima!(
+          "∀ #Text #Bytes
+             -> ∀ (0 P: Type) (& err: ∀ #Text -> P) (& ok: ∀ #Nat -> P) -> P"
+        )
+      },
+      move |path, bytes| {
+        let path = sandbox.resolve(&path, true)?;
+        let mut file = fs::OpenOptions::new()
+          .create(true)
+          .append(true)
+          .open(&path)
+          .map_err(|e| e.to_string())?;
+        file.write_all(&bytes).map_err(|e| e.to_string())?;
+        Ok(Term::Lit(Pos::None, Literal::Nat(bytes.len().into())))
+      },
+    )
+  }
+
+  /// `fs.list_dir`: lists the entry names of a directory in sorted order
+  pub fn list_dir_op(&self) -> IoOp {
+    let sandbox = self.clone();
+    file_op1(
+      || "list_dir".to_string(),
+      || {
+        yatima!(
+          "∀ #Text
+             -> ∀ (0 P: Type)
+                  (& err: ∀ #Text -> P)
+                  (& ok: ∀ (∀ (0 Q: Type)
+                              (& nil: Q)
+                              (cons: ∀ #Text Q -> Q)
+                            -> Q)
+                    -> P)
+                -> P"
+        )
+      },
+      move |path| {
+        let path = sandbox.resolve(&path, false)?;
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&path).map_err(|e| e.to_string())? {
+          let entry = entry.map_err(|e| e.to_string())?;
+          names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+        names.sort();
+        Ok(list_term(
+          names.into_iter().map(|n| Literal::Text(n.as_str().into())).collect(),
+        ))
+      },
+    )
+  }
+
+  /// `fs.exists`: checks whether a path exists
+  pub fn file_exists_op(&self) -> IoOp {
+    let sandbox = self.clone();
+    file_op1(
+      || "file_exists".to_string(),
+      || {
+        yatima!(
+          "∀ #Text
+             -> ∀ (0 P: Type) (& err: ∀ #Text -> P) (& ok: ∀ #Bool -> P) -> P"
+        )
+      },
+      move |path| {
+        let path = sandbox.resolve(&path, false)?;
+        Ok(Term::Lit(Pos::None, Literal::Bool(path.exists())))
+      },
+    )
+  }
+}
+
+/// Builds a unary file primitive taking a path, whose result is encoded with
+/// `result_term`
+fn file_op1(
+  fun_symbol: fn() -> String,
+  fun_type_of: fn() -> Term,
+  f: impl Fn(String) -> Result<Term, String> + 'static,
+) -> IoOp {
+  IoOp {
+    fun_symbol: Rc::new(fun_symbol),
+    fun_type_of: Rc::new(fun_type_of),
+    fun_arity: Rc::new(|| 1),
+    fun_apply: Rc::new(|_: &[Literal]| -> Option<Literal> { None }),
+    fun_apply_term: Rc::new(move |args: &[Literal]| match args {
+      [Literal::Text(path)] => Some(result_term(f(path.to_string()))),
+      _ => None,
+    }),
+  }
+}
+
+/// Builds a binary file primitive taking a path and bytes, whose result is
+/// encoded with `result_term`
+fn file_op2(
+  fun_symbol: fn() -> String,
+  fun_type_of: fn() -> Term,
+  f: impl Fn(String, Vec<u8>) -> Result<Term, String> + 'static,
+) -> IoOp {
+  IoOp {
+    fun_symbol: Rc::new(fun_symbol),
+    fun_type_of: Rc::new(fun_type_of),
+    fun_arity: Rc::new(|| 2),
+    fun_apply: Rc::new(|_: &[Literal]| -> Option<Literal> { None }),
+    fun_apply_term: Rc::new(move |args: &[Literal]| match args {
+      [Literal::Text(path), Literal::Bytes(bytes)] => {
+        Some(result_term(f(path.to_string(), bytes.clone())))
+      }
+      _ => None,
+    }),
+  }
+}
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+  use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+  };
+
+  static DIRS: AtomicUsize = AtomicUsize::new(0);
+
+  /// Creates a fresh directory under the system's temporary directory
+  fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+      "yatima-fs-{}-{}",
+      std::process::id(),
+      DIRS.fetch_add(1, Ordering::SeqCst)
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn text(path: &Path) -> Literal {
+    Literal::Text(path.to_str().unwrap().into())
+  }
+
+  fn ok(x: Literal) -> Option<Term> {
+    Some(result_term(Ok(Term::Lit(Pos::None, x))))
+  }
+
+  #[test]
+  fn test_write_read_append() {
+    let dir = temp_dir();
+    let sandbox = Sandbox::new(vec![dir.clone()], vec![dir.clone()]);
+    let file = dir.join("out.txt");
+    let hello = Literal::Bytes(b"hello".to_vec());
+    let world = Literal::Bytes(b" world".to_vec());
+    assert_eq!(
+      sandbox.file_exists_op().apply_term(&[text(&file)]),
+      ok(Literal::Bool(false))
+    );
+    assert_eq!(
+      sandbox.write_file_op().apply_term(&[text(&file), hello]),
+      ok(Literal::Nat(5u64.into()))
+    );
+    assert_eq!(
+      sandbox.append_file_op().apply_term(&[text(&file), world]),
+      ok(Literal::Nat(6u64.into()))
+    );
+    assert_eq!(
+      sandbox.read_file_op().apply_term(&[text(&file)]),
+      ok(Literal::Bytes(b"hello world".to_vec()))
+    );
+    assert_eq!(
+      sandbox.file_exists_op().apply_term(&[text(&file)]),
+      ok(Literal::Bool(true))
+    );
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_open_list_dir() {
+    let dir = temp_dir();
+    let sandbox = Sandbox::new(vec![dir.clone()], vec![dir.clone()]);
+    assert_eq!(
+      sandbox.open_file_op().apply_term(&[text(&dir.join("b"))]),
+      ok(Literal::Bool(true))
+    );
+    assert_eq!(
+      sandbox.open_file_op().apply_term(&[text(&dir.join("b"))]),
+      ok(Literal::Bool(false))
+    );
+    sandbox.open_file_op().apply_term(&[text(&dir.join("a"))]);
+    assert_eq!(
+      sandbox.list_dir_op().apply_term(&[text(&dir)]),
+      Some(result_term(Ok(list_term(vec![
+        Literal::Text("a".into()),
+        Literal::Text("b".into()),
+      ]))))
+    );
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_confinement() {
+    let dir = temp_dir();
+    let inner = dir.join("inner");
+    fs::create_dir_all(&inner).unwrap();
+    fs::write(dir.join("secret"), b"secret").unwrap();
+    let path = |p: &Path| p.to_str().unwrap().to_string();
+    let read_only = Sandbox::new(vec![inner.clone()], vec![]);
+    assert!(read_only.resolve(&path(&inner.join("f")), false).is_ok());
+    // Writing needs a write grant even inside a readable root
+    assert!(read_only.resolve(&path(&inner.join("f")), true).is_err());
+    // Paths may not escape the root, directly or through `..`
+    assert!(read_only.resolve(&path(&dir.join("secret")), false).is_err());
+    assert!(read_only.resolve(&path(&inner.join("../secret")), false).is_err());
+    assert!(read_only.resolve(&path(&inner.join("..")), false).is_err());
+    assert!(read_only.resolve(&path(&dir), false).is_err());
+    // A dangling symlink may not point outside the root either
+    #[cfg(unix)]
+    {
+      let link = dir.join("inner/link");
+      std::os::unix::fs::symlink(dir.join("missing"), &link).unwrap();
+      let write = Sandbox::new(vec![], vec![inner.clone()]);
+      assert_eq!(
+        write.resolve(&path(&link), true),
+        Err(format!("{} is a symlink to a missing file", link.display()))
+      );
+      let empty = Literal::Bytes(vec![]);
+      assert_eq!(
+        write.write_file_op().apply_term(&[text(&link), empty]),
+        Some(result_term(Err(format!(
+          "{} is a symlink to a missing file",
+          link.display()
+        ))))
+      );
+      assert!(!dir.join("missing").exists());
+    }
+    // Nothing is granted by default
+    let none = Sandbox::default();
+    assert!(none.resolve(&path(&inner), false).is_err());
+    assert_eq!(
+      none.read_file_op().apply_term(&[text(&dir.join("secret"))]),
+      Some(result_term(Err(format!(
+        "{} is outside the directories allowed for reading",
+        dir.join("secret").display()
+      ))))
+    );
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
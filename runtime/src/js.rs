@@ -0,0 +1,508 @@
+//! Compiles packages to JavaScript modules.
+//!
+//! Each definition becomes a lazily evaluated constant of an ES module, and
+//! the definitions of a package are getters of its default export, named
+//! after them, so a definition is only evaluated once it is read. Terms are
+//! erased as the runtime erases them: types become `null`, data and case
+//! nodes disappear and annotations keep their expression. Evaluation stays
+//! lazy, so arguments are passed as thunks that are forced at most once, but
+//! exported functions also accept plain JavaScript values.
+//!
+//! Literals map to JavaScript values: `#Nat`, `#Int`, `#U64` and `#I64` to
+//! BigInts, the smaller fixed-width integers to numbers, `#Bool` to booleans,
+//! `#Text` and `#Char` to strings and `#Bytes` to `Uint8Array`s in the order
+//! they are written. Only the
+//! pure externs `io.return` and `io.bind` are available. Extern declarations
+//! are not exported, and literals and primitive operations missing from `OPS`
+//! are rejected when compiling. A primitive operation that does not reduce,
+//! such as `#Nat.sub 2 7`, throws an `Error`, as does `#Text.abort`.
+
+use std::{
+  collections::BTreeMap,
+  fmt,
+  rc::Rc,
+};
+
+use sp_cid::Cid;
+use yatima_core::{
+  defs::{
+    Def,
+    Defs,
+  },
+  literal::Literal,
+  name::Name,
+  prim::Op,
+  term::Term,
+};
+
+use crate::{
+  error::RuntimeError,
+  transform::{
+    RunIO,
+    RuntimeIO,
+    StdIORuntime,
+  },
+};
+
+/// Errors that may occur while compiling to JavaScript
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsError {
+  /// Resolving the externs of a definition failed
+  Runtime(RuntimeError),
+  /// A literal or primitive operation the target does not support
+  Unsupported(String),
+}
+
+impl fmt::Display for JsError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Runtime(e) => write!(f, "{}", e),
+      Self::Unsupported(what) => {
+        write!(f, "The js target does not support {}", what)
+      }
+    }
+  }
+}
+
+/// The primitive operations the target supports, as JavaScript functions of
+/// their forced arguments
+#[rustfmt::skip]
+const OPS: &[(&str, &str)] = &[
+  ("#Nat.suc", "(a) => a + 1n"),
+  ("#Nat.pre", "(a) => (a === 0n ? 0n : a - 1n)"),
+  ("#Nat.eql", "(a, b) => a === b"),
+  ("#Nat.lte", "(a, b) => a <= b"),
+  ("#Nat.lth", "(a, b) => a < b"),
+  ("#Nat.gte", "(a, b) => a >= b"),
+  ("#Nat.gth", "(a, b) => a > b"),
+  ("#Nat.add", "(a, b) => a + b"),
+  ("#Nat.sub", "(a, b) => (a >= b ? a - b : $stuck(\"#Nat.sub\"))"),
+  ("#Nat.mul", "(a, b) => a * b"),
+  ("#Nat.div", "(a, b) => (b === 0n ? $stuck(\"#Nat.div\") : a / b)"),
+  ("#Nat.mod", "(a, b) => (b === 0n ? $stuck(\"#Nat.mod\") : a % b)"),
+  ("#Nat.to_Text", "(a) => String(a)"),
+  ("#Nat.pow", "(a, b) => a ** BigInt(b)"),
+  ("#Nat.shl", "(a, b) => b << BigInt(a)"),
+  ("#Nat.shr", "(a, b) => b >> BigInt(a)"),
+  ("#Nat.and", "(a, b) => a & b"),
+  ("#Nat.or", "(a, b) => a | b"),
+  ("#Nat.xor", "(a, b) => a ^ b"),
+  ("#Int.new", "(a, b) => (a ? b : -b)"),
+  ("#Int.sgn", "(a) => a > 0n"),
+  ("#Int.abs", "(a) => (a < 0n ? -a : a)"),
+  ("#Int.eql", "(a, b) => a === b"),
+  ("#Int.lte", "(a, b) => a <= b"),
+  ("#Int.lth", "(a, b) => a < b"),
+  ("#Int.gte", "(a, b) => a >= b"),
+  ("#Int.gth", "(a, b) => a > b"),
+  ("#Int.add", "(a, b) => a + b"),
+  ("#Int.sub", "(a, b) => a - b"),
+  ("#Int.mul", "(a, b) => a * b"),
+  ("#Int.div", "(a, b) => (b === 0n ? $stuck(\"#Int.div\") : a / b)"),
+  ("#Int.mod", "(a, b) => (b === 0n ? $stuck(\"#Int.mod\") : a % b)"),
+  ("#Int.to_Text", "(a) => String(a)"),
+  ("#Int.pow", "(a, b) => a ** BigInt(b)"),
+  ("#Bool.eql", "(a, b) => a === b"),
+  ("#Bool.lte", "(a, b) => a <= b"),
+  ("#Bool.lth", "(a, b) => a < b"),
+  ("#Bool.gte", "(a, b) => a >= b"),
+  ("#Bool.gth", "(a, b) => a > b"),
+  ("#Bool.and", "(a, b) => a && b"),
+  ("#Bool.or", "(a, b) => a || b"),
+  ("#Bool.xor", "(a, b) => a !== b"),
+  ("#Bool.not", "(a) => !a"),
+  ("#Text.append", "(a, b) => a + b"),
+  ("#Text.eql", "(a, b) => a === b"),
+  ("#Text.len_chars", "(a) => BigInt([...a].length)"),
+  ("#Text.len_bytes", "(a) => BigInt(new TextEncoder().encode(a).length)"),
+  ("#Text.to_bytes", "(a) => new TextEncoder().encode(a)"),
+  ("#Text.trim", "(a) => a.trim()"),
+  // The prefix or suffix comes first
+  ("#Text.starts_with", "(a, b) => b.startsWith(a)"),
+  ("#Text.ends_with", "(a, b) => b.endsWith(a)"),
+  ("#Text.to_upper", "(a) => a.toUpperCase()"),
+  ("#Text.to_lower", "(a) => a.toLowerCase()"),
+  ("#Text.abort", "(_, a) => { throw new Error(a); }"),
+  ("#Bytes.len", "(a) => BigInt(a.length)"),
+  ("#Bytes.append", "(a, b) => { const c = new Uint8Array(a.length + b.length); c.set(a); c.set(b, a.length); return c; }"),
+  ("#Char.eql", "(a, b) => a === b"),
+  // Behind `io.bind`: forces the action, then the continuation
+  ("#Io.seq", "(a, b) => b"),
+];
+
+/// The JavaScript runtime of a module: thunks, forcing and curried primitive
+/// operations
+const PRELUDE: &str = r#"class $Thunk {
+  constructor(f) {
+    this.f = f;
+    this.v = undefined;
+  }
+}
+
+const $loop = () => {
+  throw new Error("A value depends on itself");
+};
+
+const $stuck = (op) => {
+  throw new Error(`${op} does not reduce`);
+};
+
+function $force(x) {
+  if (!(x instanceof $Thunk)) return x;
+  if (x.f !== null) {
+    const f = x.f;
+    x.f = $loop;
+    x.v = f();
+    x.f = null;
+  }
+  return x.v;
+}
+
+function $prim(arity, f) {
+  const go = (args) => (x) => {
+    const xs = [...args, x];
+    return xs.length < arity ? go(xs) : f(...xs.map($force));
+  };
+  return go([]);
+}
+"#;
+
+/// Resolves the externs the target supports, as `StdIORuntime` does
+struct JsIO;
+
+impl RuntimeIO for JsIO {
+  fn resolve_extern(&self, symbol: &str) -> Option<Term> {
+    if self.extern_symbols().iter().any(|s| s == symbol) {
+      StdIORuntime::new().resolve_extern(symbol)
+    }
+    else {
+      None
+    }
+  }
+
+  fn extern_symbols(&self) -> Vec<String> {
+    vec!["io.bind".to_owned(), "io.return".to_owned()]
+  }
+}
+
+/// Compiles the definitions in `exports`, usually the index of a package, to
+/// an ES module whose default export has a getter for each of them, under its
+/// name. The definitions they refer to are compiled as well.
+pub fn build(
+  defs: Rc<Defs>,
+  exports: &[(Name, Cid)],
+) -> Result<String, JsError> {
+  let mut codegen = Codegen {
+    defs: &defs,
+    io: Rc::new(JsIO),
+    consts: BTreeMap::new(),
+    names: vec![],
+    ops: vec![],
+  };
+  let mut exported = vec![];
+  for (name, cid) in exports {
+    let is_extern = |def: &Def| matches!(def.term, Term::Opr(_, Op::Extern(_)));
+    if defs.defs.get(cid).map_or(false, |def| !is_extern(def)) {
+      exported.push((name, codegen.def(name, cid)));
+    }
+  }
+  let mut compiled = vec![];
+  while compiled.len() < codegen.names.len() {
+    let (name, cid) = codegen.names[compiled.len()].clone();
+    let term = &defs.defs[&cid].term;
+    let this = compiled.len();
+    compiled.push((name, codegen.value(term, 0, this)?));
+  }
+  let mut out = String::from("// Generated by `yatima build --target js`\n\n");
+  out.push_str(PRELUDE);
+  out.push('\n');
+  for (idx, (symbol, code)) in codegen.ops.iter().enumerate() {
+    out.push_str(&format!("// {}\nconst $op{} = {};\n", symbol, idx, code));
+  }
+  for (idx, (name, code)) in compiled.iter().enumerate() {
+    out.push_str(&format!(
+      "\n// {}\nconst $d{} = new $Thunk(() => {});\n",
+      name, idx, code
+    ));
+  }
+  // Exports are evaluated when they are first read, so that importing the
+  // module does not fail when one of them diverges or throws
+  out.push_str("\nexport default {\n");
+  for (name, idx) in &exported {
+    out.push_str(&format!(
+      "  get {}() {{ return $force($d{}); }},\n",
+      string_literal(&name.to_string()),
+      idx
+    ));
+  }
+  out.push_str("};\n");
+  Ok(out)
+}
+
+/// The state of the translation of a package
+struct Codegen<'a> {
+  defs: &'a Defs,
+  io: RunIO,
+  /// The constant of each definition compiled so far
+  consts: BTreeMap<Cid, usize>,
+  /// The name and content id of each constant, in order
+  names: Vec<(Name, Cid)>,
+  /// The symbol and implementation of each primitive operation used
+  ops: Vec<(String, String)>,
+}
+
+impl<'a> Codegen<'a> {
+  /// The constant of a definition, which is compiled later if it is new
+  fn def(&mut self, name: &Name, cid: &Cid) -> usize {
+    match self.consts.get(cid) {
+      Some(idx) => *idx,
+      None => {
+        let idx = self.names.len();
+        self.consts.insert(*cid, idx);
+        self.names.push((name.clone(), *cid));
+        idx
+      }
+    }
+  }
+
+  /// The constant of a primitive operation
+  fn op(&mut self, opr: &Op) -> Result<usize, JsError> {
+    let symbol = opr.symbol();
+    if let Some(idx) = self.ops.iter().position(|(s, _)| *s == symbol) {
+      return Ok(idx);
+    }
+    let code = OPS.iter().find(|(op, _)| *op == symbol).ok_or_else(|| {
+      JsError::Unsupported(format!("the primitive operation {}", symbol))
+    })?;
+    self.ops.push((symbol, format!("$prim({}, {})", opr.arity(), code.1)));
+    Ok(self.ops.len() - 1)
+  }
+
+  /// An expression evaluating `term`, under `depth` variables of the
+  /// definition `this`, to weak head normal form
+  fn value(
+    &mut self,
+    term: &Term,
+    depth: usize,
+    this: usize,
+  ) -> Result<String, JsError> {
+    match erase(term) {
+      Term::Var(_, _, idx) => {
+        Ok(format!("$force(x{})", depth - 1 - *idx as usize))
+      }
+      Term::Lam(_, _, bod) => {
+        Ok(format!("(x{}) => {}", depth, self.value(bod, depth + 1, this)?))
+      }
+      Term::App(_, fun_arg) => {
+        let (fun, arg) = &**fun_arg;
+        let arg = self.lazy(arg, depth, this)?;
+        match erase(fun) {
+          Term::Lam(..) => {
+            Ok(format!("({})({})", self.value(fun, depth, this)?, arg))
+          }
+          _ => Ok(format!("{}({})", self.value(fun, depth, this)?, arg)),
+        }
+      }
+      Term::Let(_, rec, _, _, typ_exp_bod) => {
+        let (_, exp, bod) = &**typ_exp_bod;
+        let exp = if *rec {
+          format!("new $Thunk(() => {})", self.value(exp, depth + 1, this)?)
+        }
+        else {
+          self.lazy(exp, depth, this)?
+        };
+        Ok(format!(
+          "(() => {{ const x{} = {}; return {}; }})()",
+          depth,
+          exp,
+          self.value(bod, depth + 1, this)?
+        ))
+      }
+      Term::Ref(..) | Term::Rec(_) => {
+        Ok(format!("$force({})", self.lazy(term, depth, this)?))
+      }
+      Term::Opr(_, Op::Extern(op)) => {
+        let imp = self.extern_term(&op.symbol)?;
+        self.value(&imp, depth, this)
+      }
+      _ => self.lazy(term, depth, this),
+    }
+  }
+
+  /// An expression for `term` that does not evaluate it: a thunk, unless the
+  /// term is a variable, a value or a constant
+  fn lazy(
+    &mut self,
+    term: &Term,
+    depth: usize,
+    this: usize,
+  ) -> Result<String, JsError> {
+    match erase(term) {
+      Term::Var(_, _, idx) => Ok(format!("x{}", depth - 1 - *idx as usize)),
+      Term::Lam(..) => self.value(term, depth, this),
+      Term::Ref(_, name, cid, _) => match self.defs.defs.get(cid) {
+        Some(_) => Ok(format!("$d{}", self.def(name, cid))),
+        None => panic!("undefined runtime reference: {}, {}", name, cid),
+      },
+      Term::Rec(_) => Ok(format!("$d{}", this)),
+      Term::Lit(_, lit) => literal(lit),
+      Term::Opr(_, Op::Extern(op)) => {
+        let imp = self.extern_term(&op.symbol)?;
+        self.lazy(&imp, depth, this)
+      }
+      Term::Opr(_, opr) => Ok(format!("$op{}", self.op(opr)?)),
+      Term::App(..) | Term::Let(..) => {
+        Ok(format!("new $Thunk(() => {})", self.value(term, depth, this)?))
+      }
+      // Types are erased to a placeholder, as in graph reduction
+      _ => Ok("null".to_owned()),
+    }
+  }
+
+  /// The implementation of an extern symbol
+  fn extern_term(&self, symbol: &str) -> Result<Term, JsError> {
+    self.io.resolve_extern(symbol).ok_or_else(|| {
+      JsError::Runtime(RuntimeError::UnknownExtern(
+        symbol.to_owned(),
+        self.io.extern_symbols(),
+      ))
+    })
+  }
+}
+
+/// Skips the data, case and annotation nodes around a term
+fn erase(term: &Term) -> &Term {
+  let mut term = term;
+  loop {
+    term = match term {
+      Term::Dat(_, bod) | Term::Cse(_, bod) => &**bod,
+      Term::Ann(_, typ_exp) => &typ_exp.1,
+      _ => return term,
+    };
+  }
+}
+
+/// A JavaScript string literal
+fn string_literal(string: &str) -> String {
+  let mut out = String::from("\"");
+  for c in string.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      c if (c as u32) < 0x20 || c == '\u{2028}' || c == '\u{2029}' => {
+        out.push_str(&format!("\\u{:04x}", c as u32))
+      }
+      c => out.push(c),
+    }
+  }
+  out.push('"');
+  out
+}
+
+/// Wraps negative numbers in parentheses so they can be applied or negated
+fn number(x: String) -> String {
+  if x.starts_with('-') { format!("({})", x) } else { x }
+}
+
+/// The JavaScript value of a literal
+fn literal(lit: &Literal) -> Result<String, JsError> {
+  match lit {
+    Literal::Nat(x) => Ok(format!("{}n", x)),
+    Literal::Int(x) => Ok(number(format!("{}n", x))),
+    Literal::Bool(b) => Ok(b.to_string()),
+    Literal::Text(xs) => Ok(string_literal(&xs.to_string())),
+    Literal::Char(c) => Ok(string_literal(&c.to_string())),
+    // Bytes are stored last byte first
+    Literal::Bytes(xs) => Ok(format!(
+      "new Uint8Array([{}])",
+      xs.iter().rev().map(|x| x.to_string()).collect::<Vec<_>>().join(", ")
+    )),
+    Literal::U8(x) => Ok(x.to_string()),
+    Literal::U16(x) => Ok(x.to_string()),
+    Literal::U32(x) => Ok(x.to_string()),
+    Literal::U64(x) => Ok(format!("{}n", x)),
+    Literal::I8(x) => Ok(number(x.to_string())),
+    Literal::I16(x) => Ok(number(x.to_string())),
+    Literal::I32(x) => Ok(number(x.to_string())),
+    Literal::I64(x) => Ok(number(format!("{}n", x))),
+    _ => Err(JsError::Unsupported(format!("the literal {}", lit))),
+  }
+}
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+  use crate::snapshot;
+  use yatima_core::parse::{
+    package::parse_defs,
+    span::Span,
+    term::input_cid,
+  };
+
+  /// Builds the definitions in `src`
+  fn build_src(src: &str) -> Result<String, JsError> {
+    let (_, (defs, index)) =
+      parse_defs(input_cid(src), Defs::new())(Span::new(src)).unwrap();
+    build(Rc::new(defs), &index.0)
+  }
+
+  /// Compares the module built from `src` with `snapshots/js/<name>.mjs`
+  fn assert_snapshot(name: &str, src: &str) {
+    let module = build_src(src).unwrap();
+    snapshot::assert_snapshot("js", &format!("{}.mjs", name), &module);
+  }
+
+  #[test]
+  fn test_functions() {
+    assert_snapshot(
+      "functions",
+      "def id (0 A: Type) (x: A): A = x
+       def const (0 A: Type) (0 B: Type) (x: A) (y: B): A = x
+       def double (x: #Nat): #Nat = #Nat.add x x
+       def quadruple (x: #Nat): #Nat = double (double x)
+       def greeting: #Text = #Text.append \"hello, \" \"world\"
+       def twice (0 A: Type) (f: ∀ A -> A) (x: A): A = f (f x)
+       def ones: #Nat = let x: #Nat = 1; #Nat.add x x
+       def forever (n: #Nat): #Nat = forever n
+       extern return : ∀ #Bool -> #Bool = \"io.return\"
+       def yes: #Bool = return #Bool.true",
+    );
+  }
+
+  #[test]
+  fn test_literals() {
+    assert_snapshot(
+      "literals",
+      "def big: #Nat = 123456789012345678901234567890
+       def neg: #Int = -42
+       def small: #I8 = -5i8
+       def wide: #U64 = 18446744073709551615u64
+       def byte: #U8 = 255u8
+       def letter: #Char = 'λ'
+       def message: #Text = \"say \\\"hi\\\"\\n\"
+       def blob: #Bytes = x'ff'
+       def flag: #Bool = #Bool.false
+       def header: #Bytes = x'0102ff'",
+    );
+  }
+
+  #[test]
+  fn test_unsupported() {
+    assert_eq!(
+      build_src("def len: #Nat = #Bytes.head x'ff'"),
+      Err(JsError::Unsupported(
+        "the primitive operation #Bytes.head".to_owned()
+      ))
+    );
+    assert!(matches!(
+      build_src(
+        "extern print : ∀ #Bytes -> #Bool = \"io.print\"
+         def main: #Bool = print x'ff'"
+      ),
+      Err(JsError::Runtime(RuntimeError::UnknownExtern(..)))
+    ));
+  }
+}
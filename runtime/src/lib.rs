@@ -1,20 +1,780 @@
-use std::rc::Rc;
+use std::{
+  rc::Rc,
+  str::FromStr,
+};
 
 use yatima_core::{
   defs::{
     Defs,
   },
+  limits::{
+    Limits,
+    Meter,
+  },
   term::Term,
 };
 
+pub mod debug;
+pub mod error;
+pub mod extract;
+pub mod fs;
+pub mod js;
+pub mod memory;
+pub mod optimize;
+pub mod profile;
+pub mod replay;
 mod runtime;
+#[cfg(test)]
+mod snapshot;
 pub mod transform;
+pub mod vm;
+pub mod wasm;
+use debug::Debugger;
+use error::RuntimeError;
+use profile::Profile;
 use transform::RunIO;
 
-/// Reduce terms to WHNF and execute any IoOp with the provided RuntimeIO
-pub fn run(term: &mut Term, checked: Rc<Defs>, runtime: RunIO) {
-  let root = runtime::alloc_val(yatima_core::dll::DLL::singleton(runtime::ParentPtr::Root));
-  transform::transform(checked.clone(), term, runtime);
-  let mut dag = runtime::from_term(checked, &term, Some(root));
-  runtime::whnf(&mut dag, false);
+/// The evaluator a program runs on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+  /// Lazy graph reduction of the program's DAG
+  Graph,
+  /// The bytecode compiler and abstract machine of `vm`
+  Vm,
+}
+
+impl Default for Engine {
+  fn default() -> Self { Engine::Graph }
+}
+
+impl FromStr for Engine {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "graph" => Ok(Engine::Graph),
+      "vm" => Ok(Engine::Vm),
+      _ => Err(format!("Unknown engine \"{}\", expected graph or vm", s)),
+    }
+  }
+}
+
+/// The order in which graph reduction evaluates a program
+///
+/// Under `Lazy`, the default, an argument is substituted unevaluated and
+/// reduced at most once, when it is applied or a primitive needs its value.
+/// Under `Strict`, a lambda applied to an argument first reduces the argument
+/// to weak head normal form, so accumulators are kept as values rather than
+/// as chains of suspended operations. Either way, reduction stops at lambdas
+/// and the expressions bound by `let` are shared rather than forced.
+///
+/// A `Fix`, the node of a recursive definition, is not a value: forcing it as
+/// an argument unfolds it once and reduces the unfolded body, which for a
+/// recursive function or a stream built from constructors is a lambda. The
+/// recursive references inside the body stay `Fix` nodes until they are
+/// reached in turn, so recursive functions terminate under both strategies,
+/// while a definition that needs its own value to unfold loops under both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+  /// Call by need
+  Lazy,
+  /// Call by value
+  Strict,
+}
+
+impl Default for Strategy {
+  fn default() -> Self { Strategy::Lazy }
+}
+
+impl FromStr for Strategy {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "lazy" => Ok(Strategy::Lazy),
+      "strict" => Ok(Strategy::Strict),
+      _ => Err(format!("Unknown strategy \"{}\", expected lazy or strict", s)),
+    }
+  }
+}
+
+/// Resolves the references and externs of a term with `transform::transform`
+/// and simplifies the result with `optimize::optimize`, giving the term the
+/// engines run
+pub fn prepare(
+  checked: Rc<Defs>,
+  term: &mut Term,
+  runtime: RunIO,
+) -> Result<(), RuntimeError> {
+  transform::transform(checked.clone(), term, runtime)?;
+  optimize::optimize(term, &checked);
+  Ok(())
+}
+
+/// Reduce terms to WHNF and execute any IoOp with the provided RuntimeIO,
+/// returning the exit status of the program
+pub fn run(
+  term: &mut Term,
+  checked: Rc<Defs>,
+  runtime: RunIO,
+) -> Result<i32, RuntimeError> {
+  run_with_limits(term, checked, runtime, Limits::default())
+}
+
+/// Like `run`, but fails with `RuntimeError::Limit` as soon as the program
+/// exceeds one of the given limits
+pub fn run_with_limits(
+  term: &mut Term,
+  checked: Rc<Defs>,
+  runtime: RunIO,
+  limits: Limits,
+) -> Result<i32, RuntimeError> {
+  run_with_engine(term, checked, runtime, limits, Engine::Graph)
+}
+
+/// Like `run_with_limits`, evaluating the program on the given engine. The
+/// engines count steps and allocations differently, so the same limits stop
+/// them at different points.
+pub fn run_with_engine(
+  term: &mut Term,
+  checked: Rc<Defs>,
+  runtime: RunIO,
+  limits: Limits,
+  engine: Engine,
+) -> Result<i32, RuntimeError> {
+  prepare(checked.clone(), term, runtime.clone())?;
+  let res = match engine {
+    Engine::Graph => reduce(checked, term, limits, Strategy::Lazy, false, None),
+    Engine::Vm => {
+      let mut meter = Meter::with_counters(limits, vm::allocated, vm::freed);
+      vm::run(&checked, term, &mut meter)
+    }
+  };
+  exit_status(&runtime, res)
+}
+
+/// Like `run_with_limits`, evaluating the program by graph reduction with the
+/// given strategy. The optimizations of `optimize::optimize` assume lazy
+/// evaluation, as they drop unused arguments, so strict runs skip them.
+pub fn run_with_strategy(
+  term: &mut Term,
+  checked: Rc<Defs>,
+  runtime: RunIO,
+  limits: Limits,
+  strategy: Strategy,
+) -> Result<i32, RuntimeError> {
+  match strategy {
+    Strategy::Lazy => prepare(checked.clone(), term, runtime.clone())?,
+    Strategy::Strict => {
+      transform::transform(checked.clone(), term, runtime.clone())?
+    }
+  }
+  exit_status(&runtime, reduce(checked, term, limits, strategy, false, None))
+}
+
+/// Like `run_with_limits`, attributing the reduction steps of the program to
+/// the definitions of `checked`. The steps of `term` itself are attributed to
+/// `name`. The profile covers the steps taken until the program stopped,
+/// even if it failed.
+pub fn run_profiled(
+  term: &mut Term,
+  checked: Rc<Defs>,
+  runtime: RunIO,
+  limits: Limits,
+  name: &str,
+) -> (Result<i32, RuntimeError>, Profile) {
+  let empty = Profile::new(&checked, name);
+  if let Err(e) = prepare(checked.clone(), term, runtime.clone()) {
+    return (Err(e), empty);
+  }
+  profile::start(empty);
+  let res = reduce(checked, term, limits, Strategy::Lazy, true, None);
+  let profile = profile::stop().unwrap();
+  (exit_status(&runtime, res), profile)
+}
+
+/// Like `run_with_limits`, pausing the program where the `debugger` asks to.
+/// Steps are attributed to definitions as by `run_profiled`, and the steps of
+/// `term` itself to `name`.
+pub fn run_debugged(
+  term: &mut Term,
+  checked: Rc<Defs>,
+  runtime: RunIO,
+  limits: Limits,
+  name: &str,
+  mut debugger: Debugger,
+) -> Result<i32, RuntimeError> {
+  prepare(checked.clone(), term, runtime.clone())?;
+  profile::start(Profile::new(&checked, name));
+  let res =
+    reduce(checked, term, limits, Strategy::Lazy, true, Some(&mut debugger));
+  profile::stop();
+  exit_status(&runtime, res)
+}
+
+/// Reduces a prepared term by graph reduction
+fn reduce(
+  checked: Rc<Defs>,
+  term: &Term,
+  limits: Limits,
+  strategy: Strategy,
+  should_count: bool,
+  debugger: Option<&mut Debugger>,
+) -> Result<(), RuntimeError> {
+  let root = runtime::alloc_val(yatima_core::dll::DLL::singleton(
+    runtime::ParentPtr::Root,
+  ));
+  let mut meter =
+    Meter::with_counters(limits, runtime::allocated, runtime::freed);
+  let mut dag = runtime::from_term(checked, term, Some(root), 0);
+  runtime::whnf(&mut dag, strategy, should_count, &mut meter, debugger)
+}
+
+/// The exit status of a program that stopped with the given result. A
+/// primitive that failed to replay or record its input fails the run, since
+/// it was left unreduced.
+fn exit_status(
+  runtime: &RunIO,
+  res: Result<(), RuntimeError>,
+) -> Result<i32, RuntimeError> {
+  if let Some(e) = runtime.nondet().borrow_mut().take_error() {
+    return Err(e);
+  }
+  match res {
+    Ok(()) => Ok(0),
+    Err(RuntimeError::Exit(code)) => Ok(code),
+    Err(e) => Err(e),
+  }
+}
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+  use crate::{
+    memory::MemIORuntime,
+    profile::Step,
+  };
+  use yatima_core::{
+    defs,
+    limits::LimitError,
+    name::Name,
+    parse::{
+      self,
+      package::parse_defs,
+      span::Span,
+      term::input_cid,
+    },
+    prim::host::HostFns,
+    yatima,
+  };
+
+  pub const IO: &str = "
+    extern return : ∀ #Bool -> #Bool = \"io.return\"
+    extern print : ∀ (0 A: Type) #Bytes -> A = \"io.print\"
+    extern read : ∀ (0 A: Type) -> #Bytes = \"io.read\"
+    extern bind : ∀ (0 A: Type) (0 B: Type) (x: A) (f: ∀ A -> B) -> B
+      = \"io.bind\"
+  ";
+
+  const LIST: &str = "
+    type List (A: Type) {
+      Nil,
+      Cons A (List A),
+    }
+    def List.sum (xs: List #Nat): #Nat
+      = (case xs) (λ _ => #Nat) 0 (λ x xs => #Nat.add x (List.sum xs))
+    def main : #Bool = print #Bool (#Text.to_bytes (#Nat.to_Text
+      (List.sum (List.Cons #Nat 1 (List.Cons #Nat 2 (List.Cons #Nat 3
+        (List.Nil #Nat)))))))
+  ";
+
+  fn run_main(src: &str, io: Rc<MemIORuntime>) -> Result<i32, RuntimeError> {
+    run_main_with_limits(src, io, Limits::default())
+  }
+
+  /// Runs `main` with graph reduction, checking that the bytecode machine
+  /// gives the same result, output and remaining input from a copy of `io`
+  fn run_main_with_limits(
+    src: &str,
+    io: Rc<MemIORuntime>,
+    limits: Limits,
+  ) -> Result<i32, RuntimeError> {
+    let src = format!("{}{}", IO, src);
+    let (_, (defs, _)) =
+      parse_defs(input_cid(&src), Defs::new())(Span::new(&src)).unwrap();
+    let defs = Rc::new(defs);
+    let term = defs.get(&Name::from("main")).unwrap().term.clone();
+    let vm_io = Rc::new(io.fork());
+    let vm_res = run_with_engine(
+      &mut term.clone(),
+      defs.clone(),
+      vm_io.clone(),
+      limits,
+      Engine::Vm,
+    );
+    let res = run_with_engine(
+      &mut term.clone(),
+      defs,
+      io.clone(),
+      limits,
+      Engine::Graph,
+    );
+    assert_eq!(vm_res, res);
+    assert_eq!(vm_io.stdout(), io.stdout());
+    assert_eq!(vm_io.remaining_stdin(), io.remaining_stdin());
+    res
+  }
+
+  #[test]
+  fn test_print() {
+    let io = Rc::new(MemIORuntime::new(""));
+    let res = run_main(
+      "def main : #Bool = print #Bool (#Text.to_bytes \"hello\")",
+      io.clone(),
+    );
+    assert_eq!(res, Ok(0));
+    assert_eq!(io.stdout_string(), "hello");
+  }
+
+  #[test]
+  fn test_read() {
+    let io = Rc::new(MemIORuntime::new("first\nsecond\n"));
+    let res =
+      run_main("def main : #Bool = print #Bool (read #Bytes)", io.clone());
+    assert_eq!(res, Ok(0));
+    assert_eq!(io.stdout_string(), "first\n");
+    assert_eq!(io.remaining_stdin(), b"second\n".to_vec());
+  }
+
+  #[test]
+  fn test_bind() {
+    let io = Rc::new(MemIORuntime::new("ping\n"));
+    let res = run_main(
+      "def main : #Bool =
+         bind #Bytes #Bool (read #Bytes)
+           (λ x => print #Bool (#Bytes.append (#Text.to_bytes \"got \") x))",
+      io.clone(),
+    );
+    assert_eq!(res, Ok(0));
+    assert_eq!(io.stdout_string(), "got ping\n");
+    let io = Rc::new(MemIORuntime::new(""));
+    let res = run_main(
+      "def main : #Bool =
+         bind #Bool #Bool (return #Bool.true)
+           (λ x => print #Bool (#Text.to_bytes \"done\"))",
+      io.clone(),
+    );
+    assert_eq!(res, Ok(0));
+    assert_eq!(io.stdout_string(), "done");
+  }
+
+  #[test]
+  fn test_laziness() {
+    // Unused arguments are never evaluated, and shared ones only once
+    let io = Rc::new(MemIORuntime::new("hi\n"));
+    let res = run_main(
+      "def main : #Bool =
+         (λ x y => y)
+           (print #Bool (#Text.to_bytes \"no\"))
+           (print #Bool ((λ x => #Bytes.append x x) (read #Bytes)))",
+      io.clone(),
+    );
+    assert_eq!(res, Ok(0));
+    assert_eq!(io.stdout_string(), "hi\nhi\n");
+  }
+
+  #[test]
+  fn test_deep_reduction() {
+    // Adding one a million times builds a million nested additions, each the
+    // argument of the next, which reduction must not recurse over
+    let io = Rc::new(MemIORuntime::new(""));
+    let res = run_main(
+      "def main : #Bool =
+         (λ ten mul =>
+           print #Bool (#Text.to_bytes (#Nat.to_Text
+             (mul (mul ten (mul ten ten)) (mul ten (mul ten ten))
+               (λ n => #Nat.add n 1) 0))))
+         (λ f x => f (f (f (f (f (f (f (f (f (f x))))))))))
+         (λ m n f => m (n f))",
+      io.clone(),
+    );
+    assert_eq!(res, Ok(0));
+    assert_eq!(io.stdout_string(), "1000000");
+  }
+
+  #[test]
+  fn test_abort() {
+    // Output before the abort is kept, and the message fails the run
+    let io = Rc::new(MemIORuntime::new(""));
+    let res = run_main(
+      "def main : #Bool =
+         bind #Bool #Bool (print #Bool (#Text.to_bytes \"before\"))
+           (λ _ => #Text.abort #Bool \"stopped\")",
+      io.clone(),
+    );
+    assert_eq!(res, Err(RuntimeError::Abort("stopped".to_owned())));
+    assert_eq!(io.stdout_string(), "before");
+  }
+
+  #[test]
+  fn test_exit() {
+    let exit = |code: &str| {
+      let io = Rc::new(MemIORuntime::new(""));
+      let res = run_main(
+        &format!(
+          "extern exit : ∀ (0 A: Type) #U8 -> A = \"io.exit\"
+           def main : #Bool =
+             bind #Bool #Bool (print #Bool (#Text.to_bytes \"before\"))
+               (λ _ => exit #Bool {})",
+          code
+        ),
+        io.clone(),
+      );
+      (res, io.stdout_string())
+    };
+    assert_eq!(exit("7u8"), (Ok(7), "before".to_owned()));
+    assert_eq!(exit("0u8"), (Ok(0), "before".to_owned()));
+    assert_eq!(exit("300"), (Ok(300), "before".to_owned()));
+    assert_eq!(exit("-2i32"), (Ok(-2), "before".to_owned()));
+    // A host function that shares the symbol of `io.exit` is called normally
+    let mut host_fns = HostFns::new();
+    host_fns.register1("exit", yatima!("∀ #U8 -> #Bytes"), |x: u8| {
+      x.to_string().into_bytes()
+    });
+    let io = Rc::new(MemIORuntime::new("").with_host_fns(host_fns));
+    let res = run_main(
+      "extern quit : ∀ #U8 -> #Bytes = \"exit\"
+       def main : #Bool = print #Bool (quit 3u8)",
+      io.clone(),
+    );
+    assert_eq!(res, Ok(0));
+    assert_eq!(io.stdout_string(), "3");
+  }
+
+  #[test]
+  fn test_args() {
+    let args = vec!["a".to_owned(), "bc".to_owned()];
+    let io = Rc::new(MemIORuntime::new("").with_args(args));
+    let res = run_main(
+      "extern args : ∀ (0 P: Type) (& nil: P) (cons: ∀ #Text P -> P) -> P
+         = \"io.args\"
+       def main : #Bool = print #Bool (#Text.to_bytes
+         (args #Text \"|\" (λ x xs => #Text.append x xs)))",
+      io.clone(),
+    );
+    assert_eq!(res, Ok(0));
+    assert_eq!(io.stdout_string(), "abc|");
+  }
+
+  #[test]
+  fn test_get_env() {
+    let env = vec![("HOME".to_owned(), "/home/y".to_owned())];
+    let io = Rc::new(MemIORuntime::new("").with_env(env));
+    let res = run_main(
+      "extern get_env : ∀ #Text (0 P: Type) (& none: P) (& some: ∀ #Text -> P)
+         -> P = \"io.get_env\"
+       def show (name: #Text): #Text = get_env name #Text \"-\" (λ x => x)
+       def main : #Bool = print #Bool (#Text.to_bytes
+         (#Text.append (show \"HOME\") (show \"USER\")))",
+      io.clone(),
+    );
+    assert_eq!(res, Ok(0));
+    assert_eq!(io.stdout_string(), "/home/y-");
+  }
+
+  #[test]
+  fn test_limits() {
+    let io = Rc::new(MemIORuntime::new(""));
+    let res = run_main_with_limits(
+      "def main : #Bool = (λ x => x x) (λ x => x x)",
+      io.clone(),
+      Limits { max_steps: Some(1000), ..Limits::default() },
+    );
+    assert_eq!(res, Err(RuntimeError::Limit(LimitError::Steps(1000))));
+    let res = run_main_with_limits(
+      "def main : #Bool = (λ x => x x x) (λ x => x x x)",
+      io.clone(),
+      Limits { max_nodes: Some(10000), ..Limits::default() },
+    );
+    assert_eq!(res, Err(RuntimeError::Limit(LimitError::Nodes(10000))));
+    // Only live nodes count, so a loop that frees what it allocates runs until
+    // the step limit
+    let res = run_main_with_limits(
+      "def main : #Bool = (λ x => x x) (λ x => x x)",
+      io.clone(),
+      Limits {
+        max_steps: Some(100000),
+        max_nodes: Some(1000),
+        ..Limits::default()
+      },
+    );
+    assert_eq!(res, Err(RuntimeError::Limit(LimitError::Steps(100000))));
+    // Reading is allowed but printing what was read is not
+    let io = Rc::new(MemIORuntime::new("first\nsecond\n"));
+    let res = run_main_with_limits(
+      "def main : #Bool =
+         bind #Bytes #Bool (read #Bytes) (λ x => print #Bool x)",
+      io.clone(),
+      Limits { max_io: Some(1), ..Limits::default() },
+    );
+    assert_eq!(res, Err(RuntimeError::Limit(LimitError::Io(1))));
+    assert_eq!(io.stdout_string(), "");
+    assert_eq!(io.remaining_stdin(), b"second\n".to_vec());
+  }
+
+  #[test]
+  fn test_recursion() {
+    // Once inlined, a recursive definition still refers to itself rather than
+    // to the program
+    let io = Rc::new(MemIORuntime::new(""));
+    assert_eq!(run_main(LIST, io.clone()), Ok(0));
+    assert_eq!(io.stdout_string(), "6");
+  }
+
+  #[test]
+  fn test_profile() {
+    let src = format!("{}{}", IO, LIST);
+    let (_, (defs, _)) =
+      parse_defs(input_cid(&src), Defs::new())(Span::new(&src)).unwrap();
+    let defs = Rc::new(defs);
+    let mut term = defs.get(&Name::from("main")).unwrap().term.clone();
+    let io = Rc::new(MemIORuntime::new(""));
+    let (res, profile) =
+      run_profiled(&mut term, defs, io.clone(), Limits::default(), "main");
+    assert_eq!(res, Ok(0));
+    assert_eq!(io.stdout_string(), "6");
+    // One unfolding per call, and one addition per element
+    let sum = profile.counts("List.sum").unwrap();
+    assert_eq!((sum.prim, sum.unfold), (3, 4));
+    // Converting and printing the sum
+    assert_eq!(profile.counts("main").unwrap().prim, 3);
+    let total: u64 = profile
+      .folded()
+      .lines()
+      .map(|line| {
+        assert!(line.starts_with("main"));
+        line.rsplit(' ').next().unwrap().parse::<u64>().unwrap()
+      })
+      .sum();
+    let table = profile.table();
+    let rows: Vec<&str> = table.lines().skip(1).collect();
+    assert_eq!(
+      total,
+      rows
+        .iter()
+        .map(|row| row.rsplit(' ').next().unwrap().parse::<u64>().unwrap())
+        .sum::<u64>()
+    );
+  }
+
+  /// Counts the constructors of a unary number with an accumulator
+  const COUNT: &str = "
+    type Peano { Z, S Peano }
+    def Peano.double (n: Peano): Peano = (case n) (λ _ => Peano) Peano.Z
+      (λ m => Peano.S (Peano.S (Peano.double m)))
+    def count (acc: #Nat) (n: Peano): #Nat = (case n) (λ _ => #Nat) acc
+      (λ m => count (#Nat.add acc 1) m)
+    def main : #Bool = print #Bool (#Text.to_bytes (#Nat.to_Text
+      (count 0 (Peano.double (Peano.double (Peano.S Peano.Z))))))
+  ";
+
+  fn run_main_strategy(
+    src: &str,
+    strategy: Strategy,
+  ) -> (Result<i32, RuntimeError>, String) {
+    let src = format!("{}{}", IO, src);
+    let (_, (defs, _)) =
+      parse_defs(input_cid(&src), Defs::new())(Span::new(&src)).unwrap();
+    let defs = Rc::new(defs);
+    let mut term = defs.get(&Name::from("main")).unwrap().term.clone();
+    let io = Rc::new(MemIORuntime::new(""));
+    let res = run_with_strategy(
+      &mut term,
+      defs,
+      io.clone(),
+      Limits::default(),
+      strategy,
+    );
+    (res, io.stdout_string())
+  }
+
+  #[test]
+  fn test_strategy() {
+    for strategy in &[Strategy::Lazy, Strategy::Strict] {
+      assert_eq!(run_main_strategy(COUNT, *strategy), (Ok(0), "4".to_owned()));
+      assert_eq!(run_main_strategy(LIST, *strategy), (Ok(0), "6".to_owned()));
+    }
+    // An unused argument is only evaluated by call by value
+    let src = "
+      def main : #Bool = (λ _ => print #Bool (#Text.to_bytes \"ok\"))
+        (#Text.abort #Nat \"unused\")
+    ";
+    assert_eq!(
+      run_main_strategy(src, Strategy::Lazy),
+      (Ok(0), "ok".to_owned())
+    );
+    assert_eq!(
+      run_main_strategy(src, Strategy::Strict),
+      (Err(RuntimeError::Abort("unused".to_owned())), "".to_owned())
+    );
+    assert_eq!("strict".parse::<Strategy>(), Ok(Strategy::Strict));
+  }
+
+  /// Appends to a buffer bound with the given quantity once per constructor
+  /// of a unary number
+  fn fill(uses: &str) -> String {
+    format!(
+      "
+      type Peano {{ Z, S Peano }}
+      def fill (n: Peano) ({} acc: #Bytes): #Bytes = (case n)
+        (λ _ => ∀ ({} xs: #Bytes) -> #Bytes) (λ xs => xs)
+        (λ m xs => fill m (#Bytes.append xs (#Text.to_bytes \"!\"))) acc
+      def main : #Bool = print #Bool
+        (fill (Peano.S (Peano.S (Peano.S Peano.Z))) (#Text.to_bytes \"go\"))
+    ",
+      uses, uses
+    )
+  }
+
+  #[test]
+  fn test_in_place() {
+    // A buffer used once is appended to in place
+    let updated = runtime::updated();
+    assert_eq!(
+      run_main_strategy(&fill("1"), Strategy::Lazy),
+      (Ok(0), "go!!!".to_owned())
+    );
+    assert_eq!(runtime::updated() - updated, 3);
+    // and copied otherwise
+    let updated = runtime::updated();
+    assert_eq!(
+      run_main_strategy(&fill("ω"), Strategy::Lazy),
+      (Ok(0), "go!!!".to_owned())
+    );
+    assert_eq!(runtime::updated(), updated);
+  }
+
+  #[test]
+  fn test_in_place_inlined() {
+    // `prepare` inlines a definition with a linear parameter but leaves the
+    // redexes binding it, so that the runtime still binds the buffer as linear
+    let src = "
+      def push (1 xs: #Bytes): #Bytes = #Bytes.append xs (#Text.to_bytes \"!\")
+      def main : #Bool = print #Bool (push (push (#Text.to_bytes \"go\")))
+    ";
+    let updated = runtime::updated();
+    let io = Rc::new(MemIORuntime::new(""));
+    assert_eq!(run_main(src, io.clone()), Ok(0));
+    assert_eq!(io.stdout_string(), "go!!");
+    assert_eq!(runtime::updated() - updated, 2);
+  }
+
+  /// The pauses of a debugger continuing from every pause of `main`
+  fn debug_main(src: &str, breakpoints: &[&str]) -> Vec<debug::Pause> {
+    let src = format!("{}{}", IO, src);
+    let (_, (defs, _)) =
+      parse_defs(input_cid(&src), Defs::new())(Span::new(&src)).unwrap();
+    let defs = Rc::new(defs);
+    let mut term = defs.get(&Name::from("main")).unwrap().term.clone();
+    let breakpoints = breakpoints
+      .iter()
+      .map(|b| debug::Breakpoint::parse(&defs, b).unwrap())
+      .collect();
+    let mut pauses = vec![];
+    let debugger = Debugger::new(
+      breakpoints,
+      Box::new(|pause: &debug::Pause| {
+        pauses.push(pause.clone());
+        debug::Resume::Continue
+      }),
+    );
+    let io = Rc::new(MemIORuntime::new(""));
+    let res =
+      run_debugged(&mut term, defs, io, Limits::default(), "main", debugger);
+    assert_eq!(res, Ok(0));
+    pauses
+  }
+
+  #[test]
+  fn test_debug() {
+    // Without breakpoints, the debugger pauses at the first step
+    assert_eq!(debug_main(LIST, &[]).len(), 1);
+    // Each recursive call unfolds the definition
+    let pauses = debug_main(LIST, &["List.sum"]);
+    assert!(pauses.iter().all(|pause| pause.def == "List.sum"));
+    let unfolds = pauses.iter().filter(|pause| pause.step == Step::Unfold);
+    assert_eq!(unfolds.count(), 4);
+    // The additions, innermost first, on their literal arguments
+    let pauses = debug_main(LIST, &["#Nat.add"]);
+    let spines: Vec<Vec<&str>> = pauses
+      .iter()
+      .map(|pause| {
+        assert_eq!(pause.head, "#Nat.add");
+        pause.spine.iter().map(String::as_str).collect()
+      })
+      .collect();
+    assert_eq!(spines, vec![vec!["3", "0"], vec!["2", "3"], vec!["1", "5"]]);
+    let defs = Defs::new();
+    assert_eq!(
+      debug::Breakpoint::parse(&defs, "List.sum"),
+      Err(debug::DebugError::Undefined("List.sum".to_owned()))
+    );
+  }
+
+  #[test]
+  fn test_host_fns() {
+    let mut host_fns = HostFns::new();
+    host_fns.register1(
+      "shout",
+      yatima!("∀ #Text -> #Bytes"),
+      |text: String| text.to_uppercase().into_bytes(),
+    );
+    let io = Rc::new(MemIORuntime::new("").with_host_fns(host_fns.clone()));
+    let res = run_main(
+      "extern shout : ∀ (text: #Text) -> #Bytes = \"shout\"
+       def main : #Bool = print #Bool (shout \"hello\")",
+      io.clone(),
+    );
+    assert_eq!(res, Ok(0));
+    assert_eq!(io.stdout_string(), "HELLO");
+    let io = Rc::new(MemIORuntime::new("").with_host_fns(host_fns));
+    let res = run_main(
+      "extern shout : ∀ #Nat -> #Bytes = \"shout\"
+       def main : #Bool = print #Bool (shout 1)",
+      io,
+    );
+    assert_eq!(
+      res,
+      Err(RuntimeError::ExternType(
+        "shout".to_owned(),
+        yatima!("∀ #Nat -> #Bytes"),
+        yatima!("∀ #Text -> #Bytes"),
+      ))
+    );
+  }
+
+  #[test]
+  fn test_extern_params() {
+    let io = Rc::new(MemIORuntime::new(""));
+    let res = run_main(
+      "extern exit : ∀ (0 A: Type) #U8 -> A = \"io.exit\"
+       def main : #Bool = exit #Bool 3u8",
+      io,
+    );
+    assert_eq!(res, Ok(3));
+    let io = Rc::new(MemIORuntime::new(""));
+    let res = run_main(
+      "extern shout : ∀ #Bytes -> #Bool = \"io.print\"
+       def main : #Bool = shout x'ff'",
+      io,
+    );
+    assert_eq!(
+      res,
+      Err(RuntimeError::ExternParams(
+        "io.print".to_owned(),
+        yatima!("∀ #Bytes -> #Bool"),
+        "Type #Bytes".to_owned(),
+      ))
+    );
+  }
 }
@@ -0,0 +1,156 @@
+use std::{
+  cell::RefCell,
+  rc::Rc,
+};
+
+use yatima_core::{
+  defs,
+  literal::Literal,
+  parse,
+  prim::{
+    host::HostFns,
+    io::IoOp,
+  },
+  term::Term,
+  yatima,
+};
+
+use crate::{
+  replay::{
+    Clock,
+    Nondet,
+  },
+  transform::{
+    env_op,
+    RuntimeIO,
+  },
+};
+
+/// A RuntimeIO backed by in-memory buffers instead of the process's stdin and
+/// stdout, so that programs can be run from tests. Reads consume the scripted
+/// input a line at a time, like `StdIORuntime`, and return empty bytes once
+/// it is exhausted. Time is fixed at the epoch and random bytes are seeded,
+/// so runs are reproducible.
+#[derive(Clone)]
+pub struct MemIORuntime {
+  stdin: Rc<RefCell<Vec<u8>>>,
+  stdout: Rc<RefCell<Vec<u8>>>,
+  args: Vec<String>,
+  env: Vec<(String, String)>,
+  nondet: Rc<RefCell<Nondet>>,
+  host_fns: Rc<HostFns>,
+}
+
+impl MemIORuntime {
+  /// Creates a runtime whose stdin reads from `stdin`
+  pub fn new(stdin: &str) -> Self {
+    MemIORuntime {
+      stdin: Rc::new(RefCell::new(stdin.as_bytes().to_vec())),
+      stdout: Rc::new(RefCell::new(Vec::new())),
+      args: Vec::new(),
+      env: Vec::new(),
+      nondet: Rc::new(RefCell::new(Nondet::new(Clock::Fixed(0), Some(0)))),
+      host_fns: Rc::new(HostFns::new()),
+    }
+  }
+
+  /// Sets the arguments returned by `io.args`
+  pub fn with_args(mut self, args: Vec<String>) -> Self {
+    self.args = args;
+    self
+  }
+
+  /// Sets the environment variables read by `io.get_env`
+  pub fn with_env(mut self, env: Vec<(String, String)>) -> Self {
+    self.env = env;
+    self
+  }
+
+  /// Makes the given host functions available to `extern` declarations
+  pub fn with_host_fns(mut self, host_fns: HostFns) -> Self {
+    self.host_fns = Rc::new(host_fns);
+    self
+  }
+
+  /// A copy of the runtime with the input left to read, the same arguments,
+  /// environment and host functions, and nothing written to stdout
+  pub fn fork(&self) -> Self {
+    MemIORuntime {
+      stdin: Rc::new(RefCell::new(self.remaining_stdin())),
+      stdout: Rc::new(RefCell::new(Vec::new())),
+      nondet: Rc::new(RefCell::new(Nondet::new(Clock::Fixed(0), Some(0)))),
+      ..self.clone()
+    }
+  }
+
+  /// The bytes written to stdout so far
+  pub fn stdout(&self) -> Vec<u8> { self.stdout.borrow().clone() }
+
+  /// The bytes written to stdout so far, decoded as UTF-8
+  pub fn stdout_string(&self) -> String {
+    String::from_utf8_lossy(&self.stdout.borrow()).into_owned()
+  }
+
+  /// The scripted input that has not been read yet
+  pub fn remaining_stdin(&self) -> Vec<u8> { self.stdin.borrow().clone() }
+}
+
+impl RuntimeIO for MemIORuntime {
+  fn args(&self) -> Vec<String> { self.args.clone() }
+
+  fn nondet(&self) -> Rc<RefCell<Nondet>> { self.nondet.clone() }
+
+  fn host_fns(&self) -> Rc<HostFns> { self.host_fns.clone() }
+
+  fn get_env_op(&self) -> IoOp {
+    let env = self.env.clone();
+    env_op(move |name| {
+      env.iter().find(|(key, _)| key == name).map(|(_, val)| val.clone())
+    })
+  }
+
+  fn write_stdout_op(&self) -> IoOp {
+    fn fun_symbol() -> String { "write_stdout".to_string() }
+    fn fun_type_of() -> Term { yatima!("#Bytes -> #Bool") }
+    fn fun_arity() -> u64 { 1 }
+    let stdout = self.stdout.clone();
+    let fun_apply = Rc::new(move |args: &[Literal]| -> Option<Literal> {
+      match args {
+        [Literal::Bytes(bytes)] => {
+          stdout.borrow_mut().extend_from_slice(bytes);
+          Some(Literal::Bool(true))
+        }
+        _ => None,
+      }
+    });
+    let fun_apply_term = Rc::new(|_: &[Literal]| -> Option<Term> { None });
+    IoOp {
+      fun_symbol: Rc::new(fun_symbol),
+      fun_type_of: Rc::new(fun_type_of),
+      fun_arity: Rc::new(fun_arity),
+      fun_apply,
+      fun_apply_term,
+    }
+  }
+
+  fn read_stdin_op(&self) -> IoOp {
+    fn fun_symbol() -> String { "read_stdin".to_string() }
+    fn fun_type_of() -> Term { yatima!("#Bytes") }
+    fn fun_arity() -> u64 { 0 }
+    let stdin = self.stdin.clone();
+    let fun_apply = Rc::new(move |_: &[Literal]| -> Option<Literal> {
+      let mut stdin = stdin.borrow_mut();
+      let len =
+        stdin.iter().position(|b| *b == b'\n').map_or(stdin.len(), |i| i + 1);
+      Some(Literal::Bytes(stdin.drain(..len).collect()))
+    });
+    let fun_apply_term = Rc::new(|_: &[Literal]| -> Option<Term> { None });
+    IoOp {
+      fun_symbol: Rc::new(fun_symbol),
+      fun_type_of: Rc::new(fun_type_of),
+      fun_arity: Rc::new(fun_arity),
+      fun_apply,
+      fun_apply_term,
+    }
+  }
+}
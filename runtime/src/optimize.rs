@@ -0,0 +1,738 @@
+//! Simplifies the erased term `transform::transform` produces before it is
+//! run. The pass folds primitive operations on literals, reduces the redexes
+//! it can see, substitutes small or single-use values for the variables bound
+//! to them, and drops unused lets.
+//!
+//! `transform` has already replaced every reference by the body of its
+//! definition, so a small definition reaches this pass as a lambda bound by a
+//! redex or a `let`, and is inlined wherever it is used. Expressions that are
+//! not values are only substituted for a variable used once outside of any
+//! lambda, so the work, and the IO, they do is neither duplicated nor done
+//! when the program would not have done it.
+//!
+//! Lambdas and `let`s binding a parameter used at most once are left for the
+//! runtime, which marks a buffer they bind so that it can be updated in
+//! place. The runtime finds the parameters of a definition by the chain of
+//! lambdas sharing its position, so a chain the pass changes loses its
+//! position.
+//!
+//! Values are substituted through an environment while the body they are
+//! substituted into is simplified, so each body is simplified once rather
+//! than again after every substitution. The pass runs on an explicit stack of
+//! tasks rather than recursively, so deep terms cannot overflow the native
+//! stack.
+
+use std::mem;
+
+use yatima_core::{
+  defs::Defs,
+  literal::Literal,
+  name::Name,
+  position::{
+    Pos,
+    Position,
+  },
+  prim::{
+    int::IntOp,
+    nat::NatOp,
+    text::TextOp,
+    Op,
+  },
+  term::Term,
+  uses::Uses,
+};
+
+use crate::runtime::{
+  linear_params,
+  param_uses,
+};
+
+/// The size up to which a value used more than once is still substituted
+pub const INLINE_SIZE: usize = 24;
+
+/// How much work the pass may do: a reduction costs one unit, a substitution
+/// the size of the copies it makes and a folded operation the size of its
+/// result, in nodes or 64-bit words. Once it is spent, the term is left for
+/// the runtime to reduce, which bounds the pass on programs such as
+/// `(λ x => x x) (λ x => x x)` that do not terminate.
+pub const FUEL: usize = 10_000;
+
+/// Optimizes a term in place, given the definitions it was transformed with
+pub fn optimize(term: &mut Term, defs: &Defs) {
+  let old = mem::replace(term, Term::Typ(Pos::None));
+  *term = Optimizer::new(FUEL, linear_params(defs)).run(old);
+}
+
+/// A variable in scope of the term being simplified
+enum Bind {
+  /// A variable of the output, by its level
+  Var(u64),
+  /// A variable replaced by a simplified term of the output at the given
+  /// depth, with the number of occurrences of the variable left
+  Val(Term, u64, usize),
+  /// A variable that does not occur
+  Unused,
+}
+
+/// A step of the pass. Each task that simplifies a term leaves its result on
+/// the stack of results.
+enum Task {
+  /// Simplifies a term under the environment
+  Simplify(Term),
+  /// Binds a variable of the output
+  Enter,
+  /// Unbinds the last variable bound
+  Exit,
+  /// Pushes a term that is already simplified
+  Push(Term),
+  /// Binds the variable of a non-recursive `let` to the simplified
+  /// expression on top of the results, then simplifies its body
+  Bind {
+    pos: Pos,
+    uses: Uses,
+    nam: Name,
+    typ_bod: Box<(Term, Term)>,
+    occurs: Occurs,
+  },
+  /// Reduces the simplified head applied to the simplified arguments on top
+  /// of the results, with the positions of the applications, last first
+  Apply(Vec<Pos>),
+  /// Resumes a reduction of the simplified term on top of the results
+  /// applied to the arguments, last first
+  Reduce(Vec<(Pos, Term)>),
+  /// Ends a body simplified under an environment of its own, restoring the
+  /// previous one
+  Leave {
+    base: usize,
+    outer: u64,
+  },
+  /// Builds a node from its simplified children on top of the results. A
+  /// lambda records whether its body was a lambda of the same position.
+  Lam(Pos, Name, bool),
+  Slf(Pos, Name),
+  All(Pos, Uses, Name),
+  Let(Pos, bool, Uses, Name),
+}
+
+struct Optimizer {
+  fuel: usize,
+  /// The parameters of the definitions used at most once, as found by
+  /// `runtime::linear_params`
+  params: Vec<(Position, Vec<Uses>)>,
+  /// The variables in scope, innermost last
+  env: Vec<Bind>,
+  /// Where the variables of the term being simplified start in `env`. The
+  /// term's other free variables are variables of the output bound outside
+  /// of it.
+  base: usize,
+  /// The depth of the output at which the term being simplified starts
+  outer: u64,
+  /// The number of variables of the output in scope
+  depth: u64,
+}
+
+impl Optimizer {
+  fn new(fuel: usize, params: Vec<(Position, Vec<Uses>)>) -> Self {
+    Optimizer { fuel, params, env: vec![], base: 0, outer: 0, depth: 0 }
+  }
+
+  /// Whether a lambda at `pos` with body `bod` binds a parameter used at
+  /// most once
+  fn linear(&self, pos: Pos, bod: &Term) -> bool {
+    linear(param_uses(&self.params, pos, bod))
+  }
+
+  fn run(&mut self, term: Term) -> Term {
+    let mut work = vec![Task::Simplify(term)];
+    let mut results = vec![];
+    while let Some(task) = work.pop() {
+      match task {
+        Task::Simplify(term) => self.simplify(term, &mut work, &mut results),
+        Task::Enter => {
+          self.env.push(Bind::Var(self.depth));
+          self.depth += 1;
+        }
+        Task::Exit => {
+          if let Some(Bind::Var(_)) = self.env.pop() {
+            self.depth -= 1;
+          }
+        }
+        Task::Push(term) => results.push(term),
+        Task::Bind { pos, uses, nam, typ_bod, occurs } => {
+          let (typ, bod) = *typ_bod;
+          let exp = results.pop().unwrap();
+          if !linear(uses) && self.substitutes(&exp, occurs) {
+            self.env.push(Bind::Val(exp, self.depth, occurs.count));
+            work.push(Task::Exit);
+            work.push(Task::Simplify(bod));
+          }
+          else {
+            work.push(Task::Let(pos, false, uses, nam));
+            work.push(Task::Exit);
+            work.push(Task::Simplify(bod));
+            work.push(Task::Enter);
+            work.push(Task::Push(exp));
+            work.push(Task::Simplify(typ));
+          }
+        }
+        Task::Apply(poss) => {
+          let mut args = Vec::with_capacity(poss.len());
+          for pos in poss {
+            args.push((pos, results.pop().unwrap()));
+          }
+          let head = results.pop().unwrap();
+          self.reduce(head, args, &mut work, &mut results);
+        }
+        Task::Reduce(args) => {
+          let head = results.pop().unwrap();
+          self.reduce(head, args, &mut work, &mut results);
+        }
+        Task::Leave { base, outer } => {
+          self.env.truncate(self.base);
+          self.base = base;
+          self.outer = outer;
+        }
+        Task::Lam(pos, nam, was_chained) => {
+          let bod = results.pop().unwrap();
+          let pos =
+            if chained(pos, &bod) == was_chained { pos } else { Pos::None };
+          results.push(Term::Lam(pos, nam, Box::new(bod)));
+        }
+        Task::Slf(pos, nam) => {
+          let bod = results.pop().unwrap();
+          results.push(Term::Slf(pos, nam, Box::new(bod)));
+        }
+        Task::All(pos, uses, nam) => {
+          let img = results.pop().unwrap();
+          let dom = results.pop().unwrap();
+          results.push(Term::All(pos, uses, nam, Box::new((dom, img))));
+        }
+        Task::Let(pos, rec, uses, nam) => {
+          let bod = results.pop().unwrap();
+          let exp = results.pop().unwrap();
+          let typ = results.pop().unwrap();
+          let typ_exp_bod = Box::new((typ, exp, bod));
+          results.push(Term::Let(pos, rec, uses, nam, typ_exp_bod));
+        }
+      }
+    }
+    results.pop().unwrap()
+  }
+
+  /// Simplifies a term under the environment, pushing its result or the
+  /// tasks computing it
+  fn simplify(
+    &mut self,
+    term: Term,
+    work: &mut Vec<Task>,
+    results: &mut Vec<Term>,
+  ) {
+    match term {
+      Term::Var(pos, nam, idx) => results.push(self.lookup(pos, nam, idx)),
+      // Data, case and annotation nodes are erased, as in `runtime::from_term`
+      Term::Dat(_, bod) | Term::Cse(_, bod) => work.push(Task::Simplify(*bod)),
+      Term::Ann(_, typ_exp) => work.push(Task::Simplify(typ_exp.1)),
+      Term::Lam(pos, nam, bod) => {
+        work.push(Task::Lam(pos, nam, chained(pos, &bod)));
+        work.push(Task::Exit);
+        work.push(Task::Simplify(*bod));
+        work.push(Task::Enter);
+      }
+      Term::Slf(pos, nam, bod) => {
+        work.push(Task::Slf(pos, nam));
+        work.push(Task::Exit);
+        work.push(Task::Simplify(*bod));
+        work.push(Task::Enter);
+      }
+      Term::All(pos, uses, nam, dom_img) => {
+        let (dom, img) = *dom_img;
+        work.push(Task::All(pos, uses, nam));
+        work.push(Task::Exit);
+        work.push(Task::Simplify(img));
+        work.push(Task::Enter);
+        work.push(Task::Simplify(dom));
+      }
+      Term::App(..) => {
+        let mut poss = vec![];
+        let mut head = term;
+        let mut tasks = vec![];
+        while let Term::App(pos, fun_arg) = head {
+          let (fun, arg) = *fun_arg;
+          poss.push(pos);
+          tasks.push(Task::Simplify(arg));
+          head = fun;
+        }
+        work.push(Task::Apply(poss));
+        work.extend(tasks);
+        work.push(Task::Simplify(head));
+      }
+      Term::Let(pos, rec, uses, nam, typ_exp_bod) => {
+        let (typ, exp, bod) = *typ_exp_bod;
+        let occurs = occurrences(&bod, 0);
+        if occurs.count == 0 {
+          // The expression would never be evaluated
+          self.env.push(Bind::Unused);
+          work.push(Task::Exit);
+          work.push(Task::Simplify(bod));
+        }
+        else if rec {
+          work.push(Task::Let(pos, rec, uses, nam));
+          work.push(Task::Exit);
+          work.push(Task::Simplify(bod));
+          work.push(Task::Simplify(exp));
+          work.push(Task::Enter);
+          work.push(Task::Simplify(typ));
+        }
+        else {
+          let typ_bod = Box::new((typ, bod));
+          work.push(Task::Bind { pos, uses, nam, typ_bod, occurs });
+          work.push(Task::Simplify(exp));
+        }
+      }
+      Term::Opr(..) => self.reduce(term, vec![], work, results),
+      term => results.push(term),
+    }
+  }
+
+  /// The output for the variable of index `idx` of the term being simplified
+  fn lookup(&mut self, pos: Pos, nam: Name, idx: u64) -> Term {
+    let local = (self.env.len() - self.base) as u64;
+    if idx >= local {
+      return Term::Var(pos, nam, idx - local + self.depth - self.outer);
+    }
+    let depth = self.depth;
+    let len = self.env.len();
+    match &mut self.env[len - 1 - idx as usize] {
+      Bind::Var(level) => Term::Var(pos, nam, depth - 1 - *level),
+      Bind::Val(val, val_depth, left) => {
+        *left -= 1;
+        // The last occurrence takes the value rather than a copy
+        let mut val = if *left == 0 {
+          mem::replace(val, Term::Typ(Pos::None))
+        }
+        else {
+          val.clone()
+        };
+        shift(&mut val, (depth - *val_depth) as i64, 0);
+        val
+      }
+      Bind::Unused => unreachable!("unused variable {} occurs", nam),
+    }
+  }
+
+  /// Reduces a simplified head applied to simplified arguments, last first,
+  /// and pushes the result. Substituting an argument into the body of a
+  /// lambda leaves the tasks simplifying that body under an environment of
+  /// its own, and resuming the reduction on the result.
+  fn reduce(
+    &mut self,
+    head: Term,
+    mut args: Vec<(Pos, Term)>,
+    work: &mut Vec<Task>,
+    results: &mut Vec<Term>,
+  ) {
+    let mut head = head;
+    loop {
+      match head {
+        Term::Lam(lam_pos, nam, bod)
+          if !args.is_empty()
+            && self.fuel > 0
+            && !self.linear(lam_pos, &bod) =>
+        {
+          self.fuel -= 1;
+          let (pos, arg) = args.pop().unwrap();
+          let mut bod = *bod;
+          let occurs = occurrences(&bod, 0);
+          if occurs.count == 0 {
+            shift(&mut bod, -1, 0);
+            head = bod;
+          }
+          else if self.substitutes(&arg, occurs) {
+            work.push(Task::Reduce(args));
+            work.push(Task::Leave { base: self.base, outer: self.outer });
+            work.push(Task::Simplify(bod));
+            self.base = self.env.len();
+            self.outer = self.depth;
+            self.env.push(Bind::Val(arg, self.depth, occurs.count));
+            return;
+          }
+          else {
+            let typ = Term::Typ(pos);
+            let typ_exp_bod = Box::new((typ, arg, bod));
+            head = Term::Let(pos, false, Uses::Many, nam, typ_exp_bod);
+          }
+        }
+        // The arguments of a `let` apply to its body
+        Term::Let(pos, rec, uses, nam, typ_exp_bod) if !args.is_empty() => {
+          let (typ, exp, bod) = *typ_exp_bod;
+          for (_, arg) in &mut args {
+            shift(arg, 1, 0);
+          }
+          results.push(typ);
+          results.push(exp);
+          work.push(Task::Let(pos, rec, uses, nam));
+          work.push(Task::Exit);
+          self.env.push(Bind::Var(self.depth));
+          self.depth += 1;
+          head = bod;
+        }
+        Term::Opr(pos, opr) => match self.fold(&opr, &args) {
+          Some(res) => {
+            args.truncate(args.len() - opr.arity() as usize);
+            if let Term::Lit(..) = res {
+              head = res;
+            }
+            else {
+              // A term the operation builds, such as a list, can have
+              // redexes with the remaining arguments
+              work.push(Task::Reduce(args));
+              work.push(Task::Simplify(res));
+              return;
+            }
+          }
+          None => {
+            head = Term::Opr(pos, opr);
+            break;
+          }
+        },
+        _ => break,
+      }
+    }
+    let term = args
+      .into_iter()
+      .rev()
+      .fold(head, |fun, (pos, arg)| Term::App(pos, Box::new((fun, arg))));
+    results.push(term);
+  }
+
+  /// Whether to substitute `exp` for a variable that occurs as `occurs`
+  /// says, which is when that is cheap and does not duplicate work. The
+  /// copies it makes are charged to the fuel.
+  fn substitutes(&mut self, exp: &Term, occurs: Occurs) -> bool {
+    let cost = if is_value(exp) {
+      let size = exp.size() as usize;
+      if occurs.count == 1 || size <= INLINE_SIZE {
+        Some(occurs.count.saturating_mul(size))
+      }
+      else {
+        None
+      }
+    }
+    else if occurs.count == 1 && !occurs.under_lam {
+      Some(1)
+    }
+    else {
+      None
+    };
+    match cost {
+      Some(cost) if cost <= self.fuel => {
+        self.fuel -= cost;
+        true
+      }
+      _ => false,
+    }
+  }
+
+  /// Applies a pure primitive operation to its first arguments, the last of
+  /// `args`, if they are all literals and its result fits in the fuel
+  fn fold(&mut self, opr: &Op, args: &[(Pos, Term)]) -> Option<Term> {
+    // IO is done by the runtime, `#Text.abort` fails the program, and an
+    // operation whose result can outgrow its arguments could take longer
+    // than the program itself would
+    if self.fuel == 0 || unbounded(opr) {
+      return None;
+    }
+    let arity = opr.arity() as usize;
+    if args.len() < arity {
+      return None;
+    }
+    let mut lits = vec![];
+    for (_, arg) in args.iter().rev().take(arity) {
+      match arg {
+        Term::Lit(_, lit) => lits.push(lit.clone()),
+        _ => return None,
+      }
+    }
+    let (res, cost) = match opr.apply(&lits) {
+      Some(lit) => {
+        let cost = words(&lit);
+        (Term::Lit(Pos::None, lit), cost)
+      }
+      None => {
+        let term = opr.apply_term(&lits)?;
+        let cost = term.size() as usize;
+        (term, cost)
+      }
+    };
+    if cost > self.fuel {
+      return None;
+    }
+    self.fuel -= cost;
+    Some(res)
+  }
+}
+
+/// Whether a quantity is at most once
+fn linear(uses: Uses) -> bool { matches!(uses, Uses::Once | Uses::Affi) }
+
+/// Whether the body of a lambda at `pos` is a lambda at the same position,
+/// as the parameters of a definition are
+fn chained(pos: Pos, bod: &Term) -> bool {
+  matches!(bod, Term::Lam(bod_pos, ..) if *bod_pos == pos)
+}
+
+/// Whether `fold` must leave an operation for the runtime: IO, failure, and
+/// operations such as `#Nat.pow` whose result can be far larger than their
+/// arguments
+fn unbounded(opr: &Op) -> bool {
+  matches!(
+    opr,
+    Op::Io(_)
+      | Op::Extern(_)
+      | Op::Text(TextOp::Abort)
+      | Op::Text(TextOp::Replace)
+      | Op::Nat(NatOp::Pow)
+      | Op::Nat(NatOp::Shl)
+      | Op::Int(IntOp::Pow)
+      | Op::Int(IntOp::Shl)
+  )
+}
+
+/// The size of a literal in 64-bit words, at least one
+fn words(lit: &Literal) -> usize {
+  let bytes = match lit {
+    Literal::Nat(x) => (x.bits() / 8) as usize,
+    Literal::Int(x) => (x.bits() / 8) as usize,
+    Literal::Bits(x) => x.len() / 8,
+    Literal::Bytes(x) => x.len(),
+    Literal::Text(x) => x.len_bytes(),
+    _ => 0,
+  };
+  1 + bytes / 8
+}
+
+/// Whether a term does no work when evaluated, so that copies of it do not
+/// repeat any
+fn is_value(term: &Term) -> bool {
+  match term {
+    Term::Opr(_, opr) => opr.arity() > 0,
+    Term::Var(..)
+    | Term::Lam(..)
+    | Term::Lit(..)
+    | Term::Rec(..)
+    | Term::All(..)
+    | Term::Slf(..)
+    | Term::Typ(..)
+    | Term::LTy(..) => true,
+    _ => false,
+  }
+}
+
+/// How a bound variable occurs in a term
+#[derive(Clone, Copy, Default)]
+struct Occurs {
+  count: usize,
+  /// Whether some occurrence is under a lambda, or in a type or a recursive
+  /// expression, where it may be evaluated more than once
+  under_lam: bool,
+}
+
+/// How the variable of index `idx` occurs in a term
+fn occurrences(term: &Term, idx: u64) -> Occurs {
+  let mut occurs = Occurs::default();
+  let mut todo = vec![(term, idx, false)];
+  while let Some((term, idx, under_lam)) = todo.pop() {
+    match term {
+      Term::Var(_, _, var) => {
+        if *var == idx {
+          occurs.count += 1;
+          occurs.under_lam |= under_lam;
+        }
+      }
+      Term::Lam(_, _, bod) | Term::Slf(_, _, bod) => {
+        todo.push((&**bod, idx + 1, true))
+      }
+      Term::Dat(_, bod) | Term::Cse(_, bod) => {
+        todo.push((&**bod, idx, under_lam))
+      }
+      Term::App(_, fun_arg) => {
+        todo.push((&fun_arg.0, idx, under_lam));
+        todo.push((&fun_arg.1, idx, under_lam));
+      }
+      Term::Ann(_, typ_exp) => {
+        todo.push((&typ_exp.0, idx, true));
+        todo.push((&typ_exp.1, idx, under_lam));
+      }
+      Term::All(_, _, _, dom_img) => {
+        todo.push((&dom_img.0, idx, true));
+        todo.push((&dom_img.1, idx + 1, true));
+      }
+      Term::Let(_, rec, _, _, typ_exp_bod) => {
+        let (typ, exp, bod) = &**typ_exp_bod;
+        todo.push((typ, idx, true));
+        if *rec {
+          todo.push((exp, idx + 1, true));
+        }
+        else {
+          todo.push((exp, idx, under_lam));
+        }
+        todo.push((bod, idx + 1, under_lam));
+      }
+      _ => {}
+    }
+  }
+  occurs
+}
+
+/// Adds `inc` to the indices of the variables of a term bound more than
+/// `dep` binders above it
+fn shift(term: &mut Term, inc: i64, dep: u64) {
+  if inc == 0 {
+    return;
+  }
+  let mut todo = vec![(term, dep)];
+  while let Some((term, dep)) = todo.pop() {
+    match term {
+      Term::Var(_, _, idx) => {
+        if *idx >= dep {
+          *idx = (*idx as i64 + inc) as u64;
+        }
+      }
+      Term::Lam(_, _, bod) | Term::Slf(_, _, bod) => {
+        todo.push((&mut **bod, dep + 1))
+      }
+      Term::Dat(_, bod) | Term::Cse(_, bod) => todo.push((&mut **bod, dep)),
+      Term::App(_, two) | Term::Ann(_, two) => {
+        let (fst, snd) = &mut **two;
+        todo.push((fst, dep));
+        todo.push((snd, dep));
+      }
+      Term::All(_, _, _, dom_img) => {
+        let (dom, img) = &mut **dom_img;
+        todo.push((dom, dep));
+        todo.push((img, dep + 1));
+      }
+      Term::Let(_, rec, _, _, typ_exp_bod) => {
+        let (typ, exp, bod) = &mut **typ_exp_bod;
+        todo.push((typ, dep));
+        todo.push((exp, if *rec { dep + 1 } else { dep }));
+        todo.push((bod, dep + 1));
+      }
+      _ => {}
+    }
+  }
+}
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+  use yatima_core::{
+    defs,
+    parse,
+    yatima,
+  };
+
+  fn optimized(term: Term) -> Term {
+    let mut term = term;
+    optimize(&mut term, &Defs::new());
+    term
+  }
+
+  #[test]
+  fn test_fold() {
+    assert_eq!(optimized(yatima!("#Nat.add 1 (#Nat.mul 2 3)")), yatima!("7"));
+    assert_eq!(
+      optimized(yatima!("λ x => #Nat.add x (#Nat.mul 2 3)")),
+      yatima!("λ x => #Nat.add x 6")
+    );
+    // Operations that fail are left for the runtime
+    assert_eq!(
+      optimized(yatima!("#Text.abort #Text \"stop\"")),
+      yatima!("#Text.abort #Text \"stop\"")
+    );
+    assert_eq!(optimized(yatima!("#Nat.sub 1 2")), yatima!("#Nat.sub 1 2"));
+    // As are those whose result can be far larger than their arguments, even
+    // when they are never evaluated
+    assert_eq!(
+      optimized(yatima!("#Nat.pow 10 3u32")),
+      yatima!("#Nat.pow 10 3u32")
+    );
+    assert_eq!(
+      optimized(yatima!("(λ x => 0) (#Nat.pow 10 4294967295u32)")),
+      yatima!("0")
+    );
+    // Each fold is charged to the fuel
+    assert_eq!(
+      Optimizer::new(1, vec![]).run(yatima!("#Nat.add 1 (#Nat.mul 2 3)")),
+      yatima!("#Nat.add 1 6")
+    );
+  }
+
+  #[test]
+  fn test_inline() {
+    assert_eq!(
+      optimized(yatima!(
+        "(λ double => double (double 5)) (λ x => #Nat.add x x)"
+      )),
+      yatima!("20")
+    );
+    assert_eq!(
+      optimized(yatima!("let inc: Type = λ x => #Nat.add x 1; λ y => inc y")),
+      yatima!("λ y => #Nat.add y 1")
+    );
+  }
+
+  #[test]
+  fn test_sharing() {
+    // A shared argument that does work stays bound
+    assert_eq!(
+      optimized(yatima!("λ y => (λ x => #Nat.add x x) (#Nat.mul y 4)")),
+      yatima!("λ y => let x: Type = #Nat.mul y 4; #Nat.add x x")
+    );
+    // And is not moved under a lambda
+    assert_eq!(
+      optimized(yatima!("λ y => (λ x f => f x) (#Nat.mul y 4)")),
+      yatima!("λ y => let x: Type = #Nat.mul y 4; λ f => f x")
+    );
+    // An unused one is dropped, as it would never be evaluated
+    assert_eq!(
+      optimized(yatima!("λ y => let x: Type = #Nat.mul y 4; y")),
+      yatima!("λ y => y")
+    );
+  }
+
+  #[test]
+  fn test_fuel() {
+    assert_eq!(
+      optimized(yatima!("(λ x => x x) (λ x => x x)")),
+      yatima!("let x: Type = λ x => x x; x x")
+    );
+  }
+
+  #[test]
+  fn test_deep() {
+    // Additions nested deeper than the pass could recurse over. The innermost
+    // ones are folded until the fuel runs out.
+    let depth = 100_000;
+    let add = || Term::Opr(Pos::None, Op::Nat(NatOp::Add));
+    let one = || Term::Lit(Pos::None, Literal::Nat(1u64.into()));
+    let mut term = Term::Lit(Pos::None, Literal::Nat(0u64.into()));
+    for _ in 0..depth {
+      let fun = Term::App(Pos::None, Box::new((add(), term)));
+      term = Term::App(Pos::None, Box::new((fun, one())));
+    }
+    let mut term = optimized(term);
+    // Unwinds the result in a loop, as dropping it would recurse
+    let mut left = 0;
+    while let Term::App(_, fun_arg) = term {
+      let (fun, _) = *fun_arg;
+      match fun {
+        Term::App(_, opr_arg) => term = opr_arg.1,
+        _ => panic!("expected an addition"),
+      }
+      left += 1;
+    }
+    assert_eq!(left, depth - FUEL);
+    assert_eq!(term, Term::Lit(Pos::None, Literal::Nat(FUEL.into())));
+  }
+}
@@ -0,0 +1,230 @@
+//! A profiler for graph reduction. While a profile is active, `from_term`
+//! labels the lambdas, fixed points and primitives of the DAG with the
+//! definition they come from, and `runtime::whnf`, when called with
+//! `should_count`, charges each beta reduction, primitive application and
+//! unfolding of a recursive definition to the label of the node reduced.
+//!
+//! `transform::transform` inlines references, so a node is attributed to the
+//! innermost definition whose source span contains its position. Nodes
+//! without one, such as the implementations of externs, inherit the
+//! definition of the term around them, and recursive definitions are found by
+//! the name of the `let` binding them.
+
+use std::{
+  cell::RefCell,
+  collections::BTreeMap,
+};
+
+use yatima_core::{
+  defs::Defs,
+  name::Name,
+  position::{
+    Pos,
+    Position,
+  },
+};
+
+/// The index of a definition in a `Profile`. Origin 0 is the program itself.
+pub type Origin = usize;
+
+/// The kinds of reduction steps a profile counts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+  /// A lambda applied to an argument
+  Beta,
+  /// A primitive operation applied to literals
+  Prim,
+  /// A recursive definition unfolded
+  Unfold,
+}
+
+/// The steps attributed to a definition
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+  pub beta: u64,
+  pub prim: u64,
+  pub unfold: u64,
+}
+
+impl Counts {
+  pub fn total(&self) -> u64 { self.beta + self.prim + self.unfold }
+}
+
+/// The reduction steps of a program, by definition
+#[derive(Clone, Debug)]
+pub struct Profile {
+  names: Vec<String>,
+  spans: Vec<(Position, Origin)>,
+  counts: Vec<Counts>,
+  /// The steps taken under each stack of definitions, outermost first
+  stacks: BTreeMap<Vec<Origin>, u64>,
+}
+
+impl Profile {
+  /// An empty profile of the program `name`, whose steps are attributed to
+  /// the definitions of `defs`. The definition named `name`, if any, is the
+  /// program itself.
+  pub fn new(defs: &Defs, name: &str) -> Self {
+    let mut names = vec![name.to_owned()];
+    let mut spans = vec![];
+    for (nam, cid) in &defs.names {
+      if let Some(def) = defs.defs.get(cid) {
+        let nam = nam.to_string();
+        let origin = if nam == name { 0 } else { names.len() };
+        if let Pos::Some(pos) = def.pos {
+          spans.push((pos, origin));
+        }
+        if origin != 0 {
+          names.push(nam);
+        }
+      }
+    }
+    let counts = vec![Counts::default(); names.len()];
+    Profile { names, spans, counts, stacks: BTreeMap::new() }
+  }
+
+  /// The definition whose source contains the position, or `outer`
+  pub fn origin(&self, pos: Pos, outer: Origin) -> Origin {
+    let pos = match pos {
+      Pos::Some(pos) => pos,
+      Pos::None => return outer,
+    };
+    let width = |span: &Position| span.upto_offset - span.from_offset;
+    self
+      .spans
+      .iter()
+      .filter(|(span, _)| {
+        span.input == pos.input
+          && span.from_offset <= pos.from_offset
+          && pos.upto_offset <= span.upto_offset
+      })
+      .min_by_key(|(span, _)| width(span))
+      .map_or(outer, |(_, origin)| *origin)
+  }
+
+  /// The definition of the given name, or `outer`
+  pub fn named(&self, name: &Name, outer: Origin) -> Origin {
+    let name = name.to_string();
+    self.names.iter().position(|n| *n == name).unwrap_or(outer)
+  }
+
+  /// Charges a step to the last definition of a stack
+  pub fn record(&mut self, step: Step, stack: &[Origin]) {
+    let origin = match stack.last() {
+      Some(origin) => *origin,
+      None => return,
+    };
+    let counts = &mut self.counts[origin];
+    match step {
+      Step::Beta => counts.beta += 1,
+      Step::Prim => counts.prim += 1,
+      Step::Unfold => counts.unfold += 1,
+    }
+    let mut path: Vec<Origin> = vec![0];
+    for origin in stack {
+      if path.last() != Some(origin) {
+        path.push(*origin);
+      }
+    }
+    *self.stacks.entry(path).or_insert(0) += 1;
+  }
+
+  /// The name of the definition of an origin
+  pub fn name(&self, origin: Origin) -> Option<&str> {
+    self.names.get(origin).map(String::as_str)
+  }
+
+  /// The steps attributed to the definition of the given name
+  pub fn counts(&self, name: &str) -> Option<Counts> {
+    let origin = self.names.iter().position(|n| n == name)?;
+    Some(self.counts[origin])
+  }
+
+  /// A table of the definitions that took steps, the busiest first
+  pub fn table(&self) -> String {
+    let mut rows: Vec<(&String, &Counts)> = self
+      .names
+      .iter()
+      .zip(&self.counts)
+      .filter(|(_, counts)| counts.total() > 0)
+      .collect();
+    rows.sort_by(|(na, a), (nb, b)| b.total().cmp(&a.total()).then(na.cmp(nb)));
+    let width =
+      rows.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+    let width = width.max("definition".len());
+    let mut res = format!(
+      "{:<width$} {:>10} {:>10} {:>10} {:>10}\n",
+      "definition",
+      "beta",
+      "prim",
+      "unfold",
+      "total",
+      width = width
+    );
+    for (name, counts) in rows {
+      res.push_str(&format!(
+        "{:<width$} {:>10} {:>10} {:>10} {:>10}\n",
+        name,
+        counts.beta,
+        counts.prim,
+        counts.unfold,
+        counts.total(),
+        width = width
+      ));
+    }
+    res
+  }
+
+  /// The steps by stack of definitions, one `outer;inner count` line each, as
+  /// read by flamegraph tools
+  pub fn folded(&self) -> String {
+    let mut lines: Vec<String> = self
+      .stacks
+      .iter()
+      .map(|(path, count)| {
+        let names: Vec<&str> =
+          path.iter().map(|origin| self.names[*origin].as_str()).collect();
+        format!("{} {}", names.join(";"), count)
+      })
+      .collect();
+    lines.sort();
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+  }
+}
+
+thread_local! {
+  static PROFILE: RefCell<Option<Profile>> = RefCell::new(None);
+}
+
+/// Starts profiling the evaluations of this thread
+pub fn start(profile: Profile) {
+  PROFILE.with(|p| *p.borrow_mut() = Some(profile));
+}
+
+/// Stops profiling, returning the profile
+pub fn stop() -> Option<Profile> { PROFILE.with(|p| p.borrow_mut().take()) }
+
+/// The origin of a node at the given position while profiling, or `outer`
+pub fn origin(pos: Pos, outer: Origin) -> Origin {
+  PROFILE.with(|p| p.borrow().as_ref().map_or(outer, |p| p.origin(pos, outer)))
+}
+
+/// The origin of the definition of the given name while profiling, or
+/// `outer`
+pub fn named(name: &Name, outer: Origin) -> Origin {
+  PROFILE.with(|p| p.borrow().as_ref().map_or(outer, |p| p.named(name, outer)))
+}
+
+/// The name of the definition of an origin while profiling
+pub fn name(origin: Origin) -> Option<String> {
+  PROFILE.with(|p| p.borrow().as_ref()?.name(origin).map(str::to_owned))
+}
+
+/// Records a step in the active profile, if any
+pub fn record(step: Step, stack: &[Origin]) {
+  PROFILE.with(|p| {
+    if let Some(p) = p.borrow_mut().as_mut() {
+      p.record(step, stack)
+    }
+  })
+}
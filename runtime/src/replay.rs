@@ -0,0 +1,388 @@
+use std::{
+  cell::RefCell,
+  collections::{
+    hash_map::RandomState,
+    VecDeque,
+  },
+  fs,
+  hash::{
+    BuildHasher,
+    Hasher,
+  },
+  io::{
+    self,
+    Write,
+  },
+  path::Path,
+  rc::Rc,
+  time::{
+    SystemTime,
+    UNIX_EPOCH,
+  },
+};
+
+use crate::error::RuntimeError;
+
+use yatima_core::{
+  defs,
+  literal::Literal,
+  parse,
+  prim::io::IoOp,
+  term::Term,
+  yatima,
+};
+
+/// Where `io.now` reads the time from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Clock {
+  System,
+  /// Always returns the given number of milliseconds since the Unix epoch
+  Fixed(u64),
+}
+
+/// The SplitMix64 generator behind `io.random_bytes`. It is not suitable for
+/// cryptography, only for reproducible simulations.
+#[derive(Clone, Debug)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+  pub fn new(seed: u64) -> Self { SplitMix64(seed) }
+
+  /// Seeds a generator from the process's hash randomness and the time
+  pub fn from_entropy() -> Self {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(system_millis());
+    SplitMix64(hasher.finish())
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = self.0;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+  }
+
+  pub fn fill(&mut self, buf: &mut [u8]) {
+    for chunk in buf.chunks_mut(8) {
+      let bytes = self.next_u64().to_le_bytes();
+      chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+  }
+}
+
+/// A nondeterministic input observed by a program, as stored in a log
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+  Now(u64),
+  RandomBytes(Vec<u8>),
+}
+
+impl Event {
+  /// Formats the event as a line of a log file
+  pub fn to_line(&self) -> String {
+    match self {
+      Self::Now(millis) => format!("now {}", millis),
+      Self::RandomBytes(bytes) => {
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("random_bytes {}", hex)
+      }
+    }
+  }
+
+  /// Parses a line of a log file
+  pub fn from_line(line: &str) -> Result<Self, String> {
+    let err = || format!("Invalid replay log entry: {}", line);
+    let mut words = line.split_whitespace();
+    match (words.next(), words.next().unwrap_or(""), words.next()) {
+      (Some("now"), millis, None) => {
+        millis.parse().map(Self::Now).map_err(|_| err())
+      }
+      (Some("random_bytes"), hex, None) if hex.len() % 2 == 0 => (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| err()))
+        .collect::<Result<Vec<u8>, String>>()
+        .map(Self::RandomBytes),
+      _ => Err(err()),
+    }
+  }
+}
+
+/// What happens to the events of a run
+pub enum Log {
+  Off,
+  /// Each event is appended to the file as soon as it happens
+  Record(fs::File),
+  /// Events are read back in order instead of consulting the clock and
+  /// generator
+  Replay(VecDeque<Event>),
+}
+
+/// The nondeterministic inputs of a run: the clock, the random number
+/// generator, and a log that can record them or replay a previous run
+pub struct Nondet {
+  clock: Clock,
+  rng: SplitMix64,
+  log: Log,
+  error: Option<RuntimeError>,
+}
+
+impl Nondet {
+  /// Reads from the given clock and, if a seed is given, from a seeded
+  /// generator instead of a freshly seeded one
+  pub fn new(clock: Clock, seed: Option<u64>) -> Self {
+    let rng = match seed {
+      Some(seed) => SplitMix64::new(seed),
+      None => SplitMix64::from_entropy(),
+    };
+    Nondet { clock, rng, log: Log::Off, error: None }
+  }
+
+  /// Records every event to a log file, replacing its contents
+  pub fn record(mut self, path: &Path) -> io::Result<Self> {
+    self.log = Log::Record(fs::File::create(path)?);
+    Ok(self)
+  }
+
+  /// Replays the events of a log file
+  pub fn replay(mut self, path: &Path) -> io::Result<Self> {
+    let events = fs::read_to_string(path)?
+      .lines()
+      .filter(|line| !line.trim().is_empty())
+      .map(Event::from_line)
+      .collect::<Result<VecDeque<Event>, String>>()
+      .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    self.log = Log::Replay(events);
+    Ok(self)
+  }
+
+  /// Milliseconds since the Unix epoch
+  pub fn now(&mut self) -> Result<u64, RuntimeError> {
+    match self.replayed()? {
+      Some(Event::Now(millis)) => Ok(millis),
+      Some(event) => Err(diverged("now", &event)),
+      None => {
+        let millis = match self.clock {
+          Clock::System => system_millis(),
+          Clock::Fixed(millis) => millis,
+        };
+        self.recorded(Event::Now(millis))?;
+        Ok(millis)
+      }
+    }
+  }
+
+  pub fn random_bytes(&mut self, len: usize) -> Result<Vec<u8>, RuntimeError> {
+    match self.replayed()? {
+      Some(Event::RandomBytes(bytes)) if bytes.len() == len => Ok(bytes),
+      Some(event) => Err(diverged("random_bytes", &event)),
+      None => {
+        let mut bytes = vec![0; len];
+        self.rng.fill(&mut bytes);
+        self.recorded(Event::RandomBytes(bytes.clone()))?;
+        Ok(bytes)
+      }
+    }
+  }
+
+  /// Takes the error that stopped a primitive, if any. Primitives cannot fail
+  /// the run themselves, so they keep the first error here and are left
+  /// unreduced.
+  pub fn take_error(&mut self) -> Option<RuntimeError> { self.error.take() }
+
+  /// Keeps the error of a primitive for `take_error`
+  fn failed<A>(&mut self, res: Result<A, RuntimeError>) -> Option<A> {
+    match res {
+      Ok(x) => Some(x),
+      Err(e) => {
+        self.error.get_or_insert(e);
+        None
+      }
+    }
+  }
+
+  /// Takes the next event when replaying
+  fn replayed(&mut self) -> Result<Option<Event>, RuntimeError> {
+    match &mut self.log {
+      Log::Replay(events) => match events.pop_front() {
+        Some(event) => Ok(Some(event)),
+        None => Err(RuntimeError::ReplayEnded),
+      },
+      _ => Ok(None),
+    }
+  }
+
+  fn recorded(&mut self, event: Event) -> Result<(), RuntimeError> {
+    if let Log::Record(file) = &mut self.log {
+      writeln!(file, "{}", event.to_line())
+        .and_then(|_| file.flush())
+        .map_err(|e| RuntimeError::ReplayWrite(e.to_string()))?;
+    }
+    Ok(())
+  }
+}
+
+impl Default for Nondet {
+  fn default() -> Self { Nondet::new(Clock::System, None) }
+}
+
+fn diverged(expected: &str, found: &Event) -> RuntimeError {
+  RuntimeError::ReplayDiverged(expected.to_owned(), found.to_line())
+}
+
+fn system_millis() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// `io.now`: the current time in milliseconds since the Unix epoch
+pub fn now_op(nondet: Rc<RefCell<Nondet>>) -> IoOp {
+  fn fun_symbol() -> String { "now".to_string() }
+  fn fun_type_of() -> Term { yatima!("#U64") }
+  fn fun_arity() -> u64 { 0 }
+  let fun_apply = Rc::new(move |_: &[Literal]| -> Option<Literal> {
+    let mut nondet = nondet.borrow_mut();
+    let res = nondet.now();
+    nondet.failed(res).map(Literal::U64)
+  });
+  let fun_apply_term = Rc::new(|_: &[Literal]| -> Option<Term> { None });
+  IoOp {
+    fun_symbol: Rc::new(fun_symbol),
+    fun_type_of: Rc::new(fun_type_of),
+    fun_arity: Rc::new(fun_arity),
+    fun_apply,
+    fun_apply_term,
+  }
+}
+
+/// `io.random_bytes`: the given number of random bytes
+pub fn random_bytes_op(nondet: Rc<RefCell<Nondet>>) -> IoOp {
+  fn fun_symbol() -> String { "random_bytes".to_string() }
+  fn fun_type_of() -> Term { yatima!("∀ #Nat -> #Bytes") }
+  fn fun_arity() -> u64 { 1 }
+  let fun_apply = Rc::new(move |args: &[Literal]| -> Option<Literal> {
+    use std::convert::TryFrom;
+    match args {
+      [Literal::Nat(len)] => {
+        let len = usize::try_from(len).ok()?;
+        let mut nondet = nondet.borrow_mut();
+        let res = nondet.random_bytes(len);
+        nondet.failed(res).map(Literal::Bytes)
+      }
+      _ => None,
+    }
+  });
+  let fun_apply_term = Rc::new(|_: &[Literal]| -> Option<Term> { None });
+  IoOp {
+    fun_symbol: Rc::new(fun_symbol),
+    fun_type_of: Rc::new(fun_type_of),
+    fun_arity: Rc::new(fun_arity),
+    fun_apply,
+    fun_apply_term,
+  }
+}
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+
+  fn temp_log(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!(
+      "yatima-replay-{}-{}.log",
+      std::process::id(),
+      name
+    ))
+  }
+
+  #[test]
+  fn test_seeded_is_deterministic() {
+    let mut a = Nondet::new(Clock::Fixed(42), Some(7));
+    let mut b = Nondet::new(Clock::Fixed(42), Some(7));
+    assert_eq!(a.now(), Ok(42));
+    assert_eq!(a.random_bytes(13), b.random_bytes(13));
+    assert_eq!(a.random_bytes(3), b.random_bytes(3));
+    assert_ne!(
+      Nondet::new(Clock::System, Some(1)).random_bytes(16),
+      Nondet::new(Clock::System, Some(2)).random_bytes(16)
+    );
+  }
+
+  #[test]
+  fn test_event_lines() {
+    for event in vec![
+      Event::Now(1_600_000_000_000),
+      Event::RandomBytes(vec![0, 1, 0xab, 0xff]),
+      Event::RandomBytes(vec![]),
+    ] {
+      assert_eq!(Event::from_line(&event.to_line()), Ok(event));
+    }
+    assert!(Event::from_line("now soon").is_err());
+    assert!(Event::from_line("random_bytes abc").is_err());
+    assert!(Event::from_line("coin_flip").is_err());
+  }
+
+  #[test]
+  fn test_record_replay() {
+    let path = temp_log("record");
+    let mut recording = Nondet::default().record(&path).unwrap();
+    let time = recording.now().unwrap();
+    let bytes = recording.random_bytes(32).unwrap();
+    drop(recording);
+    let mut replaying =
+      Nondet::new(Clock::Fixed(0), Some(0)).replay(&path).unwrap();
+    assert_eq!(replaying.now(), Ok(time));
+    assert_eq!(replaying.random_bytes(32), Ok(bytes));
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn test_replay_divergence() {
+    let path = temp_log("diverge");
+    fs::write(&path, "now 5\nrandom_bytes 00ff\n").unwrap();
+    let mut replaying = Nondet::default().replay(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(
+      replaying.random_bytes(4),
+      Err(RuntimeError::ReplayDiverged(
+        "random_bytes".to_owned(),
+        "now 5".to_owned()
+      ))
+    );
+    // The length of the bytes must match too
+    assert_eq!(
+      replaying.random_bytes(4),
+      Err(RuntimeError::ReplayDiverged(
+        "random_bytes".to_owned(),
+        "random_bytes 00ff".to_owned()
+      ))
+    );
+  }
+
+  #[test]
+  fn test_replay_ended() {
+    let path = temp_log("ended");
+    fs::write(&path, "now 5\n").unwrap();
+    let nondet = Nondet::default().replay(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let nondet = Rc::new(RefCell::new(nondet));
+    let now = now_op(nondet.clone());
+    assert_eq!(now.apply(&[]), Some(Literal::U64(5)));
+    // The primitive is left unreduced and keeps the error for the runtime
+    assert_eq!(now.apply(&[]), None);
+    let error = nondet.borrow_mut().take_error();
+    assert_eq!(error, Some(RuntimeError::ReplayEnded));
+    assert_eq!(nondet.borrow_mut().take_error(), None);
+  }
+
+  #[test]
+  fn test_record_write_error() {
+    let path = temp_log("readonly");
+    fs::write(&path, "").unwrap();
+    let mut recording = Nondet::default();
+    recording.log = Log::Record(fs::File::open(&path).unwrap());
+    let res = recording.now();
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(res, Err(RuntimeError::ReplayWrite(_))));
+  }
+}
@@ -1,14 +1,40 @@
 use yatima_core::{
   defs::Defs,
   dll::*,
+  limits::Meter,
   literal::Literal,
-  prim::Op,
+  position::{
+    Pos,
+    Position,
+  },
+  prim::{
+    Op,
+    text::TextOp,
+  },
   term::Term,
+  uses::Uses,
+};
+
+use crate::{
+  debug::Debugger,
+  error::RuntimeError,
+  profile::{
+    self,
+    Origin,
+    Step,
+  },
+  transform::{
+    is_exit_op,
+    is_seq_op,
+  },
+  Strategy,
 };
 
 use std::{
   boxed::Box,
+  cell::Cell,
   collections::BTreeSet,
+  convert::TryFrom,
   fmt,
   mem,
   ptr::NonNull,
@@ -56,6 +82,10 @@ pub struct Lam {
   pub bod_ref: Parents,
   pub var: Var,
   pub parents: Option<NonNull<Parents>>,
+  /// The definition the lambda comes from, see `profile`
+  pub origin: Origin,
+  /// The quantity of the variable in the type of the definition
+  pub uses: Uses,
 }
 
 #[derive(Debug)]
@@ -74,18 +104,24 @@ pub struct Fix {
   pub bod_ref: Parents,
   pub var: Var,
   pub parents: Option<NonNull<Parents>>,
+  pub origin: Origin,
 }
 
 #[derive(Debug)]
 pub struct Lit {
   pub lit: Literal,
   pub parents: Option<NonNull<Parents>>,
+  /// The quantity the literal is bound with, which lets a primitive update it
+  /// in place when it is `Once` or `Affi`. A primitive that takes the literal
+  /// out sets it to `None`, and nothing may read the literal after that.
+  pub uses: Uses,
 }
 
 #[derive(Debug)]
 pub struct Opr {
   pub opr: Op,
   pub parents: Option<NonNull<Parents>>,
+  pub origin: Origin,
 }
 
 impl fmt::Debug for DAG {
@@ -279,49 +315,52 @@ pub fn add_to_parents(node: DAG, plink: NonNull<Parents>) {
   }
 }
 
-// Free parentless nodes.
+// Free parentless nodes, using a work list rather than recursion.
 pub fn free_dead_node(node: DAG) {
-  unsafe {
-    match node {
-      DAG::Lam(link) => {
-        let Lam { bod, bod_ref, .. } = &link.as_ref();
-        let new_bod_parents = bod_ref.unlink_node();
-        set_parents(*bod, new_bod_parents);
-        if new_bod_parents.is_none() {
-          free_dead_node(*bod)
+  let mut dead = vec![node];
+  while let Some(node) = dead.pop() {
+    unsafe {
+      match node {
+        DAG::Lam(link) => {
+          let Lam { bod, bod_ref, .. } = &link.as_ref();
+          let new_bod_parents = bod_ref.unlink_node();
+          set_parents(*bod, new_bod_parents);
+          if new_bod_parents.is_none() {
+            dead.push(*bod)
+          }
+          free_val(link);
         }
-        Box::from_raw(link.as_ptr());
-      }
-      DAG::Fix(mut link) => {
-        let Fix { bod, bod_ref, .. } = &link.as_mut();
-        let new_bod_parents = bod_ref.unlink_node();
-        set_parents(*bod, new_bod_parents);
-        if new_bod_parents.is_none() {
-          free_dead_node(*bod)
+        DAG::Fix(mut link) => {
+          let Fix { bod, bod_ref, .. } = &link.as_mut();
+          let new_bod_parents = bod_ref.unlink_node();
+          set_parents(*bod, new_bod_parents);
+          if new_bod_parents.is_none() {
+            dead.push(*bod)
+          }
+          free_val(link);
         }
-        Box::from_raw(link.as_ptr());
-      }
-      DAG::App(link) => {
-        let App { fun, arg, fun_ref, arg_ref, .. } = link.as_ref();
-        let new_fun_parents = fun_ref.unlink_node();
-        set_parents(*fun, new_fun_parents);
-        if new_fun_parents.is_none() {
-          free_dead_node(*fun)
+        DAG::App(link) => {
+          let App { fun, arg, fun_ref, arg_ref, .. } = link.as_ref();
+          let new_fun_parents = fun_ref.unlink_node();
+          set_parents(*fun, new_fun_parents);
+          if new_fun_parents.is_none() {
+            dead.push(*fun)
+          }
+          let new_arg_parents = arg_ref.unlink_node();
+          set_parents(*arg, new_arg_parents);
+          if new_arg_parents.is_none() {
+            dead.push(*arg)
+          }
+          free_val(link);
         }
-        let new_arg_parents = arg_ref.unlink_node();
-        set_parents(*arg, new_arg_parents);
-        if new_arg_parents.is_none() {
-          free_dead_node(*arg)
+        DAG::Lit(link) => {
+          free_val(link);
         }
-        Box::from_raw(link.as_ptr());
-      }
-      DAG::Lit(link) => {
-        Box::from_raw(link.as_ptr());
-      }
-      DAG::Opr(link) => {
-        Box::from_raw(link.as_ptr());
+        DAG::Opr(link) => {
+          free_val(link);
+        }
+        DAG::Var(_) => (),
       }
-      DAG::Var(_) => (),
     }
   }
 }
@@ -363,20 +402,55 @@ pub fn clean_up(cc: &ParentPtr) {
   }
 }
 
+thread_local! {
+  static ALLOCATED: Cell<usize> = Cell::new(0);
+  static FREED: Cell<usize> = Cell::new(0);
+  static UPDATED: Cell<usize> = Cell::new(0);
+}
+
+/// The number of runtime DAG allocations so far on this thread, for metering
+pub fn allocated() -> usize { ALLOCATED.with(|n| n.get()) }
+
+/// The number of runtime DAG nodes freed so far on this thread, for metering
+pub fn freed() -> usize { FREED.with(|n| n.get()) }
+
+/// The number of primitive applications so far on this thread that updated a
+/// `#Bytes` or `#Text` argument in place rather than a copy of it
+#[cfg(test)]
+pub fn updated() -> usize { UPDATED.with(|n| n.get()) }
+
 /// Auxiliary allocation functions
 #[inline]
 pub fn alloc_val<T>(val: T) -> NonNull<T> {
+  ALLOCATED.with(|n| n.set(n.get() + 1));
   NonNull::new(Box::leak(Box::new(val))).unwrap()
 }
 
+/// Frees a value allocated by `alloc_val`, returning it
+///
+/// # Safety
+/// The value must not be used through `link` again
+#[inline]
+pub unsafe fn free_val<T>(link: NonNull<T>) -> T {
+  FREED.with(|n| n.set(n.get() + 1));
+  *Box::from_raw(link.as_ptr())
+}
+
 #[inline]
-pub fn alloc_lam(bod: DAG, parents: Option<NonNull<Parents>>) -> NonNull<Lam> {
+pub fn alloc_lam(
+  bod: DAG,
+  parents: Option<NonNull<Parents>>,
+  origin: Origin,
+  uses: Uses,
+) -> NonNull<Lam> {
   unsafe {
     let lam = alloc_val(Lam {
       var: Var { parents: None },
       bod,
       bod_ref: mem::zeroed(),
       parents,
+      origin,
+      uses,
     });
     (*lam.as_ptr()).bod_ref = DLL::singleton(ParentPtr::LamBod(lam));
     lam
@@ -384,13 +458,18 @@ pub fn alloc_lam(bod: DAG, parents: Option<NonNull<Parents>>) -> NonNull<Lam> {
 }
 
 #[inline]
-pub fn alloc_fix(bod: DAG, parents: Option<NonNull<Parents>>) -> NonNull<Fix> {
+pub fn alloc_fix(
+  bod: DAG,
+  parents: Option<NonNull<Parents>>,
+  origin: Origin,
+) -> NonNull<Fix> {
   unsafe {
     let fix = alloc_val(Fix {
       var: Var { parents: None },
       bod,
       bod_ref: mem::zeroed(),
       parents,
+      origin,
     });
     (*fix.as_ptr()).bod_ref = DLL::singleton(ParentPtr::FixBod(fix));
     fix
@@ -426,8 +505,8 @@ pub fn upcopy(new_child: DAG, cc: ParentPtr, should_count: bool) {
   unsafe {
     match cc {
       ParentPtr::LamBod(link) => {
-        let Lam { var, parents, .. } = link.as_ref();
-        let new_lam = alloc_lam(new_child, None);
+        let Lam { var, parents, origin, uses, .. } = link.as_ref();
+        let new_lam = alloc_lam(new_child, None, *origin, *uses);
         let ptr: *mut Parents = &mut (*new_lam.as_ptr()).bod_ref;
         add_to_parents(new_child, NonNull::new(ptr).unwrap());
         let ptr: *mut Var = &mut (*new_lam.as_ptr()).var;
@@ -439,8 +518,8 @@ pub fn upcopy(new_child: DAG, cc: ParentPtr, should_count: bool) {
         }
       }
       ParentPtr::FixBod(link) => {
-        let Fix { var, parents, .. } = link.as_ref();
-        let new_fix = alloc_fix(new_child, None);
+        let Fix { var, parents, origin, .. } = link.as_ref();
+        let new_fix = alloc_fix(new_child, None, *origin);
         let ptr: *mut Parents = &mut (*new_fix.as_ptr()).bod_ref;
         add_to_parents(new_child, NonNull::new(ptr).unwrap());
         let ptr: *mut Var = &mut (*new_fix.as_ptr()).var;
@@ -487,8 +566,8 @@ pub fn upcopy(new_child: DAG, cc: ParentPtr, should_count: bool) {
 }
 
 enum Single {
-  Lam(Var),
-  Fix(Var),
+  Lam(Var, Origin, Uses),
+  Fix(Var, Origin),
 }
 
 /// Substitute a variable.
@@ -506,14 +585,14 @@ pub fn subst(
   let mut result = loop {
     match input {
       DAG::Lam(link) => {
-        let Lam { var, bod, .. } = unsafe { link.as_ref() };
+        let Lam { var, bod, origin, uses, .. } = unsafe { link.as_ref() };
         input = *bod;
-        spine.push(Single::Lam(var.clone()));
+        spine.push(Single::Lam(var.clone(), *origin, *uses));
       }
       DAG::Fix(link) => {
-        let Fix { var, bod, .. } = unsafe { link.as_ref() };
+        let Fix { var, bod, origin, .. } = unsafe { link.as_ref() };
         input = *bod;
-        spine.push(Single::Fix(var.clone()));
+        spine.push(Single::Fix(var.clone(), *origin));
       }
       DAG::App(link) => {
         let App { fun, arg: app_arg, .. } = unsafe { link.as_ref() };
@@ -535,8 +614,8 @@ pub fn subst(
   }
   while let Some(single) = spine.pop() {
     match single {
-      Single::Lam(var) => {
-        let new_lam = alloc_lam(result, None);
+      Single::Lam(var, origin, uses) => {
+        let new_lam = alloc_lam(result, None, origin, uses);
         let ptr: *mut Parents = unsafe { &mut (*new_lam.as_ptr()).bod_ref };
         add_to_parents(result, NonNull::new(ptr).unwrap());
         let ptr: *mut Var = unsafe { &mut (*new_lam.as_ptr()).var };
@@ -545,8 +624,8 @@ pub fn subst(
         }
         result = DAG::Lam(new_lam);
       }
-      Single::Fix(var) => {
-        let new_fix = alloc_fix(result, None);
+      Single::Fix(var, origin) => {
+        let new_fix = alloc_fix(result, None, origin);
         let ptr: *mut Parents = unsafe { &mut (*new_fix.as_ptr()).bod_ref };
         add_to_parents(result, NonNull::new(ptr).unwrap());
         let ptr: *mut Var = unsafe { &mut (*new_fix.as_ptr()).var };
@@ -600,7 +679,8 @@ pub fn reduce_lam(
   should_count: bool,
 ) -> DAG {
   let App { arg, .. } = unsafe { redex.as_ref() };
-  let Lam { var, bod, parents, .. } = unsafe { &mut *lam.as_ptr() };
+  let Lam { var, bod, parents, uses, .. } = unsafe { &mut *lam.as_ptr() };
+  bind_uses(*arg, *uses);
   let top_node = if DLL::is_singleton(*parents) {
     replace_child(DAG::Var(NonNull::new(var).unwrap()), *arg);
     *bod
@@ -611,199 +691,621 @@ pub fn reduce_lam(
   else {
     subst(*bod, var, *arg, false, should_count)
   };
+  // A nullary primitive, like `read`, stands for its result, which the parents
+  // of the redex share. The node may also be part of the lambda's body, so the
+  // redex gets a node of its own.
+  let top_node = match top_node {
+    DAG::Opr(link) if unsafe { link.as_ref().opr.arity() } == 0 => {
+      let Opr { opr, origin, .. } = unsafe { link.as_ref() };
+      DAG::Opr(alloc_val(Opr {
+        opr: opr.clone(),
+        parents: None,
+        origin: *origin,
+      }))
+    }
+    _ => top_node,
+  };
   replace_child(DAG::App(redex), top_node);
   free_dead_node(DAG::App(redex));
   top_node
 }
 
-/// Compute the Weak head normal form of the given DAG
-pub fn whnf(dag: &mut DAG, should_count: bool) {
-  let mut node = *dag;
+/// Marks a literal bound with the quantity `uses` as used at most once if
+/// `uses` says so
+fn bind_uses(node: DAG, uses: Uses) {
+  if let DAG::Lit(link) = node {
+    if matches!(uses, Uses::Once | Uses::Affi) {
+      unsafe { (*link.as_ptr()).uses = uses };
+    }
+  }
+}
+
+/// Whether a primitive may update a literal argument in place: the literal is
+/// a buffer the primitive returns an update of, and the checker established
+/// that the program uses it at most once
+fn updatable(opr: &Op, lit: &Lit) -> bool {
+  opr.updates()
+    && matches!(lit.uses, Uses::Once | Uses::Affi)
+    && matches!(lit.lit, Literal::Bytes(_) | Literal::Text(_))
+}
+
+/// The value of an argument not filled in yet, or of a literal node taken out
+/// by an update
+const HOLE: Literal = Literal::Bytes(Vec::new());
+
+/// Reads a literal argument of a primitive. An argument the primitive may
+/// update in place is left a `HOLE` until `take_args` fills it in, so that it
+/// is not copied.
+fn read_arg(opr: &Op, link: NonNull<Lit>) -> Literal {
+  let lit = unsafe { link.as_ref() };
+  debug_assert!(lit.uses != Uses::None, "a taken literal is read");
+  if updatable(opr, lit) { HOLE } else { lit.lit.clone() }
+}
+
+/// Fills in the holes `read_arg` left, given the applications of the primitive
+/// from the outermost. A literal is taken out of its node if nothing but its
+/// application refers to it, and copied otherwise: a value bound once may
+/// still be shared by the graph, such as a `Many` literal passed to a linear
+/// parameter. Returns the quantity of the result, the one of the literal taken
+/// if any.
+fn take_args(opr: &Op, apps: &[NonNull<App>], args: &mut [Literal]) -> Uses {
+  // The outermost application is replaced by the result wherever it occurs,
+  // but the arguments of the others are shared with their applications
+  let unshared = apps
+    .iter()
+    .skip(1)
+    .all(|app| DLL::is_singleton(unsafe { app.as_ref().parents }));
+  let mut taken = None;
+  for (i, arg) in args.iter_mut().enumerate() {
+    let link = match unsafe { apps[apps.len() - 1 - i].as_ref().arg } {
+      DAG::Lit(link) => link,
+      _ => continue,
+    };
+    let lit = unsafe { &mut *link.as_ptr() };
+    if !updatable(opr, lit) {
+      continue;
+    }
+    debug_assert!(*arg == HOLE, "an updated argument is read twice");
+    if unshared && DLL::is_singleton(lit.parents) {
+      *arg = mem::replace(&mut lit.lit, HOLE);
+      taken = Some(mem::replace(&mut lit.uses, Uses::None));
+    }
+    else {
+      *arg = lit.lit.clone();
+    }
+  }
+  if taken.is_some() {
+    UPDATED.with(|n| n.set(n.get() + 1));
+  }
+  taken.unwrap_or(Uses::Many)
+}
+
+/// A reduction suspended by `whnf` while it reduces an argument of a
+/// primitive operation to a literal
+struct Frame {
+  dag: *mut DAG,
+  node: DAG,
+  trail: Vec<NonNull<App>>,
+  args: Vec<Literal>,
+  /// The definition of the last step taken in the frame
+  origin: Origin,
+}
+
+/// Charges a step of the definition `origin`, under the definitions of the
+/// suspended frames, to the active profile, and shows its redex, the node
+/// `head` applied to the arguments of `trail`, to the debugger
+fn record(
+  step: Step,
+  origin: Origin,
+  head: DAG,
+  trail: &[NonNull<App>],
+  stack: &[Frame],
+  debugger: &mut Option<&mut Debugger>,
+) {
+  let mut path: Vec<Origin> = stack.iter().map(|frame| frame.origin).collect();
+  path.push(origin);
+  profile::record(step, &path);
+  if let Some(debugger) = debugger {
+    debugger.step(step, origin, head, trail, stack.len());
+  }
+}
+
+/// Reduces a DAG to weak head normal form, failing if the evaluation reaches
+/// `#Text.abort` applied to a text literal. Arguments of primitive operations
+/// are reduced on an explicit stack of suspended frames rather than
+/// recursively, so deep terms cannot overflow the native stack. Reduction
+/// steps, allocations and IO operations are charged to `meter`. While
+/// counting, each step is shown to the `debugger`, if any. Arguments of
+/// lambdas are reduced first under `Strategy::Strict`.
+pub fn whnf(
+  dag: &mut DAG,
+  strategy: Strategy,
+  should_count: bool,
+  meter: &mut Meter,
+  mut debugger: Option<&mut Debugger>,
+) -> Result<(), RuntimeError> {
+  let mut stack: Vec<Frame> = vec![];
+  let mut dag: *mut DAG = dag;
+  let mut node = unsafe { *dag };
   let mut trail: Vec<NonNull<App>> = vec![];
+  // The literal arguments collected so far for the primitive at the head
+  let mut args: Vec<Literal> = vec![];
+  // The weak head normal form of the argument the current frame waited on
+  let mut forced: Option<DAG> = None;
+  // The definition of the last step taken, when `should_count` is set
+  let mut origin: Origin = 0;
   loop {
-    match node {
-      DAG::App(link) => {
-        let App { fun, .. } = unsafe { link.as_ref() };
-        trail.push(link);
-        node = *fun;
-      }
-      DAG::Lam(link) => {
-        if let Some(app_link) = trail.pop() {
-          node = reduce_lam(app_link, link, should_count);
-        }
-        else {
-          break;
-        }
-      }
-      DAG::Fix(link) => unsafe {
-        let Fix { var, bod, .. } = &mut *link.as_ptr();
-        replace_child(node, *bod);
-        if var.parents.is_some() {
-          let new_fix = alloc_fix(mem::zeroed(), None).as_mut();
-          let result = subst(
-            *bod,
-            var,
-            DAG::Var(NonNull::new_unchecked(&mut new_fix.var)),
-            true,
-            should_count,
-          );
-          new_fix.bod = result;
-          add_to_parents(result, NonNull::new_unchecked(&mut new_fix.bod_ref));
-          replace_child(
-            DAG::Var(NonNull::new(var).unwrap()),
-            DAG::Fix(NonNull::new_unchecked(new_fix)),
-          );
+    let pending = loop {
+      match node {
+        DAG::App(link) => {
+          let App { fun, .. } = unsafe { link.as_ref() };
+          trail.push(link);
+          node = *fun;
         }
-        free_dead_node(node);
-        node = *bod;
-      },
-      DAG::Opr(link) => {
-        let opr = unsafe { (*link.as_ptr()).opr.clone() };
-        let len = trail.len();
-        if len == 0 && opr.arity() == 0 {
-          let res = opr.apply0();
-          if let Some(res) = res {
-            node = DAG::Lit(alloc_val(Lit { lit: res, parents: None }));
+        DAG::Lam(link) => {
+          if trail.is_empty() {
+            break None;
           }
-          else {
-            break;
+          // Call by value reduces the argument before substituting it
+          if strategy == Strategy::Strict && forced.take().is_none() {
+            let app = trail[trail.len() - 1].as_ptr();
+            let arg = unsafe { &mut (*app).arg as *mut DAG };
+            if !matches!(
+              unsafe { *arg },
+              DAG::Lam(_) | DAG::Lit(_) | DAG::Var(_)
+            ) {
+              break Some(arg);
+            }
+          }
+          meter.step().map_err(RuntimeError::Limit)?;
+          if should_count {
+            origin = unsafe { link.as_ref().origin };
+            record(Step::Beta, origin, node, &trail, &stack, &mut debugger);
           }
+          node = reduce_lam(trail.pop().unwrap(), link, should_count);
         }
-        else if len >= 1 && opr.arity() == 1 {
-          let arg = unsafe { &mut (*trail[len - 1].as_ptr()).arg };
-          whnf(arg, should_count);
-          match *arg {
-            DAG::Lit(link) => {
-              let x = unsafe { &(*link.as_ptr()).lit };
-              let res = opr.apply1(x);
-              if let Some(res) = res {
-                let top = DAG::App(trail.pop().unwrap());
-                let new_node =
-                  DAG::Lit(alloc_val(Lit { lit: res, parents: None }));
-                replace_child(top, new_node);
-                free_dead_node(top);
-                node = new_node;
-              }
-              else {
-                break;
+        DAG::Fix(link) => unsafe {
+          meter.step().map_err(RuntimeError::Limit)?;
+          if should_count {
+            origin = link.as_ref().origin;
+            record(Step::Unfold, origin, node, &trail, &stack, &mut debugger);
+          }
+          let Fix { var, bod, origin: fix_origin, .. } = &mut *link.as_ptr();
+          replace_child(node, *bod);
+          if var.parents.is_some() {
+            let new_fix = alloc_fix(mem::zeroed(), None, *fix_origin).as_mut();
+            let result = subst(
+              *bod,
+              var,
+              DAG::Var(NonNull::new_unchecked(&mut new_fix.var)),
+              true,
+              should_count,
+            );
+            new_fix.bod = result;
+            add_to_parents(
+              result,
+              NonNull::new_unchecked(&mut new_fix.bod_ref),
+            );
+            replace_child(
+              DAG::Var(NonNull::new(var).unwrap()),
+              DAG::Fix(NonNull::new_unchecked(new_fix)),
+            );
+          }
+          free_dead_node(node);
+          node = *bod;
+        },
+        DAG::Opr(link) => {
+          let opr = unsafe { (*link.as_ptr()).opr.clone() };
+          let opr_origin = unsafe { (*link.as_ptr()).origin };
+          let len = trail.len();
+          let arity = opr.arity() as usize;
+          if len >= 2 && opr == Op::Text(TextOp::Abort) {
+            let msg = match forced.take() {
+              Some(msg) => msg,
+              None => {
+                break Some(unsafe {
+                  &mut (*trail[len - 2].as_ptr()).arg as *mut DAG
+                });
               }
+            };
+            match msg {
+              DAG::Lit(link) => match unsafe { &(*link.as_ptr()).lit } {
+                Literal::Text(msg) => {
+                  return Err(RuntimeError::Abort(msg.to_string()));
+                }
+                _ => break None,
+              },
+              _ => break None,
             }
-            _ => break,
           }
-        }
-        else if len >= 2 && opr.arity() == 2 {
-          let arg1 = unsafe { &mut (*trail[len - 1].as_ptr()).arg };
-          let arg2 = unsafe { &mut (*trail[len - 2].as_ptr()).arg };
-          whnf(arg1, should_count);
-          whnf(arg2, should_count);
-          match (*arg1, *arg2) {
-            (DAG::Lit(x_link), DAG::Lit(y_link)) => {
-              let x = unsafe { &(*x_link.as_ptr()).lit };
-              let y = unsafe { &(*y_link.as_ptr()).lit };
-              let res = opr.apply2(x, y);
-              if let Some(res) = res {
-                trail.pop();
-                let top = DAG::App(trail.pop().unwrap());
-                let new_node =
-                  DAG::Lit(alloc_val(Lit { lit: res, parents: None }));
-                replace_child(top, new_node);
-                free_dead_node(top);
-                node = new_node;
+          else if len >= 1 && is_exit(&opr) {
+            let code = match forced.take() {
+              Some(code) => code,
+              None => {
+                break Some(unsafe {
+                  &mut (*trail[len - 1].as_ptr()).arg as *mut DAG
+                });
               }
-              else {
-                break;
+            };
+            match code {
+              DAG::Lit(link) => {
+                match exit_code(unsafe { &(*link.as_ptr()).lit }) {
+                  Some(code) => return Err(RuntimeError::Exit(code)),
+                  None => break None,
+                }
               }
+              _ => break None,
             }
-            _ => break,
           }
-        }
-        else if len >= 3 && opr.arity() == 3 {
-          let arg1 = unsafe { &mut (*trail[len - 1].as_ptr()).arg };
-          let arg2 = unsafe { &mut (*trail[len - 2].as_ptr()).arg };
-          let arg3 = unsafe { &mut (*trail[len - 3].as_ptr()).arg };
-          whnf(arg1, should_count);
-          whnf(arg2, should_count);
-          whnf(arg3, should_count);
-          match (*arg1, *arg2, *arg3) {
-            (DAG::Lit(x_link), DAG::Lit(y_link), DAG::Lit(z_link)) => {
-              let x = unsafe { &(*x_link.as_ptr()).lit };
-              let y = unsafe { &(*y_link.as_ptr()).lit };
-              let z = unsafe { &(*z_link.as_ptr()).lit };
-              let res = opr.apply3(x, y, z);
-              if let Some(res) = res {
-                trail.pop();
-                trail.pop();
-                let top = DAG::App(trail.pop().unwrap());
-                let new_node =
-                  DAG::Lit(alloc_val(Lit { lit: res, parents: None }));
-                replace_child(top, new_node);
-                free_dead_node(top);
-                node = new_node;
+          // `io.bind` reduces its action, then continues with the rest of the
+          // program, unless the action is stuck
+          else if len >= 2 && is_seq(&opr) {
+            match forced.take() {
+              Some(action) if is_value(action) => (),
+              Some(_) => break None,
+              None => {
+                break Some(unsafe {
+                  &mut (*trail[len - 1].as_ptr()).arg as *mut DAG
+                });
+              }
+            }
+            meter.step().map_err(RuntimeError::Limit)?;
+            if should_count {
+              origin = opr_origin;
+              record(Step::Prim, origin, node, &trail, &stack, &mut debugger);
+            }
+            trail.pop();
+            let top = trail.pop().unwrap();
+            let next = unsafe { (*top.as_ptr()).arg };
+            replace_child(DAG::App(top), next);
+            free_dead_node(DAG::App(top));
+            node = next;
+          }
+          else if (arity == 0 && len == 0) || (arity > 0 && len >= arity) {
+            if let Some(arg) = forced.take() {
+              match arg {
+                DAG::Lit(link) => args.push(read_arg(&opr, link)),
+                _ => break None,
+              }
+            }
+            // Collects the arguments in order, suspending on the first one
+            // that is not a literal yet
+            let mut next = None;
+            while args.len() < arity {
+              let arg =
+                unsafe { &mut (*trail[len - 1 - args.len()].as_ptr()).arg };
+              match *arg {
+                DAG::Lit(link) => args.push(read_arg(&opr, link)),
+                _ => {
+                  next = Some(arg as *mut DAG);
+                  break;
+                }
+              }
+            }
+            if next.is_some() {
+              break next;
+            }
+            let mut args = mem::take(&mut args);
+            meter.step().map_err(RuntimeError::Limit)?;
+            if let Op::Io(_) = opr {
+              meter.io().map_err(RuntimeError::Limit)?;
+            }
+            if should_count {
+              origin = opr_origin;
+              record(Step::Prim, origin, node, &trail, &stack, &mut debugger);
+            }
+            let uses = take_args(&opr, &trail[len - arity..], &mut args);
+            let new_node = match opr.apply_in_place(&mut args) {
+              Some(res) => {
+                DAG::Lit(alloc_val(Lit { lit: res, parents: None, uses }))
               }
-              else {
-                break;
+              None => match opr.apply_term(&args) {
+                Some(res) => {
+                  from_term(Rc::new(Defs::new()), &res, None, opr_origin)
+                }
+                None => break None,
+              },
+            };
+            // The result replaces the application, or a nullary primitive,
+            // in every parent, so the expressions sharing it see one result
+            let top = if arity > 0 {
+              for _ in 1..arity {
+                trail.pop();
               }
+              DAG::App(trail.pop().unwrap())
             }
-            _ => break,
+            else {
+              node
+            };
+            replace_child(top, new_node);
+            free_dead_node(top);
+            node = new_node;
+            meter.check_nodes().map_err(RuntimeError::Limit)?;
+          }
+          else {
+            break None;
           }
         }
-        else {
-          break;
-        }
+        _ => break None,
       }
-      _ => break,
+    };
+    match pending {
+      // Suspends the current frame and reduces the argument
+      Some(arg) => {
+        stack.push(Frame {
+          dag,
+          node,
+          trail: mem::take(&mut trail),
+          args: mem::take(&mut args),
+          origin,
+        });
+        dag = arg;
+        node = unsafe { *arg };
+      }
+      // The current frame is done: resumes the frame that waited on it
+      None => unsafe {
+        *dag = if trail.is_empty() { node } else { DAG::App(trail[0]) };
+        match stack.pop() {
+          Some(frame) => {
+            forced = Some(*dag);
+            dag = frame.dag;
+            node = frame.node;
+            trail = frame.trail;
+            args = frame.args;
+            origin = frame.origin;
+          }
+          None => return Ok(()),
+        }
+      },
     }
   }
-  if trail.is_empty() {
-    *dag = node;
+}
+
+/// Whether an operation is the `io.exit` primitive
+pub fn is_exit(opr: &Op) -> bool {
+  matches!(opr, Op::Io(op) if is_exit_op(op))
+}
+
+/// Whether an operation is the primitive behind `io.bind`
+pub fn is_seq(opr: &Op) -> bool {
+  matches!(opr, Op::Io(op) if is_seq_op(op))
+}
+
+/// Whether a node in weak head normal form is a value rather than a stuck
+/// term: a lambda, a literal or a primitive missing arguments
+fn is_value(node: DAG) -> bool {
+  let mut node = node;
+  let mut args = 0;
+  loop {
+    match node {
+      DAG::App(link) => {
+        node = unsafe { link.as_ref().fun };
+        args += 1;
+      }
+      DAG::Lam(_) | DAG::Lit(_) => return args == 0,
+      DAG::Opr(link) => {
+        return args < unsafe { link.as_ref().opr.arity() } as usize;
+      }
+      DAG::Var(_) | DAG::Fix(_) => return false,
+    }
   }
-  else {
-    *dag = DAG::App(trail[0]);
+}
+
+/// Reads the status code passed to `io.exit`
+pub fn exit_code(lit: &Literal) -> Option<i32> {
+  match lit {
+    Literal::Nat(x) => i32::try_from(x).ok(),
+    Literal::Int(x) => i32::try_from(x).ok(),
+    Literal::U8(x) => Some(i32::from(*x)),
+    Literal::U16(x) => Some(i32::from(*x)),
+    Literal::U32(x) => i32::try_from(*x).ok(),
+    Literal::U64(x) => i32::try_from(*x).ok(),
+    Literal::I8(x) => Some(i32::from(*x)),
+    Literal::I16(x) => Some(i32::from(*x)),
+    Literal::I32(x) => Some(*x),
+    Literal::I64(x) => i32::try_from(*x).ok(),
+    _ => None,
   }
 }
 
-/// Assumes erased terms
+/// A step of `from_term`, which converts terms from an explicit work stack
+/// rather than recursively so that deep terms cannot overflow the native
+/// stack. Every definition a term refers to is converted in its own scope,
+/// which holds the `Fix` its recursive references point to.
+enum Build<'a> {
+  /// Converts `term`, writing its node to `slot`
+  Term {
+    term: &'a Term,
+    ctx: Vec<DAG>,
+    parents: Option<NonNull<Parents>>,
+    slot: *mut DAG,
+    scope: usize,
+    origin: Origin,
+  },
+  /// Converts the body of a `let` once its expression, in `exp`, is built
+  LetBod {
+    bod: &'a Term,
+    exp: NonNull<DAG>,
+    uses: Uses,
+    ctx: Vec<DAG>,
+    parents: Option<NonNull<Parents>>,
+    slot: *mut DAG,
+    scope: usize,
+    origin: Origin,
+  },
+  /// Links the expression of a recursive `let` to its `Fix`
+  LetFix { fix: NonNull<Fix> },
+  /// Closes the scope of a definition whose body is in `slot`, wrapping it in
+  /// a `Fix` if it refers to itself
+  Def { parents: Option<NonNull<Parents>>, slot: *mut DAG, scope: usize },
+}
+
+/// Assumes erased terms. The nodes are attributed to the definition `origin`
+/// unless a profile finds a more precise one.
 pub fn from_term(
   defs: Rc<Defs>,
   term: &Term,
   parents: Option<NonNull<Parents>>,
+  origin: Origin,
 ) -> DAG {
-  let (bod, maybe_fix) = from_term_inner(defs, term, &mut vec![], None, None);
-  match maybe_fix {
-    Some(mut link) => unsafe {
-      let fix = link.as_mut();
-      fix.parents = parents;
-      fix.bod = bod;
-      add_to_parents(bod, NonNull::new_unchecked(&mut fix.bod_ref));
-      DAG::Fix(link)
-    },
-    None => {
-      set_parents(bod, parents);
-      bod
+  let mut root: DAG = unsafe { mem::zeroed() };
+  let params = linear_params(&defs);
+  // The `Fix` of each scope, allocated by its first recursive reference
+  let mut fixes: Vec<Option<NonNull<Fix>>> = vec![None];
+  let mut work =
+    vec![Build::Def { parents, slot: &mut root, scope: 0 }, Build::Term {
+      term,
+      ctx: vec![],
+      parents: None,
+      slot: &mut root,
+      scope: 0,
+      origin,
+    }];
+  while let Some(build) = work.pop() {
+    match build {
+      Build::Term { term, ctx, parents, slot, scope, origin } => {
+        let node = from_term_inner(
+          &defs, &params, term, ctx, parents, slot, scope, origin, &mut fixes,
+          &mut work,
+        );
+        unsafe { *slot = node };
+      }
+      Build::LetBod {
+        bod,
+        exp,
+        uses,
+        mut ctx,
+        parents,
+        slot,
+        scope,
+        origin,
+      } => {
+        let exp = unsafe { free_val(exp) };
+        bind_uses(exp, uses);
+        ctx.push(exp);
+        work.push(Build::Term { term: bod, ctx, parents, slot, scope, origin });
+      }
+      Build::LetFix { fix } => unsafe {
+        let fix = &mut *fix.as_ptr();
+        add_to_parents(fix.bod, NonNull::new_unchecked(&mut fix.bod_ref));
+      },
+      Build::Def { parents, slot, scope } => unsafe {
+        let bod = *slot;
+        match fixes[scope] {
+          Some(mut link) => {
+            let fix = link.as_mut();
+            fix.parents = parents;
+            fix.bod = bod;
+            add_to_parents(bod, NonNull::new_unchecked(&mut fix.bod_ref));
+            *slot = DAG::Fix(link);
+          }
+          None => set_parents(bod, parents),
+        }
+      },
     }
   }
+  root
 }
 
-pub fn from_term_inner(
-  defs: Rc<Defs>,
-  term: &Term,
-  ctx: &mut Vec<DAG>,
+/// The quantities of the parameters of the definitions with a parameter used
+/// at most once, by the position of the definition. The parameters of a
+/// definition are bound by a chain of lambdas sharing its position, which
+/// `transform::transform` preserves when it inlines the definition.
+/// `optimize::optimize` leaves the lambdas of these parameters unreduced, and
+/// drops the position of a chain it changes.
+pub(crate) fn linear_params(defs: &Defs) -> Vec<(Position, Vec<Uses>)> {
+  let mut params = vec![];
+  for def in defs.defs.values() {
+    let pos = match def.term {
+      Term::Lam(Pos::Some(pos), ..) => pos,
+      _ => continue,
+    };
+    let mut uses = vec![];
+    let mut typ = &def.typ_;
+    while let Term::All(Pos::Some(all_pos), all_uses, _, dom_img) = typ {
+      if *all_pos != pos {
+        break;
+      }
+      uses.push(*all_uses);
+      typ = &dom_img.1;
+    }
+    if uses.iter().any(|uses| matches!(uses, Uses::Once | Uses::Affi)) {
+      params.push((pos, uses));
+    }
+  }
+  params
+}
+
+/// The quantity of the parameter bound by a lambda at `pos` with body `bod`.
+/// The lambdas of a definition are counted from the last, as the first ones
+/// may have been applied already.
+pub(crate) fn param_uses(
+  params: &[(Position, Vec<Uses>)],
+  pos: Pos,
+  bod: &Term,
+) -> Uses {
+  let pos = match pos {
+    Pos::Some(pos) => pos,
+    Pos::None => return Uses::Many,
+  };
+  let uses = match params.iter().find(|(def_pos, _)| *def_pos == pos) {
+    Some((_, uses)) => uses,
+    None => return Uses::Many,
+  };
+  let mut rest = 0;
+  let mut bod = bod;
+  while let Term::Lam(Pos::Some(lam_pos), _, lam_bod) = bod {
+    if *lam_pos != pos {
+      break;
+    }
+    rest += 1;
+    bod = &**lam_bod;
+  }
+  match uses.len().checked_sub(rest + 1) {
+    Some(idx) => uses[idx],
+    None => Uses::Many,
+  }
+}
+
+/// Converts a single term node, pushing the conversion of its subterms onto
+/// `work`
+#[allow(clippy::too_many_arguments)]
+fn from_term_inner<'a>(
+  defs: &'a Defs,
+  params: &[(Position, Vec<Uses>)],
+  term: &'a Term,
+  mut ctx: Vec<DAG>,
   parents: Option<NonNull<Parents>>,
-  maybe_fix: Option<NonNull<Fix>>,
-) -> (DAG, Option<NonNull<Fix>>) {
+  slot: *mut DAG,
+  scope: usize,
+  origin: Origin,
+  fixes: &mut Vec<Option<NonNull<Fix>>>,
+  work: &mut Vec<Build<'a>>,
+) -> DAG {
+  // Data, case and annotation nodes are erased
+  let mut term = term;
+  loop {
+    term = match term {
+      Term::Dat(_, bod) | Term::Cse(_, bod) => &**bod,
+      Term::Ann(_, typ_exp) => &typ_exp.1,
+      _ => break,
+    };
+  }
+  let origin = profile::origin(term.pos(), origin);
   match term {
     Term::Rec(_) => unsafe {
-      let mut fix = match maybe_fix {
+      let mut fix = match fixes[scope] {
         Some(fix) => fix,
-        None => alloc_fix(mem::zeroed(), None),
+        None => alloc_fix(mem::zeroed(), None, origin),
       };
+      fixes[scope] = Some(fix);
       let Fix { var, .. } = fix.as_mut();
       let var = DAG::Var(NonNull::new_unchecked(var));
       if let Some(parents) = parents {
         DLL::concat(parents, get_parents(var));
         set_parents(var, Some(parents));
       }
-      (var, Some(fix))
+      var
     },
     Term::Var(_, _, idx) => match ctx.get(ctx.len() - 1 - *idx as usize) {
       Some(val) => {
@@ -811,79 +1313,132 @@ pub fn from_term_inner(
           DLL::concat(parents, get_parents(*val));
           set_parents(*val, Some(parents));
         }
-        (*val, maybe_fix)
+        *val
       }
       None => panic!("Free variable found"),
     },
     Term::Lit(_, lit) => {
-      (DAG::Lit(alloc_val(Lit { lit: lit.clone(), parents })), maybe_fix)
+      DAG::Lit(alloc_val(Lit { lit: lit.clone(), parents, uses: Uses::Many }))
     }
     Term::Opr(_, opr) => {
-      (DAG::Opr(alloc_val(Opr { opr: opr.clone(), parents })), maybe_fix)
+      DAG::Opr(alloc_val(Opr { opr: opr.clone(), parents, origin }))
     }
     Term::Ref(_, nam, exp, _) => {
       if let Some(def) = defs.defs.get(exp) {
-        (from_term(defs.clone(), &def.term, parents), maybe_fix)
+        fixes.push(None);
+        let scope = fixes.len() - 1;
+        work.push(Build::Def { parents, slot, scope });
+        work.push(Build::Term {
+          term: &def.term,
+          ctx: vec![],
+          parents: None,
+          slot,
+          scope,
+          origin: profile::named(nam, origin),
+        });
+        // A placeholder, overwritten once the definition is built
+        unsafe { mem::zeroed() }
       }
       else {
         panic!("undefined runtime reference: {}, {}", nam, exp);
       }
     }
-    Term::Lam(_, _, bod) => unsafe {
-      let lam = alloc_lam(mem::zeroed(), parents);
+    Term::Lam(pos, _, bod) => unsafe {
+      let uses = param_uses(params, *pos, bod);
+      let lam = alloc_lam(mem::zeroed(), parents, origin, uses);
       let Lam { var, bod_ref, .. } = &mut *lam.as_ptr();
       ctx.push(DAG::Var(NonNull::new(var).unwrap()));
-      let (bod, maybe_fix) =
-        from_term_inner(defs, &**bod, ctx, NonNull::new(bod_ref), maybe_fix);
-      (*lam.as_ptr()).bod = bod;
-      (DAG::Lam(lam), maybe_fix)
+      work.push(Build::Term {
+        term: &**bod,
+        ctx,
+        parents: NonNull::new(bod_ref),
+        slot: &mut (*lam.as_ptr()).bod,
+        scope,
+        origin,
+      });
+      DAG::Lam(lam)
     },
-    Term::Dat(_, bod) => from_term_inner(defs, &**bod, ctx, parents, maybe_fix),
-    Term::Cse(_, bod) => from_term_inner(defs, &**bod, ctx, parents, maybe_fix),
     Term::App(_, fun_arg) => unsafe {
       let (fun, arg) = &**fun_arg;
       let app = alloc_app(mem::zeroed(), mem::zeroed(), parents);
       let App { fun_ref, arg_ref, .. } = &mut *app.as_ptr();
-      let (fun, maybe_fix) = from_term_inner(
-        defs.clone(),
-        fun,
-        &mut ctx.clone(),
-        NonNull::new(fun_ref),
-        maybe_fix,
-      );
-      let (arg, maybe_fix) =
-        from_term_inner(defs, arg, ctx, NonNull::new(arg_ref), maybe_fix);
-      (*app.as_ptr()).fun = fun;
-      (*app.as_ptr()).arg = arg;
-      (DAG::App(app), maybe_fix)
+      work.push(Build::Term {
+        term: arg,
+        ctx: ctx.clone(),
+        parents: NonNull::new(arg_ref),
+        slot: &mut (*app.as_ptr()).arg,
+        scope,
+        origin,
+      });
+      work.push(Build::Term {
+        term: fun,
+        ctx,
+        parents: NonNull::new(fun_ref),
+        slot: &mut (*app.as_ptr()).fun,
+        scope,
+        origin,
+      });
+      DAG::App(app)
     },
-    Term::Ann(_, typ_exp) => {
-      let (_, exp) = (**typ_exp).clone();
-      from_term_inner(defs, &exp, ctx, parents, maybe_fix)
-    }
-    Term::Let(_, rec, _, _, typ_exp_bod) => unsafe {
+    Term::Let(_, rec, uses, nam, typ_exp_bod) => unsafe {
       let (_, exp, bod) = &**typ_exp_bod;
-      let (exp, maybe_fix) = if *rec {
-        let new_fix = alloc_fix(mem::zeroed(), None).as_mut();
-        let (bod, maybe_fix) = from_term_inner(
-          defs.clone(),
-          exp,
-          &mut ctx.clone(),
+      if *rec {
+        // Recursive definitions are inlined as recursive lets named after them
+        let exp_origin = profile::named(nam, origin);
+        let new_fix = alloc_fix(mem::zeroed(), None, exp_origin);
+        let mut bod_ctx = ctx.clone();
+        bod_ctx.push(DAG::Fix(new_fix));
+        work.push(Build::Term {
+          term: bod,
+          ctx: bod_ctx,
           parents,
-          maybe_fix,
-        );
-        new_fix.bod = bod;
-        add_to_parents(bod, NonNull::new_unchecked(&mut new_fix.bod_ref));
-        (DAG::Fix(NonNull::new_unchecked(new_fix)), maybe_fix)
+          slot,
+          scope,
+          origin,
+        });
+        work.push(Build::LetFix { fix: new_fix });
+        // The expression refers to itself through the variable of the `Fix`,
+        // which also becomes the parent of its node
+        let var = NonNull::new_unchecked(&mut (*new_fix.as_ptr()).var);
+        let mut exp_ctx = ctx;
+        exp_ctx.push(DAG::Var(var));
+        work.push(Build::Term {
+          term: exp,
+          ctx: exp_ctx,
+          parents: None,
+          slot: &mut (*new_fix.as_ptr()).bod,
+          scope,
+          origin: exp_origin,
+        });
       }
       else {
-        from_term_inner(defs.clone(), exp, &mut ctx.clone(), None, maybe_fix)
-      };
-      ctx.push(exp);
-      from_term_inner(defs, bod, ctx, parents, maybe_fix)
+        let new_exp = alloc_val(mem::zeroed());
+        work.push(Build::LetBod {
+          bod,
+          exp: new_exp,
+          uses: *uses,
+          ctx: ctx.clone(),
+          parents,
+          slot,
+          scope,
+          origin,
+        });
+        work.push(Build::Term {
+          term: exp,
+          ctx,
+          parents: None,
+          slot: new_exp.as_ptr(),
+          scope,
+          origin,
+        });
+      }
+      // A placeholder, overwritten once the body is built
+      mem::zeroed()
     },
-    _ => {
-      (DAG::Lit(alloc_val(Lit { lit: Literal::I32(0), parents })), maybe_fix)
-    }
+    _ => DAG::Lit(alloc_val(Lit {
+      lit: Literal::I32(0),
+      parents,
+      uses: Uses::Many,
+    })),
   }
 }
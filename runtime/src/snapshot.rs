@@ -0,0 +1,32 @@
+//! Snapshot testing of generated code
+
+use std::{
+  fs,
+  path::{
+    Path,
+    PathBuf,
+  },
+};
+
+/// The path of the snapshot `name` in the directory `dir` of `snapshots`
+pub fn path(dir: &str, name: &str) -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("snapshots").join(dir).join(name)
+}
+
+/// Compares `output` with the snapshot `name` in the directory `dir` of
+/// `snapshots`, or rewrites the snapshot when `YATIMA_UPDATE_SNAPSHOTS` is set
+pub fn assert_snapshot(dir: &str, name: &str, output: &str) {
+  let path = path(dir, name);
+  if std::env::var_os("YATIMA_UPDATE_SNAPSHOTS").is_some() {
+    fs::write(&path, output).unwrap();
+  }
+  else {
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+      panic!(
+        "Missing {}, run with YATIMA_UPDATE_SNAPSHOTS=1 to create it",
+        path.display()
+      )
+    });
+    assert_eq!(output, expected);
+  }
+}
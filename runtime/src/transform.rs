@@ -1,105 +1,185 @@
-use sp_std::rc::Rc;
+use sp_std::{
+  cell::RefCell,
+  rc::Rc,
+  vec::Vec,
+};
 
 use yatima_core::{
   defs,
-  defs::Defs,
+  defs::{
+    Def,
+    Defs,
+  },
   literal::Literal,
+  name::Name,
   parse,
-  prim::io::IoOp,
+  prim::{
+    host::HostFns,
+    io::IoOp,
+    list_term,
+    option_term,
+  },
   term::{
     Op,
     Pos,
     Term,
   },
+  uses::Uses,
   yatima,
 };
 
-/// The str CID of IO.Return
-const IO_RETURN: &str =
-  "bafy2bzaceakfamngkzwsi3zzkxyfz5rfabb5x3t6it7nhsxo7jjb2grpu7gmm";
-
-/// The str CID of IO.Print
-const IO_PRINT: &str =
-  "bafy2bzaceajjrezmgr6mulbz4htf72yknifanxgsoqquzhno5vtx5wggwo7k6";
-
-/// The str CID of IO.Read
-const IO_READ: &str =
-  "bafy2bzaceabdrrnmkljwxmj5xe63nhtygjjpgxtptdrdolsipdojrbsjjyrd6";
-
-/// The str CID of IO.Bind
-const IO_BIND: &str =
-  "bafy2bzaceanc3j2lxoz2jttiinlxqge7u3tnmufrfoil4udxptljacv5fmmq6";
+use crate::{
+  error::RuntimeError,
+  fs::Sandbox,
+  replay::{
+    now_op,
+    random_bytes_op,
+    Nondet,
+  },
+};
 
-pub fn transform(defs: Rc<Defs>, term: &mut Term, runtime: RunIO) {
+pub fn transform(
+  defs: Rc<Defs>,
+  term: &mut Term,
+  runtime: RunIO,
+) -> Result<(), RuntimeError> {
   match term {
     Term::Var(_pos, _name, _index) => {}
     Term::Lam(_pos, _name, boxed) => {
-      transform_boxed(defs, boxed, runtime);
+      transform_boxed(defs, boxed, runtime)?;
     }
     Term::App(_pos, boxed) => {
-      transform_boxed2(defs, boxed, runtime);
+      transform_boxed2(defs, boxed, runtime)?;
     }
     Term::All(_pos, _uses, _name, boxed) => {
-      transform_boxed2(defs, boxed, runtime);
+      transform_boxed2(defs, boxed, runtime)?;
     }
     Term::Slf(_pos, _name, boxed) => {
-      transform_boxed(defs, boxed, runtime);
+      transform_boxed(defs, boxed, runtime)?;
     }
     Term::Dat(_pos, boxed) => {
-      transform_boxed(defs, boxed, runtime);
+      transform_boxed(defs, boxed, runtime)?;
     }
     Term::Cse(_pos, boxed) => {
-      transform_boxed(defs, boxed, runtime);
-    }
-    Term::Ref(_pos, _name, exp, _cid2) => match exp.to_string().as_ref() {
-      IO_RETURN => {
-        *term = yatima!("lambda x => x");
-        transform(defs, term, runtime);
-      }
-      IO_PRINT => {
-        *term = yatima!(
-          "lambda _type x => #$0 x",
-          Term::Opr(Pos::None, Op::Io(runtime.write_stdout_op()))
-        );
-        transform(defs, term, runtime);
-      }
-      IO_READ => {
-        *term = yatima!(
-          "lambda _type => #$0",
-          Term::Opr(Pos::None, Op::Io(runtime.read_stdin_op()))
-        );
-        transform(defs, term, runtime);
-      }
-      IO_BIND => {
-        *term = yatima!("lambda _type1 _type2 io fun => fun io");
-        transform(defs, term, runtime);
-      }
-      _ => {
-        if let Some(def) = defs.defs.get(exp) {
-          *term = def.term.clone();
-          transform(defs, term, runtime);
-        }
+      transform_boxed(defs, boxed, runtime)?;
+    }
+    Term::Ref(_pos, name, exp, _cid2) => {
+      if let Some(def) = defs.defs.get(exp) {
+        *term = inline_def(name.clone(), def);
+        transform(defs, term, runtime)?;
       }
-    },
+    }
     Term::Let(_pos, _bool, _uses, _name, boxed) => {
-      transform_boxed3(defs, boxed, runtime);
+      transform_boxed3(defs, boxed, runtime)?;
     }
     Term::Typ(_pos) => {}
     Term::Ann(_pos, boxed) => {
-      transform_boxed2(defs, boxed, runtime);
+      transform_boxed2(defs, boxed, runtime)?;
     }
     Term::Lit(_pos, _lit) => {}
     Term::LTy(_pos, _lit_type) => {}
+    Term::Opr(_pos, Op::Extern(op)) => {
+      let symbol = op.symbol.clone();
+      if let Err(typ) = runtime.host_fns().check_type(&symbol, &op.typ) {
+        return Err(RuntimeError::ExternType(symbol, *op.typ.clone(), typ));
+      }
+      if runtime.host_fns().get(&symbol).is_none() {
+        if let Some(params) = builtin_params(&symbol) {
+          if !has_params(&op.typ, params) {
+            return Err(RuntimeError::ExternParams(
+              symbol,
+              *op.typ.clone(),
+              params.join(" "),
+            ));
+          }
+        }
+      }
+      match runtime.resolve_extern(&symbol) {
+        Some(imp) => {
+          *term = imp;
+          transform(defs, term, runtime)?;
+        }
+        None => {
+          return Err(RuntimeError::UnknownExtern(
+            symbol,
+            runtime.extern_symbols(),
+          ));
+        }
+      }
+    }
     Term::Opr(_pos, _op) => {}
     Term::Rec(_pos) => {}
   }
+  Ok(())
+}
+
+/// The body of a definition to inline in place of a reference to it. The
+/// `Rec` of a recursive definition would refer to the whole program once
+/// inlined, so its body is bound by a recursive `let` named after it instead.
+fn inline_def(name: Name, def: &Def) -> Term {
+  fn go(term: Term, name: &Name, dep: u64, found: &mut bool) -> Term {
+    match term {
+      Term::Rec(pos) => {
+        *found = true;
+        Term::Var(pos, name.clone(), dep)
+      }
+      Term::Lam(pos, nam, bod) => {
+        Term::Lam(pos, nam, Box::new(go(*bod, name, dep + 1, found)))
+      }
+      Term::Slf(pos, nam, bod) => {
+        Term::Slf(pos, nam, Box::new(go(*bod, name, dep + 1, found)))
+      }
+      Term::Dat(pos, bod) => {
+        Term::Dat(pos, Box::new(go(*bod, name, dep, found)))
+      }
+      Term::Cse(pos, bod) => {
+        Term::Cse(pos, Box::new(go(*bod, name, dep, found)))
+      }
+      Term::App(pos, fun_arg) => {
+        let (fun, arg) = *fun_arg;
+        let fun = go(fun, name, dep, found);
+        Term::App(pos, Box::new((fun, go(arg, name, dep, found))))
+      }
+      Term::Ann(pos, typ_exp) => {
+        let (typ, exp) = *typ_exp;
+        let typ = go(typ, name, dep, found);
+        Term::Ann(pos, Box::new((typ, go(exp, name, dep, found))))
+      }
+      Term::All(pos, uses, nam, dom_img) => {
+        let (dom, img) = *dom_img;
+        let dom = go(dom, name, dep, found);
+        let img = go(img, name, dep + 1, found);
+        Term::All(pos, uses, nam, Box::new((dom, img)))
+      }
+      Term::Let(pos, rec, uses, nam, typ_exp_bod) => {
+        let (typ, exp, bod) = *typ_exp_bod;
+        let typ = go(typ, name, dep, found);
+        let exp = go(exp, name, if rec { dep + 1 } else { dep }, found);
+        let bod = go(bod, name, dep + 1, found);
+        Term::Let(pos, rec, uses, nam, Box::new((typ, exp, bod)))
+      }
+      term => term,
+    }
+  }
+  let mut found = false;
+  let bod = go(def.term.clone(), &name, 0, &mut found);
+  if !found {
+    return bod;
+  }
+  Term::Let(
+    def.pos,
+    true,
+    Uses::Many,
+    name.clone(),
+    Box::new((Term::Typ(def.pos), bod, Term::Var(def.pos, name, 0))),
+  )
 }
 
 fn transform_boxed(
   defs: Rc<Defs>,
   boxed: &mut Box<Term>,
   runtime: RunIO,
-) {
+) -> Result<(), RuntimeError> {
   transform(defs, boxed.as_mut(), runtime)
 }
 
@@ -107,25 +187,155 @@ fn transform_boxed2(
   defs: Rc<Defs>,
   boxed: &mut Box<(Term, Term)>,
   runtime: RunIO,
-) {
+) -> Result<(), RuntimeError> {
   let (t1, t2) = boxed.as_mut();
-  transform(defs.clone(), t1, runtime.clone());
-  transform(defs, t2, runtime);
+  transform(defs.clone(), t1, runtime.clone())?;
+  transform(defs, t2, runtime)
 }
 
 fn transform_boxed3(
   defs: Rc<Defs>,
   boxed: &mut Box<(Term, Term, Term)>,
   runtime: RunIO,
-) {
+) -> Result<(), RuntimeError> {
   let (t1, t2, t3) = boxed.as_mut();
-  transform(defs.clone(), t1, runtime.clone());
-  transform(defs.clone(), t2, runtime.clone());
-  transform(defs, t3, runtime);
+  transform(defs.clone(), t1, runtime.clone())?;
+  transform(defs.clone(), t2, runtime.clone())?;
+  transform(defs, t3, runtime)
+}
+
+/// The parameter types an `extern` declaration of a built-in symbol must
+/// start with, matching the arguments its implementation takes. `_` accepts
+/// any type, for the values `io.return` and `io.bind` pass through and the
+/// integer status of `io.exit`; the result type is left to the declaration.
+pub fn builtin_params(symbol: &str) -> Option<&'static [&'static str]> {
+  match symbol {
+    "io.return" => Some(&["_"]),
+    "io.print" => Some(&["Type", "#Bytes"]),
+    "io.read" => Some(&["Type"]),
+    "io.bind" => Some(&["Type", "Type", "_", "_"]),
+    "io.args" | "io.now" => Some(&[]),
+    "io.get_env" => Some(&["#Text"]),
+    "io.random_bytes" => Some(&["#Nat"]),
+    "io.exit" => Some(&["Type", "_"]),
+    "fs.open" | "fs.read" | "fs.list_dir" | "fs.exists" => Some(&["#Text"]),
+    "fs.write" | "fs.append" => Some(&["#Text", "#Bytes"]),
+    _ => None,
+  }
+}
+
+/// Whether the leading `∀` binders of a type have the given parameter types
+fn has_params(typ: &Term, params: &[&str]) -> bool {
+  let mut typ = typ;
+  for param in params {
+    match typ {
+      Term::All(_, _, _, terms) => {
+        let (dom, img) = terms.as_ref();
+        if *param != "_" {
+          let expected = parse::term::parse(param, Defs::new()).unwrap().1;
+          if dom.embed().0 != expected.embed().0 {
+            return false;
+          }
+        }
+        typ = img;
+      }
+      _ => return false,
+    }
+  }
+  true
 }
 
 /// A trait defining the behavior of primitive IO operations.
 pub trait RuntimeIO {
+  /// Resolves the symbol of an `extern` declaration to the term implementing
+  /// it. The term replaces the declaration before evaluation, so it must take
+  /// the same arguments as the declared type, including type arguments.
+  fn resolve_extern(&self, symbol: &str) -> Option<Term> {
+    if let Some(op) = self.host_fns().get(symbol) {
+      return Some(io_term(op.clone()));
+    }
+    match symbol {
+      "io.return" => Some(yatima!("lambda x => x")),
+      "io.print" => Some(yatima!(
+        "lambda _type x => #$0 x",
+        Term::Opr(Pos::None, Op::Io(self.write_stdout_op()))
+      )),
+      "io.read" => Some(yatima!(
+        "lambda _type => #$0",
+        Term::Opr(Pos::None, Op::Io(self.read_stdin_op()))
+      )),
+      "io.bind" => Some(yatima!(
+        "lambda _type1 _type2 io fun => #$0 io (fun io)",
+        io_term(seq_op())
+      )),
+      "io.args" => Some(list_term(
+        self
+          .args()
+          .iter()
+          .map(|arg| Literal::Text(arg.as_str().into()))
+          .collect(),
+      )),
+      "io.get_env" => Some(io_term(self.get_env_op())),
+      "io.now" => Some(io_term(now_op(self.nondet()))),
+      "io.random_bytes" => Some(io_term(random_bytes_op(self.nondet()))),
+      "io.exit" => {
+        Some(yatima!("lambda _type code => #$0 code", io_term(exit_op())))
+      }
+      "fs.open" => Some(io_term(self.sandbox().open_file_op())),
+      "fs.read" => Some(io_term(self.sandbox().read_file_op())),
+      "fs.write" => Some(io_term(self.sandbox().write_file_op())),
+      "fs.append" => Some(io_term(self.sandbox().append_file_op())),
+      "fs.list_dir" => Some(io_term(self.sandbox().list_dir_op())),
+      "fs.exists" => Some(io_term(self.sandbox().file_exists_op())),
+      _ => None,
+    }
+  }
+  /// Lists the extern symbols this runtime can resolve
+  fn extern_symbols(&self) -> Vec<String> {
+    let mut symbols: Vec<String> = [
+      "fs.append",
+      "fs.exists",
+      "fs.list_dir",
+      "fs.open",
+      "fs.read",
+      "fs.write",
+      "io.args",
+      "io.bind",
+      "io.exit",
+      "io.get_env",
+      "io.now",
+      "io.print",
+      "io.random_bytes",
+      "io.read",
+      "io.return",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    symbols.extend(self.host_fns().names());
+    symbols.sort();
+    symbols.dedup();
+    symbols
+  }
+  /// Host functions registered by an embedder. They take precedence over the
+  /// built-in symbols, and the type of an `extern` declaration must match the
+  /// type they were registered with.
+  fn host_fns(&self) -> Rc<HostFns> { Rc::new(HostFns::new()) }
+  /// The directories file primitives may access. Nothing is granted by
+  /// default, so file primitives return errors unless a runtime overrides
+  /// this.
+  fn sandbox(&self) -> Sandbox { Sandbox::default() }
+  /// The arguments passed to the program, returned by `io.args`
+  fn args(&self) -> Vec<String> { Vec::new() }
+  /// `io.get_env`: looks up an environment variable, returning a
+  /// Church-encoded option. By default no variables are set.
+  fn get_env_op(&self) -> IoOp { env_op(|_| None) }
+  /// The clock and random number generator behind `io.now` and
+  /// `io.random_bytes`. By default these read the system time and a freshly
+  /// seeded generator.
+  fn nondet(&self) -> Rc<RefCell<Nondet>> {
+    Rc::new(RefCell::new(Nondet::default()))
+  }
   fn write_stdout_op(&self) -> IoOp {
     todo!("write_stdout not implemented for this runtime")
   }
@@ -136,40 +346,180 @@ pub trait RuntimeIO {
 /// An Rc wrapper for a RuntimeIO instance
 pub type RunIO = Rc<dyn RuntimeIO>;
 
-/// A RuntimeIO using std::io
+/// Wraps an IO primitive as a term
+fn io_term(op: IoOp) -> Term { Term::Opr(Pos::None, Op::Io(op)) }
+
+/// The symbol of the `io.exit` primitive, which the runtime handles by
+/// stopping evaluation with the given status
+pub const EXIT_SYMBOL: &str = "exit";
+
+thread_local! {
+  static EXIT_OP: IoOp = {
+    fn fun_symbol() -> String { EXIT_SYMBOL.to_string() }
+    fn fun_type_of() -> Term { yatima!("∀ (0 A: Type) #U8 -> A") }
+    fn fun_arity() -> u64 { 1 }
+    let fun_apply = Rc::new(|_: &[Literal]| -> Option<Literal> { None });
+    let fun_apply_term = Rc::new(|_: &[Literal]| -> Option<Term> { None });
+    IoOp {
+      fun_symbol: Rc::new(fun_symbol),
+      fun_type_of: Rc::new(fun_type_of),
+      fun_arity: Rc::new(fun_arity),
+      fun_apply,
+      fun_apply_term,
+    }
+  };
+}
+
+/// `io.exit`: stops the program with an integer status. It never reduces to a
+/// value; `runtime::whnf` intercepts it instead.
+pub fn exit_op() -> IoOp { EXIT_OP.with(IoOp::clone) }
+
+/// Whether a primitive is the one returned by `exit_op`. Host functions may
+/// share its symbol, so it is told apart by the functions it was built with.
+pub fn is_exit_op(op: &IoOp) -> bool {
+  EXIT_OP.with(|exit| {
+    Rc::as_ptr(&op.fun_apply) as *const u8
+      == Rc::as_ptr(&exit.fun_apply) as *const u8
+  })
+}
+
+/// The symbol of the primitive behind `io.bind`, which the runtime handles by
+/// reducing its first argument before continuing with the second
+pub const SEQ_SYMBOL: &str = "seq";
+
+thread_local! {
+  static SEQ_OP: IoOp = {
+    fn fun_symbol() -> String { SEQ_SYMBOL.to_string() }
+    fn fun_type_of() -> Term { yatima!("∀ (0 A: Type) (0 B: Type) A B -> B") }
+    fn fun_arity() -> u64 { 2 }
+    let fun_apply = Rc::new(|_: &[Literal]| -> Option<Literal> { None });
+    let fun_apply_term = Rc::new(|_: &[Literal]| -> Option<Term> { None });
+    IoOp {
+      fun_symbol: Rc::new(fun_symbol),
+      fun_type_of: Rc::new(fun_type_of),
+      fun_arity: Rc::new(fun_arity),
+      fun_apply,
+      fun_apply_term,
+    }
+  };
+}
+
+/// Sequences the action of `io.bind` before its continuation: applied to an
+/// action and a continuation, it reduces the action to weak head normal form,
+/// performing its IO, then reduces to the continuation. Its arguments need not
+/// be literals, so `runtime::whnf` intercepts it rather than applying it.
+pub fn seq_op() -> IoOp { SEQ_OP.with(IoOp::clone) }
+
+/// Whether a primitive is the one returned by `seq_op`
+pub fn is_seq_op(op: &IoOp) -> bool {
+  SEQ_OP.with(|seq| {
+    Rc::as_ptr(&op.fun_apply) as *const u8
+      == Rc::as_ptr(&seq.fun_apply) as *const u8
+  })
+}
+
+/// Builds the `io.get_env` primitive from a lookup function
+pub fn env_op(lookup: impl Fn(&str) -> Option<String> + 'static) -> IoOp {
+  fn fun_symbol() -> String { "get_env".to_string() }
+  fn fun_type_of() -> Term {
+    yatima!("∀ #Text -> ∀ (0 P: Type) (& none: P) (& some: ∀ #Text -> P) -> P")
+  }
+  fn fun_arity() -> u64 { 1 }
+  let fun_apply = Rc::new(|_: &[Literal]| -> Option<Literal> { None });
+  let fun_apply_term = Rc::new(move |args: &[Literal]| -> Option<Term> {
+    match args {
+      [Literal::Text(name)] => Some(option_term(
+        lookup(&name.to_string()).map(|val| Literal::Text(val.as_str().into())),
+      )),
+      _ => None,
+    }
+  });
+  IoOp {
+    fun_symbol: Rc::new(fun_symbol),
+    fun_type_of: Rc::new(fun_type_of),
+    fun_arity: Rc::new(fun_arity),
+    fun_apply,
+    fun_apply_term,
+  }
+}
+
+/// A RuntimeIO using std::io and std::fs
 #[derive(Clone)]
-pub struct StdIORuntime {}
+pub struct StdIORuntime {
+  sandbox: Sandbox,
+  args: Vec<String>,
+  nondet: Rc<RefCell<Nondet>>,
+  host_fns: Rc<HostFns>,
+}
 
 impl StdIORuntime {
   pub fn new() -> Self {
-    StdIORuntime {}
+    StdIORuntime {
+      sandbox: Sandbox::default(),
+      args: Vec::new(),
+      nondet: Rc::new(RefCell::new(Nondet::default())),
+      host_fns: Rc::new(HostFns::new()),
+    }
+  }
+
+  /// Confines the runtime's file primitives to the given sandbox
+  pub fn with_sandbox(mut self, sandbox: Sandbox) -> Self {
+    self.sandbox = sandbox;
+    self
+  }
+
+  /// Sets the arguments returned by `io.args`
+  pub fn with_args(mut self, args: Vec<String>) -> Self {
+    self.args = args;
+    self
+  }
+
+  /// Sets the clock, generator and log used by `io.now` and
+  /// `io.random_bytes`
+  pub fn with_nondet(mut self, nondet: Nondet) -> Self {
+    self.nondet = Rc::new(RefCell::new(nondet));
+    self
+  }
+
+  /// Makes the given host functions available to `extern` declarations
+  pub fn with_host_fns(mut self, host_fns: HostFns) -> Self {
+    self.host_fns = Rc::new(host_fns);
+    self
   }
 }
 
 impl RuntimeIO for StdIORuntime {
+  fn sandbox(&self) -> Sandbox { self.sandbox.clone() }
+
+  fn args(&self) -> Vec<String> { self.args.clone() }
+
+  fn nondet(&self) -> Rc<RefCell<Nondet>> { self.nondet.clone() }
+
+  fn host_fns(&self) -> Rc<HostFns> { self.host_fns.clone() }
+
+  fn get_env_op(&self) -> IoOp { env_op(|name| std::env::var(name).ok()) }
+
   fn write_stdout_op(&self) -> IoOp {
     fn fun_symbol() -> String { "write_stdout".to_string() }
     fn fun_type_of() -> Term { yatima!("#Bytes -> #Bool") }
     fn fun_arity() -> u64 { 1 }
-    fn fun_apply0() -> Option<Literal> { None }
-    let fun_apply1 = Rc::new(|lit: Literal| -> Option<Literal> {
-      match lit {
-        Literal::Bytes(bytes) => {
+    let fun_apply = Rc::new(|args: &[Literal]| -> Option<Literal> {
+      match args {
+        [Literal::Bytes(bytes)] => {
           use std::io::Write;
           let mut stdout = std::io::stdout();
-          Some(Literal::Bool(stdout.write_all(&bytes).is_ok()))
+          Some(Literal::Bool(stdout.write_all(bytes).is_ok()))
         }
         _ => None,
       }
     });
-    fn fun_apply2(_: Literal, _: Literal) -> Option<Literal> { None }
+    let fun_apply_term = Rc::new(|_: &[Literal]| -> Option<Term> { None });
     IoOp {
-      fun_symbol,
-      fun_type_of,
-      fun_arity,
-      fun_apply0,
-      fun_apply1,
-      fun_apply2,
+      fun_symbol: Rc::new(fun_symbol),
+      fun_type_of: Rc::new(fun_type_of),
+      fun_arity: Rc::new(fun_arity),
+      fun_apply,
+      fun_apply_term,
     }
   }
 
@@ -177,21 +527,19 @@ impl RuntimeIO for StdIORuntime {
     fn fun_symbol() -> String { "read_stdin".to_string() }
     fn fun_type_of() -> Term { yatima!("#Bytes") }
     fn fun_arity() -> u64 { 0 }
-    fn fun_apply0() -> Option<Literal> {
+    let fun_apply = Rc::new(|_: &[Literal]| -> Option<Literal> {
       let stdin = std::io::stdin();
       let mut buf = String::new();
       let _ = stdin.read_line(&mut buf).unwrap();
       Some(Literal::Bytes(buf.as_bytes().to_vec()))
-    }
-    let fun_apply1 = Rc::new(|_: Literal| -> Option<Literal> { None });
-    fn fun_apply2(_: Literal, _: Literal) -> Option<Literal> { None }
+    });
+    let fun_apply_term = Rc::new(|_: &[Literal]| -> Option<Term> { None });
     IoOp {
-      fun_symbol,
-      fun_type_of,
-      fun_arity,
-      fun_apply0,
-      fun_apply1,
-      fun_apply2,
+      fun_symbol: Rc::new(fun_symbol),
+      fun_type_of: Rc::new(fun_type_of),
+      fun_arity: Rc::new(fun_arity),
+      fun_apply,
+      fun_apply_term,
     }
   }
 }
@@ -0,0 +1,645 @@
+//! A bytecode compiler and abstract machine for erased terms, an alternative
+//! to graph reduction selected with `Engine::Vm`.
+//!
+//! Terms compile to blocks of instructions for a lazy Krivine machine.
+//! Applications push their arguments as thunks, lambdas pop them into their
+//! environment, and a thunk is overwritten with its value the first time it
+//! is forced. Arguments are thus evaluated at most once and only when needed,
+//! as in graph reduction, and programs perform the same IO on both engines.
+//! The machine keeps its continuation on an explicit stack, so deep terms
+//! cannot overflow the native stack.
+
+use yatima_core::{
+  defs::Defs,
+  limits::Meter,
+  literal::Literal,
+  prim::{
+    text::TextOp,
+    Op,
+  },
+  term::Term,
+};
+
+use crate::{
+  error::RuntimeError,
+  runtime::{
+    exit_code,
+    is_exit,
+    is_seq,
+  },
+};
+
+use std::{
+  cell::{
+    Cell,
+    Ref,
+    RefCell,
+    RefMut,
+  },
+  rc::Rc,
+  vec::Vec,
+};
+
+/// An instruction of the machine. A block of code is a run of `Arg`, `Grab`
+/// and `Let` instructions ending in one that enters or returns a value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instr {
+  /// Pushes a thunk of a block, closed over the environment, as the argument
+  /// of an application
+  Arg(usize),
+  /// Pushes the thunk of a variable as the argument of an application
+  ArgVar(usize),
+  /// Pops an argument into the environment. Without an argument, the lambda
+  /// starting at this instruction is a value.
+  Grab,
+  /// Binds a thunk of a block, closed over the environment
+  Let(usize),
+  /// Binds a thunk of a block, closed over the environment it extends
+  LetRec(usize),
+  /// Enters the thunk of a variable
+  Var(usize),
+  /// Enters a definition: a closed block whose environment only holds a
+  /// thunk of the definition itself, the target of `Rec`
+  Def(usize),
+  Lit(Literal),
+  Opr(Op),
+}
+
+/// Compiled code. The first block is the entry point.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Program {
+  pub blocks: Vec<Vec<Instr>>,
+}
+
+impl Program {
+  /// Compiles an erased term, such as the result of `transform::transform`.
+  /// Fails on a reference to a definition missing from `defs`.
+  pub fn compile(defs: &Defs, term: &Term) -> Result<Self, RuntimeError> {
+    let mut program = Program::default();
+    program.add(defs, term)?;
+    Ok(program)
+  }
+
+  /// Compiles a closed term into new blocks and returns the index of the
+  /// first one
+  pub fn add(
+    &mut self,
+    defs: &Defs,
+    term: &Term,
+  ) -> Result<usize, RuntimeError> {
+    let mut work = vec![];
+    let entry = self.reserve(term, 0, &mut work);
+    while let Some((block, term, depth)) = work.pop() {
+      self.blocks[block] = self.compile_block(defs, term, depth, &mut work)?;
+    }
+    Ok(entry)
+  }
+
+  /// Allocates a block for `term`, to be compiled from `work`. `depth` is the
+  /// number of variables bound since the start of the enclosing definition.
+  fn reserve<'a>(
+    &mut self,
+    term: &'a Term,
+    depth: usize,
+    work: &mut Vec<(usize, &'a Term, usize)>,
+  ) -> usize {
+    self.blocks.push(vec![]);
+    work.push((self.blocks.len() - 1, term, depth));
+    self.blocks.len() - 1
+  }
+
+  /// Compiles the spine of a term into a block, reserving blocks for its
+  /// arguments and bound expressions
+  fn compile_block<'a>(
+    &mut self,
+    defs: &'a Defs,
+    term: &'a Term,
+    depth: usize,
+    work: &mut Vec<(usize, &'a Term, usize)>,
+  ) -> Result<Vec<Instr>, RuntimeError> {
+    let mut code = vec![];
+    let mut term = term;
+    let mut depth = depth;
+    loop {
+      match term {
+        // Data, case and annotation nodes are erased
+        Term::Dat(_, bod) | Term::Cse(_, bod) => term = &**bod,
+        Term::Ann(_, typ_exp) => term = &typ_exp.1,
+        Term::Lam(_, _, bod) => {
+          code.push(Instr::Grab);
+          depth += 1;
+          term = &**bod;
+        }
+        Term::App(_, fun_arg) => {
+          let (fun, arg) = &**fun_arg;
+          code.push(match arg {
+            Term::Var(_, _, idx) => Instr::ArgVar(*idx as usize),
+            Term::Rec(_) => Instr::ArgVar(depth),
+            _ => Instr::Arg(self.reserve(arg, depth, work)),
+          });
+          term = fun;
+        }
+        Term::Let(_, rec, _, _, typ_exp_bod) => {
+          let (_, exp, bod) = &**typ_exp_bod;
+          code.push(if *rec {
+            Instr::LetRec(self.reserve(exp, depth + 1, work))
+          }
+          else {
+            Instr::Let(self.reserve(exp, depth, work))
+          });
+          depth += 1;
+          term = bod;
+        }
+        Term::Var(_, _, idx) => {
+          code.push(Instr::Var(*idx as usize));
+          break;
+        }
+        // The definition itself is bound below all of its variables
+        Term::Rec(_) => {
+          code.push(Instr::Var(depth));
+          break;
+        }
+        Term::Ref(_, nam, exp, _) => match defs.defs.get(exp) {
+          Some(def) => {
+            code.push(Instr::Def(self.reserve(&def.term, 0, work)));
+            break;
+          }
+          None => {
+            return Err(RuntimeError::UndefinedReference(
+              nam.to_string(),
+              exp.to_string(),
+            ));
+          }
+        },
+        Term::Lit(_, lit) => {
+          code.push(Instr::Lit(lit.clone()));
+          break;
+        }
+        Term::Opr(_, opr) => {
+          code.push(Instr::Opr(opr.clone()));
+          break;
+        }
+        // Types are erased to a placeholder, as in graph reduction
+        _ => {
+          code.push(Instr::Lit(Literal::I32(0)));
+          break;
+        }
+      }
+    }
+    Ok(code)
+  }
+}
+
+type Thunk = Rc<ThunkCell>;
+
+/// The state of a thunk, counted as a node of the machine
+struct ThunkCell {
+  state: RefCell<State>,
+  _counted: Counted,
+}
+
+impl ThunkCell {
+  fn borrow(&self) -> Ref<'_, State> { self.state.borrow() }
+
+  fn borrow_mut(&self) -> RefMut<'_, State> { self.state.borrow_mut() }
+}
+
+/// An environment, innermost variable first
+type Env = Option<Rc<Bind>>;
+
+struct Bind {
+  thunk: Thunk,
+  next: Env,
+  _counted: Counted,
+}
+
+enum State {
+  /// A block to run in an environment
+  Delayed(usize, Env),
+  Forced(Value),
+  /// Being forced. Entering it again means the thunk depends on itself.
+  Blackhole,
+}
+
+/// A term in weak head normal form
+#[derive(Clone)]
+enum Value {
+  /// The block, position of the `Grab` and environment of a lambda
+  Lam(usize, usize, Env),
+  Lit(Literal),
+  /// A primitive operation with fewer arguments than its arity
+  Opr(Op, Vec<Thunk>),
+}
+
+/// What to do with the value the machine returns
+enum Cont {
+  /// Apply it to an argument
+  Arg(Thunk),
+  /// Overwrite a thunk with it
+  Update(Thunk),
+  /// Add it to the literal arguments of a primitive operation, then force
+  /// the remaining argument thunks, last first
+  Force(Op, Vec<Thunk>, Vec<Literal>),
+  /// Drop it and enter a thunk: the continuation of the action of `io.bind`
+  Then(Thunk),
+}
+
+/// The continuation stack of the machine. Applying a variable to itself can
+/// grow it without allocating thunks, so its entries count as nodes too.
+#[derive(Default)]
+struct Stack(Vec<(Cont, Counted)>);
+
+impl Stack {
+  fn push(&mut self, cont: Cont) { self.0.push((cont, Counted::new())) }
+
+  fn pop(&mut self) -> Option<Cont> { self.0.pop().map(|(cont, _)| cont) }
+
+  fn last(&self) -> Option<&Cont> { self.0.last().map(|(cont, _)| cont) }
+}
+
+enum Control {
+  Code(usize, usize, Env),
+  Enter(Thunk),
+  Return(Value),
+  /// The term cannot reduce further
+  Stuck,
+}
+
+impl State {
+  fn take_children(
+    &mut self,
+    thunks: &mut Vec<Thunk>,
+    envs: &mut Vec<Rc<Bind>>,
+  ) {
+    match self {
+      State::Delayed(_, env) | State::Forced(Value::Lam(_, _, env)) => {
+        envs.extend(env.take())
+      }
+      State::Forced(Value::Opr(_, args)) => thunks.append(args),
+      _ => (),
+    }
+  }
+}
+
+impl Drop for State {
+  /// Frees the thunks only reachable from this one with a work list, as a
+  /// long chain of suspended computations would otherwise be dropped
+  /// recursively
+  fn drop(&mut self) {
+    let mut thunks = vec![];
+    let mut envs = vec![];
+    self.take_children(&mut thunks, &mut envs);
+    loop {
+      if let Some(env) = envs.pop() {
+        if let Ok(Bind { thunk, next, .. }) = Rc::try_unwrap(env) {
+          thunks.push(thunk);
+          envs.extend(next);
+        }
+      }
+      else if let Some(thunk) = thunks.pop() {
+        if let Ok(cell) = Rc::try_unwrap(thunk) {
+          cell.state.into_inner().take_children(&mut thunks, &mut envs);
+        }
+      }
+      else {
+        break;
+      }
+    }
+  }
+}
+
+std::thread_local! {
+  static ALLOCATED: Cell<usize> = Cell::new(0);
+  static FREED: Cell<usize> = Cell::new(0);
+}
+
+/// The number of thunks, environment entries and stack entries allocated by
+/// the machine on this thread so far
+pub fn allocated() -> usize { ALLOCATED.with(|n| n.get()) }
+
+/// The number of thunks, environment entries and stack entries freed by the
+/// machine on this thread so far
+pub fn freed() -> usize { FREED.with(|n| n.get()) }
+
+/// Counts the allocation of a thunk, environment entry or stack entry, and
+/// its freeing once dropped
+struct Counted;
+
+impl Counted {
+  fn new() -> Self {
+    ALLOCATED.with(|n| n.set(n.get() + 1));
+    Counted
+  }
+}
+
+impl Drop for Counted {
+  fn drop(&mut self) { FREED.with(|n| n.set(n.get() + 1)) }
+}
+
+fn thunk(state: State) -> Thunk {
+  Rc::new(ThunkCell { state: RefCell::new(state), _counted: Counted::new() })
+}
+
+fn bind(thunk: Thunk, next: Env) -> Env {
+  Some(Rc::new(Bind { thunk, next, _counted: Counted::new() }))
+}
+
+fn lookup(env: &Env, idx: usize) -> Result<Thunk, RuntimeError> {
+  let mut env = env;
+  for _ in 0..idx {
+    env = &env.as_ref().ok_or(RuntimeError::FreeVariable(idx))?.next;
+  }
+  Ok(env.as_ref().ok_or(RuntimeError::FreeVariable(idx))?.thunk.clone())
+}
+
+/// Compiles an erased term and reduces it to weak head normal form on the
+/// machine, executing its IO operations. Like `runtime::whnf`, it fails on
+/// `#Text.abort` and `io.exit`, as well as on a reference or variable the term
+/// does not bind, and stops without error on a term that cannot reduce
+/// further, such as a primitive applied to a non-literal or a value that
+/// depends on itself. Beta reductions, definition unfoldings and
+/// primitive applications are charged to `meter` as steps, and thunks,
+/// environment entries, stack entries and the instructions compiled from
+/// primitive results as nodes.
+pub fn run(
+  defs: &Defs,
+  term: &Term,
+  meter: &mut Meter,
+) -> Result<(), RuntimeError> {
+  let mut program = Program::compile(defs, term)?;
+  let mut stack = Stack::default();
+  // The term is entered as a definition, the target of its `Rec`
+  let main = thunk(State::Blackhole);
+  *main.borrow_mut() = State::Delayed(0, bind(main.clone(), None));
+  let mut control = Control::Enter(main);
+  loop {
+    control = match control {
+      Control::Code(block, pc, env) => {
+        match program.blocks[block][pc].clone() {
+          Instr::Arg(arg) => {
+            stack.push(Cont::Arg(thunk(State::Delayed(arg, env.clone()))));
+            Control::Code(block, pc + 1, env)
+          }
+          Instr::ArgVar(idx) => {
+            stack.push(Cont::Arg(lookup(&env, idx)?));
+            Control::Code(block, pc + 1, env)
+          }
+          Instr::Grab => match stack.last() {
+            Some(Cont::Arg(_)) => {
+              meter.step().map_err(RuntimeError::Limit)?;
+              let arg = match stack.pop() {
+                Some(Cont::Arg(arg)) => arg,
+                _ => unreachable!(),
+              };
+              Control::Code(block, pc + 1, bind(arg, env))
+            }
+            _ => Control::Return(Value::Lam(block, pc, env)),
+          },
+          Instr::Let(exp) => {
+            let exp = thunk(State::Delayed(exp, env.clone()));
+            Control::Code(block, pc + 1, bind(exp, env))
+          }
+          Instr::LetRec(exp) => {
+            let fix = thunk(State::Blackhole);
+            let env = bind(fix.clone(), env);
+            *fix.borrow_mut() = State::Delayed(exp, env.clone());
+            Control::Code(block, pc + 1, env)
+          }
+          Instr::Var(idx) => Control::Enter(lookup(&env, idx)?),
+          Instr::Def(def) => {
+            meter.step().map_err(RuntimeError::Limit)?;
+            let fix = thunk(State::Blackhole);
+            *fix.borrow_mut() = State::Delayed(def, bind(fix.clone(), None));
+            Control::Enter(fix)
+          }
+          Instr::Lit(lit) => Control::Return(Value::Lit(lit)),
+          Instr::Opr(opr) => {
+            if opr.arity() > 0 {
+              Control::Return(Value::Opr(opr, vec![]))
+            }
+            // Like graph reduction, applies a nullary operation only when it
+            // has no arguments
+            else if let Some(Cont::Arg(_)) = stack.last() {
+              Control::Stuck
+            }
+            else {
+              saturate(&mut program, opr, vec![], &mut stack, meter)?
+            }
+          }
+        }
+      },
+      Control::Enter(thunk) => {
+        let control = match &*thunk.borrow() {
+          State::Forced(value) => Control::Return(value.clone()),
+          State::Delayed(block, env) => Control::Code(*block, 0, env.clone()),
+          // A thunk that depends on itself never reduces, so the term is
+          // stuck
+          State::Blackhole => Control::Stuck,
+        };
+        if let Control::Code(..) = control {
+          *thunk.borrow_mut() = State::Blackhole;
+          stack.push(Cont::Update(thunk));
+        }
+        control
+      }
+      Control::Return(value) => match stack.pop() {
+        None => return Ok(()),
+        Some(Cont::Update(thunk)) => {
+          *thunk.borrow_mut() = State::Forced(value.clone());
+          Control::Return(value)
+        }
+        Some(Cont::Arg(arg)) => match value {
+          Value::Lam(block, pc, env) => {
+            meter.step().map_err(RuntimeError::Limit)?;
+            Control::Code(block, pc + 1, bind(arg, env))
+          }
+          Value::Opr(opr, mut args) => {
+            args.push(arg);
+            if (args.len() as u64) < opr.arity() {
+              Control::Return(Value::Opr(opr, args))
+            }
+            else {
+              saturate(&mut program, opr, args, &mut stack, meter)?
+            }
+          }
+          // Applying a literal is stuck
+          Value::Lit(_) => Control::Stuck,
+        },
+        Some(Cont::Force(opr, mut thunks, mut lits)) => match value {
+          Value::Lit(lit) => {
+            lits.push(lit);
+            match thunks.pop() {
+              Some(next) => {
+                stack.push(Cont::Force(opr, thunks, lits));
+                Control::Enter(next)
+              }
+              None => apply(&mut program, opr, lits, meter)?,
+            }
+          }
+          // A primitive applied to a non-literal is stuck
+          _ => Control::Stuck,
+        },
+        Some(Cont::Then(next)) => Control::Enter(next),
+      },
+      Control::Stuck => return Ok(()),
+    };
+  }
+}
+
+/// Forces the arguments of a primitive operation applied to as many
+/// arguments as its arity, first argument first. `#Text.abort` only forces
+/// its message, and the primitive behind `io.bind` forces its action, which
+/// need not be a literal, then enters its continuation.
+fn saturate(
+  program: &mut Program,
+  opr: Op,
+  args: Vec<Thunk>,
+  stack: &mut Stack,
+  meter: &mut Meter,
+) -> Result<Control, RuntimeError> {
+  if is_seq(&opr) {
+    meter.step().map_err(RuntimeError::Limit)?;
+    let mut args = args;
+    let next = args.pop().unwrap();
+    stack.push(Cont::Then(next));
+    return Ok(Control::Enter(args.pop().unwrap()));
+  }
+  let mut thunks =
+    if opr == Op::Text(TextOp::Abort) { args[1..].to_vec() } else { args };
+  thunks.reverse();
+  match thunks.pop() {
+    Some(next) => {
+      stack.push(Cont::Force(opr, thunks, vec![]));
+      Ok(Control::Enter(next))
+    }
+    None => apply(program, opr, vec![], meter),
+  }
+}
+
+/// Applies a primitive operation to its literal arguments. A result that is
+/// not a literal is compiled into new blocks of the program, which stay in it
+/// for the rest of the run and so count as live nodes, one per instruction.
+fn apply(
+  program: &mut Program,
+  opr: Op,
+  lits: Vec<Literal>,
+  meter: &mut Meter,
+) -> Result<Control, RuntimeError> {
+  if opr == Op::Text(TextOp::Abort) {
+    return match &lits[0] {
+      Literal::Text(msg) => Err(RuntimeError::Abort(msg.to_string())),
+      _ => Ok(Control::Stuck),
+    };
+  }
+  if is_exit(&opr) {
+    return match exit_code(&lits[0]) {
+      Some(code) => Err(RuntimeError::Exit(code)),
+      None => Ok(Control::Stuck),
+    };
+  }
+  meter.step().map_err(RuntimeError::Limit)?;
+  if let Op::Io(_) = opr {
+    meter.io().map_err(RuntimeError::Limit)?;
+  }
+  match opr.apply(&lits) {
+    Some(res) => Ok(Control::Return(Value::Lit(res))),
+    None => match opr.apply_term(&lits) {
+      Some(res) => {
+        let block = program.add(&Defs::new(), &res)?;
+        let instrs: usize = program.blocks[block..].iter().map(Vec::len).sum();
+        ALLOCATED.with(|n| n.set(n.get() + instrs));
+        meter.check_nodes().map_err(RuntimeError::Limit)?;
+        Ok(Control::Code(block, 0, None))
+      }
+      None => Ok(Control::Stuck),
+    },
+  }
+}
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+  use yatima_core::{
+    defs,
+    limits::{
+      LimitError,
+      Limits,
+    },
+    name::Name,
+    parse::{
+      self,
+      package::parse_defs,
+      span::Span,
+      term::input_cid,
+    },
+    position::Pos,
+    prim::nat::NatOp,
+    yatima,
+  };
+
+  #[test]
+  fn test_compile() {
+    let program = Program::compile(
+      &Defs::new(),
+      &yatima!("λ f x => (λ y => f y y) (#Nat.add x 1)"),
+    )
+    .unwrap();
+    assert_eq!(program.blocks, vec![
+      vec![
+        Instr::Grab,
+        Instr::Grab,
+        Instr::Arg(1),
+        Instr::Grab,
+        Instr::ArgVar(0),
+        Instr::ArgVar(0),
+        Instr::Var(2),
+      ],
+      vec![Instr::Arg(2), Instr::ArgVar(0), Instr::Opr(Op::Nat(NatOp::Add)),],
+      vec![Instr::Lit(Literal::Nat(1u64.into()))],
+    ]);
+  }
+
+  #[test]
+  fn test_sharing() {
+    // The argument is forced once, by the first addition
+    let mut meter = Meter::default();
+    let term = yatima!("(λ x => #Nat.add x x) (#Nat.mul 3 4)");
+    assert_eq!(run(&Defs::new(), &term, &mut meter), Ok(()));
+    assert_eq!(meter.steps, 3);
+  }
+
+  #[test]
+  fn test_self_dependent() {
+    // A value that depends on itself is stuck rather than looping
+    let term = yatima!("letrec x: #Nat = x; x");
+    assert_eq!(run(&Defs::new(), &term, &mut Meter::default()), Ok(()));
+  }
+
+  #[test]
+  fn test_ill_formed() {
+    // Terms the checker would refuse fail the run instead of panicking
+    let src = "def one: #Nat = 1";
+    let (_, (defs, _)) =
+      parse_defs(input_cid(src), Defs::new())(Span::new(src)).unwrap();
+    let term = parse::term::parse("one", defs).unwrap().1;
+    let res = run(&Defs::new(), &term, &mut Meter::default());
+    assert!(matches!(res, Err(RuntimeError::UndefinedReference(..))));
+    let term = Term::Var(Pos::None, Name::from("x"), 1);
+    let res = run(&Defs::new(), &term, &mut Meter::default());
+    assert_eq!(res, Err(RuntimeError::FreeVariable(1)));
+  }
+
+  #[test]
+  fn test_compiled_result_nodes() {
+    // The blocks a result compiles to count against the node limit
+    let limits = Limits { max_nodes: Some(10), ..Limits::default() };
+    let mut meter = Meter::with_counters(limits, allocated, freed);
+    let lits = vec![
+      Literal::Text(",".into()),
+      Literal::Text("a,".repeat(100).as_str().into()),
+    ];
+    let split = Op::Text(TextOp::Split);
+    let res = apply(&mut Program::default(), split, lits, &mut meter);
+    assert_eq!(res.err(), Some(RuntimeError::Limit(LimitError::Nodes(10))));
+  }
+}
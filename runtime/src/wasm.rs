@@ -0,0 +1,946 @@
+//! Compiles programs to standalone WebAssembly modules.
+//!
+//! The bytecode of `vm` is translated to a WebAssembly text module, then
+//! assembled. Every run of instructions up to a `Grab` becomes a function,
+//! and a small runtime written in WebAssembly provides the rest of the lazy
+//! machine: a bump allocated heap of thunks, environments and values, the
+//! continuation stack and the primitive operations. The module needs no
+//! interpreter and never frees memory.
+//!
+//! The module exports its `memory` and a `main` function returning the exit
+//! status, and imports its IO from the `yatima` module, mirroring `RuntimeIO`:
+//!
+//! - `write_stdout(ptr: i32, len: i32) -> i32` writes bytes to stdout and
+//!   returns whether it succeeded
+//! - `read_stdin_len() -> i32` reads a line of stdin and returns its length
+//! - `read_stdin(ptr: i32)` copies the line last read to memory
+//! - `abort(ptr: i32, len: i32)` receives the UTF-8 message of `#Text.abort`,
+//!   after which the module traps
+//!
+//! Only the externs `io.return`, `io.print`, `io.read`, `io.bind` and
+//! `io.exit` are available. `#Nat` is represented by 64-bit integers, and
+//! the module traps when one overflows. Literals of other types than `#Nat`,
+//! `#Bool`, `#Text`, `#Bytes` and `#U8` and the primitive operations missing
+//! from `OPS` are rejected when compiling.
+
+use std::{
+  convert::TryFrom,
+  fmt,
+  rc::Rc,
+};
+
+use yatima_core::{
+  defs::Defs,
+  literal::Literal,
+  prim::Op,
+  term::Term,
+};
+
+use crate::{
+  error::RuntimeError,
+  transform::{
+    self,
+    RuntimeIO,
+    StdIORuntime,
+  },
+  vm::{
+    Instr,
+    Program,
+  },
+};
+
+/// Errors that may occur while compiling to WebAssembly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WasmError {
+  /// Resolving the externs of the program failed
+  Runtime(RuntimeError),
+  /// A literal or primitive operation the target does not support
+  Unsupported(String),
+  /// The generated text module did not assemble
+  Assemble(String),
+}
+
+impl fmt::Display for WasmError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Runtime(e) => write!(f, "{}", e),
+      Self::Unsupported(what) => {
+        write!(f, "The wasm target does not support {}", what)
+      }
+      Self::Assemble(e) => write!(f, "Invalid WebAssembly generated: {}", e),
+    }
+  }
+}
+
+// Tags of the literals on the heap. Lambdas are tagged 0.
+const NAT: u32 = 1;
+const BOOL: u32 = 2;
+const TEXT: u32 = 3;
+const BYTES: u32 = 4;
+const OPR: u32 = 5;
+const I32: u32 = 6;
+const U8: u32 = 7;
+
+/// The primitive operations the target supports, in the order of their
+/// opcodes, with the tags of the arguments they force. Each body applies the
+/// operation to the literals in `$l`, in the style of the runtime below:
+/// `%x` and `%y` are the first two arguments as `#Nat`s, `%i` and `%j` as
+/// `#Bool`s or `#U8`s and `%a` and `%b` as heap values. The runtime handles
+/// `seq`, the primitive behind `io.bind`, without applying it.
+#[rustfmt::skip]
+const OPS: &[(&str, &[u32], &str)] = &[
+  ("#Text.abort", &[TEXT], "
+    (call $abort (i32.load offset=4 %a) (i32.load offset=8 %a))
+    (unreachable)"),
+  ("seq", &[], "(unreachable)"),
+  ("exit", &[U8], "(call $set (i32.const 4) (i32.load offset=4 %a) (i32.const 0))"),
+  ("write_stdout", &[BYTES], "
+    (call $ret (call $mk_bool (i32.ne
+      (call $write_stdout (i32.load offset=4 %a) (i32.load offset=8 %a))
+      (i32.const 0))))"),
+  ("read_stdin", &[], "
+    (local.set $n (call $read_stdin_len))
+    (local.set $p (call $alloc (local.get $n)))
+    (call $read_stdin (local.get $p))
+    (call $ret (call $mk_bytes (i32.const 4) (local.get $p) (local.get $n)))"),
+  ("#Nat.suc", &[NAT], "
+    (if (i64.eq %x (i64.const -1)) (then (unreachable)))
+    (call $ret (call $mk_nat (i64.add %x (i64.const 1))))"),
+  ("#Nat.pre", &[NAT], "
+    (call $ret (call $mk_nat
+      (select (i64.sub %x (i64.const 1)) (i64.const 0) (i64.ne %x (i64.const 0)))))"),
+  ("#Nat.eql", &[NAT, NAT], "(call $ret (call $mk_bool (i64.eq %x %y)))"),
+  ("#Nat.lte", &[NAT, NAT], "(call $ret (call $mk_bool (i64.le_u %x %y)))"),
+  ("#Nat.lth", &[NAT, NAT], "(call $ret (call $mk_bool (i64.lt_u %x %y)))"),
+  ("#Nat.gte", &[NAT, NAT], "(call $ret (call $mk_bool (i64.ge_u %x %y)))"),
+  ("#Nat.gth", &[NAT, NAT], "(call $ret (call $mk_bool (i64.gt_u %x %y)))"),
+  ("#Nat.add", &[NAT, NAT], "
+    (if (i64.lt_u (i64.add %x %y) %x) (then (unreachable)))
+    (call $ret (call $mk_nat (i64.add %x %y)))"),
+  ("#Nat.sub", &[NAT, NAT], "
+    (if (i64.lt_u %x %y) (then (call $halt) (return)))
+    (call $ret (call $mk_nat (i64.sub %x %y)))"),
+  ("#Nat.mul", &[NAT, NAT], "
+    (if (i64.ne %x (i64.const 0))
+      (then (if (i64.gt_u %y (i64.div_u (i64.const -1) %x)) (then (unreachable)))))
+    (call $ret (call $mk_nat (i64.mul %x %y)))"),
+  ("#Nat.div", &[NAT, NAT], "
+    (if (i64.eqz %y) (then (call $halt) (return)))
+    (call $ret (call $mk_nat (i64.div_u %x %y)))"),
+  ("#Nat.mod", &[NAT, NAT], "
+    (if (i64.eqz %y) (then (call $halt) (return)))
+    (call $ret (call $mk_nat (i64.rem_u %x %y)))"),
+  ("#Nat.to_Text", &[NAT], "(call $ret (call $nat_to_text %x))"),
+  ("#Bool.eql", &[BOOL, BOOL], "(call $ret (call $mk_bool (i32.eq %i %j)))"),
+  ("#Bool.lte", &[BOOL, BOOL], "(call $ret (call $mk_bool (i32.le_u %i %j)))"),
+  ("#Bool.lth", &[BOOL, BOOL], "(call $ret (call $mk_bool (i32.lt_u %i %j)))"),
+  ("#Bool.gte", &[BOOL, BOOL], "(call $ret (call $mk_bool (i32.ge_u %i %j)))"),
+  ("#Bool.gth", &[BOOL, BOOL], "(call $ret (call $mk_bool (i32.gt_u %i %j)))"),
+  ("#Bool.and", &[BOOL, BOOL], "(call $ret (call $mk_bool (i32.and %i %j)))"),
+  ("#Bool.or", &[BOOL, BOOL], "(call $ret (call $mk_bool (i32.or %i %j)))"),
+  ("#Bool.xor", &[BOOL, BOOL], "(call $ret (call $mk_bool (i32.xor %i %j)))"),
+  ("#Bool.not", &[BOOL], "(call $ret (call $mk_bool (i32.eqz %i)))"),
+  ("#Text.append", &[TEXT, TEXT], "(call $ret (call $concat (i32.const 3) %a %b))"),
+  ("#Text.to_bytes", &[TEXT], "
+    (call $ret (call $mk_bytes (i32.const 4)
+      (i32.load offset=4 %a) (i32.load offset=8 %a)))"),
+  ("#Bytes.append", &[BYTES, BYTES], "(call $ret (call $concat (i32.const 4) %a %b))"),
+];
+
+/// The name of an operation in `OPS`
+fn op_name(opr: &Op) -> String {
+  match opr {
+    Op::Io(op) => op.symbol(),
+    _ => opr.symbol(),
+  }
+}
+
+/// The opcode of a supported operation
+fn opcode(opr: &Op) -> Result<usize, WasmError> {
+  let name = op_name(opr);
+  OPS.iter().position(|(op, ..)| *op == name).ok_or_else(|| {
+    WasmError::Unsupported(format!("the primitive operation {}", name))
+  })
+}
+
+/// Resolves the externs the target supports, as `StdIORuntime` does
+struct WasmIO;
+
+impl RuntimeIO for WasmIO {
+  fn resolve_extern(&self, symbol: &str) -> Option<Term> {
+    if self.extern_symbols().iter().any(|s| s == symbol) {
+      StdIORuntime::new().resolve_extern(symbol)
+    }
+    else {
+      None
+    }
+  }
+
+  fn extern_symbols(&self) -> Vec<String> {
+    ["io.bind", "io.exit", "io.print", "io.read", "io.return"]
+      .iter()
+      .map(|s| s.to_string())
+      .collect()
+  }
+}
+
+/// Compiles the program `term`, with its externs unresolved, to a binary
+/// WebAssembly module
+pub fn build(defs: Rc<Defs>, term: &Term) -> Result<Vec<u8>, WasmError> {
+  let text = build_text(defs, term)?;
+  wat::parse_str(&text).map_err(|e| WasmError::Assemble(e.to_string()))
+}
+
+/// Compiles the program `term`, with its externs unresolved, to a
+/// WebAssembly text module
+pub fn build_text(defs: Rc<Defs>, term: &Term) -> Result<String, WasmError> {
+  let mut term = term.clone();
+  transform::transform(defs.clone(), &mut term, Rc::new(WasmIO))
+    .map_err(WasmError::Runtime)?;
+  let program =
+    Program::compile(&defs, &term).map_err(WasmError::Runtime)?;
+  Codegen::new(&program).module(&program)
+}
+
+/// The state of the translation of a program
+struct Codegen {
+  /// The function index of the first segment of each block
+  segments: Vec<usize>,
+  /// The number of segments
+  count: usize,
+  /// The static data, starting at address `DATA`
+  data: Vec<u8>,
+}
+
+/// The address of the static data. Address 0 is the null pointer.
+const DATA: usize = 8;
+
+impl Codegen {
+  fn new(program: &Program) -> Self {
+    let mut segments = vec![];
+    let mut next = 0;
+    for block in &program.blocks {
+      segments.push(next);
+      next += 1 + block.iter().filter(|i| **i == Instr::Grab).count();
+    }
+    Codegen { segments, count: next, data: vec![] }
+  }
+
+  /// Adds words to the static data and returns their address
+  fn words(&mut self, words: &[u32]) -> usize {
+    while self.data.len() % 8 != 0 {
+      self.data.push(0);
+    }
+    let addr = DATA + self.data.len();
+    for word in words {
+      self.data.extend_from_slice(&word.to_le_bytes());
+    }
+    addr
+  }
+
+  /// Adds a literal to the static data and returns its address
+  fn literal(&mut self, lit: &Literal) -> Result<usize, WasmError> {
+    match lit {
+      Literal::Nat(x) => {
+        let x = u64::try_from(x).map_err(|_| {
+          WasmError::Unsupported(format!("the #Nat {} of more than 64 bits", x))
+        })?;
+        Ok(self.words(&[NAT, 0, x as u32, (x >> 32) as u32]))
+      }
+      Literal::Bool(b) => Ok(self.words(&[BOOL, *b as u32])),
+      Literal::U8(x) => Ok(self.words(&[U8, u32::from(*x)])),
+      // Erased types are `#I32` literals
+      Literal::I32(x) => Ok(self.words(&[I32, *x as u32])),
+      Literal::Text(_) | Literal::Bytes(_) => {
+        let (tag, bytes) = match lit {
+          Literal::Text(xs) => (TEXT, xs.to_string().into_bytes()),
+          Literal::Bytes(xs) => (BYTES, xs.clone()),
+          _ => unreachable!(),
+        };
+        let ptr = DATA + self.data.len();
+        self.data.extend_from_slice(&bytes);
+        Ok(self.words(&[tag, ptr as u32, bytes.len() as u32]))
+      }
+      _ => Err(WasmError::Unsupported(format!("the literal {}", lit))),
+    }
+  }
+
+  /// Translates the instructions of a block, one function per segment
+  fn block(
+    &mut self,
+    program: &Program,
+    block: usize,
+    out: &mut String,
+  ) -> Result<(), WasmError> {
+    let mut segment = self.segments[block];
+    let mut code = String::new();
+    for instr in &program.blocks[block] {
+      match instr {
+        Instr::Arg(arg) => code.push_str(&format!(
+          "    (call $push (i32.const 0) (call $thunk (i32.const {}) \
+           (local.get $env)))\n",
+          self.segments[*arg]
+        )),
+        Instr::ArgVar(idx) => code.push_str(&format!(
+          "    (call $push (i32.const 0) (call $lookup (local.get $env) \
+           (i32.const {})))\n",
+          idx
+        )),
+        Instr::Grab => code.push_str(&format!(
+          "    (call $grab (i32.const {}) (local.get $env))\n",
+          segment + 1
+        )),
+        Instr::Let(exp) => code.push_str(&format!(
+          "    (local.set $env (call $bind (call $thunk (i32.const {}) \
+           (local.get $env)) (local.get $env)))\n",
+          self.segments[*exp]
+        )),
+        Instr::LetRec(exp) => code.push_str(&format!(
+          "    (local.set $t (call $thunk (i32.const {}) (i32.const 0)))\n    \
+           (local.set $env (call $bind (local.get $t) (local.get $env)))\n    \
+           (i32.store offset=8 (local.get $t) (local.get $env))\n",
+          self.segments[*exp]
+        )),
+        Instr::Var(idx) => code.push_str(&format!(
+          "    (call $enter (call $lookup (local.get $env) (i32.const {})))\n",
+          idx
+        )),
+        Instr::Def(def) => code.push_str(&format!(
+          "    (local.set $t (call $thunk (i32.const {}) (i32.const 0)))\n    \
+           (i32.store offset=8 (local.get $t) (call $bind (local.get $t) \
+           (i32.const 0)))\n    (call $enter (local.get $t))\n",
+          self.segments[*def]
+        )),
+        Instr::Lit(lit) => {
+          let addr = self.literal(lit)?;
+          code.push_str(&format!("    (call $ret (i32.const {}))\n", addr))
+        }
+        Instr::Opr(opr) if opr.arity() == 0 => code.push_str(&format!(
+          "    (if (call $top_is_arg) (then (call $halt)) (else (call $apply \
+           (i32.const {}) (i32.const 0))))\n",
+          opcode(opr)?
+        )),
+        Instr::Opr(opr) => {
+          let addr =
+            self.words(&[OPR, opcode(opr)? as u32, opr.arity() as u32, 0]);
+          code.push_str(&format!("    (call $ret (i32.const {}))\n", addr))
+        }
+      }
+      if let Instr::Grab = instr {
+        out.push_str(&segment_func(segment, &code));
+        code.clear();
+        segment += 1;
+      }
+    }
+    out.push_str(&segment_func(segment, &code));
+    Ok(())
+  }
+
+  /// The function applying the primitive operations
+  fn apply(&self) -> String {
+    let mut out = String::from(
+      "  (func $apply (param $op i32) (param $l i32)\n    (local $n i32) \
+       (local $p i32)\n",
+    );
+    for (opcode, (_, tags, body)) in OPS.iter().enumerate() {
+      let mut checks = String::new();
+      for (idx, tag) in tags.iter().enumerate() {
+        checks.push_str(&format!(
+          "      (if (i32.ne (i32.load (call $lit (local.get $l) (i32.const \
+           {}))) (i32.const {})) (then (call $halt) (return)))\n",
+          idx, tag
+        ));
+      }
+      let body = body
+        .replace("%x", "(i64.load offset=8 %a)")
+        .replace("%y", "(i64.load offset=8 %b)")
+        .replace("%i", "(i32.load offset=4 %a)")
+        .replace("%j", "(i32.load offset=4 %b)")
+        .replace("%a", "(call $lit (local.get $l) (i32.const 0))")
+        .replace("%b", "(call $lit (local.get $l) (i32.const 1))");
+      out.push_str(&format!(
+        "    (if (i32.eq (local.get $op) (i32.const {}))\n      (then\n{}      \
+         {}\n      (return)))\n",
+        opcode, checks, body
+      ));
+    }
+    out.push_str("    (unreachable))\n");
+    out
+  }
+
+  fn module(mut self, program: &Program) -> Result<String, WasmError> {
+    let mut funcs = String::new();
+    for block in 0..program.blocks.len() {
+      self.block(program, block, &mut funcs)?;
+    }
+    let heap = DATA + self.data.len() + 8 - (DATA + self.data.len()) % 8;
+    let pages = heap / 65536 + 1;
+    let elems: Vec<String> =
+      (0..self.count).map(|s| format!("$s{}", s)).collect();
+    let data: String =
+      self.data.iter().map(|byte| format!("\\{:02x}", byte)).collect();
+    Ok(format!(
+      "(module\n{}\n  (memory (export \"memory\") {})\n  (data (i32.const {}) \
+       \"{}\")\n  (table {} funcref)\n  (elem (i32.const 0) {})\n  (global \
+       $hp (mut i32) (i32.const {}))\n{}\n{}{}{})\n",
+      IMPORTS,
+      pages,
+      DATA,
+      data,
+      self.count,
+      elems.join(" "),
+      heap,
+      RUNTIME,
+      self.apply(),
+      funcs,
+      MAIN,
+    ))
+  }
+}
+
+/// The function of a segment of a block
+fn segment_func(segment: usize, code: &str) -> String {
+  format!(
+    "  (func $s{} (type $seg) (param $env i32)\n    (local $t i32)\n{}  )\n",
+    segment, code
+  )
+}
+
+const IMPORTS: &str = r#"  (import "yatima" "write_stdout"
+    (func $write_stdout (param i32 i32) (result i32)))
+  (import "yatima" "read_stdin_len" (func $read_stdin_len (result i32)))
+  (import "yatima" "read_stdin" (func $read_stdin (param i32)))
+  (import "yatima" "abort" (func $abort (param i32 i32)))
+  (type $seg (func (param i32)))"#;
+
+/// The runtime of the machine. Thunks are `[state, a, b]`: a segment and
+/// environment while delayed (0), a value once forced (1), and nothing while
+/// being forced (2). Environments are `[thunk, next]`, and stack entries
+/// `[kind, ptr, next]` hold an argument (0), a thunk to update (1), a frame
+/// forcing the arguments of a primitive (2) or a thunk to enter once a value
+/// is returned (3). Values are tagged: a lambda is `[0, segment, env]`, a
+/// primitive `[5, opcode, arity, n, args...]` and the literals hold their
+/// payload after the tag. `$ctl` is what the machine does next: run segment
+/// `$ca` in environment `$cb` (0), return the value `$ca` (2), halt (3) or
+/// exit with status `$ca` (4).
+const RUNTIME: &str = r#"  (global $sp (mut i32) (i32.const 0))
+  (global $ctl (mut i32) (i32.const 0))
+  (global $ca (mut i32) (i32.const 0))
+  (global $cb (mut i32) (i32.const 0))
+
+  (func $alloc (param $n i32) (result i32)
+    (local $p i32)
+    (local.set $p (global.get $hp))
+    (global.set $hp (i32.and
+      (i32.add (i32.add (local.get $p) (local.get $n)) (i32.const 7))
+      (i32.const -8)))
+    (block $ok
+      (loop $grow
+        (br_if $ok (i32.le_u (global.get $hp)
+          (i32.mul (memory.size) (i32.const 65536))))
+        (br_if $grow (i32.ne (memory.grow (i32.const 16)) (i32.const -1)))
+        (unreachable)))
+    (local.get $p))
+
+  (func $obj2 (param $a i32) (param $b i32) (result i32)
+    (local $p i32)
+    (local.set $p (call $alloc (i32.const 8)))
+    (i32.store (local.get $p) (local.get $a))
+    (i32.store offset=4 (local.get $p) (local.get $b))
+    (local.get $p))
+
+  (func $obj3 (param $a i32) (param $b i32) (param $c i32) (result i32)
+    (local $p i32)
+    (local.set $p (call $alloc (i32.const 12)))
+    (i32.store (local.get $p) (local.get $a))
+    (i32.store offset=4 (local.get $p) (local.get $b))
+    (i32.store offset=8 (local.get $p) (local.get $c))
+    (local.get $p))
+
+  (func $copy (param $dst i32) (param $src i32) (param $n i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $n)))
+        (i32.store8 (local.get $dst) (i32.load8_u (local.get $src)))
+        (local.set $dst (i32.add (local.get $dst) (i32.const 1)))
+        (local.set $src (i32.add (local.get $src) (i32.const 1)))
+        (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+        (br $next))))
+
+  (func $thunk (param $seg i32) (param $env i32) (result i32)
+    (call $obj3 (i32.const 0) (local.get $seg) (local.get $env)))
+
+  (func $bind (param $t i32) (param $env i32) (result i32)
+    (call $obj2 (local.get $t) (local.get $env)))
+
+  (func $lookup (param $env i32) (param $i i32) (result i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $i)))
+        (local.set $env (i32.load offset=4 (local.get $env)))
+        (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i32.load (local.get $env)))
+
+  (func $push (param $kind i32) (param $p i32)
+    (global.set $sp (call $obj3 (local.get $kind) (local.get $p) (global.get $sp))))
+
+  (func $top_is_arg (result i32)
+    (if (result i32) (global.get $sp)
+      (then (i32.eqz (i32.load (global.get $sp))))
+      (else (i32.const 0))))
+
+  (func $set (param $ctl i32) (param $a i32) (param $b i32)
+    (global.set $ctl (local.get $ctl))
+    (global.set $ca (local.get $a))
+    (global.set $cb (local.get $b)))
+
+  (func $ret (param $v i32)
+    (call $set (i32.const 2) (local.get $v) (i32.const 0)))
+
+  (func $halt
+    (call $set (i32.const 3) (i32.const 0) (i32.const 0)))
+
+  (func $lit (param $l i32) (param $i i32) (result i32)
+    (i32.load (i32.add (local.get $l) (i32.shl (local.get $i) (i32.const 2)))))
+
+  (func $mk_nat (param $x i64) (result i32)
+    (local $p i32)
+    (local.set $p (call $alloc (i32.const 16)))
+    (i32.store (local.get $p) (i32.const 1))
+    (i64.store offset=8 (local.get $p) (local.get $x))
+    (local.get $p))
+
+  (func $mk_bool (param $b i32) (result i32)
+    (call $obj2 (i32.const 2) (local.get $b)))
+
+  (func $mk_bytes (param $tag i32) (param $ptr i32) (param $len i32) (result i32)
+    (call $obj3 (local.get $tag) (local.get $ptr) (local.get $len)))
+
+  (func $concat (param $tag i32) (param $x i32) (param $y i32) (result i32)
+    (local $lx i32) (local $ly i32) (local $p i32)
+    (local.set $lx (i32.load offset=8 (local.get $x)))
+    (local.set $ly (i32.load offset=8 (local.get $y)))
+    (local.set $p (call $alloc (i32.add (local.get $lx) (local.get $ly))))
+    (call $copy (local.get $p) (i32.load offset=4 (local.get $x)) (local.get $lx))
+    (call $copy (i32.add (local.get $p) (local.get $lx))
+      (i32.load offset=4 (local.get $y)) (local.get $ly))
+    (call $mk_bytes (local.get $tag) (local.get $p)
+      (i32.add (local.get $lx) (local.get $ly))))
+
+  (func $nat_to_text (param $x i64) (result i32)
+    (local $buf i32) (local $i i32)
+    (local.set $buf (call $alloc (i32.const 20)))
+    (local.set $i (i32.const 20))
+    (loop $digit
+      (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+      (i32.store8 (i32.add (local.get $buf) (local.get $i))
+        (i32.add (i32.const 48)
+          (i32.wrap_i64 (i64.rem_u (local.get $x) (i64.const 10)))))
+      (local.set $x (i64.div_u (local.get $x) (i64.const 10)))
+      (br_if $digit (i64.ne (local.get $x) (i64.const 0))))
+    (call $mk_bytes (i32.const 3) (i32.add (local.get $buf) (local.get $i))
+      (i32.sub (i32.const 20) (local.get $i))))
+
+  (func $enter (param $t i32)
+    (local $state i32)
+    (local.set $state (i32.load (local.get $t)))
+    (if (i32.eq (local.get $state) (i32.const 1))
+      (then
+        (call $ret (i32.load offset=4 (local.get $t)))
+        (return)))
+    ;; A thunk that depends on itself never reduces
+    (if (i32.eq (local.get $state) (i32.const 2)) (then (unreachable)))
+    (call $push (i32.const 1) (local.get $t))
+    (call $set (i32.const 0)
+      (i32.load offset=4 (local.get $t)) (i32.load offset=8 (local.get $t)))
+    (i32.store (local.get $t) (i32.const 2)))
+
+  (func $grab (param $seg i32) (param $env i32)
+    (if (call $top_is_arg)
+      (then
+        (call $set (i32.const 0) (local.get $seg)
+          (call $bind (i32.load offset=4 (global.get $sp)) (local.get $env)))
+        (global.set $sp (i32.load offset=8 (global.get $sp))))
+      (else
+        (call $ret (call $obj3 (i32.const 0) (local.get $seg) (local.get $env))))))
+
+  (func $saturate (param $o i32)
+    (local $op i32) (local $n i32) (local $first i32) (local $f i32) (local $i i32)
+    (local.set $op (i32.load offset=4 (local.get $o)))
+    ;; `seq` forces its first argument, then enters the second
+    (if (i32.eq (local.get $op) (i32.const 1))
+      (then
+        (call $push (i32.const 3) (i32.load offset=20 (local.get $o)))
+        (call $enter (i32.load offset=16 (local.get $o)))
+        (return)))
+    ;; `#Text.abort` only forces its message
+    (local.set $first (i32.eqz (local.get $op)))
+    (local.set $n (i32.sub (i32.load offset=12 (local.get $o)) (local.get $first)))
+    (local.set $f (call $alloc (i32.add (i32.const 12)
+      (i32.shl (local.get $n) (i32.const 3)))))
+    (i32.store (local.get $f) (local.get $op))
+    (i32.store offset=4 (local.get $f) (local.get $n))
+    (i32.store offset=8 (local.get $f) (i32.const 0))
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $n)))
+        (i32.store
+          (i32.add (local.get $f) (i32.shl (i32.add (local.get $i) (i32.const 3))
+            (i32.const 2)))
+          (i32.load (i32.add (local.get $o) (i32.shl
+            (i32.add (i32.add (local.get $i) (local.get $first)) (i32.const 4))
+            (i32.const 2)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (call $push (i32.const 2) (local.get $f))
+    (call $enter (i32.load offset=12 (local.get $f))))
+
+  (func $push_arg (param $v i32) (param $t i32)
+    (local $n i32) (local $o i32) (local $i i32)
+    (local.set $n (i32.load offset=12 (local.get $v)))
+    (local.set $o (call $alloc (i32.add (i32.const 20)
+      (i32.shl (local.get $n) (i32.const 2)))))
+    (call $copy (local.get $o) (local.get $v)
+      (i32.add (i32.const 16) (i32.shl (local.get $n) (i32.const 2))))
+    (i32.store offset=12 (local.get $o) (i32.add (local.get $n) (i32.const 1)))
+    (i32.store (i32.add (local.get $o) (i32.shl (i32.add (local.get $n)
+      (i32.const 4)) (i32.const 2))) (local.get $t))
+    (if (i32.eq (i32.add (local.get $n) (i32.const 1))
+          (i32.load offset=8 (local.get $o)))
+      (then (call $saturate (local.get $o)))
+      (else (call $ret (local.get $o)))))
+
+  (func $force_next (param $f i32) (param $v i32)
+    (local $n i32) (local $i i32) (local $tag i32)
+    (local.set $tag (i32.load (local.get $v)))
+    ;; A primitive applied to a non-literal is stuck
+    (if (i32.or (i32.eqz (local.get $tag)) (i32.eq (local.get $tag) (i32.const 5)))
+      (then (call $halt) (return)))
+    (local.set $n (i32.load offset=4 (local.get $f)))
+    (local.set $i (i32.load offset=8 (local.get $f)))
+    (i32.store (i32.add (local.get $f) (i32.shl
+      (i32.add (i32.add (local.get $n) (local.get $i)) (i32.const 3))
+      (i32.const 2))) (local.get $v))
+    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+    (i32.store offset=8 (local.get $f) (local.get $i))
+    (if (i32.lt_u (local.get $i) (local.get $n))
+      (then
+        (call $push (i32.const 2) (local.get $f))
+        (call $enter (i32.load (i32.add (local.get $f)
+          (i32.shl (i32.add (local.get $i) (i32.const 3)) (i32.const 2)))))
+        (return)))
+    (call $apply (i32.load (local.get $f)) (i32.add (local.get $f)
+      (i32.shl (i32.add (local.get $n) (i32.const 3)) (i32.const 2)))))
+
+  (func $resume (param $v i32)
+    (local $kind i32) (local $p i32) (local $tag i32)
+    (if (i32.eqz (global.get $sp)) (then (call $halt) (return)))
+    (local.set $kind (i32.load (global.get $sp)))
+    (local.set $p (i32.load offset=4 (global.get $sp)))
+    (global.set $sp (i32.load offset=8 (global.get $sp)))
+    (if (i32.eq (local.get $kind) (i32.const 1))
+      (then
+        (i32.store (local.get $p) (i32.const 1))
+        (i32.store offset=4 (local.get $p) (local.get $v))
+        (call $ret (local.get $v))
+        (return)))
+    (if (i32.eq (local.get $kind) (i32.const 2))
+      (then (call $force_next (local.get $p) (local.get $v)) (return)))
+    (if (i32.eq (local.get $kind) (i32.const 3))
+      (then (call $enter (local.get $p)) (return)))
+    (local.set $tag (i32.load (local.get $v)))
+    (if (i32.eqz (local.get $tag))
+      (then
+        (call $set (i32.const 0) (i32.load offset=4 (local.get $v))
+          (call $bind (local.get $p) (i32.load offset=8 (local.get $v))))
+        (return)))
+    (if (i32.eq (local.get $tag) (i32.const 5))
+      (then (call $push_arg (local.get $v) (local.get $p)) (return)))
+    ;; Applying a literal is stuck
+    (call $halt))
+"#;
+
+const MAIN: &str = r#"  (func (export "main") (result i32)
+    (local $t i32)
+    ;; The program is entered as a definition, the target of its `Rec`
+    (local.set $t (call $thunk (i32.const 0) (i32.const 0)))
+    (i32.store offset=8 (local.get $t) (call $bind (local.get $t) (i32.const 0)))
+    (call $enter (local.get $t))
+    (block $halt
+      (loop $step
+        (br_if $halt (i32.ge_u (global.get $ctl) (i32.const 3)))
+        (if (i32.eqz (global.get $ctl))
+          (then (call_indirect (type $seg) (global.get $cb) (global.get $ca)))
+          (else (call $resume (global.get $ca))))
+        (br $step)))
+    (if (result i32) (i32.eq (global.get $ctl) (i32.const 4))
+      (then (global.get $ca))
+      (else (i32.const 0))))
+"#;
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+  use crate::{
+    memory::MemIORuntime,
+    run_with_engine,
+    tests::IO,
+    Engine,
+  };
+  use wasmi::{
+    Externals,
+    FuncInstance,
+    FuncRef,
+    ImportsBuilder,
+    MemoryRef,
+    Module,
+    ModuleImportResolver,
+    ModuleInstance,
+    RuntimeArgs,
+    RuntimeValue,
+    Signature,
+    Trap,
+    TrapKind,
+  };
+  use yatima_core::{
+    limits::Limits,
+    name::Name,
+    parse::{
+      package::parse_defs,
+      span::Span,
+      term::input_cid,
+    },
+    position::Pos,
+    prim::text::TextOp,
+  };
+
+  const WRITE_STDOUT: usize = 0;
+  const READ_STDIN_LEN: usize = 1;
+  const READ_STDIN: usize = 2;
+  const ABORT: usize = 3;
+
+  struct Resolver;
+
+  impl ModuleImportResolver for Resolver {
+    fn resolve_func(
+      &self,
+      field_name: &str,
+      signature: &Signature,
+    ) -> Result<FuncRef, wasmi::Error> {
+      let index = match field_name {
+        "write_stdout" => WRITE_STDOUT,
+        "read_stdin_len" => READ_STDIN_LEN,
+        "read_stdin" => READ_STDIN,
+        "abort" => ABORT,
+        _ => {
+          return Err(wasmi::Error::Instantiation(format!(
+            "unknown import {}",
+            field_name
+          )));
+        }
+      };
+      Ok(FuncInstance::alloc_host(signature.clone(), index))
+    }
+  }
+
+  /// The imports of a module, reading and writing memory
+  struct Host {
+    memory: MemoryRef,
+    stdin: Vec<u8>,
+    line: Vec<u8>,
+    stdout: Vec<u8>,
+    abort: Option<String>,
+  }
+
+  impl Host {
+    fn bytes(&self, args: &RuntimeArgs) -> Result<Vec<u8>, Trap> {
+      let ptr: u32 = args.nth_checked(0)?;
+      let len: u32 = args.nth_checked(1)?;
+      self
+        .memory
+        .get(ptr, len as usize)
+        .map_err(|_| Trap::new(TrapKind::MemoryAccessOutOfBounds))
+    }
+  }
+
+  impl Externals for Host {
+    fn invoke_index(
+      &mut self,
+      index: usize,
+      args: RuntimeArgs,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+      match index {
+        WRITE_STDOUT => {
+          let bytes = self.bytes(&args)?;
+          self.stdout.extend(bytes);
+          Ok(Some(RuntimeValue::I32(1)))
+        }
+        READ_STDIN_LEN => {
+          let len =
+            self.stdin.iter().position(|b| *b == b'\n').map_or(0, |i| i + 1);
+          let len = if len == 0 { self.stdin.len() } else { len };
+          self.line = self.stdin.drain(..len).collect();
+          Ok(Some(RuntimeValue::I32(self.line.len() as i32)))
+        }
+        READ_STDIN => {
+          let ptr: u32 = args.nth_checked(0)?;
+          self
+            .memory
+            .set(ptr, &self.line)
+            .map_err(|_| Trap::new(TrapKind::MemoryAccessOutOfBounds))?;
+          Ok(None)
+        }
+        ABORT => {
+          let bytes = self.bytes(&args)?;
+          self.abort = Some(String::from_utf8_lossy(&bytes).into_owned());
+          Ok(None)
+        }
+        _ => panic!("unknown host function {}", index),
+      }
+    }
+  }
+
+  /// Runs `main` compiled to WebAssembly, checking that graph reduction gives
+  /// the same result and output
+  fn run_main(src: &str, stdin: &str) -> Result<i32, RuntimeError> {
+    let src = format!("{}{}", IO, src);
+    let (_, (defs, _)) =
+      parse_defs(input_cid(&src), Defs::new())(Span::new(&src)).unwrap();
+    let defs = Rc::new(defs);
+    let term = defs.get(&Name::from("main")).unwrap().term.clone();
+    let wasm = build(defs.clone(), &term).unwrap();
+    let module = Module::from_buffer(&wasm).unwrap();
+    let imports = ImportsBuilder::new().with_resolver("yatima", &Resolver);
+    let instance =
+      ModuleInstance::new(&module, &imports).unwrap().assert_no_start();
+    let memory = instance
+      .export_by_name("memory")
+      .and_then(|export| export.as_memory().cloned())
+      .unwrap();
+    let mut host = Host {
+      memory,
+      stdin: stdin.as_bytes().to_vec(),
+      line: vec![],
+      stdout: vec![],
+      abort: None,
+    };
+    let res = match instance.invoke_export("main", &[], &mut host) {
+      Ok(Some(RuntimeValue::I32(0))) => Ok(0),
+      Ok(Some(RuntimeValue::I32(code))) => Err(RuntimeError::Exit(code)),
+      res => match host.abort.clone() {
+        Some(msg) => Err(RuntimeError::Abort(msg)),
+        None => panic!("unexpected result {:?}", res),
+      },
+    };
+    let io = Rc::new(MemIORuntime::new(stdin));
+    let graph_res = run_with_engine(
+      &mut term.clone(),
+      defs,
+      io.clone(),
+      Limits::default(),
+      Engine::Graph,
+    );
+    let graph_res = match graph_res {
+      Ok(0) => Ok(0),
+      Ok(code) => Err(RuntimeError::Exit(code)),
+      Err(e) => Err(e),
+    };
+    assert_eq!(res, graph_res);
+    assert_eq!(host.stdout, io.stdout());
+    assert_eq!(host.stdin, io.remaining_stdin());
+    res
+  }
+
+  #[test]
+  fn test_io() {
+    assert_eq!(
+      run_main(
+        "def main : #Bool =
+           bind #Bytes #Bool (read #Bytes)
+             (λ x => print #Bool (#Bytes.append (#Text.to_bytes \"got \") x))",
+        "ping\npong\n",
+      ),
+      Ok(0)
+    );
+    assert_eq!(
+      run_main(
+        "def main : #Bool =
+           (λ x y => y)
+             (print #Bool (#Text.to_bytes \"no\"))
+             (print #Bool ((λ x => #Bytes.append x x) (read #Bytes)))",
+        "hi\n",
+      ),
+      Ok(0)
+    );
+  }
+
+  #[test]
+  fn test_arithmetic() {
+    assert_eq!(
+      run_main(
+        "def main : #Bool =
+           print #Bool (#Text.to_bytes (#Text.append
+             (#Nat.to_Text (#Nat.mod (#Nat.mul 1234 5678) 1000))
+             (#Nat.to_Text (#Nat.pre (#Nat.sub 7 2)))))",
+        "",
+      ),
+      Ok(0)
+    );
+    // Subtracting a larger number is stuck, which ends the program
+    assert_eq!(
+      run_main(
+        "def main : #Bool = print #Bool (#Text.to_bytes (#Nat.to_Text \
+         (#Nat.sub 2 7)))",
+        "",
+      ),
+      Ok(0)
+    );
+  }
+
+  #[test]
+  fn test_deep_reduction() {
+    assert_eq!(
+      run_main(
+        "def main : #Bool =
+           (λ ten mul =>
+             print #Bool (#Text.to_bytes (#Nat.to_Text
+               (mul ten (mul ten (mul ten (mul ten ten)))
+                 (λ n => #Nat.add n 1) 0))))
+           (λ f x => f (f (f (f (f (f (f (f (f (f x))))))))))
+           (λ m n f => m (n f))",
+        "",
+      ),
+      Ok(0)
+    );
+  }
+
+  #[test]
+  fn test_exit() {
+    assert_eq!(
+      run_main(
+        "extern exit : ∀ (0 A: Type) #U8 -> A = \"io.exit\"
+         def main : #Bool =
+           bind #Bool #Bool (print #Bool (#Text.to_bytes \"bye\"))
+             (λ x => exit #Bool 3u8)",
+        "",
+      ),
+      Err(RuntimeError::Exit(3))
+    );
+    assert_eq!(
+      run_main("def main : #Bool = #Text.abort #Bool \"boom\"", ""),
+      Err(RuntimeError::Abort("boom".to_owned()))
+    );
+  }
+
+  #[test]
+  fn test_unsupported() {
+    let defs = Rc::new(Defs::new());
+    assert_eq!(
+      build(defs.clone(), &Term::Lit(Pos::None, Literal::Int(3.into()))),
+      Err(WasmError::Unsupported("the literal +3".to_owned()))
+    );
+    let term = Term::Opr(Pos::None, Op::Text(TextOp::LenChars));
+    assert!(matches!(build(defs, &term), Err(WasmError::Unsupported(_))));
+  }
+}
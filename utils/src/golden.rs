@@ -0,0 +1,338 @@
+//! Golden-output tests for Yatima programs. Every `*.ya` file in a directory
+//! that defines `main` is run through the runtime, and its stdout, exit code
+//! and runtime error are compared with the sibling `*.out`, `*.code` and
+//! `*.err` files. Missing `*.code` and `*.err` files expect a zero exit code
+//! and no error. A sibling `*.in` file, when present, is fed to the program as
+//! stdin.
+
+use std::{
+  fs,
+  io,
+  path::{
+    Path,
+    PathBuf,
+  },
+  rc::Rc,
+};
+use yatima_core::name::Name;
+use yatima_runtime::{
+  memory::MemIORuntime,
+  run,
+};
+
+use crate::{
+  file,
+  store::Store,
+};
+
+/// The result of a single golden test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+  Passed,
+  /// The expected files were rewritten with the actual results
+  Updated,
+  Failed(String),
+}
+
+/// The observed behaviour of a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+  pub stdout: String,
+  pub code: i32,
+  /// The runtime error that stopped the program, followed by a newline, or
+  /// nothing if it ran to completion or called `io.exit`
+  pub error: String,
+}
+
+/// Finds every `*.ya` file under `dir`, sorted by path
+pub fn find_programs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+  let mut programs = Vec::new();
+  let mut dirs = vec![dir.to_path_buf()];
+  while let Some(dir) = dirs.pop() {
+    for entry in fs::read_dir(dir)? {
+      let path = entry?.path();
+      if path.is_dir() {
+        dirs.push(path);
+      }
+      else if path.extension().map_or(false, |ext| ext == "ya") {
+        programs.push(path);
+      }
+    }
+  }
+  programs.sort();
+  Ok(programs)
+}
+
+/// Parses, checks and runs the `main` of a program with the given stdin.
+/// Returns `None` if the program has no `main`. Runtime errors other than
+/// `io.exit` give exit code 1, matching `yatima run`.
+pub fn run_program(
+  root: PathBuf,
+  path: PathBuf,
+  stdin: &str,
+  store: Rc<dyn Store>,
+) -> Result<Option<Outcome>, String> {
+  let env = file::parse::PackageEnv::new(root, path, store.clone());
+  let (_, p, defs) = file::parse::parse_file(env)?;
+  let p = Rc::new(p);
+  store.put(p.to_ipld());
+  if defs.get(&Name::from("main")).is_none() {
+    return Ok(None);
+  }
+  let checked = file::check_all(p, Rc::new(defs), store)?;
+  let mut term = checked.get(&Name::from("main")).unwrap().term.clone();
+  let io = Rc::new(MemIORuntime::new(stdin));
+  let (code, error) = match run(&mut term, checked, io.clone()) {
+    Ok(code) => (code, String::new()),
+    Err(e) => (1, format!("{}\n", e)),
+  };
+  Ok(Some(Outcome { stdout: io.stdout_string(), code, error }))
+}
+
+/// Runs one golden test, rewriting its expected files if `update` is set.
+/// Returns `None` if the program has no `main`.
+pub fn test_program(
+  root: PathBuf,
+  path: PathBuf,
+  update: bool,
+  store: Rc<dyn Store>,
+) -> Option<Status> {
+  let stdin = fs::read_to_string(path.with_extension("in")).unwrap_or_default();
+  let outcome = match run_program(root, path.clone(), &stdin, store) {
+    Ok(Some(outcome)) => outcome,
+    Ok(None) => return None,
+    Err(e) => return Some(Status::Failed(e)),
+  };
+  let out_path = path.with_extension("out");
+  let code_path = path.with_extension("code");
+  let err_path = path.with_extension("err");
+  if update {
+    return Some(match write_expected(&path, &outcome) {
+      Ok(()) => Status::Updated,
+      Err(e) => {
+        Status::Failed(format!("Failed to write expected files: {}", e))
+      }
+    });
+  }
+  let expected_stdout = match fs::read_to_string(&out_path) {
+    Ok(stdout) => stdout,
+    Err(_) => {
+      return Some(Status::Failed(format!(
+        "Missing {}, run with --update to create it",
+        out_path.display()
+      )));
+    }
+  };
+  let expected_code = match fs::read_to_string(&code_path) {
+    Ok(code) => match code.trim().parse::<i32>() {
+      Ok(code) => code,
+      Err(_) => {
+        return Some(Status::Failed(format!(
+          "{} does not contain an exit code",
+          code_path.display()
+        )));
+      }
+    },
+    Err(_) => 0,
+  };
+  let expected_error = fs::read_to_string(&err_path).unwrap_or_default();
+  if outcome.code != expected_code {
+    Some(Status::Failed(format!(
+      "Expected exit code {}, got {}\n{}",
+      expected_code, outcome.code, outcome.error
+    )))
+  }
+  else if outcome.error != expected_error {
+    Some(Status::Failed(format!(
+      "Expected error:\n{}\nGot:\n{}",
+      expected_error, outcome.error
+    )))
+  }
+  else if outcome.stdout != expected_stdout {
+    Some(Status::Failed(format!(
+      "Expected stdout:\n{}\nGot:\n{}",
+      expected_stdout, outcome.stdout
+    )))
+  }
+  else {
+    Some(Status::Passed)
+  }
+}
+
+/// Writes the `*.out` file of a program, and its `*.code` and `*.err` files
+/// only for a nonzero exit code and a runtime error
+fn write_expected(path: &Path, outcome: &Outcome) -> io::Result<()> {
+  fs::write(path.with_extension("out"), &outcome.stdout)?;
+  let code = if outcome.code != 0 {
+    format!("{}\n", outcome.code)
+  }
+  else {
+    String::new()
+  };
+  write_or_remove(&path.with_extension("code"), &code)?;
+  write_or_remove(&path.with_extension("err"), &outcome.error)
+}
+
+/// Writes a file, or removes it if the contents are empty
+fn write_or_remove(path: &Path, contents: &str) -> io::Result<()> {
+  if !contents.is_empty() {
+    fs::write(path, contents)
+  }
+  else if path.exists() {
+    fs::remove_file(path)
+  }
+  else {
+    Ok(())
+  }
+}
+
+/// Runs the golden tests of every program under `dir`, returning the status
+/// of each program that has a `main`
+pub fn test_dir(
+  root: PathBuf,
+  dir: &Path,
+  update: bool,
+  store: Rc<dyn Store>,
+) -> io::Result<Vec<(PathBuf, Status)>> {
+  let mut results = Vec::new();
+  for path in find_programs(dir)? {
+    let status =
+      test_program(root.clone(), path.clone(), update, store.clone());
+    if let Some(status) = status {
+      results.push((path, status));
+    }
+  }
+  Ok(results)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::store::Callback;
+  use multiaddr::Multiaddr;
+  use sp_cid::Cid;
+  use sp_ipld::{
+    dag_cbor::cid,
+    Ipld,
+  };
+  use std::{
+    cell::RefCell,
+    collections::HashMap,
+  };
+  use yatima_core::defs::Defs;
+
+  /// A store that keeps everything in memory and cannot load by name
+  #[derive(Debug, Default)]
+  struct MemStore(RefCell<HashMap<Cid, Ipld>>);
+
+  impl Store for MemStore {
+    fn get_by_multiaddr(&self, _addr: Multiaddr) -> Result<Ipld, String> {
+      Err("Not implemented".to_owned())
+    }
+
+    fn load_by_name(&self, _path: Vec<&str>) -> Result<Ipld, String> {
+      Err("Not implemented".to_owned())
+    }
+
+    fn load_by_name_with_callback(
+      &self,
+      _path: Vec<&str>,
+      _callback: Callback<Ipld, Defs>,
+    ) {
+      panic!("Not implemented for this platform.")
+    }
+
+    fn put(&self, expr: Ipld) -> Cid {
+      let link = cid(&expr);
+      self.0.borrow_mut().insert(link, expr);
+      link
+    }
+
+    fn get(&self, link: Cid) -> Option<Ipld> {
+      self.0.borrow().get(&link).cloned()
+    }
+
+    fn get_with_callback(&self, _link: Cid, _callback: Callback<Ipld, Defs>) {
+      panic!("Not implemented for this platform.")
+    }
+
+    fn needs_callback(&self) -> bool { false }
+  }
+
+  fn fixtures(dir: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(dir)
+  }
+
+  fn test_fixtures(dir: &Path, update: bool) -> Vec<(String, Status)> {
+    let store = Rc::new(MemStore::default());
+    test_dir(dir.to_path_buf(), dir, update, store)
+      .unwrap()
+      .into_iter()
+      .map(|(path, status)| {
+        (path.file_stem().unwrap().to_string_lossy().into_owned(), status)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_pass() {
+    assert_eq!(test_fixtures(&fixtures("pass"), false), vec![
+      ("Abort".to_owned(), Status::Passed),
+      ("Echo".to_owned(), Status::Passed),
+      ("Hello".to_owned(), Status::Passed),
+    ]);
+  }
+
+  #[test]
+  fn test_fail() {
+    assert_eq!(test_fixtures(&fixtures("fail"), false), vec![(
+      "Wrong".to_owned(),
+      Status::Failed("Expected stdout:\nbye\nGot:\nhello".to_owned())
+    )]);
+  }
+
+  #[test]
+  fn test_update() {
+    let dir = std::env::temp_dir()
+      .join(format!("yatima-golden-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for name in &["Abort.ya", "Hello.ya"] {
+      fs::copy(fixtures("pass").join(name), dir.join(name)).unwrap();
+    }
+    // A stale exit code is removed along with the missing files being created
+    fs::write(dir.join("Hello.code"), "2\n").unwrap();
+    assert_eq!(test_fixtures(&dir, false), vec![
+      (
+        "Abort".to_owned(),
+        Status::Failed(format!(
+          "Missing {}, run with --update to create it",
+          dir.join("Abort.out").display()
+        ))
+      ),
+      (
+        "Hello".to_owned(),
+        Status::Failed(format!(
+          "Missing {}, run with --update to create it",
+          dir.join("Hello.out").display()
+        ))
+      ),
+    ]);
+    assert_eq!(test_fixtures(&dir, true), vec![
+      ("Abort".to_owned(), Status::Updated),
+      ("Hello".to_owned(), Status::Updated),
+    ]);
+    for name in &["Abort.out", "Abort.code", "Abort.err", "Hello.out"] {
+      assert_eq!(
+        fs::read_to_string(dir.join(name)).unwrap(),
+        fs::read_to_string(fixtures("pass").join(name)).unwrap()
+      );
+    }
+    assert!(!dir.join("Hello.code").exists());
+    assert!(!dir.join("Hello.err").exists());
+    assert_eq!(test_fixtures(&dir, false), vec![
+      ("Abort".to_owned(), Status::Passed),
+      ("Hello".to_owned(), Status::Passed),
+    ]);
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
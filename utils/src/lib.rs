@@ -1,4 +1,5 @@
 pub mod file;
+pub mod golden;
 pub mod repl;
 pub mod store;
 #[macro_use]
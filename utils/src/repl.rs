@@ -20,7 +20,15 @@ use sp_std::{
   sync::Arc,
 };
 use yatima_runtime::{
+  debug::{
+    Breakpoint,
+    Debugger,
+    Pause,
+    Resume,
+  },
   run,
+  run_debugged,
+  run_profiled,
   transform::{
     RunIO,
     StdIORuntime,
@@ -35,6 +43,7 @@ use yatima_core::{
   },
   dag::DAG,
   defs::Defs,
+  limits::Limits,
   package::Package,
   parse::{
     span::Span,
@@ -54,6 +63,7 @@ pub struct ReplEnv {
   var_index: bool,
   defs: Defs,
   runtime_io: RunIO,
+  breakpoints: Vec<Breakpoint>,
 }
 
 pub enum LineResult {
@@ -68,7 +78,8 @@ impl Default for ReplEnv {
       type_system: true,
       var_index: false,
       defs: Defs::new(),
-      runtime_io: Rc::new(StdIORuntime {}),
+      runtime_io: Rc::new(StdIORuntime::new()),
+      breakpoints: vec![],
     }
   }
 }
@@ -225,11 +236,18 @@ pub trait Repl {
                 match res {
                   Ok(typ) => {
                     let mut mterm = term;
-                    run(&mut mterm, Rc::new(env.defs.clone()), env.runtime_io.clone());
-                    dag.norm(&env.defs, false);
-                    self.println(format!("{}", dag))?;
-                    self.println(format!(": {}", typ))?;
-                    Ok(LineResult::Success)
+                    match run(&mut mterm, Rc::new(env.defs.clone()), env.runtime_io.clone()) {
+                      Ok(_) => {
+                        dag.norm(&env.defs, false);
+                        self.println(format!("{}", dag))?;
+                        self.println(format!(": {}", typ))?;
+                        Ok(LineResult::Success)
+                      }
+                      Err(e) => {
+                        self.println(format!("Runtime Error: {}", e))?;
+                        Err("Runtime Error.".to_owned())
+                      }
+                    }
                   }
                   Err(e) => {
                     self.println(format!("Type Error: {}", e))?;
@@ -251,6 +269,92 @@ pub trait Repl {
               };
               Ok(LineResult::Success)
             }
+            Command::Profile(term) => {
+              if env.type_system {
+                if let Err(e) = infer_term(&env.defs, &term, false) {
+                  self.println(format!("Type Error: {}", e))?;
+                  return Err("Type Error.".to_owned());
+                }
+              }
+              let mut mterm = *term;
+              let (res, profile) = run_profiled(
+                &mut mterm,
+                Rc::new(env.defs.clone()),
+                env.runtime_io.clone(),
+                Limits::default(),
+                "<expr>",
+              );
+              match res {
+                Ok(_) => {
+                  self.println(profile.table().trim_end().to_owned())?;
+                  Ok(LineResult::Success)
+                }
+                Err(e) => {
+                  self.println(format!("Runtime Error: {}", e))?;
+                  Err("Runtime Error.".to_owned())
+                }
+              }
+            }
+            Command::Break(breakpoint) => {
+              match Breakpoint::parse(&env.defs, &breakpoint) {
+                Ok(breakpoint) => {
+                  self.println(format!("Breakpoint {}", breakpoint))?;
+                  env.breakpoints.push(breakpoint);
+                }
+                Err(e) => self.println(format!("Error: {}", e))?,
+              };
+              Ok(LineResult::Success)
+            }
+            Command::Debug(term) => {
+              if env.type_system {
+                if let Err(e) = infer_term(&env.defs, &term, false) {
+                  self.println(format!("Type Error: {}", e))?;
+                  return Err("Type Error.".to_owned());
+                }
+              }
+              let mut mterm = *term;
+              let mut last = Resume::Step;
+              let debugger = Debugger::new(
+                env.breakpoints.clone(),
+                // An empty line repeats the last command, and an unknown one
+                // steps, as the web REPL cannot wait for the next line
+                Box::new(|pause: &Pause| {
+                  let _ = self.println(format!("{}", pause).trim_end().to_owned());
+                  let line = match self.readline("(debug) ") {
+                    Ok(line) => line,
+                    Err(_) => return Resume::Continue,
+                  };
+                  if line.trim().is_empty() {
+                    return last;
+                  }
+                  match line.trim().parse() {
+                    Ok(resume) => {
+                      last = resume;
+                      resume
+                    }
+                    Err(e) => {
+                      let _ = self.println(e);
+                      Resume::Step
+                    }
+                  }
+                }),
+              );
+              let res = run_debugged(
+                &mut mterm,
+                Rc::new(env.defs.clone()),
+                env.runtime_io.clone(),
+                Limits::default(),
+                "<expr>",
+                debugger,
+              );
+              match res {
+                Ok(_) => Ok(LineResult::Success),
+                Err(e) => {
+                  self.println(format!("Runtime Error: {}", e))?;
+                  Err("Runtime Error.".to_owned())
+                }
+              }
+            }
             Command::Define(boxed) => {
               let (n, def, _) = *boxed;
               let mut tmp_defs = env.defs.clone();
@@ -62,6 +62,9 @@ pub enum Reference {
 pub enum Command {
   Eval(Box<Term>),
   Type(Box<Term>),
+  Profile(Box<Term>),
+  Debug(Box<Term>),
+  Break(String),
   Set(String, bool),
   Browse,
   // Help,
@@ -126,6 +129,53 @@ pub fn parse_type(
   }
 }
 
+pub fn parse_profile(
+  input: Cid,
+  defs: Rc<RefCell<Defs>>,
+) -> impl Fn(Span) -> IResult<Span, Command, FileError<Span>> {
+  move |i: Span| {
+    let (i, _) = alt((tag(":profile"), tag(":p")))(i)?;
+    let (i, trm) = parse_expression(
+      input,
+      defs.clone(),
+      None,
+      ConsList::new(),
+      Rc::new(VecDeque::new()),
+    )(i)
+    .map_err(error::convert)?;
+    Ok((i, Command::Profile(Box::new(trm))))
+  }
+}
+
+pub fn parse_debug(
+  input: Cid,
+  defs: Rc<RefCell<Defs>>,
+) -> impl Fn(Span) -> IResult<Span, Command, FileError<Span>> {
+  move |i: Span| {
+    let (i, _) = tag(":debug")(i)?;
+    let (i, trm) = parse_expression(
+      input,
+      defs.clone(),
+      None,
+      ConsList::new(),
+      Rc::new(VecDeque::new()),
+    )(i)
+    .map_err(error::convert)?;
+    Ok((i, Command::Debug(Box::new(trm))))
+  }
+}
+
+/// Parse the :break command, whose breakpoint is checked when it is set
+pub fn parse_break() -> impl Fn(Span) -> IResult<Span, Command, FileError<Span>>
+{
+  move |i: Span| {
+    let (i, _) = tag(":break")(i)?;
+    let (i, _) = parse_space1(i).map_err(error::convert)?;
+    let (i, s) = take_till1(|x| char::is_whitespace(x))(i)?;
+    Ok((i, Command::Break(String::from(s.fragment().to_owned()))))
+  }
+}
+
 pub fn parse_define(
   input: Cid,
   defs: Rc<RefCell<Defs>>,
@@ -226,11 +276,14 @@ pub fn parse_command(
   move |i: Span| {
     alt((
       parse_quit(),
+      parse_break(),
       parse_browse(),
       parse_set(),
       parse_load(),
       parse_show(),
       parse_type(input, defs.clone()),
+      parse_profile(input, defs.clone()),
+      parse_debug(input, defs.clone()),
       parse_define(input, defs.clone()),
       parse_eval(input, defs.clone()),
     ))(i)